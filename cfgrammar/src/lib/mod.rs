@@ -53,10 +53,13 @@
 use serde::{Deserialize, Serialize};
 
 mod idxnewtype;
+mod staticslice;
 pub mod yacc;
 
 /// A type specifically for rule indices.
 pub use crate::idxnewtype::{PIdx, RIdx, SIdx, TIdx};
+#[doc(hidden)]
+pub use crate::staticslice::StaticSlice;
 
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
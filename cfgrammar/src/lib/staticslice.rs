@@ -0,0 +1,83 @@
+// This module defines a slice which is either owned or borrowed from `'static` data. Grammars (and
+// lrtable's state tables) store their contents in `StaticSlice`s so that code generated by lrpar
+// can embed them as `static`s which borrow, rather than copy, their contents. Unlike `Cow`,
+// `StaticSlice` does not require `T: Clone`, so it doesn't force extra bounds on `StorageT`.
+
+use std::{fmt, iter::FromIterator, ops::Deref};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A slice which is either owned or borrowed from `'static` data. This interface is unstable and
+/// should only be used by grmtools itself.
+#[doc(hidden)]
+pub enum StaticSlice<T: 'static> {
+    Owned(Vec<T>),
+    Borrowed(&'static [T]),
+}
+
+impl<T> Deref for StaticSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            StaticSlice::Owned(v) => v,
+            StaticSlice::Borrowed(s) => s,
+        }
+    }
+}
+
+impl<T: Clone> Clone for StaticSlice<T> {
+    fn clone(&self) -> Self {
+        match self {
+            StaticSlice::Owned(v) => StaticSlice::Owned(v.clone()),
+            StaticSlice::Borrowed(s) => StaticSlice::Borrowed(s),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StaticSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for StaticSlice<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Eq> Eq for StaticSlice<T> {}
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for StaticSlice<T> {
+    fn eq(&self, other: &Vec<U>) -> bool {
+        **self == other[..]
+    }
+}
+
+impl<T> From<Vec<T>> for StaticSlice<T> {
+    fn from(v: Vec<T>) -> Self {
+        StaticSlice::Owned(v)
+    }
+}
+
+impl<T> FromIterator<T> for StaticSlice<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        StaticSlice::Owned(iter.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for StaticSlice<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for StaticSlice<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(StaticSlice::Owned)
+    }
+}
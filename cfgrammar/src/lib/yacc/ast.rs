@@ -13,6 +13,8 @@ use super::Precedence;
 /// point, any further mutations made to the struct lead to undefined behaviour.
 pub struct GrammarAST {
     pub start: Option<String>,
+    /// Additional rules declared with `%entry` from which input can be parsed.
+    pub entries: Vec<String>,
     // map from a rule name to indexes into prods
     pub rules: IndexMap<String, Rule>,
     pub prods: Vec<Production>,
@@ -39,6 +41,12 @@ pub struct Rule {
 pub struct Production {
     pub symbols: Vec<Symbol>,
     pub precedence: Option<String>,
+    /// The production's `%dprec` (if any), used to choose between competing derivations when
+    /// parsing with a GLR parser.
+    pub dprec: Option<usize>,
+    /// The name of the production's `%merge` function (if any), used to combine competing
+    /// derivations when parsing with a GLR parser.
+    pub merge: Option<String>,
     pub action: Option<String>,
 }
 
@@ -46,6 +54,9 @@ pub struct Production {
 pub enum Symbol {
     Rule(String),
     Token(String),
+    /// The Yacc `error` pseudo-token, which stands for input skipped by panic mode error
+    /// recovery.
+    Error,
 }
 
 /// The various different possible grammar validation errors.
@@ -53,6 +64,7 @@ pub enum Symbol {
 pub enum GrammarValidationErrorKind {
     NoStartRule,
     InvalidStartRule,
+    InvalidEntryRule,
     UnknownRuleRef,
     UnknownToken,
     NoPrecForToken,
@@ -77,6 +89,11 @@ impl fmt::Display for GrammarValidationError {
                 "Start rule '{}' does not appear in grammar",
                 self.sym.as_ref().unwrap()
             ),
+            GrammarValidationErrorKind::InvalidEntryRule => write!(
+                f,
+                "Entry rule '{}' does not appear in grammar",
+                self.sym.as_ref().unwrap()
+            ),
             GrammarValidationErrorKind::UnknownRuleRef => write!(
                 f,
                 "Unknown reference to rule '{}'",
@@ -104,6 +121,7 @@ impl fmt::Display for Symbol {
         match *self {
             Symbol::Rule(ref s) => write!(f, "{}", s),
             Symbol::Token(ref s) => write!(f, "{}", s),
+            Symbol::Error => write!(f, "error"),
         }
     }
 }
@@ -112,6 +130,7 @@ impl GrammarAST {
     pub fn new() -> GrammarAST {
         GrammarAST {
             start: None,
+            entries: Vec::new(),
             rules: IndexMap::new(), // Using an IndexMap means that we retain the order
             // of rules as they're found in the input file.
             prods: Vec::new(),
@@ -143,12 +162,16 @@ impl GrammarAST {
         rule_name: String,
         symbols: Vec<Symbol>,
         precedence: Option<String>,
+        dprec: Option<usize>,
+        merge: Option<String>,
         action: Option<String>,
     ) {
         self.rules[&rule_name].pidxs.push(self.prods.len());
         self.prods.push(Production {
             symbols,
             precedence,
+            dprec,
+            merge,
             action,
         });
     }
@@ -172,7 +195,7 @@ impl GrammarAST {
 
     /// After the AST has been populated, perform any final operations, and validate the grammar
     /// checking that:
-    ///   1) The start rule references a rule in the grammar
+    ///   1) The start rule, and every rule declared with %entry, references a rule in the grammar
     ///   2) Every rule reference references a rule in the grammar
     ///   3) Every token reference references a declared token
     ///   4) If a production has a precedence token, then it references a declared token
@@ -195,6 +218,14 @@ impl GrammarAST {
                 }
             }
         }
+        for e in &self.entries {
+            if !self.rules.contains_key(e) {
+                return Err(GrammarValidationError {
+                    kind: GrammarValidationErrorKind::InvalidEntryRule,
+                    sym: Some(Symbol::Rule(e.clone())),
+                });
+            }
+        }
         for rule in self.rules.values() {
             for &pidx in &rule.pidxs {
                 let prod = &self.prods[pidx];
@@ -230,6 +261,7 @@ impl GrammarAST {
                                 });
                             }
                         }
+                        Symbol::Error => (),
                     }
                 }
             }
@@ -284,7 +316,7 @@ mod test {
         let mut grm = GrammarAST::new();
         grm.start = Some("A".to_string());
         grm.add_rule("B".to_string(), None);
-        grm.add_prod("B".to_string(), vec![], None, None);
+        grm.add_prod("B".to_string(), vec![], None, None, None, None);
         match grm.complete_and_validate() {
             Err(GrammarValidationError {
                 kind: GrammarValidationErrorKind::InvalidStartRule,
@@ -299,18 +331,34 @@ mod test {
         let mut grm = GrammarAST::new();
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
-        grm.add_prod("A".to_string(), vec![], None, None);
+        grm.add_prod("A".to_string(), vec![], None, None, None, None);
         assert!(grm.complete_and_validate().is_ok());
     }
 
+    #[test]
+    fn test_invalid_entry_rule() {
+        let mut grm = GrammarAST::new();
+        grm.start = Some("A".to_string());
+        grm.entries.push("B".to_string());
+        grm.add_rule("A".to_string(), None);
+        grm.add_prod("A".to_string(), vec![], None, None, None, None);
+        match grm.complete_and_validate() {
+            Err(GrammarValidationError {
+                kind: GrammarValidationErrorKind::InvalidEntryRule,
+                ..
+            }) => (),
+            _ => panic!("Validation error"),
+        }
+    }
+
     #[test]
     fn test_valid_rule_ref() {
         let mut grm = GrammarAST::new();
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
         grm.add_rule("B".to_string(), None);
-        grm.add_prod("A".to_string(), vec![rule("B")], None, None);
-        grm.add_prod("B".to_string(), vec![], None, None);
+        grm.add_prod("A".to_string(), vec![rule("B")], None, None, None, None);
+        grm.add_prod("B".to_string(), vec![], None, None, None, None);
         assert!(grm.complete_and_validate().is_ok());
     }
 
@@ -319,7 +367,7 @@ mod test {
         let mut grm = GrammarAST::new();
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
-        grm.add_prod("A".to_string(), vec![rule("B")], None, None);
+        grm.add_prod("A".to_string(), vec![rule("B")], None, None, None, None);
         match grm.complete_and_validate() {
             Err(GrammarValidationError {
                 kind: GrammarValidationErrorKind::UnknownRuleRef,
@@ -335,7 +383,7 @@ mod test {
         grm.tokens.insert("b".to_string());
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
-        grm.add_prod("A".to_string(), vec![token("b")], None, None);
+        grm.add_prod("A".to_string(), vec![token("b")], None, None, None, None);
         assert!(grm.complete_and_validate().is_ok());
     }
 
@@ -347,7 +395,7 @@ mod test {
         grm.tokens.insert("b".to_string());
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
-        grm.add_prod("A".to_string(), vec![rule("b")], None, None);
+        grm.add_prod("A".to_string(), vec![rule("b")], None, None, None, None);
         assert!(grm.complete_and_validate().is_err());
     }
 
@@ -356,7 +404,7 @@ mod test {
         let mut grm = GrammarAST::new();
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
-        grm.add_prod("A".to_string(), vec![token("b")], None, None);
+        grm.add_prod("A".to_string(), vec![token("b")], None, None, None, None);
         match grm.complete_and_validate() {
             Err(GrammarValidationError {
                 kind: GrammarValidationErrorKind::UnknownToken,
@@ -371,7 +419,14 @@ mod test {
         let mut grm = GrammarAST::new();
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
-        grm.add_prod("A".to_string(), vec![rule("b"), token("b")], None, None);
+        grm.add_prod(
+            "A".to_string(),
+            vec![rule("b"), token("b")],
+            None,
+            None,
+            None,
+            None,
+        );
         match grm.complete_and_validate() {
             Err(GrammarValidationError {
                 kind: GrammarValidationErrorKind::UnknownRuleRef,
@@ -386,7 +441,7 @@ mod test {
         let mut grm = GrammarAST::new();
         grm.start = Some("A".to_string());
         grm.add_rule("A".to_string(), None);
-        grm.add_prod("A".to_string(), vec![], None, None);
+        grm.add_prod("A".to_string(), vec![], None, None, None, None);
         grm.epp.insert("k".to_owned(), "v".to_owned());
         match grm.complete_and_validate() {
             Err(GrammarValidationError {
@@ -415,6 +470,8 @@ mod test {
             vec![token("b")],
             Some("b".to_string()),
             None,
+            None,
+            None,
         );
         assert!(grm.complete_and_validate().is_ok());
     }
@@ -429,6 +486,8 @@ mod test {
             vec![token("b")],
             Some("b".to_string()),
             None,
+            None,
+            None,
         );
         match grm.complete_and_validate() {
             Err(GrammarValidationError {
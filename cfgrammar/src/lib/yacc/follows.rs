@@ -37,7 +37,10 @@ where
             Vob::from_elem(false, usize::from(grm.tokens_len()));
             usize::from(grm.rules_len())
         ];
-        follows[usize::from(grm.start_rule_idx())].set(usize::from(grm.eof_token_idx()), true);
+        for &(_, pidx) in grm.entries() {
+            follows[usize::from(grm.prod_to_rule(pidx))]
+                .set(usize::from(grm.eof_token_idx()), true);
+        }
 
         let firsts = grm.firsts();
         loop {
@@ -1,9 +1,8 @@
-use std::{cell::RefCell, collections::HashMap, error::Error, fmt};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, error::Error, fmt};
 
 use num_traits::{self, AsPrimitive, PrimInt, Unsigned};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use vob::Vob;
 
 use super::{
     ast::{self, GrammarValidationError},
@@ -12,7 +11,7 @@ use super::{
     parser::{YaccParser, YaccParserError},
     YaccKind,
 };
-use crate::{PIdx, RIdx, SIdx, Symbol, TIdx};
+use crate::{PIdx, RIdx, SIdx, StaticSlice, Symbol, TIdx};
 
 const START_RULE: &str = "^";
 const IMPLICIT_RULE: &str = "~";
@@ -37,44 +36,59 @@ pub enum AssocKind {
 /// Representation of a `YaccGrammar`. See the [top-level documentation](../../index.html) for the
 /// guarantees this struct makes about rules, tokens, productions, and symbols.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct YaccGrammar<StorageT = u32> {
+pub struct YaccGrammar<StorageT: 'static = u32> {
     /// How many rules does this grammar have?
     rules_len: RIdx<StorageT>,
     /// A mapping from `RIdx` -> `String`.
-    rule_names: Vec<String>,
+    rule_names: StaticSlice<Cow<'static, str>>,
     /// A mapping from `TIdx` -> `Option<String>`. Every user-specified token will have a name,
-    /// but tokens inserted by cfgrammar (e.g. the EOF token) won't.
-    token_names: Vec<Option<String>>,
+    /// but tokens inserted by cfgrammar (e.g. the EOF token) won't, with the exception of the
+    /// `error` token.
+    token_names: StaticSlice<Option<Cow<'static, str>>>,
     /// A mapping from `TIdx` -> `Option<Precedence>`
-    token_precs: Vec<Option<Precedence>>,
+    token_precs: StaticSlice<Option<Precedence>>,
     /// A mapping from `TIdx` -> `Option<String>` for the %epp declaration, giving pretty-printed
     /// versions of token names that can be presented to the user in case of an error. Every
     /// user-specified token will have a name that can be presented to the user (if a token doesn't
     /// have an %epp entry, the token name will be used in lieu), but tokens inserted by cfgrammar
     /// (e.g. the EOF token) won't.
-    token_epp: Vec<Option<String>>,
+    token_epp: StaticSlice<Option<Cow<'static, str>>>,
     /// How many tokens does this grammar have?
     tokens_len: TIdx<StorageT>,
     /// The offset of the EOF token.
     eof_token_idx: TIdx<StorageT>,
+    /// The offset of the `error` token, if the grammar uses it; otherwise `None`.
+    error_token_idx: Option<TIdx<StorageT>>,
     /// How many productions does this grammar have?
     prods_len: PIdx<StorageT>,
     /// Which production is the sole production of the start rule?
     start_prod: PIdx<StorageT>,
+    /// The grammar's entry points as `(user rule, start production)` pairs. The first entry is
+    /// always the user's start rule and `start_prod`; subsequent entries are the rules declared
+    /// with `%entry`, each of which has its own cfgrammar-generated start rule.
+    entries: StaticSlice<(RIdx<StorageT>, PIdx<StorageT>)>,
     /// A list of all productions.
-    prods: Vec<Vec<Symbol<StorageT>>>,
+    prods: StaticSlice<StaticSlice<Symbol<StorageT>>>,
     /// A mapping from rules to their productions. Note that 1) the order of rules is identical to
     /// that of `rule_names` 2) every rule will have at least 1 production 3) productions
     /// are not necessarily stored sequentially.
-    rules_prods: Vec<Vec<PIdx<StorageT>>>,
+    rules_prods: StaticSlice<StaticSlice<PIdx<StorageT>>>,
     /// A mapping from productions to their corresponding rule indexes.
-    prods_rules: Vec<RIdx<StorageT>>,
+    prods_rules: StaticSlice<RIdx<StorageT>>,
     /// The precedence of each production.
-    prod_precs: Vec<Option<Precedence>>,
+    prod_precs: StaticSlice<Option<Precedence>>,
+    /// The token named by each production's `%prec` declaration (if any).
+    prod_prec_tokens: StaticSlice<Option<TIdx<StorageT>>>,
+    /// The `%dprec` of each production.
+    prod_dprecs: StaticSlice<Option<usize>>,
+    /// The name of the `%merge` function of each production.
+    prod_merges: StaticSlice<Option<Cow<'static, str>>>,
     /// The index of the rule added for implicit tokens, if they were specified; otherwise
     /// `None`.
     implicit_rule: Option<RIdx<StorageT>>,
-    /// User defined Rust programs which can be called within actions
+    /// User defined Rust programs which can be called within actions. This, `parse_param`,
+    /// `programs`, and `actiontypes` are only needed to generate code from the grammar, so
+    /// grammars created with [YaccGrammar::from_static] leave them empty.
     actions: Vec<Option<String>>,
     /// A `(name, type)` pair defining an extra parameter to pass to action functions.
     parse_param: Option<(String, String)>,
@@ -82,17 +96,91 @@ pub struct YaccGrammar<StorageT = u32> {
     programs: Option<String>,
     /// The actiontypes of rules (one per rule).
     actiontypes: Vec<Option<String>>,
-    /// Tokens marked as %avoid_insert (if any).
-    avoid_insert: Option<Vob>,
+    /// Tokens marked as %avoid_insert (if any), sorted.
+    avoid_insert: Option<StaticSlice<TIdx<StorageT>>>,
     /// How many shift/reduce conflicts the grammar author expected (if any).
     expect: Option<usize>,
     /// How many reduce/reduce conflicts the grammar author expected (if any).
     expectrr: Option<usize>,
 }
 
+/// The contents of a [YaccGrammar] as borrowed slices, allowing a grammar to be embedded in
+/// generated source code as `static` data (see lrpar's `CTParserBuilder`) rather than as a
+/// serialised blob. [YaccGrammar::to_static] borrows an existing grammar's contents;
+/// [YaccGrammar::from_static] creates a grammar which borrows (rather than copies) `'static`
+/// contents. Since the latter is a `const fn`, a generated grammar can itself be a `static`.
+/// This interface is unstable and should only be used by grmtools itself.
+#[doc(hidden)]
+pub struct StaticYaccGrammar<'a, StorageT: 'static> {
+    pub rules_len: RIdx<StorageT>,
+    pub rule_names: &'a [Cow<'static, str>],
+    pub tokens_len: TIdx<StorageT>,
+    pub token_names: &'a [Option<Cow<'static, str>>],
+    pub token_precs: &'a [Option<Precedence>],
+    pub token_epp: &'a [Option<Cow<'static, str>>],
+    pub eof_token_idx: TIdx<StorageT>,
+    pub error_token_idx: Option<TIdx<StorageT>>,
+    pub prods_len: PIdx<StorageT>,
+    pub start_prod: PIdx<StorageT>,
+    pub entries: &'a [(RIdx<StorageT>, PIdx<StorageT>)],
+    pub prods: &'a [StaticSlice<Symbol<StorageT>>],
+    pub rules_prods: &'a [StaticSlice<PIdx<StorageT>>],
+    pub prods_rules: &'a [RIdx<StorageT>],
+    pub prod_precs: &'a [Option<Precedence>],
+    pub prod_prec_tokens: &'a [Option<TIdx<StorageT>>],
+    pub prod_dprecs: &'a [Option<usize>],
+    pub prod_merges: &'a [Option<Cow<'static, str>>],
+    pub implicit_rule: Option<RIdx<StorageT>>,
+    /// The tokens marked as `%avoid_insert` (if any), sorted.
+    pub avoid_insert: Option<&'a [TIdx<StorageT>]>,
+    pub expect: Option<usize>,
+    pub expectrr: Option<usize>,
+}
+
 // Internally, we assume that a grammar's start rule has a single production. Since we manually
 // create the start rule ourselves (without relying on user input), this is a safe assumption.
 
+impl<StorageT: Copy> YaccGrammar<StorageT> {
+    /// Create a `YaccGrammar` which borrows the contents of `sgrm` without copying them. The
+    /// grammar's actions, action types, parse parameter, and programs are not recorded. This
+    /// interface is unstable and should only be used by code generated by grmtools itself.
+    #[doc(hidden)]
+    pub const fn from_static(sgrm: &StaticYaccGrammar<'static, StorageT>) -> Self {
+        let avoid_insert = match sgrm.avoid_insert {
+            Some(ai) => Some(StaticSlice::Borrowed(ai)),
+            None => None,
+        };
+        YaccGrammar {
+            rules_len: sgrm.rules_len,
+            rule_names: StaticSlice::Borrowed(sgrm.rule_names),
+            tokens_len: sgrm.tokens_len,
+            token_names: StaticSlice::Borrowed(sgrm.token_names),
+            token_precs: StaticSlice::Borrowed(sgrm.token_precs),
+            token_epp: StaticSlice::Borrowed(sgrm.token_epp),
+            eof_token_idx: sgrm.eof_token_idx,
+            error_token_idx: sgrm.error_token_idx,
+            prods_len: sgrm.prods_len,
+            start_prod: sgrm.start_prod,
+            entries: StaticSlice::Borrowed(sgrm.entries),
+            prods: StaticSlice::Borrowed(sgrm.prods),
+            rules_prods: StaticSlice::Borrowed(sgrm.rules_prods),
+            prods_rules: StaticSlice::Borrowed(sgrm.prods_rules),
+            prod_precs: StaticSlice::Borrowed(sgrm.prod_precs),
+            prod_prec_tokens: StaticSlice::Borrowed(sgrm.prod_prec_tokens),
+            prod_dprecs: StaticSlice::Borrowed(sgrm.prod_dprecs),
+            prod_merges: StaticSlice::Borrowed(sgrm.prod_merges),
+            implicit_rule: sgrm.implicit_rule,
+            actions: Vec::new(),
+            parse_param: None,
+            programs: None,
+            actiontypes: Vec::new(),
+            avoid_insert,
+            expect: sgrm.expect,
+            expectrr: sgrm.expectrr,
+        }
+    }
+}
+
 impl YaccGrammar<u32> {
     pub fn new(yacc_kind: YaccKind, s: &str) -> Result<Self, YaccGrammarError> {
         YaccGrammar::new_with_storaget(yacc_kind, s)
@@ -181,6 +269,33 @@ where
         for k in ast.rules.keys() {
             rule_names.push(k.clone());
         }
+        // Each `%entry` rule E gets its own start rule `^E` (and, if there are implicit tokens,
+        // its own intermediate start rule `^~E`), whose names are made unique in the same way as
+        // the main start rule.
+        let mut entry_rules = Vec::new();
+        let mut entry_implicit_start_rules = Vec::new();
+        for e in &ast.entries {
+            if Some(e) == ast.start.as_ref() {
+                continue;
+            }
+            let mut n1 = format!("{}{}", START_RULE, e);
+            while rule_names.contains(&n1) {
+                n1.insert_str(0, START_RULE);
+            }
+            rule_names.push(n1.clone());
+            let n2 = if implicit_start_rule.is_some() {
+                let mut n2 = format!("{}{}", IMPLICIT_START_RULE, e);
+                while rule_names.contains(&n2) {
+                    n2.insert_str(0, START_RULE);
+                }
+                rule_names.push(n2.clone());
+                entry_implicit_start_rules.push((n2.clone(), e.clone()));
+                Some(n2)
+            } else {
+                None
+            };
+            entry_rules.push((n1, n2, e.clone()));
+        }
         let mut rules_prods: Vec<Vec<PIdx<StorageT>>> = Vec::with_capacity(rule_names.len());
         let mut rule_map = HashMap::<String, RIdx<StorageT>>::new();
         for (i, v) in rule_names.iter().enumerate() {
@@ -206,13 +321,31 @@ where
                 token_map.insert(n.clone(), TIdx(i.as_()));
             }
         }
+        // The `error` token is only added if the grammar uses it. It has a name (so that it can
+        // be pretty printed), but since it can't be produced by a lexer, it's not in `token_map`.
+        let error_token_idx = if ast
+            .prods
+            .iter()
+            .any(|p| p.symbols.contains(&ast::Symbol::Error))
+        {
+            let error_token_idx = TIdx(token_names.len().as_());
+            token_names.push(Some("error".to_owned()));
+            token_precs.push(None);
+            token_epp.push(None);
+            Some(error_token_idx)
+        } else {
+            None
+        };
 
         // In order to avoid fiddling about with production indices from the AST, we simply map
         // tem 1:1 to grammar indices. That means that any new productions are added to the *end*
         // of the list of productions.
         let mut prods = vec![None; ast.prods.len()];
         let mut prod_precs: Vec<Option<Option<Precedence>>> = vec![None; ast.prods.len()];
+        let mut prod_prec_tokens = vec![None; ast.prods.len()];
         let mut prods_rules = vec![None; ast.prods.len()];
+        let mut prod_dprecs = vec![None; ast.prods.len()];
+        let mut prod_merges = vec![None; ast.prods.len()];
         let mut actions = vec![None; ast.prods.len()];
         let mut actiontypes = vec![None; rule_names.len()];
         for astrulename in &rule_names {
@@ -235,6 +368,9 @@ where
                 };
                 prods.push(Some(start_prod));
                 prod_precs.push(Some(None));
+                prod_prec_tokens.push(None);
+                prod_dprecs.push(None);
+                prod_merges.push(None);
                 prods_rules.push(Some(ridx));
                 actions.push(None);
                 continue;
@@ -251,6 +387,41 @@ where
                     Symbol::Rule(rule_map[ast.start.as_ref().unwrap()]),
                 ]));
                 prod_precs.push(Some(None));
+                prod_prec_tokens.push(None);
+                prod_dprecs.push(None);
+                prod_merges.push(None);
+                prods_rules.push(Some(ridx));
+                continue;
+            } else if let Some((_, n2, e)) = entry_rules.iter().find(|(n1, _, _)| n1 == astrulename)
+            {
+                // Add the start rule for an entry point E, mirroring the main start rule:
+                //   ^E: E;
+                // or, if an implicit rule has been specified:
+                //   ^E: ^~E;
+                rules_prods[usize::from(ridx)].push(PIdx(prods.len().as_()));
+                prods.push(Some(vec![Symbol::Rule(rule_map[n2.as_ref().unwrap_or(e)])]));
+                prod_precs.push(Some(None));
+                prod_prec_tokens.push(None);
+                prod_dprecs.push(None);
+                prod_merges.push(None);
+                prods_rules.push(Some(ridx));
+                actions.push(None);
+                continue;
+            } else if let Some((_, e)) = entry_implicit_start_rules
+                .iter()
+                .find(|(n2, _)| n2 == astrulename)
+            {
+                // Add the intermediate start rule for an entry point E:
+                //   ^~E: ~ E;
+                rules_prods[usize::from(ridx)].push(PIdx(prods.len().as_()));
+                prods.push(Some(vec![
+                    Symbol::Rule(rule_map[implicit_rule.as_ref().unwrap()]),
+                    Symbol::Rule(rule_map[e]),
+                ]));
+                prod_precs.push(Some(None));
+                prod_prec_tokens.push(None);
+                prod_dprecs.push(None);
+                prod_merges.push(None);
                 prods_rules.push(Some(ridx));
                 continue;
             } else if implicit_rule.as_ref().map_or(false, |s| s == astrulename) {
@@ -261,12 +432,18 @@ where
                     implicit_prods.push(PIdx(prods.len().as_()));
                     prods.push(Some(vec![Symbol::Token(token_map[t]), Symbol::Rule(ridx)]));
                     prod_precs.push(Some(None));
+                    prod_prec_tokens.push(None);
+                    prod_dprecs.push(None);
+                    prod_merges.push(None);
                     prods_rules.push(Some(ridx));
                 }
                 // Add an empty production
                 implicit_prods.push(PIdx(prods.len().as_()));
                 prods.push(Some(vec![]));
                 prod_precs.push(Some(None));
+                prod_prec_tokens.push(None);
+                prod_dprecs.push(None);
+                prod_merges.push(None);
                 prods_rules.push(Some(ridx));
                 continue;
             } else {
@@ -288,11 +465,15 @@ where
                                 prod.push(Symbol::Rule(rule_map[&implicit_rule.clone().unwrap()]));
                             }
                         }
+                        ast::Symbol::Error => {
+                            prod.push(Symbol::Token(error_token_idx.unwrap()));
+                        }
                     };
                 }
                 let mut prec = None;
                 if let Some(ref n) = astprod.precedence {
                     prec = Some(ast.precs[n]);
+                    prod_prec_tokens[pidx] = Some(token_map[n]);
                 } else {
                     for astsym in astprod.symbols.iter().rev() {
                         if let ast::Symbol::Token(ref n) = *astsym {
@@ -307,38 +488,54 @@ where
                 prods[pidx] = Some(prod);
                 prod_precs[pidx] = Some(prec);
                 prods_rules[pidx] = Some(ridx);
+                prod_dprecs[pidx] = astprod.dprec;
+                prod_merges[pidx] = astprod.merge.clone();
                 if let Some(ref s) = astprod.action {
                     actions[pidx] = Some(s.clone());
                 }
             }
         }
 
-        let avoid_insert = if let Some(ai) = ast.avoid_insert {
-            let mut aiv = Vob::from_elem(false, token_names.len());
-            for n in ai.iter() {
-                aiv.set(usize::from(token_map[n]), true);
-            }
-            Some(aiv)
-        } else {
-            None
-        };
+        let avoid_insert = ast.avoid_insert.map(|ai| {
+            let mut ai = ai.iter().map(|n| token_map[n]).collect::<Vec<_>>();
+            ai.sort_unstable();
+            StaticSlice::Owned(ai)
+        });
+
+        let start_prod = rules_prods[usize::from(rule_map[&start_rule])][0];
+        let mut entries = vec![(rule_map[ast.start.as_ref().unwrap()], start_prod)];
+        for (n1, _, e) in &entry_rules {
+            entries.push((rule_map[e], rules_prods[usize::from(rule_map[n1])][0]));
+        }
 
         assert!(!token_names.is_empty());
         assert!(!rule_names.is_empty());
+        fn cow_strs(v: Vec<Option<String>>) -> StaticSlice<Option<Cow<'static, str>>> {
+            v.into_iter().map(|x| x.map(Cow::Owned)).collect()
+        }
+
         Ok(YaccGrammar {
             rules_len: RIdx(rule_names.len().as_()),
-            rule_names,
+            rule_names: rule_names.into_iter().map(Cow::Owned).collect(),
             tokens_len: TIdx(token_names.len().as_()),
             eof_token_idx,
-            token_names,
-            token_precs,
-            token_epp,
+            error_token_idx,
+            token_names: cow_strs(token_names),
+            token_precs: token_precs.into(),
+            token_epp: cow_strs(token_epp),
             prods_len: PIdx(prods.len().as_()),
-            start_prod: rules_prods[usize::from(rule_map[&start_rule])][0],
-            rules_prods,
+            start_prod,
+            entries: entries.into(),
+            rules_prods: rules_prods.into_iter().map(StaticSlice::Owned).collect(),
             prods_rules: prods_rules.into_iter().map(Option::unwrap).collect(),
-            prods: prods.into_iter().map(Option::unwrap).collect(),
+            prods: prods
+                .into_iter()
+                .map(|x| StaticSlice::Owned(x.unwrap()))
+                .collect(),
             prod_precs: prod_precs.into_iter().map(Option::unwrap).collect(),
+            prod_prec_tokens: prod_prec_tokens.into(),
+            prod_dprecs: prod_dprecs.into(),
+            prod_merges: cow_strs(prod_merges),
             implicit_rule: implicit_rule.map(|x| rule_map[&x]),
             actions,
             parse_param: ast.parse_param,
@@ -350,6 +547,36 @@ where
         })
     }
 
+    /// Return a [StaticYaccGrammar] which borrows this grammar's contents. This interface is
+    /// unstable and should only be used by grmtools itself.
+    #[doc(hidden)]
+    pub fn to_static(&self) -> StaticYaccGrammar<'_, StorageT> {
+        StaticYaccGrammar {
+            rules_len: self.rules_len,
+            rule_names: &self.rule_names,
+            tokens_len: self.tokens_len,
+            token_names: &self.token_names,
+            token_precs: &self.token_precs,
+            token_epp: &self.token_epp,
+            eof_token_idx: self.eof_token_idx,
+            error_token_idx: self.error_token_idx,
+            prods_len: self.prods_len,
+            start_prod: self.start_prod,
+            entries: &self.entries,
+            prods: &self.prods,
+            rules_prods: &self.rules_prods,
+            prods_rules: &self.prods_rules,
+            prod_precs: &self.prod_precs,
+            prod_prec_tokens: &self.prod_prec_tokens,
+            prod_dprecs: &self.prod_dprecs,
+            prod_merges: &self.prod_merges,
+            implicit_rule: self.implicit_rule,
+            avoid_insert: self.avoid_insert.as_deref(),
+            expect: self.expect,
+            expectrr: self.expectrr,
+        }
+    }
+
     /// How many productions does this grammar have?
     pub fn prods_len(&self) -> PIdx<StorageT> {
         self.prods_len
@@ -387,12 +614,52 @@ where
         self.prod_precs[usize::from(pidx)]
     }
 
+    /// Return the token named by production `pidx`'s `%prec` declaration (where `None` indicates
+    /// "no `%prec` specified"). Panics if `pidx` doesn't exist.
+    pub fn prod_prec_token(&self, pidx: PIdx<StorageT>) -> Option<TIdx<StorageT>> {
+        self.prod_prec_tokens[usize::from(pidx)]
+    }
+
+    /// Return the `%dprec` of production `pidx` (where `None` indicates "no `%dprec`
+    /// specified"). Panics if `pidx` doesn't exist.
+    pub fn prod_dprec(&self, pidx: PIdx<StorageT>) -> Option<usize> {
+        self.prod_dprecs[usize::from(pidx)]
+    }
+
+    /// Return the name of the `%merge` function of production `pidx` (where `None` indicates "no
+    /// `%merge` specified"). Panics if `pidx` doesn't exist.
+    pub fn prod_merge(&self, pidx: PIdx<StorageT>) -> Option<&str> {
+        self.prod_merges[usize::from(pidx)].as_deref()
+    }
+
     /// Return the production index of the start rule's sole production (for Yacc grammars the
     /// start rule is defined to have precisely one production).
     pub fn start_prod(&self) -> PIdx<StorageT> {
         self.start_prod
     }
 
+    /// Return the grammar's entry points as `(rule, start production)` pairs, where `rule` is the
+    /// user rule that can be parsed from and `start production` is the sole production of the start
+    /// rule cfgrammar created for it. The first entry is always the user's start rule (paired with
+    /// [start_prod](Self::start_prod)); subsequent entries are the rules declared with `%entry`.
+    pub fn entries(&self) -> &[(RIdx<StorageT>, PIdx<StorageT>)] {
+        &self.entries
+    }
+
+    /// Return the start production of the entry point for the user rule `ridx`, or `None` if
+    /// `ridx` is neither the start rule nor declared with `%entry`.
+    pub fn entry_prod(&self, ridx: RIdx<StorageT>) -> Option<PIdx<StorageT>> {
+        self.entries
+            .iter()
+            .find(|(e_ridx, _)| *e_ridx == ridx)
+            .map(|&(_, pidx)| pidx)
+    }
+
+    /// Is `pidx` the start production of one of the grammar's entry points?
+    pub fn is_entry_prod(&self, pidx: PIdx<StorageT>) -> bool {
+        self.entries.iter().any(|&(_, e_pidx)| e_pidx == pidx)
+    }
+
     /// How many rules does this grammar have?
     pub fn rules_len(&self) -> RIdx<StorageT> {
         self.rules_len
@@ -457,6 +724,13 @@ where
         self.eof_token_idx
     }
 
+    /// Return the index of the `error` token, or `None` if the grammar doesn't use it. Since the
+    /// `error` token can't be produced by a lexer, it is not included in
+    /// [tokens_map](YaccGrammar::tokens_map) and has no `%epp` entry.
+    pub fn error_token_idx(&self) -> Option<TIdx<StorageT>> {
+        self.error_token_idx
+    }
+
     /// Return the name of token `tidx` (where `None` indicates "the rule has no name"). Panics if
     /// `tidx` doesn't exist.
     pub fn token_name(&self, tidx: TIdx<StorageT>) -> Option<&str> {
@@ -498,6 +772,9 @@ where
     pub fn tokens_map(&self) -> HashMap<&str, TIdx<StorageT>> {
         let mut m = HashMap::with_capacity(usize::from(self.tokens_len) - 1);
         for tidx in self.iter_tidxs() {
+            if Some(tidx) == self.error_token_idx {
+                continue;
+            }
             if let Some(n) = self.token_names[usize::from(tidx)].as_ref() {
                 m.insert(&**n, tidx);
             }
@@ -517,10 +794,9 @@ where
 
     /// Is the token `tidx` marked as `%avoid_insert`?
     pub fn avoid_insert(&self, tidx: TIdx<StorageT>) -> bool {
-        if let Some(ai) = &self.avoid_insert {
-            ai.get(usize::from(tidx)).unwrap()
-        } else {
-            false
+        match self.avoid_insert {
+            Some(ref ai) => ai.binary_search(&tidx).is_ok(),
+            None => false,
         }
     }
 
@@ -628,7 +904,7 @@ where
 /// C: [x]
 /// D: [y, x] or [y, z]
 /// ```
-pub struct SentenceGenerator<'a, StorageT> {
+pub struct SentenceGenerator<'a, StorageT: 'static> {
     grm: &'a YaccGrammar<StorageT>,
     rule_min_costs: RefCell<Option<Vec<u16>>>,
     rule_max_costs: RefCell<Option<Vec<u16>>>,
@@ -1207,6 +1483,36 @@ mod test {
         assert_eq!(grm.prod_precs[4].unwrap(), Precedence{level: 1, kind: AssocKind::Left});
         assert!(grm.prod_precs[5].is_none());
         assert_eq!(grm.prod_precs[6], None);
+        assert_eq!(grm.prod_prec_token(PIdx(4)), grm.token_idx("*"));
+        assert_eq!(grm.prod_prec_token(PIdx(2)), None);
+    }
+
+    #[test]
+    fn test_dprec_merge() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S : A %dprec 1 %merge <m> | B %dprec 2 %merge <m> | 'c';
+            A : 'c';
+            B : 'c';
+        ",
+        )
+        .unwrap();
+        // `from_static` borrows `'static` data, which a generated `static` would provide.
+        let grm: &'static YaccGrammar = Box::leak(Box::new(grm));
+        let s_prods = grm.rule_to_prods(grm.rule_idx("S").unwrap());
+        assert_eq!(grm.prod_dprec(s_prods[0]), Some(1));
+        assert_eq!(grm.prod_merge(s_prods[0]), Some("m"));
+        assert_eq!(grm.prod_dprec(s_prods[1]), Some(2));
+        assert_eq!(grm.prod_merge(s_prods[1]), Some("m"));
+        assert_eq!(grm.prod_dprec(s_prods[2]), None);
+        assert_eq!(grm.prod_merge(s_prods[2]), None);
+        assert_eq!(grm.prod_dprec(grm.start_prod()), None);
+        let grm2 = YaccGrammar::from_static(&grm.to_static());
+        assert_eq!(grm2.prod_dprec(s_prods[1]), Some(2));
+        assert_eq!(grm2.prod_merge(s_prods[0]), Some("m"));
     }
 
     #[test]
@@ -1285,6 +1591,105 @@ mod test {
         assert_eq!(i_prod3.len(), 0);
     }
 
+    #[test]
+    fn test_error_token() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+          %start S
+          %%
+          S: S T | T;
+          T: 'a' ';' | error ';';
+          ",
+        )
+        .unwrap();
+        let err_tidx = grm.error_token_idx().unwrap();
+        assert_ne!(err_tidx, grm.eof_token_idx());
+        assert_eq!(grm.token_name(err_tidx), Some("error"));
+        assert_eq!(grm.token_epp(err_tidx), None);
+        assert!(!grm.tokens_map().values().any(|&tidx| tidx == err_tidx));
+        let t_ridx = grm.rule_idx("T").unwrap();
+        let semi_tidx = grm.token_idx(";").unwrap();
+        assert_eq!(
+            grm.prod(grm.rule_to_prods(t_ridx)[1]),
+            &[Symbol::Token(err_tidx), Symbol::Token(semi_tidx)]
+        );
+        assert!(grm.firsts().is_set(t_ridx, err_tidx));
+
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+          %start S
+          %%
+          S: 'a';
+          ",
+        )
+        .unwrap();
+        assert_eq!(grm.error_token_idx(), None);
+    }
+
+    #[test]
+    fn test_entries() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+          %start S
+          %entry T S
+          %%
+          S: T 'a';
+          T: 'b';
+          ",
+        )
+        .unwrap();
+
+        // The %entry S is redundant, so there should only be one additional start rule:
+        //   ^T: T;
+        let s_ridx = grm.rule_idx("S").unwrap();
+        let t_ridx = grm.rule_idx("T").unwrap();
+        let et_ridx = grm.rule_idx("^T").unwrap();
+        assert_eq!(grm.rules_len(), RIdx(4));
+        assert_eq!(grm.entries().len(), 2);
+        assert_eq!(grm.entries()[0], (s_ridx, grm.start_prod()));
+        let et_pidx = grm.entry_prod(t_ridx).unwrap();
+        assert_eq!(grm.rule_to_prods(et_ridx), &[et_pidx]);
+        assert_eq!(grm.prod(et_pidx), &[Symbol::Rule(t_ridx)]);
+        assert!(grm.is_entry_prod(et_pidx));
+        assert!(grm.is_entry_prod(grm.start_prod()));
+        assert!(!grm.is_entry_prod(grm.rule_to_prods(t_ridx)[0]));
+        assert_eq!(grm.entry_prod(grm.start_rule_idx()), None);
+        assert!(grm.follows().is_set(et_ridx, grm.eof_token_idx()));
+        assert!(grm.follows().is_set(t_ridx, grm.eof_token_idx()));
+
+        let grm = YaccGrammar::new(
+            YaccKind::Eco,
+            "
+          %implicit_tokens ws
+          %start S
+          %entry T
+          %%
+          S: T 'a';
+          T: 'b';
+          ",
+        )
+        .unwrap();
+        // There should be two additional rules:
+        //   ^T : ^~T;
+        //   ^~T: ~ T;
+        let t_ridx = grm.rule_idx("T").unwrap();
+        let itfs_ridx = grm.rule_idx("^~T").unwrap();
+        assert_eq!(
+            grm.prod(grm.entry_prod(t_ridx).unwrap()),
+            &[Symbol::Rule(itfs_ridx)]
+        );
+        assert_eq!(
+            grm.prod(grm.rule_to_prods(itfs_ridx)[0]),
+            &[
+                Symbol::Rule(grm.implicit_rule().unwrap()),
+                Symbol::Rule(t_ridx)
+            ]
+        );
+    }
+
     #[test]
     #[rustfmt::skip]
     fn test_has_path() {
@@ -21,6 +21,7 @@ pub enum YaccParserErrorKind {
     IllegalString,
     IncompleteRule,
     DuplicateRule,
+    ReservedRuleName,
     IncompleteComment,
     IncompleteAction,
     MissingColon,
@@ -31,11 +32,14 @@ pub enum YaccParserErrorKind {
     UnknownDeclaration,
     DuplicatePrecedence,
     PrecNotFollowedByToken,
+    DuplicateDPrec,
+    DuplicateMerge,
     DuplicateAvoidInsertDeclaration,
     DuplicateImplicitTokensDeclaration,
     DuplicateExpectDeclaration,
     DuplicateExpectRRDeclaration,
     DuplicateStartDeclaration,
+    DuplicateEntryDeclaration,
     DuplicateActiontypeDeclaration,
     DuplicateEPP,
     ReachedEOL,
@@ -60,6 +64,9 @@ impl fmt::Display for YaccParserError {
             YaccParserErrorKind::IllegalString => "Illegal string",
             YaccParserErrorKind::IncompleteRule => "Incomplete rule",
             YaccParserErrorKind::DuplicateRule => "Duplicate rule",
+            YaccParserErrorKind::ReservedRuleName => {
+                "A rule cannot be named 'error' if the grammar uses the error token"
+            }
             YaccParserErrorKind::IncompleteComment => "Incomplete comment",
             YaccParserErrorKind::IncompleteAction => "Incomplete action",
             YaccParserErrorKind::MissingColon => "Missing ':'",
@@ -70,6 +77,8 @@ impl fmt::Display for YaccParserError {
             YaccParserErrorKind::UnknownDeclaration => "Unknown declaration",
             YaccParserErrorKind::DuplicatePrecedence => "Token already has a precedence",
             YaccParserErrorKind::PrecNotFollowedByToken => "%prec not followed by token name",
+            YaccParserErrorKind::DuplicateDPrec => "Production already has a %dprec",
+            YaccParserErrorKind::DuplicateMerge => "Production already has a %merge",
             YaccParserErrorKind::DuplicateAvoidInsertDeclaration => {
                 "Duplicate %avoid_insert declaration"
             }
@@ -79,6 +88,7 @@ impl fmt::Display for YaccParserError {
                 "Duplicate %implicit_tokens declaration"
             }
             YaccParserErrorKind::DuplicateStartDeclaration => "Duplicate %start declaration",
+            YaccParserErrorKind::DuplicateEntryDeclaration => "Duplicate %entry declaration",
             YaccParserErrorKind::DuplicateActiontypeDeclaration => {
                 "Duplicate %actiontype declaration"
             }
@@ -98,6 +108,10 @@ pub(crate) struct YaccParser {
     newlines: Vec<usize>,
     ast: GrammarAST,
     global_actiontype: Option<String>,
+    /// The offset of the first rule named `error`, if there is one.
+    error_rule: Option<usize>,
+    /// Does any production use the `error` token?
+    uses_error: bool,
 }
 
 lazy_static! {
@@ -115,6 +129,8 @@ impl YaccParser {
             newlines: vec![0],
             ast: GrammarAST::new(),
             global_actiontype: None,
+            error_rule: None,
+            uses_error: false,
         }
     }
 
@@ -124,6 +140,11 @@ impl YaccParser {
         // every byte within the string is also a valid character).
         let mut i = self.parse_declarations(0)?;
         i = self.parse_rules(i)?;
+        // A rule named `error` is only a problem if the grammar also uses the `error` token, since
+        // references to `error` would then be ambiguous.
+        if let (Some(off), true) = (self.error_rule, self.uses_error) {
+            return Err(self.mk_error(YaccParserErrorKind::ReservedRuleName, off));
+        }
         self.parse_programs(i)
     }
 
@@ -174,6 +195,21 @@ impl YaccParser {
                 i = self.parse_ws(j, true)?;
                 continue;
             }
+            if let Some(j) = self.lookahead_is("%entry", i) {
+                i = self.parse_ws(j, false)?;
+                let num_newlines = self.newlines.len();
+                while i < self.src.len() && self.newlines.len() == num_newlines {
+                    let (j, n) = self.parse_name(i)?;
+                    if self.ast.entries.contains(&n) {
+                        return Err(
+                            self.mk_error(YaccParserErrorKind::DuplicateEntryDeclaration, i)
+                        );
+                    }
+                    self.ast.entries.push(n);
+                    i = self.parse_ws(j, true)?;
+                }
+                continue;
+            }
             if let Some(j) = self.lookahead_is("%epp", i) {
                 i = self.parse_ws(j, false)?;
                 let (j, n) = self.parse_token(i)?;
@@ -313,6 +349,9 @@ impl YaccParser {
 
     fn parse_rule(&mut self, mut i: usize) -> YaccResult<usize> {
         let (j, rn) = self.parse_name(i)?;
+        if rn == "error" && self.error_rule.is_none() {
+            self.error_rule = Some(i);
+        }
         if self.ast.start.is_none() {
             self.ast.start = Some(rn.clone());
         }
@@ -349,18 +388,23 @@ impl YaccParser {
         }
         let mut syms = Vec::new();
         let mut prec = None;
+        let mut dprec = None;
+        let mut merge = None;
         let mut action = None;
         i = self.parse_ws(i, true)?;
         while i < self.src.len() {
             if let Some(j) = self.lookahead_is("|", i) {
-                self.ast.add_prod(rn.clone(), syms, prec, action);
+                self.ast
+                    .add_prod(rn.clone(), syms, prec, dprec, merge, action);
                 syms = Vec::new();
                 prec = None;
+                dprec = None;
+                merge = None;
                 action = None;
                 i = self.parse_ws(j, true)?;
                 continue;
             } else if let Some(j) = self.lookahead_is(";", i) {
-                self.ast.add_prod(rn, syms, prec, action);
+                self.ast.add_prod(rn, syms, prec, dprec, merge, action);
                 return Ok(j);
             }
 
@@ -378,6 +422,22 @@ impl YaccParser {
                     return Err(self.mk_error(YaccParserErrorKind::PrecNotFollowedByToken, i));
                 }
                 i = k;
+            } else if let Some(j) = self.lookahead_is("%dprec", i) {
+                if dprec.is_some() {
+                    return Err(self.mk_error(YaccParserErrorKind::DuplicateDPrec, i));
+                }
+                i = self.parse_ws(j, false)?;
+                let (k, n) = self.parse_int(i)?;
+                dprec = Some(n);
+                i = k;
+            } else if let Some(j) = self.lookahead_is("%merge", i) {
+                if merge.is_some() {
+                    return Err(self.mk_error(YaccParserErrorKind::DuplicateMerge, i));
+                }
+                i = self.parse_ws(j, false)?;
+                let (k, n) = self.parse_merge(i)?;
+                merge = Some(n);
+                i = k;
             } else if self.lookahead_is("{", i).is_some() {
                 let (j, a) = self.parse_action(i)?;
                 i = j;
@@ -386,6 +446,9 @@ impl YaccParser {
                 let (j, sym) = self.parse_token(i)?;
                 if self.ast.tokens.contains(&sym) {
                     syms.push(Symbol::Token(sym));
+                } else if sym == "error" {
+                    self.uses_error = true;
+                    syms.push(Symbol::Error);
                 } else {
                     syms.push(Symbol::Rule(sym));
                 }
@@ -406,6 +469,19 @@ impl YaccParser {
         }
     }
 
+    /// Parse a `%merge` function name of the form `<name>`.
+    fn parse_merge(&self, i: usize) -> YaccResult<(usize, String)> {
+        let j = match self.lookahead_is("<", i) {
+            Some(j) => j,
+            None => return Err(self.mk_error(YaccParserErrorKind::IllegalName, i)),
+        };
+        let (k, n) = self.parse_name(j)?;
+        match self.lookahead_is(">", k) {
+            Some(k) => Ok((k, n)),
+            None => Err(self.mk_error(YaccParserErrorKind::IllegalName, k)),
+        }
+    }
+
     fn parse_token(&self, i: usize) -> YaccResult<(usize, String)> {
         match RE_TOKEN.find(&self.src[i..]) {
             Some(m) => {
@@ -724,6 +800,8 @@ mod test {
             Production {
                 symbols: vec![token("a")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -747,6 +825,8 @@ mod test {
             Production {
                 symbols: vec![token("a")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -755,6 +835,8 @@ mod test {
             Production {
                 symbols: vec![token("b")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -780,6 +862,8 @@ mod test {
             Production {
                 symbols: vec![],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -789,6 +873,8 @@ mod test {
             Production {
                 symbols: vec![token("b")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -797,6 +883,8 @@ mod test {
             Production {
                 symbols: vec![],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -806,6 +894,8 @@ mod test {
             Production {
                 symbols: vec![],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -814,6 +904,8 @@ mod test {
             Production {
                 symbols: vec![token("c")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -842,6 +934,8 @@ mod test {
             Production {
                 symbols: vec![token("a"), rule("B")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -860,6 +954,8 @@ mod test {
             Production {
                 symbols: vec![token("a"), token("b")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -936,6 +1032,8 @@ mod test {
             Production {
                 symbols: vec![token("T")],
                 precedence: None,
+                dprec: None,
+                merge: None,
                 action: None
             }
         );
@@ -1301,6 +1399,59 @@ x"
         }
     }
 
+    #[test]
+    fn test_dprec_merge() {
+        let src = "
+            %%
+            S : A %dprec 2 %merge <m> | B %merge <m> %dprec 1 | 'c';
+            A : 'c';
+            B : 'c';
+        ";
+        let grm = parse(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            src,
+        )
+        .unwrap();
+        let pidxs = &grm.rules["S"].pidxs;
+        assert_eq!(grm.prods[pidxs[0]].dprec, Some(2));
+        assert_eq!(grm.prods[pidxs[0]].merge, Some("m".to_string()));
+        assert_eq!(grm.prods[pidxs[0]].symbols, vec![rule("A")]);
+        assert_eq!(grm.prods[pidxs[1]].dprec, Some(1));
+        assert_eq!(grm.prods[pidxs[1]].merge, Some("m".to_string()));
+        assert_eq!(grm.prods[pidxs[2]].dprec, None);
+        assert_eq!(grm.prods[pidxs[2]].merge, None);
+    }
+
+    #[test]
+    fn test_bad_dprec_merge() {
+        let bad = [
+            ("S: 'A' %dprec ;", YaccParserErrorKind::IllegalInteger),
+            (
+                "S: 'A' %dprec 1 %dprec 2;",
+                YaccParserErrorKind::DuplicateDPrec,
+            ),
+            ("S: 'A' %merge m;", YaccParserErrorKind::IllegalName),
+            ("S: 'A' %merge <m;", YaccParserErrorKind::IllegalName),
+            (
+                "S: 'A' %merge <m> %merge <n>;",
+                YaccParserErrorKind::DuplicateMerge,
+            ),
+        ];
+        for (rule, kind) in bad.iter() {
+            let src = format!("%%\n{}\n", rule);
+            match parse(
+                YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+                &src,
+            ) {
+                Ok(_) => panic!("Incorrect %dprec / %merge parsed"),
+                Err(YaccParserError {
+                    kind: k, line: 2, ..
+                }) if std::mem::discriminant(&k) == std::mem::discriminant(kind) => {}
+                Err(e) => panic!("Incorrect error returned {}", e),
+            }
+        }
+    }
+
     #[test]
     fn test_parse_avoid_insert() {
         let ast = parse(
@@ -1326,6 +1477,113 @@ x"
         assert!(ast.tokens.get("ws2").is_some());
     }
 
+    #[test]
+    fn test_error_symbol() {
+        let src = "
+          %start A
+          %%
+          A: error ';' | B;
+          B: 'x';
+          "
+        .to_string();
+        let ast = parse(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            &src,
+        )
+        .unwrap();
+        assert_eq!(
+            ast.prods[ast.get_rule("A").unwrap().pidxs[0]].symbols,
+            vec![Symbol::Error, token(";")]
+        );
+
+        // A token explicitly declared as `error` is just another token.
+        let src = "
+          %start A
+          %token error
+          %%
+          A: error;
+          "
+        .to_string();
+        let ast = parse(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            &src,
+        )
+        .unwrap();
+        assert_eq!(
+            ast.prods[ast.get_rule("A").unwrap().pidxs[0]].symbols,
+            vec![token("error")]
+        );
+    }
+
+    #[test]
+    fn test_error_rule() {
+        let src = "%%\nA: error;\nerror: 'x';".to_string();
+        match parse(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            &src,
+        ) {
+            Ok(_) => panic!("Rule named error parsed"),
+            Err(YaccParserError {
+                kind: YaccParserErrorKind::ReservedRuleName,
+                line: 3,
+                col: 1,
+            }) => (),
+            Err(e) => panic!("Incorrect error returned {}", e),
+        }
+
+        // A rule named `error` is fine as long as the error token isn't used.
+        let src = "%%\nA: 'y';\nerror: 'x';".to_string();
+        let ast = parse(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            &src,
+        )
+        .unwrap();
+        assert!(ast.get_rule("error").is_some());
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let ast = parse(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            &"
+          %start A
+          %entry B C
+          %entry D
+          %%
+          A: B;
+          B: C;
+          C: D;
+          D: 'x';
+          ",
+        )
+        .unwrap();
+        assert_eq!(ast.entries, vec!["B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_duplicate_entry() {
+        match parse(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            &"
+          %start A
+          %entry B
+          %entry C B
+          %%
+          A: B;
+          B: C;
+          C: 'x';
+          ",
+        ) {
+            Ok(_) => panic!(),
+            Err(YaccParserError {
+                kind: YaccParserErrorKind::DuplicateEntryDeclaration,
+                line: 4,
+                ..
+            }) => (),
+            Err(e) => panic!("Incorrect error returned {}", e),
+        }
+    }
+
     #[test]
     fn test_multiple_avoid_insert() {
         let ast = parse(
@@ -206,19 +206,52 @@ impl<
     > LRNonStreamingLexerDef<LexemeT, StorageT>
 {
     /// Return an [LRNonStreamingLexer] for the `String` `s` that will lex relative to this
-    /// [LRNonStreamingLexerDef].
+    /// [LRNonStreamingLexerDef]. Input which no rule matches (or which matches a rule whose token
+    /// is not used by the parser) does not stop lexing: it is recorded as a [LexError] whose span
+    /// covers it, and lexing continues after it. Lexemes are not stored: they are created on
+    /// demand each time the lexer is iterated over.
     pub fn lexer<'lexer, 'input: 'lexer>(
         &'lexer self,
         s: &'input str,
     ) -> LRNonStreamingLexer<'lexer, 'input, LexemeT, StorageT> {
-        let mut lexemes = vec![];
-        let mut newlines = vec![];
-        let mut i = 0;
-        while i < s.len() {
-            let old_i = i;
+        let newlines = s
+            .char_indices()
+            .filter(|&(_, c)| c == '\n')
+            .map(|(j, _)| j + 1)
+            .collect();
+        LRNonStreamingLexer {
+            s,
+            lexemes: LexemesSource::Rules(&self.rules),
+            newlines,
+        }
+    }
+}
+
+/// An iterator which lexes its input on demand, one lexeme at a time.
+struct LazyLexemes<'lexer, 'input, LexemeT, StorageT> {
+    rules: &'lexer [Rule<StorageT>],
+    s: &'input str,
+    /// The byte index in `s` that the next lexeme starts at.
+    i: usize,
+    phantom: PhantomData<LexemeT>,
+}
+
+impl<
+        'lexer,
+        'input,
+        LexemeT: Lexeme<StorageT>,
+        StorageT: Copy + Eq + fmt::Debug + Hash + PrimInt + Unsigned,
+    > Iterator for LazyLexemes<'lexer, 'input, LexemeT, StorageT>
+{
+    type Item = Result<LexemeT, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let s = self.s;
+        while self.i < s.len() {
+            let old_i = self.i;
             let mut longest = 0; // Length of the longest match
             let mut longest_ridx = 0; // This is only valid iff longest != 0
-            for (ridx, r) in self.iter_rules().enumerate() {
+            for (ridx, r) in self.rules.iter().enumerate() {
                 if let Some(m) = r.re.find(&s[old_i..]) {
                     let len = m.end();
                     // Note that by using ">", we implicitly prefer an earlier over a later rule, if
@@ -230,45 +263,55 @@ impl<
                 }
             }
             if longest > 0 {
-                newlines.extend(
-                    s[old_i..old_i + longest]
-                        .chars()
-                        .enumerate()
-                        .filter(|&(_, c)| c == '\n')
-                        .map(|(j, _)| old_i + j + 1),
-                );
-                let r = self.get_rule(longest_ridx).unwrap();
+                self.i += longest;
+                let r = &self.rules[longest_ridx];
                 if r.name.is_some() {
-                    match r.tok_id {
-                        Some(tok_id) => {
-                            lexemes.push(Ok(Lexeme::new(tok_id, old_i, longest)));
-                        }
-                        None => {
-                            lexemes.push(Err(LexError::new(Span::new(old_i, old_i))));
-                            break;
-                        }
-                    }
+                    return Some(match r.tok_id {
+                        Some(tok_id) => Ok(Lexeme::new(tok_id, old_i, longest)),
+                        None => Err(LexError::new(Span::new(old_i, old_i + longest))),
+                    });
                 }
-                i += longest;
             } else {
-                lexemes.push(Err(LexError::new(Span::new(old_i, old_i))));
-                break;
+                // No rule matches, so skip to the next character at which one does, recording the
+                // skipped input as a single error.
+                self.i = next_char(s, self.i);
+                while self.i < s.len()
+                    && !self
+                        .rules
+                        .iter()
+                        .any(|r| matches!(r.re.find(&s[self.i..]), Some(m) if m.end() > 0))
+                {
+                    self.i = next_char(s, self.i);
+                }
+                return Some(Err(LexError::new(Span::new(old_i, self.i))));
             }
         }
-        LRNonStreamingLexer::new(s, lexemes, newlines)
+        None
     }
 }
 
+/// Return the byte index of the character after the one starting at byte index `i` in `s`.
+fn next_char(s: &str, i: usize) -> usize {
+    i + s[i..].chars().next().unwrap().len_utf8()
+}
+
+/// Where an [LRNonStreamingLexer]'s lexemes come from.
+enum LexemesSource<'lexer, LexemeT, StorageT> {
+    /// Lexemes created in advance (see [LRNonStreamingLexer::new]).
+    Lexed(Vec<Result<LexemeT, LexError>>),
+    /// Lexemes are created from these rules each time they are iterated over.
+    Rules(&'lexer [Rule<StorageT>]),
+}
+
 /// An `LRNonStreamingLexer` holds a reference to a string and can lex it into [lrpar::Lexeme]s.
 /// Although the struct is tied to a single string, no guarantees are made about whether the
 /// lexemes are cached or not.
 pub struct LRNonStreamingLexer<'lexer, 'input: 'lexer, LexemeT, StorageT: fmt::Debug> {
     s: &'input str,
-    lexemes: Vec<Result<LexemeT, LexError>>,
+    lexemes: LexemesSource<'lexer, LexemeT, StorageT>,
     /// A sorted list of the byte index of the start of the following line. i.e. for the input
     /// string `" a\nb\n  c d"` this will contain `[3, 5]`.
     newlines: Vec<usize>,
-    phantom: PhantomData<(&'lexer (), StorageT)>,
 }
 
 impl<
@@ -292,9 +335,8 @@ impl<
     ) -> LRNonStreamingLexer<'lexer, 'input, LexemeT, StorageT> {
         LRNonStreamingLexer {
             s,
-            lexemes,
+            lexemes: LexemesSource::Lexed(lexemes),
             newlines,
-            phantom: PhantomData,
         }
     }
 }
@@ -307,7 +349,15 @@ impl<
     > Lexer<LexemeT, StorageT> for LRNonStreamingLexer<'lexer, 'input, LexemeT, StorageT>
 {
     fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = Result<LexemeT, LexError>> + 'a> {
-        Box::new(self.lexemes.iter().cloned())
+        match self.lexemes {
+            LexemesSource::Lexed(ref lexemes) => Box::new(lexemes.iter().cloned()),
+            LexemesSource::Rules(rules) => Box::new(LazyLexemes {
+                rules,
+                s: self.s,
+                i: 0,
+                phantom: PhantomData,
+            }),
+        }
     }
 }
 
@@ -389,6 +439,30 @@ impl<
             lc_char(self, span.end(), self.s),
         )
     }
+
+    fn iter_from<'a>(
+        &'a self,
+        start: usize,
+    ) -> Box<dyn Iterator<Item = Result<LexemeT, LexError>> + 'a>
+    where
+        LexemeT: 'a,
+    {
+        match self.lexemes {
+            LexemesSource::Lexed(ref lexemes) => {
+                let i = lexemes.partition_point(|r| match r {
+                    Ok(l) => l.span().start() < start,
+                    Err(e) => e.span().start() < start,
+                });
+                Box::new(lexemes[i..].iter().cloned())
+            }
+            LexemesSource::Rules(rules) => Box::new(LazyLexemes {
+                rules,
+                s: self.s,
+                i: start,
+                phantom: PhantomData,
+            }),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -439,13 +513,77 @@ mod test {
         match lexerdef.lexer(&"abc").iter().next().unwrap() {
             Ok(_) => panic!("Invalid input lexed"),
             Err(e) => {
-                if e.span().start() != 0 || e.span().end() != 0 {
+                if e.span().start() != 0 || e.span().end() != 3 {
                     panic!("Incorrect span returned {:?}", e.span());
                 }
             }
         };
     }
 
+    #[test]
+    fn test_continue_after_error() {
+        let src = "%%
+[0-9]+ 'int'
+[ \\n] ;"
+            .to_string();
+        let mut lexerdef = LRNonStreamingLexerDef::<DefaultLexeme<u8>, u8>::from_str(&src).unwrap();
+        let mut map = HashMap::new();
+        map.insert("int", 0);
+        assert_eq!(lexerdef.set_rule_ids(&map), (None, None));
+
+        let lexer = lexerdef.lexer("1 §§\n2 a");
+        let lexemes = lexer
+            .iter()
+            .map(|x| x.map(|l| l.span()).map_err(|e| e.span()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lexemes,
+            vec![
+                Ok(Span::new(0, 1)),
+                Err(Span::new(2, 6)),
+                Ok(Span::new(7, 8)),
+                Err(Span::new(9, 10))
+            ]
+        );
+        assert_eq!(lexer.span_str(Span::new(2, 6)), "§§");
+        assert_eq!(lexer.line_col(Span::new(7, 8)), ((2, 1), (2, 2)));
+    }
+
+    #[test]
+    fn test_iter_from() {
+        let src = "%%
+[0-9]+ 'int'
+[ \\n] ;"
+            .to_string();
+        let mut lexerdef = LRNonStreamingLexerDef::<DefaultLexeme<u8>, u8>::from_str(&src).unwrap();
+        let mut map = HashMap::new();
+        map.insert("int", 0);
+        assert_eq!(lexerdef.set_rule_ids(&map), (None, None));
+
+        let input = "1 §§\n23 4";
+        let spans = |lexer: &dyn NonStreamingLexer<DefaultLexeme<u8>, u8>, start| {
+            lexer
+                .iter_from(start)
+                .map(|x| x.map(|l| l.span()).map_err(|e| e.span()))
+                .collect::<Vec<_>>()
+        };
+        let lexer = lexerdef.lexer(input);
+        let lexed = LRNonStreamingLexer::new(input, lexer.iter().collect(), vec![7]);
+        for lexer in [&lexer as &dyn NonStreamingLexer<_, _>, &lexed] {
+            assert_eq!(spans(lexer, 0).len(), 4);
+            assert_eq!(
+                spans(lexer, 1),
+                vec![
+                    Err(Span::new(2, 6)),
+                    Ok(Span::new(7, 9)),
+                    Ok(Span::new(10, 11))
+                ]
+            );
+            assert_eq!(spans(lexer, 9), vec![Ok(Span::new(10, 11))]);
+            assert_eq!(spans(lexer, 11), vec![]);
+        }
+    }
+
     #[test]
     fn test_longest_match() {
         let src = "%%
@@ -635,7 +773,7 @@ if 'IF'
         match lexerdef.lexer(&" a ").iter().next().unwrap() {
             Ok(_) => panic!("Invalid input lexed"),
             Err(e) => {
-                if e.span().start() != 1 || e.span().end() != 1 {
+                if e.span().start() != 1 || e.span().end() != 2 {
                     panic!("Incorrect span returned {:?}", e.span());
                 }
             }
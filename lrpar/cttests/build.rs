@@ -1,15 +1,22 @@
 use cfgrammar::yacc::{YaccKind, YaccOriginalActionKind};
 use glob::glob;
 use lrlex::{CTLexerBuilder, DefaultLexeme};
-use lrpar::CTParserBuilder;
+use lrpar::{CTParserBuilder, RecoveryBudget, RecoveryKind, TablesKind};
+use lrtable::Minimiser;
 use std::{env, fs, path::PathBuf};
 use yaml_rust::YamlLoader;
 
 // Compiles the `*.test` files within `src`. Test files are written in Yaml syntax and have 4
 // mandatory sections: name (describing what the test does), yacckind (defining the grammar type
-// used), grammar (the grammar rules), and lexer (the lexing rules). The tests are compiled into
-// two modules `<filename>_y` and `<filename>_l`, which we can then import into src/lib.rs and
-// write tests for.
+// used), grammar (the grammar rules), and lexer (the lexing rules). An optional tables_kind section
+// (`Bincode` or `Static`, the default) specifies how the parse tables are embedded and an optional
+// minimiser section (`Canonical`, `IELR`, `LALR`, or `Pager`) specifies how the stategraph is
+// built. An optional glr section (`true` or `false`) specifies whether a GLR parser is generated.
+// An optional recoverer section (`CPCTPlus`, `Panic`, or `None`) specifies the error recovery
+// algorithm, and an optional recovery_budget section (`Nodes(n)`) a deterministic error recovery
+// budget. An optional error_messages section contains erroneous example inputs and their error
+// messages (see `CTParserBuilder::error_messages_path`). The tests are compiled into two modules
+// `<filename>_y` and `<filename>_l`, which we can then import into src/lib.rs and write tests for.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let out_dir = env::var("OUT_DIR").unwrap();
     for entry in glob("src/*.test")? {
@@ -33,6 +40,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
                 s => panic!("YaccKind '{}' not supported", s),
             };
+            let tables_kind = match docs[0]["tables_kind"].as_str() {
+                Some("Bincode") => TablesKind::Bincode,
+                None | Some("Static") => TablesKind::Static,
+                Some(s) => panic!("TablesKind '{}' not supported", s),
+            };
+            let minimiser = match docs[0]["minimiser"].as_str() {
+                None | Some("Pager") => Minimiser::Pager,
+                Some("Canonical") => Minimiser::Canonical,
+                Some("IELR") => Minimiser::IELR,
+                Some("LALR") => Minimiser::LALR,
+                Some(s) => panic!("Minimiser '{}' not supported", s),
+            };
+            let glr = docs[0]["glr"].as_bool().unwrap_or(false);
+            let recoverer = match docs[0]["recoverer"].as_str() {
+                None | Some("CPCTPlus") => RecoveryKind::CPCTPlus,
+                Some("Panic") => RecoveryKind::Panic,
+                Some("None") => RecoveryKind::None,
+                Some(s) => panic!("RecoveryKind '{}' not supported", s),
+            };
+            let recovery_budget = docs[0]["recovery_budget"].as_str().map(|s| {
+                match s
+                    .strip_prefix("Nodes(")
+                    .and_then(|s| s.strip_suffix(')'))
+                    .and_then(|s| s.parse::<usize>().ok())
+                {
+                    Some(n) => RecoveryBudget::Nodes(n),
+                    None => panic!("RecoveryBudget '{}' not supported", s),
+                }
+            });
 
             // The code below, in essence, replicates lrlex and lrpar's internal / undocumented
             // filename conventions. If those change, this code will also have to change.
@@ -45,13 +81,33 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut pl = PathBuf::from(&out_dir);
             pl.push(format!("{}.l.rs", base));
             fs::write(&pl, &lex).unwrap();
+            let pm = docs[0]["error_messages"].as_str().map(|msgs| {
+                let mut pm = PathBuf::from(&out_dir);
+                pm.push(format!("{}.msgs", base));
+                fs::write(&pm, msgs).unwrap();
+                pm
+            });
 
             // Build parser and lexer
             let mut outp = PathBuf::from(&out_dir);
             outp.push(format!("{}.y.rs", base));
             outp.set_extension("rs");
-            let cp = CTParserBuilder::<DefaultLexeme<u32>, _>::new()
+            let mut cp = CTParserBuilder::<DefaultLexeme<u32>, _>::new();
+            if let Some(rb) = recovery_budget {
+                cp = cp.recovery_budget(rb);
+            }
+            if let Some(pm) = pm {
+                cp = cp.error_messages_path(pm);
+            }
+            // Many of the test grammars deliberately contain conflicts: the resulting warnings are
+            // tested directly, so there's no point in Cargo showing them.
+            let cp = cp
+                .show_warnings(false)
                 .yacckind(yacckind)
+                .tables_kind(tables_kind)
+                .minimiser(minimiser)
+                .glr(glr)
+                .recoverer(recoverer)
                 .grammar_path(pg.to_str().unwrap())
                 .output_path(&outp)
                 .build()?;
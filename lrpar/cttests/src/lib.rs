@@ -11,9 +11,27 @@ lrpar_mod!("calc_multitypes.y");
 lrlex_mod!("calc_actiontype.l");
 lrpar_mod!("calc_actiontype.y");
 
+lrlex_mod!("calc_static.l");
+lrpar_mod!("calc_static.y");
+
 lrlex_mod!("calc_noactions.l");
 lrpar_mod!("calc_noactions.y");
 
+lrlex_mod!("entry.l");
+lrpar_mod!("entry.y");
+
+lrlex_mod!("error_messages.l");
+lrpar_mod!("error_messages.y");
+
+lrlex_mod!("error_token.l");
+lrpar_mod!("error_token.y");
+
+lrlex_mod!("glr.l");
+lrpar_mod!("glr.y");
+
+lrlex_mod!("glr_merge.l");
+lrpar_mod!("glr_merge.y");
+
 lrlex_mod!("expect.l");
 lrpar_mod!("expect.y");
 
@@ -29,9 +47,15 @@ lrpar_mod!("parseparam.y");
 lrlex_mod!("passthrough.l");
 lrpar_mod!("passthrough.y");
 
+lrlex_mod!("recovery_budget.l");
+lrpar_mod!("recovery_budget.y");
+
 lrlex_mod!("span.l");
 lrpar_mod!("span.y");
 
+lrlex_mod!("warnings_static.l");
+lrpar_mod!("warnings_static.y");
+
 #[test]
 fn multitypes() {
     let lexerdef = multitypes_l::lexerdef();
@@ -80,6 +104,15 @@ fn test_error_recovery_and_actions() {
         LexParseError::ParseError(..) => (),
         _ => unreachable!(),
     }
+    assert_eq!(
+        errs[0]
+            .pp(&lexer, &calc_actiontype_y::token_epp)
+            .lines()
+            .next(),
+        Some(
+            "Parsing error at line 1 column 3. Expected one of `INT`, `(`. Repair sequences found:"
+        )
+    );
 
     let lexer = lexerdef.lexer("2+3)");
     let (r, errs) = calc_actiontype_y::parse(&lexer);
@@ -89,6 +122,11 @@ fn test_error_recovery_and_actions() {
         LexParseError::ParseError(..) => (),
         _ => unreachable!(),
     }
+    assert_eq!(
+        errs[0].pp(&lexer, &calc_actiontype_y::token_epp),
+        "Parsing error at line 1 column 4. Expected one of `+`, end of input. Repair sequences \
+         found:\n   1: Delete )"
+    );
 
     let lexer = lexerdef.lexer("2+3+18446744073709551616");
     let (r, errs) = calc_actiontype_y::parse(&lexer);
@@ -96,6 +134,29 @@ fn test_error_recovery_and_actions() {
     assert!(errs.is_empty());
 }
 
+#[test]
+fn test_recovery_budget() {
+    use lrpar::LexParseError;
+
+    let lexerdef = recovery_budget_l::lexerdef();
+    let repairs = || {
+        let lexer = lexerdef.lexer("(2++3");
+        let (r, errs) = recovery_budget_y::parse(&lexer);
+        assert!(r.is_some());
+        errs.into_iter()
+            .map(|e| match e {
+                LexParseError::ParseError(e) => e.repairs().to_owned(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let rprs = repairs();
+    assert!(!rprs.is_empty());
+    for _ in 0..10 {
+        assert_eq!(repairs(), rprs);
+    }
+}
+
 #[test]
 fn test_calc_multitypes() {
     let lexerdef = calc_multitypes_l::lexerdef();
@@ -108,6 +169,167 @@ fn test_calc_multitypes() {
     assert_eq!(res, Some(Ok(3)));
 }
 
+#[test]
+fn test_static_tables() {
+    let lexerdef = calc_static_l::lexerdef();
+    let lexer = lexerdef.lexer("1+2*3");
+    let (res, errs) = calc_static_y::parse(&lexer);
+    assert_eq!(res, Some(Ok(7)));
+    assert!(errs.is_empty());
+
+    let lexer = lexerdef.lexer("1++2");
+    let (res, errs) = calc_static_y::parse(&lexer);
+    assert_eq!(res, Some(Ok(3)));
+    assert_eq!(errs.len(), 1);
+
+    let (grm, _) = calc_static_y::grm_stable();
+    assert!(grm.avoid_insert(grm.token_idx("INT").unwrap()));
+}
+
+#[test]
+fn test_entry() {
+    let lexerdef = entry_l::lexerdef();
+    let lexer = lexerdef.lexer("1 + 2; 3;");
+    let (res, errs) = entry_y::parse(&lexer);
+    assert_eq!(res, Some(vec![3, 3]));
+    assert!(errs.is_empty());
+
+    let lexer = lexerdef.lexer("1 + 2;");
+    let (res, errs) = entry_y::parse_Stmt(&lexer);
+    assert_eq!(res, Some(3));
+    assert!(errs.is_empty());
+
+    let lexer = lexerdef.lexer("1 + 2 + 3");
+    let (res, errs) = entry_y::parse_Expr(&lexer);
+    assert_eq!(res, Some(6));
+    assert!(errs.is_empty());
+
+    let lexer = lexerdef.lexer("1 + 2;");
+    let (_, errs) = entry_y::parse_Expr(&lexer);
+    assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_error_messages() {
+    use lrpar::LexParseError;
+
+    let lexerdef = error_messages_l::lexerdef();
+    let msgs = |input| {
+        let lexer = lexerdef.lexer(input);
+        let (_, errs) = error_messages_y::parse(&lexer);
+        errs.iter()
+            .map(|e| match e {
+                LexParseError::ParseError(e) => error_messages_y::error_message(e),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        msgs("f(a b); g(c);"),
+        vec![Some("Missing ',' between arguments.")]
+    );
+    assert_eq!(msgs("f() g();"), vec![Some("Missing ';' after call.")]);
+    // An example's message also applies to other lookaheads in the same state.
+    assert_eq!(msgs("f("), vec![Some("Missing ')' in call.")]);
+    assert_eq!(msgs("f(,);"), vec![None]);
+}
+
+#[test]
+fn test_error_token() {
+    let lexerdef = error_token_l::lexerdef();
+    let lexer = lexerdef.lexer("1 + 2; 3 4 + 5; 6;");
+    let (res, errs) = error_token_y::parse(&lexer);
+    assert_eq!(res, Some(vec![Ok(3), Err(Span::new(7, 14)), Ok(6)]));
+    assert_eq!(errs.len(), 1);
+
+    let lexer = lexerdef.lexer("1 + 2; 3 4");
+    let (res, errs) = error_token_y::parse(&lexer);
+    assert_eq!(res, None);
+    assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_glr() {
+    let lexerdef = glr_l::lexerdef();
+    let lexer = lexerdef.lexer("3 - 2 - 1");
+    let (sppf, errs) = glr_y::parse(&lexer);
+    assert!(errs.is_empty());
+    let sppf = sppf.unwrap();
+    assert!(sppf.is_ambiguous());
+    let (grm, _) = glr_y::grm_stable();
+    let mut trees = sppf
+        .trees()
+        .iter()
+        .map(|t| t.pp(grm, "3 - 2 - 1"))
+        .collect::<Vec<_>>();
+    trees.sort();
+    assert_eq!(
+        trees,
+        vec![
+            "Expr
+ Expr
+  Expr
+   INT 3
+  - -
+  Expr
+   INT 2
+ - -
+ Expr
+  INT 1
+",
+            "Expr
+ Expr
+  INT 3
+ - -
+ Expr
+  Expr
+   INT 2
+  - -
+  Expr
+   INT 1
+"
+        ]
+    );
+
+    let lexer = lexerdef.lexer("3 - - 1");
+    let (sppf, errs) = glr_y::parse(&lexer);
+    assert!(sppf.is_none());
+    assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_glr_merge() {
+    let lexerdef = glr_merge_l::lexerdef();
+    let lexer = lexerdef.lexer("3 - 2 - 1");
+    let (r, errs) = glr_merge_y::parse(&lexer);
+    assert!(errs.is_empty());
+    // Both ways of bracketing the input are merged, with each integer parsed as `Thousands`.
+    let mut r = r.unwrap();
+    r.sort();
+    assert_eq!(r, vec![0, 2000]);
+
+    let lexer = lexerdef.lexer("3 - - 1");
+    let (r, errs) = glr_merge_y::parse(&lexer);
+    assert!(r.is_none());
+    assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn test_grm_stable_shared() {
+    let (grm1, stable1) = calc_multitypes_y::grm_stable();
+    let (grm2, stable2) = std::thread::spawn(calc_multitypes_y::grm_stable)
+        .join()
+        .unwrap();
+    assert!(std::ptr::eq(grm1, grm2));
+    assert!(std::ptr::eq(stable1, stable2));
+
+    let lexerdef = calc_multitypes_l::lexerdef();
+    let lexer = lexerdef.lexer("2*3");
+    let (res, _errs) = calc_multitypes_y::parse(&lexer);
+    assert_eq!(res, Some(Ok(6)));
+    assert!(std::ptr::eq(calc_multitypes_y::grm_stable().0, grm1));
+}
+
 #[test]
 fn test_input_lifetime() {
     // This test only exists to make sure that this code compiles: there's no need for us to
@@ -257,3 +479,17 @@ fn test_passthrough() {
 fn test_expect() {
     // This test merely needs to compile in order to be successful.
 }
+
+#[test]
+fn test_warnings_static() {
+    let (grm, stable) = warnings_static_y::grm_stable();
+    let ws = stable
+        .warnings()
+        .iter()
+        .map(|w| w.pp(grm))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ws,
+        vec!["Production B: \"a\" is never reduced due to conflicts"]
+    );
+}
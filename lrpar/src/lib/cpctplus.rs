@@ -1,24 +1,27 @@
 use std::{
     cmp::Ordering,
-    collections::HashSet,
     fmt::Debug,
     hash::{Hash, Hasher},
-    time::Instant,
 };
 
 use cactus::Cactus;
 use cfgrammar::TIdx;
+use indexmap::IndexSet;
 use lrtable::{Action, StIdx};
 use num_traits::{AsPrimitive, PrimInt, Unsigned};
 
 use super::{
     dijkstra::dijkstra,
-    parser::{AStackType, ParseRepair, Parser, Recoverer},
+    parser::{AStackType, ParseRepair, Parser, Recoverer, RecoveryAllowance},
     Lexeme, Span,
 };
 
 const PARSE_AT_LEAST: usize = 3; // N in Corchuelo et al.
 const TRY_PARSE_AT_MOST: usize = 250;
+/// How many lexemes beyond the point of an error does the recoverer examine? When parsing
+/// incrementally, recovery must wait until this many lexemes are available (or the input ends) if
+/// its results are to be the same as when parsing complete input.
+pub(super) const LOOKAHEAD: usize = TRY_PARSE_AT_MOST;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 enum Repair<StorageT> {
@@ -39,7 +42,7 @@ enum RepairMerge<StorageT> {
 
 #[derive(Clone, Debug)]
 struct PathFNode<StorageT> {
-    pstack: Cactus<StIdx>,
+    pstack: Cactus<StIdx<StorageT>>,
     laidx: usize,
     repairs: Cactus<RepairMerge<StorageT>>,
     cf: u16,
@@ -55,7 +58,7 @@ impl<StorageT: PrimInt + Unsigned> PathFNode<StorageT> {
     }
 }
 
-impl<StorageT: PrimInt + Unsigned> Hash for PathFNode<StorageT> {
+impl<StorageT: Hash + PrimInt + Unsigned> Hash for PathFNode<StorageT> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.pstack.hash(state);
         self.laidx.hash(state);
@@ -141,10 +144,10 @@ where
 {
     fn recover(
         &self,
-        finish_by: Instant,
+        allowance: &RecoveryAllowance,
         parser: &Parser<LexemeT, StorageT, ActionT, ParamT>,
         in_laidx: usize,
-        in_pstack: &mut Vec<StIdx>,
+        in_pstack: &mut Vec<StIdx<StorageT>>,
         astack: &mut Vec<AStackType<LexemeT, ActionT>>,
         spans: &mut Vec<Span>,
     ) -> (usize, Vec<Vec<ParseRepair<LexemeT, StorageT>>>) {
@@ -180,7 +183,7 @@ where
             |explore_all, n, nbrs| {
                 // Calculate n's neighbours.
 
-                if Instant::now() >= finish_by {
+                if !allowance.explore_node() {
                     return false;
                 }
 
@@ -243,7 +246,7 @@ where
         }
 
         let full_rprs = self.collect_repairs(in_laidx, astar_cnds);
-        let mut rnk_rprs = rank_cnds(parser, finish_by, in_laidx, in_pstack, full_rprs);
+        let mut rnk_rprs = rank_cnds(parser, allowance, in_laidx, in_pstack, full_rprs);
         if rnk_rprs.is_empty() {
             return (in_laidx, vec![]);
         }
@@ -276,7 +279,9 @@ where
     fn insert(&self, n: &PathFNode<StorageT>, nbrs: &mut Vec<(u16, PathFNode<StorageT>)>) {
         let laidx = n.laidx;
         for tidx in self.parser.stable.state_actions(*n.pstack.val().unwrap()) {
-            if tidx == self.parser.grm.eof_token_idx() {
+            if tidx == self.parser.grm.eof_token_idx()
+                || Some(tidx) == self.parser.grm.error_token_idx()
+            {
                 continue;
             }
 
@@ -316,7 +321,13 @@ where
         }
 
         let la_tidx = self.parser.next_tidx(n.laidx);
-        let cost = (self.parser.token_cost)(la_tidx);
+        // Error lexemes (see `parser::error_lexeme`) have no token, and thus no cost, in the
+        // grammar: deleting them costs the minimum.
+        let cost = if la_tidx < self.parser.grm.tokens_len() {
+            (self.parser.token_cost)(la_tidx)
+        } else {
+            1
+        };
         let nn = PathFNode {
             pstack: n.pstack.clone(),
             laidx: n.laidx + 1,
@@ -450,7 +461,7 @@ where
 
 /// Apply the `repairs` to `pstack` starting at position `laidx`: return the resulting parse
 /// distance and a new pstack.
-fn apply_repairs<
+pub(super) fn apply_repairs<
     'a,
     LexemeT: Lexeme<StorageT>,
     StorageT: 'static + Debug + Hash + PrimInt + Unsigned,
@@ -459,7 +470,7 @@ fn apply_repairs<
 >(
     parser: &Parser<LexemeT, StorageT, ActionT, ParamT>,
     mut laidx: usize,
-    pstack: &mut Vec<StIdx>,
+    pstack: &mut Vec<StIdx<StorageT>>,
     astack: &mut Option<&mut Vec<AStackType<LexemeT, ActionT>>>,
     spans: &mut Option<&mut Vec<Span>>,
     repairs: &[ParseRepair<LexemeT, StorageT>],
@@ -512,11 +523,12 @@ fn simplify_repairs<
         }
     }
 
-    // Use a HashSet as a quick way of deduplicating repair sequences: occasionally we can end up
+    // Use an IndexSet as a quick way of deduplicating repair sequences: occasionally we can end up
     // with hundreds of thousands (!), and we don't have a sensible ordering on ParseRepair to make
-    // it plausible to do a sort and dedup.
-    let mut hs: HashSet<Vec<ParseRepair<LexemeT, StorageT>>> = all_rprs.drain(..).collect();
-    all_rprs.extend(hs.drain());
+    // it plausible to do a sort and dedup. Unlike a HashSet, an IndexSet preserves the order in
+    // which repair sequences were found, so the same input always leads to the same repairs.
+    let mut hs: IndexSet<Vec<ParseRepair<LexemeT, StorageT>>> = all_rprs.drain(..).collect();
+    all_rprs.extend(hs.drain(..));
 
     // Sort repair sequences:
     //   1) by whether they contain Inserts that are %insert_avoid
@@ -531,7 +543,7 @@ fn simplify_repairs<
         }
         false
     };
-    all_rprs.sort_unstable_by(|x, y| {
+    all_rprs.sort_by(|x, y| {
         let x_cai = contains_avoid_insert(x);
         let y_cai = contains_avoid_insert(y);
         if x_cai && !y_cai {
@@ -548,7 +560,7 @@ fn simplify_repairs<
 /// highest to lowest) by the distance they allow parsing to continue without error. If two or more
 /// `ParseRepair`s allow the same distance of parsing, then the `ParseRepair` which requires
 /// repairs over the shortest distance is preferred. Amongst `ParseRepair`s of the same rank, the
-/// ordering is non-deterministic.
+/// order in which they were found is preserved.
 fn rank_cnds<
     'a,
     LexemeT: Lexeme<StorageT>,
@@ -557,9 +569,9 @@ fn rank_cnds<
     ParamT: Copy,
 >(
     parser: &Parser<LexemeT, StorageT, ActionT, ParamT>,
-    finish_by: Instant,
+    allowance: &RecoveryAllowance,
     in_laidx: usize,
-    in_pstack: &[StIdx],
+    in_pstack: &[StIdx<StorageT>],
     in_cnds: Vec<Vec<Vec<ParseRepair<LexemeT, StorageT>>>>,
 ) -> Vec<Vec<ParseRepair<LexemeT, StorageT>>>
 where
@@ -568,7 +580,7 @@ where
     let mut cnds = Vec::new();
     let mut furthest = 0;
     for rpr_seqs in in_cnds {
-        if Instant::now() >= finish_by {
+        if allowance.timed_out() {
             return vec![];
         }
         let mut pstack = in_pstack.to_owned();
@@ -626,7 +638,10 @@ mod test {
     use num_traits::{AsPrimitive, PrimInt, ToPrimitive, Unsigned};
 
     use crate::{
-        parser::{test::do_parse, ParseRepair, RecoveryKind},
+        parser::{
+            test::{do_parse, do_parse_with_budget},
+            ParseRepair, RecoveryBudget, RecoveryKind,
+        },
         LexParseError, Lexeme,
     };
 
@@ -770,6 +785,72 @@ E : 'N'
         check_all_repairs(&grm, &errs[1], &["Insert \")\""]);
     }
 
+    #[test]
+    fn test_recovery_budget() {
+        let lexs = "\\( '('
+                    \\) ')'
+                    \\+ '+'
+                    n 'N'";
+        let grms = "%start E
+%%
+E : 'N'
+  | E '+' 'N'
+  | '(' E ')'
+  ;
+";
+
+        // With a node budget, the same input must always lead to the same repairs, in the same
+        // order.
+        let all_repairs = || {
+            let (grm, pr) = do_parse_with_budget(
+                RecoveryKind::CPCTPlus,
+                RecoveryBudget::Nodes(10000),
+                lexs,
+                grms,
+                "(nn",
+            );
+            let (_, errs) = pr.unwrap_err();
+            assert_eq!(errs.len(), 1);
+            check_all_repairs(
+                &grm,
+                &errs[0],
+                &[
+                    "Insert \")\", Insert \"+\"",
+                    "Insert \")\", Delete",
+                    "Insert \"+\", Shift, Insert \")\"",
+                ],
+            );
+            match &errs[0] {
+                LexParseError::ParseError(e) => e
+                    .repairs()
+                    .iter()
+                    .map(|rprs| pp_repairs(&grm, rprs))
+                    .collect::<Vec<_>>(),
+                _ => unreachable!(),
+            }
+        };
+        let rprs = all_repairs();
+        for _ in 0..10 {
+            assert_eq!(all_repairs(), rprs);
+        }
+
+        // An exhausted budget means that no repairs can be found.
+        let (_, pr) = do_parse_with_budget(
+            RecoveryKind::CPCTPlus,
+            RecoveryBudget::Nodes(0),
+            lexs,
+            grms,
+            "(nn",
+        );
+        let (pt, errs) = pr.unwrap_err();
+        assert!(pt.is_none());
+        assert_eq!(errs.len(), 1);
+        match &errs[0] {
+            LexParseError::ParseError(e) => assert!(e.repairs().is_empty()),
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn test_merge() {
         let lexs = "a 'a'
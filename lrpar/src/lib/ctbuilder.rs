@@ -7,28 +7,38 @@ use std::{
     convert::AsRef,
     env::{current_dir, var},
     error::Error,
-    fmt::{self, Debug, Write as fmtWrite},
+    fmt::{self, Debug},
     fs::{self, create_dir_all, read_to_string, File},
     hash::Hash,
-    io::{self, Write},
+    io::Write,
     marker::PhantomData,
     path::{Path, PathBuf},
     sync::Mutex,
 };
+#[cfg(feature = "bincode")]
+use std::{fmt::Write as fmtWrite, io};
 
+#[cfg(feature = "bincode")]
 use bincode::{deserialize, serialize_into};
 use cfgrammar::{
-    yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind},
+    yacc::{Precedence, YaccGrammar, YaccKind, YaccOriginalActionKind},
     RIdx, Symbol,
 };
 use filetime::FileTime;
 use lazy_static::lazy_static;
-use lrtable::{from_yacc, statetable::Conflicts, Minimiser, StateGraph, StateTable};
+use lrtable::{from_yacc, statetable::Conflicts, Minimiser, Report, StateGraph};
 use num_traits::{AsPrimitive, PrimInt, Unsigned};
 use regex::Regex;
+#[cfg(feature = "bincode")]
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{Lexeme, RecoveryKind};
+use crate::{error_messages, ErrorMessages, Lexeme, RecoveryBudget, RecoveryKind};
+
+/// Generated files refer to [StIdx], [StateTable], [StateTableWarning], [StaticStateTable], and
+/// [StaticUInts] through these re-exports so that users' crates do not need to depend on
+/// `lrtable` directly.
+#[doc(hidden)]
+pub use lrtable::{StIdx, StateTable, StateTableWarning, StaticStateTable, StaticUInts};
 
 const ACTION_PREFIX: &str = "__gt_";
 const GLOBAL_PREFIX: &str = "__GT_";
@@ -38,7 +48,9 @@ const ACTIONS_KIND_HIDDEN: &str = "__GtActionsKindHidden";
 
 const RUST_FILE_EXT: &str = "rs";
 
+#[cfg(feature = "bincode")]
 const GRM_CONST_NAME: &str = "__GRM_DATA";
+#[cfg(feature = "bincode")]
 const STABLE_CONST_NAME: &str = "__STABLE_DATA";
 
 lazy_static! {
@@ -46,29 +58,49 @@ lazy_static! {
     static ref GENERATED_PATHS: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
 }
 
-struct CTConflictsError<StorageT: Eq + Hash> {
+/// The bounds a `StorageT` must satisfy so that grammars and state tables using it can be
+/// serialised for [TablesKind::Bincode]. Without the `bincode` feature, every type satisfies
+/// them.
+#[cfg(feature = "bincode")]
+pub trait SerializeStorageT: Serialize {}
+#[cfg(feature = "bincode")]
+impl<T: Serialize> SerializeStorageT for T {}
+#[cfg(not(feature = "bincode"))]
+pub trait SerializeStorageT {}
+#[cfg(not(feature = "bincode"))]
+impl<T> SerializeStorageT for T {}
+
+struct CTConflictsError<StorageT: 'static + Eq + Hash> {
+    grm: YaccGrammar<StorageT>,
+    sgraph: StateGraph<StorageT>,
     stable: StateTable<StorageT>,
 }
 
 impl<StorageT> fmt::Display for CTConflictsError<StorageT>
 where
-    StorageT: 'static + Debug + Hash + PrimInt + Serialize + Unsigned,
+    StorageT: 'static + Debug + Hash + PrimInt + SerializeStorageT + Unsigned,
     usize: AsPrimitive<StorageT>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let conflicts = self.stable.conflicts().unwrap();
-        write!(
+        writeln!(
             f,
             "CTConflictsError{{{} Reduce/Reduce, {} Shift/Reduce}}",
             conflicts.rr_len(),
             conflicts.sr_len()
+        )?;
+        write!(
+            f,
+            "{}{}",
+            conflicts.pp_sr_counterexamples(&self.grm, &self.sgraph),
+            conflicts.pp_rr_counterexamples(&self.grm, &self.sgraph)
         )
     }
 }
 
 impl<StorageT> fmt::Debug for CTConflictsError<StorageT>
 where
-    StorageT: 'static + Debug + Hash + PrimInt + Serialize + Unsigned,
+    StorageT: 'static + Debug + Hash + PrimInt + SerializeStorageT + Unsigned,
     usize: AsPrimitive<StorageT>,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -84,7 +116,7 @@ where
 
 impl<StorageT> Error for CTConflictsError<StorageT>
 where
-    StorageT: 'static + Debug + Hash + PrimInt + Serialize + Unsigned,
+    StorageT: 'static + Debug + Hash + PrimInt + SerializeStorageT + Unsigned,
     usize: AsPrimitive<StorageT>,
 {
 }
@@ -119,29 +151,51 @@ impl Visibility {
     }
 }
 
+/// Specify how the grammar and state table are embedded in the module generated by
+/// `CTParserBuilder`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TablesKind {
+    /// Serialise the grammar and state table with `bincode` into byte arrays, which are
+    /// deserialised the first time they are needed. Requires the `bincode` feature.
+    #[cfg(feature = "bincode")]
+    Bincode,
+    /// Emit the grammar and state table as `static`s whose contents are `static` Rust arrays. No
+    /// deserialisation or other processing (and thus no `bincode` or `serde`) is needed at
+    /// run-time: the `YaccGrammar` and `StateTable` borrow the arrays directly.
+    Static,
+}
+
 /// A `CTParserBuilder` allows one to specify the criteria for building a statically generated
-/// parser.
+/// parser. `StorageT` is used to store grammar and state indexes: if the grammar's state table has
+/// more states than `StorageT` can represent, building the parser fails with an error.
 pub struct CTParserBuilder<'a, LexemeT, StorageT = u32>
 where
     StorageT: Eq + Hash,
 {
-    // Anything stored in here (except `output_path`, `conflicts`, and `error_on_conflict`) almost
-    // certainly needs to be included as part of the rebuild_cache function below so that, if it's
-    // changed, the grammar is rebuilt.
+    // Anything stored in here (except `output_path`, `conflicts`, `error_on_conflict`, and
+    // `show_warnings`) almost certainly needs to be included as part of the rebuild_cache function
+    // below so that, if it's changed, the grammar is rebuilt.
     grammar_path: Option<PathBuf>,
     output_path: Option<PathBuf>,
+    error_messages_path: Option<PathBuf>,
     mod_name: Option<&'a str>,
     recoverer: RecoveryKind,
+    recovery_budget: Option<RecoveryBudget>,
+    minimiser: Minimiser,
     yacckind: Option<YaccKind>,
     error_on_conflicts: bool,
+    glr: bool,
+    report: bool,
+    show_warnings: bool,
     visibility: Visibility,
+    tables_kind: TablesKind,
     phantom: PhantomData<(LexemeT, StorageT)>,
 }
 
 impl<'a, LexemeT, StorageT> CTParserBuilder<'a, LexemeT, StorageT>
 where
     LexemeT: Lexeme<StorageT>,
-    StorageT: 'static + Debug + Hash + PrimInt + Serialize + Unsigned,
+    StorageT: 'static + Debug + Hash + PrimInt + SerializeStorageT + Unsigned,
     usize: AsPrimitive<StorageT>,
 {
     /// Create a new `CTParserBuilder`.
@@ -166,11 +220,18 @@ where
         CTParserBuilder {
             grammar_path: None,
             output_path: None,
+            error_messages_path: None,
             mod_name: None,
             recoverer: RecoveryKind::CPCTPlus,
+            recovery_budget: None,
+            minimiser: Minimiser::Pager,
             yacckind: None,
             error_on_conflicts: true,
+            glr: false,
+            report: false,
+            show_warnings: true,
             visibility: Visibility::Private,
+            tables_kind: TablesKind::Static,
             phantom: PhantomData,
         }
     }
@@ -235,6 +296,48 @@ where
         self
     }
 
+    /// Set the path of the error messages file to a file relative to this project's `src`
+    /// directory. See [CTParserBuilder::error_messages_path] for details.
+    pub fn error_messages_in_src_dir<P>(mut self, srcp: P) -> Result<Self, Box<dyn Error>>
+    where
+        P: AsRef<Path>,
+    {
+        if !srcp.as_ref().is_relative() {
+            return Err(format!(
+                "Error messages path '{}' must be a relative path.",
+                srcp.as_ref().to_str().unwrap_or("<invalid UTF-8>")
+            )
+            .into());
+        }
+
+        let mut msgsp = current_dir()?;
+        msgsp.push("src");
+        msgsp.push(srcp.as_ref());
+        self.error_messages_path = Some(msgsp);
+        Ok(self)
+    }
+
+    /// Set the path of the error messages file to `inp`. The file contains erroneous example
+    /// inputs, each with a hand-written message (see [ErrorMessages::from_examples] for the
+    /// format). [CTParserBuilder::build] runs each example through the parser, returning an error
+    /// if an example no longer fails at its last token (e.g. because the grammar has changed), and
+    /// adds the following function to the generated module:
+    ///
+    /// ```text
+    ///   pub fn error_message(e: &::lrpar::ParseError<LexemeT, StorageT>)
+    ///     -> ::std::option::Option<&'static str> { ... }
+    /// ```
+    ///
+    /// which returns the message of the example which failed in the same state (see
+    /// [crate::ParseError::stidx]) as `e` (see [ErrorMessages::message]).
+    pub fn error_messages_path<P>(mut self, inp: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        self.error_messages_path = Some(inp.as_ref().to_owned());
+        self
+    }
+
     /// Set the output grammar path to `outp`. Note that there are no requirements on `outp`: the
     /// file can exist anywhere you can create a valid [Path] to. However, if you wish to use
     /// [lrpar_mod!] you will need to make sure that `outp` is in [std::env::var]`("OUT_DIR")` or
@@ -267,6 +370,23 @@ where
         self
     }
 
+    /// Set the work the recoverer may do over the course of a parse to `rb`. Defaults to
+    /// `RecoveryBudget::default()`. Use `RecoveryBudget::Nodes` if the repairs found for a given
+    /// input must not depend on how fast the machine parsing it is.
+    pub fn recovery_budget(mut self, rb: RecoveryBudget) -> Self {
+        self.recovery_budget = Some(rb);
+        self
+    }
+
+    /// Set the stategraph minimiser for this parser to `m`. Defaults to `Minimiser::Pager`. Note
+    /// that `%expect` and `%expect-rr` declarations are checked against the conflicts of the
+    /// resulting statetable, so grammars ported from Bison will typically want
+    /// `Minimiser::LALR`.
+    pub fn minimiser(mut self, m: Minimiser) -> Self {
+        self.minimiser = m;
+        self
+    }
+
     /// Set the `YaccKind` for this parser to `ak`.
     pub fn yacckind(mut self, yk: YaccKind) -> Self {
         self.yacckind = Some(yk);
@@ -280,6 +400,49 @@ where
         self
     }
 
+    /// If set to true, the generated `parse` function uses a generalised LR (GLR) parser which
+    /// explores every alternative of the grammar's conflicts. Conflicts are thus permitted (i.e.
+    /// [CTParserBuilder::error_on_conflicts] is ignored) and the recoverer is not used. For
+    /// grammars whose `yacckind` is `YaccKind::Original(YaccOriginalActionKind::GenericParseTree)`,
+    /// `parse` returns a shared packed parse forest (see [crate::RTParserBuilder::parse_sppf]) of
+    /// `Node`s; for grammars with user actions, actions are executed over that forest, with
+    /// ambiguities resolved using productions' `%dprec` and `%merge` annotations (see
+    /// [crate::RTParserBuilder::parse_sppf_actions]). A `%merge <f>` annotation requires a
+    /// function `f(x: T, y: T) -> T` (where `T` is the rule's action type) to be in scope in the
+    /// grammar's programs section. GLR parsing supports the `YaccKind::Grmtools` and
+    /// `YaccKind::Original` `yacckind`s: [CTParserBuilder::build] returns an error if `glr` is
+    /// set for any other `yacckind`. Defaults to `false`.
+    pub fn glr(mut self, b: bool) -> Self {
+        self.glr = b;
+        self
+    }
+
+    /// If set to true, [CTParserBuilder::build] will emit any warnings about the grammar (see
+    /// [lrtable::StateTableWarning]) as `cargo:warning` lines, so that Cargo displays them when
+    /// the parser is rebuilt. Defaults to `true`.
+    pub fn show_warnings(mut self, b: bool) -> Self {
+        self.show_warnings = b;
+        self
+    }
+
+    /// If set to true, [CTParserBuilder::build] will write a detailed report about the grammar and
+    /// its statetable next to the generated Rust module: a human readable version with the
+    /// extension `.output` and a machine readable (XML) version with the extension `.xml` (see
+    /// [lrtable::Report]). The report is written even if building the parser then fails because
+    /// of conflicts. Defaults to `false`.
+    pub fn report(mut self, b: bool) -> Self {
+        self.report = b;
+        self
+    }
+
+    /// Set how the grammar and state table are embedded in the generated module to `tk`.
+    /// Defaults to `TablesKind::Static`. `TablesKind::Bincode` is only available if the
+    /// (non-default) `bincode` feature is enabled.
+    pub fn tables_kind(mut self, tk: TablesKind) -> Self {
+        self.tables_kind = tk;
+        self
+    }
+
     /// Statically compile the Yacc file specified by [CTParserBuilder::grammar_path()] into Rust,
     /// placing the output into the file spec [CTParserBuilder::output_path()]. Note that three
     /// additional files will be created with the same name as specified in [self.output_path] but
@@ -292,6 +455,10 @@ where
     ///     pub fn parse<'lexer, 'input: 'lexer>(lexer: &'lexer dyn NonStreamingLexer<...>)
     ///       -> (Option<ActionT>, Vec<LexParseError<...>> { ... }
     ///
+    ///     pub fn grm_stable() -> (&'static YaccGrammar<StorageT>, &'static StateTable<StorageT>) {
+    ///       ...
+    ///     }
+    ///
     ///     pub fn token_epp<'a>(tidx: ::cfgrammar::TIdx<StorageT>) -> ::std::option::Option<&'a str> {
     ///       ...
     ///     }
@@ -307,6 +474,10 @@ where
     ///     pub fn parse<'lexer, 'input: 'lexer>(lexer: &'lexer dyn NonStreamingLexer<...>, x: t)
     ///       -> (Option<ActionT>, Vec<LexParseError<...>> { ... }
     ///
+    ///     pub fn grm_stable() -> (&'static YaccGrammar<StorageT>, &'static StateTable<StorageT>) {
+    ///       ...
+    ///     }
+    ///
     ///     pub fn token_epp<'a>(tidx: ::cfgrammar::TIdx<StorageT>) -> ::std::option::Option<&'a str> {
     ///       ...
     ///     }
@@ -327,7 +498,18 @@ where
     ///      the return type of the `%start` rule;
     ///    * or, if the `yacckind` was set to
     ///      `YaccKind::Original(YaccOriginalActionKind::GenericParseTree)`, it
-    ///      is [crate::Node<StorageT>].
+    ///      is [crate::Node<StorageT>] (or [crate::Sppf<StorageT>] if [CTParserBuilder::glr] was
+    ///      set to true).
+    ///
+    /// For each rule `R` declared with `%entry`, the generated module also contains a function
+    /// `parse_R` with the same form as `parse`, which parses input as an instance of `R` and whose
+    /// `ActionT` is `R`'s return type.
+    ///
+    /// The grammar and state table returned by `grm_stable` (and used by `parse`) are `static`s
+    /// which borrow the generated arrays directly (see [TablesKind::Static]). If
+    /// [TablesKind::Bincode] is used, they are instead deserialised the first time either
+    /// function is called, and are then shared by all subsequent calls, including those from
+    /// other threads.
     ///
     /// # Panics
     ///
@@ -341,13 +523,24 @@ where
             .output_path
             .as_ref()
             .expect("output_path must be specified before processing.");
+        if self.glr {
+            match self.yacckind {
+                Some(YaccKind::Original(YaccOriginalActionKind::GenericParseTree))
+                | Some(YaccKind::Original(YaccOriginalActionKind::NoAction))
+                | Some(YaccKind::Original(YaccOriginalActionKind::UserAction))
+                | Some(YaccKind::Grmtools)
+                | None => (),
+                Some(yk) => {
+                    return Err(format!("GLR parsing is not supported with {:?}.", yk).into());
+                }
+            }
+        }
         let yk = match self.yacckind {
             None => panic!("yacckind must be specified before processing."),
             Some(YaccKind::Original(x)) => YaccKind::Original(x),
             Some(YaccKind::Grmtools) => YaccKind::Grmtools,
             Some(YaccKind::Eco) => panic!("Eco compile-time grammar generation not supported."),
         };
-
         {
             let mut lk = GENERATED_PATHS.lock().unwrap();
             if lk.contains(outp.as_path()) {
@@ -366,17 +559,22 @@ where
         let cache = self.rebuild_cache(&grm);
 
         // We don't need to go through the full rigmarole of generating an output file if all of
-        // the following are true: the output file exists; it is newer than the input file; and the
-        // cache hasn't changed. The last of these might be surprising, but it's vital: we don't
-        // know, for example, what the IDs map might be from one run to the next, and it might
-        // change for reasons beyond lrpar's control. If it does change, that means that the lexer
-        // and lrpar would get out of sync, so we have to play it safe and regenerate in such
-        // cases.
+        // the following are true: the output file exists; it is newer than the input file (and the
+        // error messages file, if there is one); and the cache hasn't changed. The last of these
+        // might be surprising, but it's vital: we don't know, for example, what the IDs map might
+        // be from one run to the next, and it might change for reasons beyond lrpar's control. If
+        // it does change, that means that the lexer and lrpar would get out of sync, so we have to
+        // play it safe and regenerate in such cases.
         if let Ok(ref inmd) = fs::metadata(grmp) {
             if let Ok(ref out_rs_md) = fs::metadata(outp) {
-                if FileTime::from_last_modification_time(out_rs_md)
-                    > FileTime::from_last_modification_time(inmd)
-                {
+                let out_time = FileTime::from_last_modification_time(out_rs_md);
+                let msgs_older = match self.error_messages_path {
+                    Some(ref msgsp) => fs::metadata(msgsp)
+                        .map(|md| out_time > FileTime::from_last_modification_time(&md))
+                        .unwrap_or(false),
+                    None => true,
+                };
+                if out_time > FileTime::from_last_modification_time(inmd) && msgs_older {
                     if let Ok(outc) = read_to_string(outp) {
                         if outc.contains(&cache) {
                             return Ok(CTParser {
@@ -398,19 +596,47 @@ where
         // confusing than the alternatives).
         fs::remove_file(outp).ok();
 
-        let (sgraph, stable) = from_yacc(&grm, Minimiser::Pager)?;
-        if self.error_on_conflicts {
+        let (sgraph, stable) = from_yacc(&grm, self.minimiser)?;
+        if self.report {
+            let report = Report::new(&grm, &sgraph, &stable);
+            fs::write(outp.with_extension("output"), report.pp())?;
+            fs::write(outp.with_extension("xml"), report.to_xml())?;
+        }
+        if self.show_warnings {
+            for w in stable.warnings() {
+                println!("cargo:warning={}", w.pp(&grm));
+            }
+        }
+        if self.error_on_conflicts && !self.glr {
             if let Some(c) = stable.conflicts() {
                 match (grm.expect(), grm.expectrr()) {
                     (Some(i), Some(j)) if i == c.sr_len() && j == c.rr_len() => (),
                     (Some(i), None) if i == c.sr_len() && 0 == c.rr_len() => (),
                     (None, Some(j)) if 0 == c.sr_len() && j == c.rr_len() => (),
                     (None, None) if 0 == c.rr_len() && 0 == c.sr_len() => (),
-                    _ => return Err(Box::new(CTConflictsError { stable })),
+                    _ => {
+                        return Err(Box::new(CTConflictsError {
+                            grm,
+                            sgraph,
+                            stable,
+                        }))
+                    }
                 }
             }
         }
 
+        let msgs = match self.error_messages_path {
+            Some(ref msgsp) => {
+                let src = read_to_string(msgsp)
+                    .map_err(|e| format!("Can't read '{}': {}", msgsp.display(), e))?;
+                Some(
+                    ErrorMessages::from_examples(&grm, &stable, &src)
+                        .map_err(|e| format!("{}: {}", msgsp.display(), e))?,
+                )
+            }
+            None => None,
+        };
+
         let mod_name = match self.mod_name {
             Some(s) => s.to_owned(),
             None => {
@@ -430,7 +656,7 @@ where
             }
         };
 
-        self.output_file(&grm, &stable, &mod_name, outp, &cache)?;
+        self.output_file(&grm, &stable, msgs.as_ref(), &mod_name, outp, &cache)?;
         let conflicts = if stable.conflicts().is_some() {
             Some((grm, sgraph, stable))
         } else {
@@ -530,11 +756,18 @@ where
         let cl: CTParserBuilder<LexemeT, StorageT> = CTParserBuilder {
             grammar_path: self.grammar_path.clone(),
             output_path: self.output_path.clone(),
+            error_messages_path: self.error_messages_path.clone(),
             mod_name: self.mod_name,
             recoverer: self.recoverer,
+            recovery_budget: self.recovery_budget,
+            minimiser: self.minimiser,
             yacckind: self.yacckind,
             error_on_conflicts: self.error_on_conflicts,
+            glr: self.glr,
+            report: self.report,
+            show_warnings: self.show_warnings,
             visibility: self.visibility.clone(),
+            tables_kind: self.tables_kind,
             phantom: PhantomData,
         };
         Ok(cl.build()?.rule_ids)
@@ -544,6 +777,7 @@ where
         &self,
         grm: &YaccGrammar<StorageT>,
         stable: &StateTable<StorageT>,
+        msgs: Option<&ErrorMessages<StorageT>>,
         mod_name: &str,
         outp_rs: P,
         cache: &str,
@@ -566,6 +800,9 @@ where
         outs.push_str(&self.gen_parse_function(grm, stable)?);
         outs.push_str(&self.gen_rule_consts(grm));
         outs.push_str(&self.gen_token_epp(grm));
+        if let Some(msgs) = msgs {
+            outs.push_str(&self.gen_error_messages(msgs));
+        }
         match self.yacckind.unwrap() {
             YaccKind::Original(YaccOriginalActionKind::UserAction) | YaccKind::Grmtools => {
                 outs.push_str(&self.gen_wrappers(grm));
@@ -603,14 +840,23 @@ where
         ));
 
         cache.push_str(&format!("   Grammar path: {:?}\n", self.grammar_path));
+        cache.push_str(&format!(
+            "   Error messages path: {:?}\n",
+            self.error_messages_path
+        ));
         cache.push_str(&format!("   Mod name: {:?}\n", self.mod_name));
         cache.push_str(&format!("   Recoverer: {:?}\n", self.recoverer));
+        cache.push_str(&format!("   Recovery budget: {:?}\n", self.recovery_budget));
+        cache.push_str(&format!("   Minimiser: {:?}\n", self.minimiser));
         cache.push_str(&format!("   YaccKind: {:?}\n", self.yacckind));
         cache.push_str(&format!("   Visibility: {:?}\n", self.visibility.cow_str()));
         cache.push_str(&format!(
             "   Error on conflicts: {:?}\n",
             self.error_on_conflicts
         ));
+        cache.push_str(&format!("   GLR: {:?}\n", self.glr));
+        cache.push_str(&format!("   Report: {:?}\n", self.report));
+        cache.push_str(&format!("   Tables kind: {:?}\n", self.tables_kind));
 
         // Record the rule IDs map
         for tidx in grm.iter_tidxs() {
@@ -625,7 +871,8 @@ where
         cache
     }
 
-    /// Generate the main parse() function for the output file.
+    /// Generate the main parse() function for the output file, and a parse_<rule>() function for
+    /// each rule declared with `%entry`.
     fn gen_parse_function(
         &self,
         grm: &YaccGrammar<StorageT>,
@@ -633,11 +880,45 @@ where
     ) -> Result<String, Box<dyn Error>> {
         let mut outs = String::new();
 
-        // bincode format is serialized into constants which the generated
-        // source code.
-        serialize_bin_output(grm, GRM_CONST_NAME, &mut outs)?;
-        serialize_bin_output(stable, STABLE_CONST_NAME, &mut outs)?;
+        match self.tables_kind {
+            #[cfg(feature = "bincode")]
+            TablesKind::Bincode => {
+                // bincode format is serialized into constants which the generated
+                // source code.
+                serialize_bin_output(grm, GRM_CONST_NAME, &mut outs)?;
+                serialize_bin_output(stable, STABLE_CONST_NAME, &mut outs)?;
+            }
+            TablesKind::Static => outs.push_str(&self.gen_static_tables(grm, stable)),
+        }
+
+        for (i, &(ridx, _)) in grm.entries().iter().enumerate() {
+            // The start rule is parsed by parse(), which calls RTParserBuilder's default
+            // parse_* functions; other entry points call the corresponding parse_*_from
+            // functions.
+            let (fn_name, from) = if i == 0 {
+                ("parse".to_owned(), "(".to_owned())
+            } else {
+                (
+                    format!("parse_{}", grm.rule_name(ridx)),
+                    format!("_from(::cfgrammar::RIdx({}), ", usize::from(ridx)),
+                )
+            };
+            outs.push_str(&self.gen_entry_parse_function(grm, ridx, &fn_name, &from));
+        }
+        outs.push_str(&self.gen_grm_stable());
+        Ok(outs)
+    }
 
+    /// Generate a function `fn_name` which parses input as an instance of the entry rule `ridx`,
+    /// where `from` is appended to the name of the `RTParserBuilder` function called.
+    fn gen_entry_parse_function(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        ridx: RIdx<StorageT>,
+        fn_name: &str,
+        from: &str,
+    ) -> String {
+        let mut outs = String::new();
         match self.yacckind.unwrap() {
             YaccKind::Original(YaccOriginalActionKind::UserAction) | YaccKind::Grmtools => {
                 let parse_param = match grm.parse_param() {
@@ -646,25 +927,40 @@ where
                 };
                 outs.push_str(&format!(
                     "
-    #[allow(dead_code)]
-    pub fn parse<'lexer, 'input: 'lexer>(
+    #[allow(dead_code, non_snake_case)]
+    pub fn {fn_name}<'lexer, 'input: 'lexer>(
         lexer: &'lexer dyn ::lrpar::NonStreamingLexer<'input, {lexemet}, {storaget}>{parse_param})
           -> (::std::option::Option<{actiont}>, ::std::vec::Vec<::lrpar::LexParseError<{lexemet}, {storaget}>>)
     {{",
+                    fn_name = fn_name,
                     lexemet = type_name::<LexemeT>(),
                     storaget = type_name::<StorageT>(),
                     parse_param = parse_param,
-                    actiont = grm.actiontype(self.user_start_ridx(grm)).as_ref().unwrap(),
+                    actiont = grm.actiontype(ridx).as_ref().unwrap(),
+                ));
+            }
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree) if self.glr => {
+                outs.push_str(&format!(
+                    "
+    #[allow(dead_code, non_snake_case)]
+    pub fn {fn_name}(lexer: &dyn ::lrpar::NonStreamingLexer<{lexemet}, {storaget}>)
+          -> (::std::option::Option<::lrpar::Sppf<{lexemet}, {storaget}>>,
+              ::std::vec::Vec<::lrpar::LexParseError<{lexemet}, {storaget}>>)
+    {{",
+                    fn_name = fn_name,
+                    lexemet = type_name::<LexemeT>(),
+                    storaget = type_name::<StorageT>()
                 ));
             }
             YaccKind::Original(YaccOriginalActionKind::GenericParseTree) => {
                 outs.push_str(&format!(
                     "
-    #[allow(dead_code)]
-    pub fn parse(lexer: &dyn ::lrpar::NonStreamingLexer<{lexemet}, {storaget}>)
+    #[allow(dead_code, non_snake_case)]
+    pub fn {fn_name}(lexer: &dyn ::lrpar::NonStreamingLexer<{lexemet}, {storaget}>)
           -> (::std::option::Option<::lrpar::Node<{lexemet}, {storaget}>>,
               ::std::vec::Vec<::lrpar::LexParseError<{lexemet}, {storaget}>>)
     {{",
+                    fn_name = fn_name,
                     lexemet = type_name::<LexemeT>(),
                     storaget = type_name::<StorageT>()
                 ));
@@ -672,10 +968,11 @@ where
             YaccKind::Original(YaccOriginalActionKind::NoAction) => {
                 outs.push_str(&format!(
                     "
-    #[allow(dead_code)]
-    pub fn parse(lexer: &dyn ::lrpar::NonStreamingLexer<{lexemet}, {storaget}>)
+    #[allow(dead_code, non_snake_case)]
+    pub fn {fn_name}(lexer: &dyn ::lrpar::NonStreamingLexer<{lexemet}, {storaget}>)
           -> ::std::vec::Vec<::lrpar::LexParseError<{lexemet}, {storaget}>>
     {{",
+                    fn_name = fn_name,
                     lexemet = type_name::<LexemeT>(),
                     storaget = type_name::<StorageT>()
                 ));
@@ -683,16 +980,34 @@ where
             YaccKind::Eco => unreachable!(),
         };
 
-        outs.push_str(&format!(
+        outs.push_str(
             "
-        let (grm, stable) = ::lrpar::ctbuilder::_reconstitute({}, {});",
-            GRM_CONST_NAME, STABLE_CONST_NAME
-        ));
+        let (grm, stable) = grm_stable();",
+        );
 
-        let recoverer = match self.recoverer {
-            RecoveryKind::CPCTPlus => "CPCTPlus",
-            RecoveryKind::None => "None",
-        };
+        let mut recoverer = format!(
+            "
+            .recoverer(::lrpar::RecoveryKind::{})",
+            match self.recoverer {
+                RecoveryKind::CPCTPlus => "CPCTPlus",
+                RecoveryKind::Panic => "Panic",
+                RecoveryKind::None => "None",
+            }
+        );
+        match self.recovery_budget {
+            Some(RecoveryBudget::Time(d)) => recoverer.push_str(&format!(
+                "
+            .recovery_budget(::lrpar::RecoveryBudget::Time(::std::time::Duration::new({}, {})))",
+                d.as_secs(),
+                d.subsec_nanos()
+            )),
+            Some(RecoveryBudget::Nodes(n)) => recoverer.push_str(&format!(
+                "
+            .recovery_budget(::lrpar::RecoveryBudget::Nodes({}))",
+                n
+            )),
+            None => (),
+        }
         match self.yacckind.unwrap() {
             YaccKind::Original(YaccOriginalActionKind::UserAction) | YaccKind::Grmtools => {
                 // action function references
@@ -725,51 +1040,356 @@ where
                     parse_paramty = parse_paramty,
                     wrappers = wrappers
                 ));
+                let parse_call = if self.glr {
+                    let merges = grm
+                        .iter_pidxs()
+                        .map(|pidx| match grm.prod_merge(pidx) {
+                            Some(_) => format!(
+                                "Some(&{prefix}merge_{})",
+                                usize::from(pidx),
+                                prefix = ACTION_PREFIX
+                            ),
+                            None => "None".to_owned(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",\n                        ");
+                    outs.push_str(&format!(
+                        "\n        #[allow(clippy::type_complexity)]
+        let merges: ::std::vec::Vec<::std::option::Option<&dyn Fn({actionskind}<'input>, {actionskind}<'input>)
+                    -> {actionskind}<'input>>> = ::std::vec![{merges}];\n",
+                        actionskind = ACTIONS_KIND,
+                        merges = merges
+                    ));
+                    format!(
+                        "::lrpar::RTParserBuilder::new(grm, stable)
+            .parse_sppf_actions{}lexer, &actions, &merges, {})",
+                        from, parse_param
+                    )
+                } else {
+                    format!(
+                        "::lrpar::RTParserBuilder::new(grm, stable){}
+            .parse_actions{}lexer, &actions, {})",
+                        recoverer, from, parse_param
+                    )
+                };
                 outs.push_str(&format!(
                     "
-        match ::lrpar::RTParserBuilder::new(&grm, &stable)
-            .recoverer(::lrpar::RecoveryKind::{recoverer})
-            .parse_actions(lexer, &actions, {parse_param}) {{
+        match {parse_call} {{
                 (Some({actionskind}::{actionskindprefix}{ridx}(x)), y) => (Some(x), y),
                 (None, y) => (None, y),
                 _ => unreachable!()
         }}",
-                    parse_param = parse_param,
+                    parse_call = parse_call,
                     actionskind = ACTIONS_KIND,
                     actionskindprefix = ACTIONS_KIND_PREFIX,
-                    ridx = usize::from(self.user_start_ridx(grm)),
-                    recoverer = recoverer,
+                    ridx = usize::from(ridx),
+                ));
+            }
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree) if self.glr => {
+                outs.push_str(&format!(
+                    "
+        ::lrpar::RTParserBuilder::new(grm, stable).parse_sppf{}lexer)\n",
+                    from
                 ));
             }
             YaccKind::Original(YaccOriginalActionKind::GenericParseTree) => {
                 outs.push_str(&format!(
                     "
-        ::lrpar::RTParserBuilder::new(&grm, &stable)
-            .recoverer(::lrpar::RecoveryKind::{})
-            .parse_generictree(lexer)\n",
-                    recoverer
+        ::lrpar::RTParserBuilder::new(grm, stable){}
+            .parse_generictree{}lexer)\n",
+                    recoverer, from
+                ));
+            }
+            YaccKind::Original(YaccOriginalActionKind::NoAction) if self.glr => {
+                outs.push_str(&format!(
+                    "
+        ::lrpar::RTParserBuilder::new(grm, stable).parse_sppf{}lexer).1\n",
+                    from
                 ));
             }
             YaccKind::Original(YaccOriginalActionKind::NoAction) => {
                 outs.push_str(&format!(
                     "
-        ::lrpar::RTParserBuilder::new(&grm, &stable)
-            .recoverer(::lrpar::RecoveryKind::{})
-            .parse_noaction(lexer)\n",
-                    recoverer
+        ::lrpar::RTParserBuilder::new(grm, stable){}
+            .parse_noaction{}lexer)\n",
+                    recoverer, from
                 ));
             }
             YaccKind::Eco => unreachable!(),
         };
 
         outs.push_str("\n    }\n\n");
-        Ok(outs)
+        outs
+    }
+
+    /// Generate the `grm_stable()` function. For [TablesKind::Bincode], it lazily deserialises the
+    /// grammar and state table the first time it is called and then shares them between all
+    /// subsequent calls (including those from other threads); for [TablesKind::Static], it
+    /// returns references to the `static`s created by [CTParserBuilder::gen_static_tables].
+    fn gen_grm_stable(&self) -> String {
+        let body = match self.tables_kind {
+            #[cfg(feature = "bincode")]
+            TablesKind::Bincode => format!(
+                "        #[allow(clippy::type_complexity)]
+        static {global_prefix}GRM_STABLE: ::std::sync::OnceLock<(::cfgrammar::yacc::YaccGrammar<{storaget}>,
+                                                            ::lrpar::ctbuilder::StateTable<{storaget}>)>
+            = ::std::sync::OnceLock::new();
+        let (grm, stable) = {global_prefix}GRM_STABLE.get_or_init(|| {{
+            ::lrpar::ctbuilder::_reconstitute({grm_const}, {stable_const})
+        }});
+        (grm, stable)",
+                global_prefix = GLOBAL_PREFIX,
+                storaget = type_name::<StorageT>(),
+                grm_const = GRM_CONST_NAME,
+                stable_const = STABLE_CONST_NAME
+            ),
+            TablesKind::Static => format!("        (&{prefix}GRM, &{prefix}STABLE)", prefix = GLOBAL_PREFIX),
+        };
+        format!(
+            "    #[allow(dead_code)]
+    pub fn grm_stable() -> (&'static ::cfgrammar::yacc::YaccGrammar<{storaget}>,
+                            &'static ::lrpar::ctbuilder::StateTable<{storaget}>)
+    {{
+{body}
+    }}
+
+",
+            storaget = type_name::<StorageT>(),
+            body = body
+        )
+    }
+
+    /// Generate `static` representations of the grammar and state table (see
+    /// [TablesKind::Static]). The `YaccGrammar` and `StateTable` `static`s borrow the arrays in
+    /// `StaticYaccGrammar` and `StaticStateTable` `static`s, so nothing is copied or decoded at
+    /// run-time.
+    fn gen_static_tables(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        stable: &StateTable<StorageT>,
+    ) -> String {
+        fn list<T, F: Fn(&T) -> String>(v: &[T], f: F) -> String {
+            format!("&[{}]", v.iter().map(f).collect::<Vec<_>>().join(", "))
+        }
+
+        fn uints(v: StaticUInts) -> String {
+            let (ty, l) = match v {
+                StaticUInts::U8(s) => ("U8", list(s, |x| x.to_string())),
+                StaticUInts::U16(s) => ("U16", list(s, |x| x.to_string())),
+                StaticUInts::U32(s) => ("U32", list(s, |x| x.to_string())),
+                StaticUInts::U64(s) => ("U64", list(s, |x| x.to_string())),
+            };
+            format!("::lrpar::ctbuilder::StaticUInts::{}({})", ty, l)
+        }
+
+        fn cow_str(s: &Option<Cow<'static, str>>) -> String {
+            match s {
+                Some(s) => format!("Some(::std::borrow::Cow::Borrowed({:?}))", s),
+                None => "None".to_owned(),
+            }
+        }
+
+        fn prec(p: &Option<Precedence>) -> String {
+            match p {
+                Some(p) => format!(
+                    "Some(::cfgrammar::yacc::Precedence {{ level: {}, kind: ::cfgrammar::yacc::AssocKind::{:?} }})",
+                    p.level, p.kind
+                ),
+                None => "None".to_owned(),
+            }
+        }
+
+        fn sym<StorageT: PrimInt + Unsigned>(sym: &Symbol<StorageT>) -> String {
+            match sym {
+                Symbol::Rule(ridx) => format!(
+                    "::cfgrammar::Symbol::Rule(::cfgrammar::RIdx({}))",
+                    usize::from(*ridx)
+                ),
+                Symbol::Token(tidx) => format!(
+                    "::cfgrammar::Symbol::Token(::cfgrammar::TIdx({}))",
+                    usize::from(*tidx)
+                ),
+            }
+        }
+
+        fn stidx<StorageT: PrimInt + Unsigned>(stidx: StIdx<StorageT>) -> String {
+            format!(
+                "::lrpar::ctbuilder::StIdx::from_storaget({})",
+                usize::from(stidx)
+            )
+        }
+
+        let sgrm = grm.to_static();
+        let mut outs = String::new();
+        outs.push_str(&format!(
+            "    static {prefix}STATIC_GRM: ::cfgrammar::yacc::grammar::StaticYaccGrammar<'static, {storaget}>
+        = ::cfgrammar::yacc::grammar::StaticYaccGrammar {{
+        rules_len: ::cfgrammar::RIdx({rules_len}),
+        rule_names: {rule_names},
+        tokens_len: ::cfgrammar::TIdx({tokens_len}),
+        token_names: {token_names},
+        token_precs: {token_precs},
+        token_epp: {token_epp},
+        eof_token_idx: ::cfgrammar::TIdx({eof_token_idx}),
+        error_token_idx: {error_token_idx},
+        prods_len: ::cfgrammar::PIdx({prods_len}),
+        start_prod: ::cfgrammar::PIdx({start_prod}),
+        entries: {entries},
+        prods: {prods},
+        rules_prods: {rules_prods},
+        prods_rules: {prods_rules},
+        prod_precs: {prod_precs},
+        prod_prec_tokens: {prod_prec_tokens},
+        prod_dprecs: {prod_dprecs},
+        prod_merges: {prod_merges},
+        implicit_rule: {implicit_rule},
+        avoid_insert: {avoid_insert},
+        expect: {expect:?},
+        expectrr: {expectrr:?},
+    }};
+    static {prefix}GRM: ::cfgrammar::yacc::YaccGrammar<{storaget}>
+        = ::cfgrammar::yacc::YaccGrammar::from_static(&{prefix}STATIC_GRM);
+",
+            prefix = GLOBAL_PREFIX,
+            storaget = type_name::<StorageT>(),
+            rules_len = usize::from(sgrm.rules_len),
+            rule_names = list(sgrm.rule_names, |x| format!("::std::borrow::Cow::Borrowed({:?})", x)),
+            tokens_len = usize::from(sgrm.tokens_len),
+            token_names = list(sgrm.token_names, cow_str),
+            token_precs = list(sgrm.token_precs, prec),
+            token_epp = list(sgrm.token_epp, cow_str),
+            eof_token_idx = usize::from(sgrm.eof_token_idx),
+            error_token_idx = match sgrm.error_token_idx {
+                Some(tidx) => format!("Some(::cfgrammar::TIdx({}))", usize::from(tidx)),
+                None => "None".to_owned(),
+            },
+            prods_len = usize::from(sgrm.prods_len),
+            start_prod = usize::from(sgrm.start_prod),
+            entries = list(sgrm.entries, |(ridx, pidx)| format!(
+                "(::cfgrammar::RIdx({}), ::cfgrammar::PIdx({}))",
+                usize::from(*ridx),
+                usize::from(*pidx)
+            )),
+            prods = list(sgrm.prods, |p| format!(
+                "::cfgrammar::StaticSlice::Borrowed({})",
+                list(p, sym)
+            )),
+            rules_prods = list(sgrm.rules_prods, |r| format!(
+                "::cfgrammar::StaticSlice::Borrowed({})",
+                list(r, |pidx| format!("::cfgrammar::PIdx({})", usize::from(*pidx)))
+            )),
+            prods_rules = list(sgrm.prods_rules, |ridx| format!("::cfgrammar::RIdx({})", usize::from(*ridx))),
+            prod_precs = list(sgrm.prod_precs, prec),
+            prod_prec_tokens = list(sgrm.prod_prec_tokens, |x| match x {
+                Some(tidx) => format!("Some(::cfgrammar::TIdx({}))", usize::from(*tidx)),
+                None => "None".to_owned(),
+            }),
+            prod_dprecs = list(sgrm.prod_dprecs, |x| format!("{:?}", x)),
+            prod_merges = list(sgrm.prod_merges, cow_str),
+            implicit_rule = match sgrm.implicit_rule {
+                Some(ridx) => format!("Some(::cfgrammar::RIdx({}))", usize::from(ridx)),
+                None => "None".to_owned(),
+            },
+            avoid_insert = match sgrm.avoid_insert {
+                Some(ai) => format!(
+                    "Some({})",
+                    list(ai, |tidx| format!("::cfgrammar::TIdx({})", usize::from(*tidx)))
+                ),
+                None => "None".to_owned(),
+            },
+            expect = sgrm.expect,
+            expectrr = sgrm.expectrr,
+        ));
+
+        let sstable = stable.to_static();
+        outs.push_str(&format!(
+            "    static {prefix}STATIC_STABLE: ::lrpar::ctbuilder::StaticStateTable<'static, {storaget}>
+        = ::lrpar::ctbuilder::StaticStateTable {{
+        actions_row_len: {actions_row_len},
+        actions_uniform: {actions_uniform},
+        actions_displacement: {actions_displacement},
+        actions_data: {actions_data},
+        state_actions: {state_actions},
+        gotos_row_len: {gotos_row_len},
+        gotos_uniform: {gotos_uniform},
+        gotos_displacement: {gotos_displacement},
+        gotos_data: {gotos_data},
+        state_gotos: {state_gotos},
+        start_states: {start_states},
+        core_reduces: {core_reduces},
+        state_shifts: {state_shifts},
+        reduce_states: {reduce_states},
+        states_len: {states_len},
+        prods_len: ::cfgrammar::PIdx({prods_len}),
+        tokens_len: ::cfgrammar::TIdx({tokens_len}),
+        reduce_reduce: {reduce_reduce},
+        shift_reduce: {shift_reduce},
+        conflict_actions: {conflict_actions},
+        warnings: {warnings},
+        final_state: {final_state},
+    }};
+    static {prefix}STABLE: ::lrpar::ctbuilder::StateTable<{storaget}>
+        = ::lrpar::ctbuilder::StateTable::from_static(&{prefix}STATIC_STABLE);
+",
+            prefix = GLOBAL_PREFIX,
+            storaget = type_name::<StorageT>(),
+            actions_row_len = sstable.actions_row_len,
+            actions_displacement = uints(sstable.actions_displacement),
+            actions_data = uints(sstable.actions_data),
+            actions_uniform = uints(sstable.actions_uniform),
+            state_actions = list(sstable.state_actions, |x| x.to_string()),
+            gotos_row_len = sstable.gotos_row_len,
+            gotos_displacement = uints(sstable.gotos_displacement),
+            gotos_data = uints(sstable.gotos_data),
+            gotos_uniform = uints(sstable.gotos_uniform),
+            state_gotos = list(sstable.state_gotos, |x| x.to_string()),
+            start_states = list(sstable.start_states, |x| stidx(*x)),
+            core_reduces = list(sstable.core_reduces, |x| x.to_string()),
+            state_shifts = list(sstable.state_shifts, |x| x.to_string()),
+            reduce_states = list(sstable.reduce_states, |x| x.to_string()),
+            states_len = sstable.states_len,
+            prods_len = usize::from(sstable.prods_len),
+            tokens_len = usize::from(sstable.tokens_len),
+            reduce_reduce = list(sstable.reduce_reduce, |(p1, p2, st)| format!(
+                "(::cfgrammar::PIdx({}), ::cfgrammar::PIdx({}), {})",
+                usize::from(*p1),
+                usize::from(*p2),
+                stidx(*st)
+            )),
+            shift_reduce = list(sstable.shift_reduce, |(tidx, pidx, st)| format!(
+                "(::cfgrammar::TIdx({}), ::cfgrammar::PIdx({}), {})",
+                usize::from(*tidx),
+                usize::from(*pidx),
+                stidx(*st)
+            )),
+            conflict_actions = list(sstable.conflict_actions, |x| format!("{:?}", x)),
+            warnings = list(sstable.warnings, |w| match *w {
+                StateTableWarning::NeverReduced(pidx) => format!(
+                    "::lrpar::ctbuilder::StateTableWarning::NeverReduced(::cfgrammar::PIdx({}))",
+                    usize::from(pidx)
+                ),
+                StateTableWarning::NeverShifted(tidx) => format!(
+                    "::lrpar::ctbuilder::StateTableWarning::NeverShifted(::cfgrammar::TIdx({}))",
+                    usize::from(tidx)
+                ),
+                StateTableWarning::UnusedPrecedence(tidx) => format!(
+                    "::lrpar::ctbuilder::StateTableWarning::UnusedPrecedence(::cfgrammar::TIdx({}))",
+                    usize::from(tidx)
+                ),
+            }),
+            final_state = stidx(sstable.final_state),
+        ));
+        outs
     }
 
     fn gen_rule_consts(&self, grm: &YaccGrammar<StorageT>) -> String {
         let mut outs = String::new();
         for ridx in grm.iter_rules() {
-            if !grm.rule_to_prods(ridx).contains(&grm.start_prod()) {
+            if !grm
+                .rule_to_prods(ridx)
+                .iter()
+                .any(|&pidx| grm.is_entry_prod(pidx))
+            {
                 outs.push_str(&format!(
                     "    #[allow(dead_code)]\n    pub const R_{}: {} = {:?};\n",
                     grm.rule_name(ridx).to_ascii_uppercase(),
@@ -804,6 +1424,36 @@ where
         )
     }
 
+    fn gen_error_messages(&self, msgs: &ErrorMessages<StorageT>) -> String {
+        let entries = msgs
+            .iter()
+            .map(|(stidx, tidx, m)| {
+                format!(
+                    "({}, {}, \"{}\")",
+                    usize::from(stidx),
+                    usize::from(tidx),
+                    str_escape(m)
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "
+
+    const {prefix}ERROR_MSGS: &[(usize, usize, &str)] = &[{}];
+
+    /// Return the hand-written message for the syntax error `e`, if an example in the error
+    /// messages file failed in the same state as `e`.
+    #[allow(dead_code)]
+    pub fn error_message(e: &::lrpar::ParseError<{lexemet}, {storaget}>) -> ::std::option::Option<&'static str> {{
+        ::lrpar::ctbuilder::_error_message({prefix}ERROR_MSGS, usize::from(e.stidx()), usize::from(::cfgrammar::TIdx(e.lexeme().tok_id())))
+    }}",
+            entries.join(", "),
+            lexemet = type_name::<LexemeT>(),
+            storaget = type_name::<StorageT>(),
+            prefix = GLOBAL_PREFIX
+        )
+    }
+
     /// Generate the wrappers that call user actions
     fn gen_wrappers(&self, grm: &YaccGrammar<StorageT>) -> String {
         let mut outs = String::new();
@@ -899,8 +1549,8 @@ where
                             args = args.join(", ")));
                     }
                 }
-            } else if pidx == grm.start_prod() {
-                // The action for the start production (i.e. the extra rule/production
+            } else if grm.is_entry_prod(pidx) {
+                // The action for a start production (i.e. an extra rule/production
                 // added by lrpar) will never be executed, so a dummy function is all
                 // that's required. We add "unreachable" as a check in case some other
                 // detail of lrpar changes in the future.
@@ -917,6 +1567,52 @@ where
             outs.push_str("\n    }\n\n");
         }
 
+        // Merge wrappers
+
+        if self.glr {
+            for pidx in grm.iter_pidxs() {
+                let name = match grm.prod_merge(pidx) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let ridx = usize::from(grm.prod_to_rule(pidx));
+                // As with actions, we avoid passing the result of a function returning the unit
+                // type directly to an enum constructor.
+                let merged = match grm.actiontype(grm.prod_to_rule(pidx)) {
+                    Some(s) if s == "()" => format!(
+                        "{{ {name}(x, y); {actionskind}::{actionskindprefix}{ridx}(()) }}",
+                        name = name,
+                        actionskind = ACTIONS_KIND,
+                        actionskindprefix = ACTIONS_KIND_PREFIX,
+                        ridx = ridx
+                    ),
+                    _ => format!(
+                        "{actionskind}::{actionskindprefix}{ridx}({name}(x, y))",
+                        name = name,
+                        actionskind = ACTIONS_KIND,
+                        actionskindprefix = ACTIONS_KIND_PREFIX,
+                        ridx = ridx
+                    ),
+                };
+                outs.push_str(&format!(
+                    "    fn {prefix}merge_{pidx}<'input>({prefix}x: {actionskind}<'input>,
+                      {prefix}y: {actionskind}<'input>)
+                   -> {actionskind}<'input> {{
+        match ({prefix}x, {prefix}y) {{
+            ({actionskind}::{actionskindprefix}{ridx}(x), {actionskind}::{actionskindprefix}{ridx}(y)) => {merged},
+            _ => unreachable!()
+        }}
+    }}\n\n",
+                    prefix = ACTION_PREFIX,
+                    pidx = usize::from(pidx),
+                    actionskind = ACTIONS_KIND,
+                    actionskindprefix = ACTIONS_KIND_PREFIX,
+                    ridx = ridx,
+                    merged = merged
+                ));
+            }
+        }
+
         // Wrappers enum
 
         outs.push_str(&format!(
@@ -961,7 +1657,7 @@ where
             None => ("()".to_owned(), "_: ()".to_owned()),
         };
         for pidx in grm.iter_pidxs() {
-            if pidx == grm.start_prod() {
+            if grm.is_entry_prod(pidx) {
                 continue;
             }
 
@@ -1053,17 +1749,6 @@ where
         }
         outs
     }
-
-    /// Return the `RIdx` of the %start rule in the grammar (which will not be the same as
-    /// grm.start_rule_idx because the latter has an additional rule insert by cfgrammar
-    /// which then calls the user's %start rule).
-    fn user_start_ridx(&self, grm: &YaccGrammar<StorageT>) -> RIdx<StorageT> {
-        debug_assert_eq!(grm.prod(grm.start_prod()).len(), 1);
-        match grm.prod(grm.start_prod())[0] {
-            Symbol::Rule(ridx) => ridx,
-            _ => unreachable!(),
-        }
-    }
 }
 
 /// Return a version of the string `s` which is safe to embed in source code as a string.
@@ -1073,6 +1758,7 @@ fn str_escape(s: &str) -> String {
 
 /// This function is called by generated files; it exists so that generated files don't require a
 /// dependency on serde and rmps.
+#[cfg(feature = "bincode")]
 #[doc(hidden)]
 pub fn _reconstitute<StorageT: DeserializeOwned + Hash + PrimInt + Unsigned>(
     grm_buf: &[u8],
@@ -1083,6 +1769,18 @@ pub fn _reconstitute<StorageT: DeserializeOwned + Hash + PrimInt + Unsigned>(
     (grm, stable)
 }
 
+/// This function is called by generated files to look up the message for a syntax error in state
+/// `stidx` with lookahead `tidx`.
+#[doc(hidden)]
+pub fn _error_message(
+    msgs: &'static [(usize, usize, &'static str)],
+    stidx: usize,
+    tidx: usize,
+) -> Option<&'static str> {
+    error_messages::lookup(msgs.iter().copied(), stidx, tidx)
+}
+
+#[cfg(feature = "bincode")]
 fn serialize_bin_output<T: Serialize + ?Sized>(
     ser: &T,
     name: &str,
@@ -1096,10 +1794,12 @@ fn serialize_bin_output<T: Serialize + ?Sized>(
 }
 
 /// Makes formatting bytes into a rust array relatively painless.
+#[cfg(feature = "bincode")]
 struct ArrayWriter {
     buffer: String,
 }
 
+#[cfg(feature = "bincode")]
 impl ArrayWriter {
     /// create a new array with the specified name
     fn new(name: &str) -> Self {
@@ -1115,6 +1815,7 @@ impl ArrayWriter {
     }
 }
 
+#[cfg(feature = "bincode")]
 impl Write for ArrayWriter {
     #[allow(dead_code)]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
@@ -1133,7 +1834,7 @@ impl Write for ArrayWriter {
 /// An interface to the result of [CTParserBuilder::build()].
 pub struct CTParser<StorageT = u32>
 where
-    StorageT: Eq + Hash,
+    StorageT: 'static + Eq + Hash,
 {
     regenerated: bool,
     rule_ids: HashMap<String, StorageT>,
@@ -1146,7 +1847,7 @@ where
 
 impl<StorageT> CTParser<StorageT>
 where
-    StorageT: 'static + Debug + Hash + PrimInt + Serialize + Unsigned,
+    StorageT: 'static + Debug + Hash + PrimInt + SerializeStorageT + Unsigned,
     usize: AsPrimitive<StorageT>,
 {
     /// Returns `true` if this compile-time parser was regenerated or `false` if it was not.
@@ -1221,6 +1922,26 @@ C : 'a';"
         }
     }
 
+    #[test]
+    fn test_glr_unsupported_yacckind() {
+        let temp = TempDir::new().unwrap();
+        let mut file_path = PathBuf::from(temp.as_ref());
+        file_path.push("grm.y");
+        let mut f = File::create(&file_path).unwrap();
+        let _ = f.write_all("%start A\n%%\nA : 'a';".as_bytes());
+
+        match CTParserBuilder::<TestLexeme, _>::new()
+            .yacckind(YaccKind::Eco)
+            .glr(true)
+            .grammar_path(file_path.to_str().unwrap())
+            .output_path(file_path.with_extension("ignored"))
+            .build()
+        {
+            Ok(_) => panic!("Expected error"),
+            Err(e) => assert_eq!(e.to_string(), "GLR parsing is not supported with Eco."),
+        }
+    }
+
     #[test]
     fn test_conflicts_error() {
         let temp = TempDir::new().unwrap();
@@ -1247,6 +1968,7 @@ C : 'a';"
                 let cs = e.downcast_ref::<CTConflictsError<u16>>();
                 assert_eq!(cs.unwrap().stable.conflicts().unwrap().rr_len(), 1);
                 assert_eq!(cs.unwrap().stable.conflicts().unwrap().sr_len(), 1);
+                assert!(e.to_string().contains("Ambiguous example: \"a\" • \"b\""));
             }
         }
     }
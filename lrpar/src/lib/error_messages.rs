@@ -0,0 +1,468 @@
+// This file implements error messages by example, in the style of Jeffery's "Generating LR syntax
+// error messages from examples". Users write erroneous example inputs, each with a hand-written
+// message, as sequences of tokens:
+//
+//   // A comment.
+//   ID '(' ID ';' => "Missing ')' in function call."
+//   Expr: ID '+' ';' => "Missing expression after '+'."
+//
+// where an example prefixed with `Rule:` is parsed from that `%entry` rule rather than from the
+// grammar's start rule.
+//
+// Each example is run through the parser until it fails: the state the parser is in at that point,
+// and the lookahead token, are then associated with the example's message. Since an LR parser's
+// behaviour depends only on its state and the next token, any input which fails in the same state
+// with the same lookahead is erroneous in the same way as the example, and can be given the same
+// message.
+//
+// Examples are checked whenever the table is built (i.e., for compile-time parsers, whenever the
+// grammar changes), so that examples which have become stale are reported rather than silently
+// giving misleading messages: an example must fail at (or immediately after) its last token; and
+// two examples which fail in the same state with the same lookahead must have the same message.
+
+use std::{error::Error, fmt, hash::Hash, iter};
+
+use cfgrammar::{yacc::YaccGrammar, RIdx, TIdx};
+use lrtable::{Action, StIdx, StateTable};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+
+use crate::{Lexeme, ParseError};
+
+/// A table of hand-written messages for syntax errors, built from erroneous example inputs.
+#[derive(Debug)]
+pub struct ErrorMessages<StorageT> {
+    /// The state, lookahead, and message of each example, in the order they were defined.
+    msgs: Vec<(StIdx<StorageT>, TIdx<StorageT>, String)>,
+}
+
+impl<StorageT: 'static + Hash + PrimInt + Unsigned> ErrorMessages<StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Build a table of error messages from the examples in `src`, for the parser defined by
+    /// `grm` and `stable`. Each non-blank line of `src` is either a comment starting with `//`,
+    /// or an example: a sequence of whitespace-separated token names (which can be quoted with
+    /// `'` or `"`, as in a grammar) followed by `=>` and a message in double quotes (in which
+    /// `\"`, `\\`, and `\n` are escapes). Each example must cause a syntax error at its last token
+    /// or, if the example is a prefix of valid input, at the end of the input. Examples are parsed
+    /// from the grammar's start rule unless they are prefixed with the name of a rule declared
+    /// with `%entry` followed by `:` (e.g. `Expr: ID '+' => "..."`).
+    pub fn from_examples(
+        grm: &YaccGrammar<StorageT>,
+        stable: &StateTable<StorageT>,
+        src: &str,
+    ) -> Result<Self, ErrorMessagesError> {
+        let mut msgs: Vec<(StIdx<StorageT>, TIdx<StorageT>, String)> = Vec::new();
+        let mut lines = Vec::new();
+        for (i, l) in src.lines().enumerate() {
+            let line = i + 1;
+            let err = |kind| ErrorMessagesError { kind, line };
+            let (entry, names, msg) = match parse_example(l).map_err(err)? {
+                Some(x) => x,
+                None => continue,
+            };
+            let start_stidx = match entry {
+                None => stable.start_state(),
+                Some(n) => match grm.rule_idx(n).and_then(|ridx| entry_idx(grm, ridx)) {
+                    Some(i) => stable.start_states()[i],
+                    None => return Err(err(ErrorMessagesErrorKind::UnknownEntry(n.to_owned()))),
+                },
+            };
+            let mut toks = Vec::with_capacity(names.len());
+            for n in names {
+                match grm.token_idx(n) {
+                    Some(tidx) => toks.push(tidx),
+                    None => return Err(err(ErrorMessagesErrorKind::UnknownToken(n.to_owned()))),
+                }
+            }
+            let (stidx, tidx) = syntax_error(grm, stable, start_stidx, &toks).map_err(err)?;
+            match msgs.iter().position(|&(s, t, _)| s == stidx && t == tidx) {
+                Some(j) if msgs[j].2 != msg => {
+                    return Err(err(ErrorMessagesErrorKind::ConflictingMessage(lines[j])));
+                }
+                Some(_) => (),
+                None => {
+                    msgs.push((stidx, tidx, msg));
+                    lines.push(line);
+                }
+            }
+        }
+        Ok(ErrorMessages { msgs })
+    }
+
+    /// Return the message for the syntax error `e`: that of the first example which failed in the
+    /// same state as `e` with the same lookahead or, if there is no such example, that of the
+    /// first example which failed in the same state. Returns `None` if no example failed in the
+    /// same state as `e`.
+    pub fn message<LexemeT: Lexeme<StorageT>>(
+        &self,
+        e: &ParseError<LexemeT, StorageT>,
+    ) -> Option<&str> {
+        lookup(
+            self.msgs.iter().map(|(s, t, m)| (*s, *t, m.as_str())),
+            e.stidx(),
+            TIdx(e.lexeme().tok_id()),
+        )
+    }
+
+    /// Iterate over the state, lookahead, and message of each example, in the order they were
+    /// defined.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (StIdx<StorageT>, TIdx<StorageT>, &str)> {
+        self.msgs.iter().map(|(s, t, m)| (*s, *t, m.as_str()))
+    }
+}
+
+/// Return the message of the first entry in `msgs` whose state is `stidx` and whose lookahead is
+/// `tidx` or, if there is no such entry, of the first entry whose state is `stidx`.
+pub(crate) fn lookup<'a, S: Copy + PartialEq, T: Copy + PartialEq>(
+    msgs: impl Iterator<Item = (S, T, &'a str)> + Clone,
+    stidx: S,
+    tidx: T,
+) -> Option<&'a str> {
+    msgs.clone()
+        .find(|&(s, t, _)| s == stidx && t == tidx)
+        .or_else(|| msgs.clone().find(|&(s, _, _)| s == stidx))
+        .map(|(_, _, m)| m)
+}
+
+/// Return the index in `grm.entries()` of the entry point for rule `ridx`, if there is one.
+fn entry_idx<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    ridx: RIdx<StorageT>,
+) -> Option<usize>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    grm.entries().iter().position(|&(e_ridx, _)| e_ridx == ridx)
+}
+
+/// An example's entry rule (if it has one), token names, and message.
+type Example<'a> = (Option<&'a str>, Vec<&'a str>, String);
+
+/// Parse the line `l` of an examples file, returning `None` if it is blank or a comment, or the
+/// example's entry rule (if it has one), token names, and message otherwise.
+fn parse_example(l: &str) -> Result<Option<Example<'_>>, ErrorMessagesErrorKind> {
+    let mut entry = None;
+    let mut names = Vec::new();
+    let mut rest = l.trim_start();
+    // An unquoted first word ending in `:` (e.g. `Expr:`) names the rule to parse from.
+    let first = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+    if first.len() > 1 && !first.starts_with(['\'', '"']) {
+        if let Some(n) = first.strip_suffix(':') {
+            entry = Some(n);
+            rest = rest[first.len()..].trim_start();
+        }
+    }
+    loop {
+        if rest.is_empty() || rest.starts_with("//") {
+            if names.is_empty() && entry.is_none() {
+                return Ok(None);
+            }
+            return Err(ErrorMessagesErrorKind::MissingMessage);
+        }
+        if let Some(r) = rest.strip_prefix("=>") {
+            if r.is_empty() || r.starts_with(char::is_whitespace) {
+                rest = r.trim_start();
+                break;
+            }
+        }
+        let (n, r) = match rest.chars().next().unwrap() {
+            q @ ('\'' | '"') => match rest[1..].find(q) {
+                Some(i) => (&rest[1..i + 1], &rest[i + 2..]),
+                None => return Err(ErrorMessagesErrorKind::UnterminatedString),
+            },
+            _ => {
+                let i = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..i], &rest[i..])
+            }
+        };
+        names.push(n);
+        rest = r.trim_start();
+    }
+
+    let mut chars = match rest.strip_prefix('"') {
+        Some(r) => r.chars(),
+        None => return Err(ErrorMessagesErrorKind::MissingMessage),
+    };
+    let mut msg = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some('n') => msg.push('\n'),
+                Some(c @ ('"' | '\\')) => msg.push(c),
+                Some(_) => return Err(ErrorMessagesErrorKind::InvalidEscape),
+                None => return Err(ErrorMessagesErrorKind::UnterminatedString),
+            },
+            Some(c) => msg.push(c),
+            None => return Err(ErrorMessagesErrorKind::UnterminatedString),
+        }
+    }
+    let trailing = chars.as_str().trim_start();
+    if !trailing.is_empty() && !trailing.starts_with("//") {
+        return Err(ErrorMessagesErrorKind::TrailingText);
+    }
+    Ok(Some((entry, names, msg)))
+}
+
+/// Parse the tokens `toks` followed by the end of the input from `start_stidx`, returning the
+/// state and lookahead at which the resulting syntax error is detected.
+fn syntax_error<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    stable: &StateTable<StorageT>,
+    start_stidx: StIdx<StorageT>,
+    toks: &[TIdx<StorageT>],
+) -> Result<(StIdx<StorageT>, TIdx<StorageT>), ErrorMessagesErrorKind>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut pstack = vec![start_stidx];
+    for (i, &tidx) in toks
+        .iter()
+        .chain(iter::once(&grm.eof_token_idx()))
+        .enumerate()
+    {
+        loop {
+            let stidx = *pstack.last().unwrap();
+            match stable.action(stidx, tidx) {
+                Action::Reduce(pidx) => {
+                    let ridx = grm.prod_to_rule(pidx);
+                    pstack.truncate(pstack.len() - grm.prod(pidx).len());
+                    let prior = *pstack.last().unwrap();
+                    pstack.push(stable.goto(prior, ridx).unwrap());
+                }
+                Action::Shift(stidx) => {
+                    pstack.push(stidx);
+                    break;
+                }
+                Action::Accept => return Err(ErrorMessagesErrorKind::NoSyntaxError),
+                Action::Error if i + 1 < toks.len() => {
+                    return Err(ErrorMessagesErrorKind::EarlySyntaxError)
+                }
+                Action::Error => return Ok((stidx, tidx)),
+            }
+        }
+    }
+    // The end of the input is either accepted or a syntax error: it is never shifted.
+    unreachable!()
+}
+
+/// Any error from building [ErrorMessages] returns an instance of this struct.
+#[derive(Debug)]
+pub struct ErrorMessagesError {
+    pub kind: ErrorMessagesErrorKind,
+    /// The line of the examples file on which the error occurred.
+    pub line: usize,
+}
+
+impl Error for ErrorMessagesError {}
+
+/// The various different possible errors in an examples file.
+#[derive(Debug, Eq, PartialEq)]
+pub enum ErrorMessagesErrorKind {
+    /// An example has no `=>` followed by a message.
+    MissingMessage,
+    /// A quoted token name or message is not terminated.
+    UnterminatedString,
+    /// A message contains an escape other than `\"`, `\\`, or `\n`.
+    InvalidEscape,
+    /// An example has text after its message.
+    TrailingText,
+    /// An example contains a token which is not in the grammar.
+    UnknownToken(String),
+    /// An example's entry rule is neither the grammar's start rule nor declared with `%entry`.
+    UnknownEntry(String),
+    /// An example parses without a syntax error.
+    NoSyntaxError,
+    /// An example's syntax error occurs before its last token.
+    EarlySyntaxError,
+    /// An example's syntax error occurs in the same state, with the same lookahead, as that of the
+    /// example on the given line, but the two examples have different messages.
+    ConflictingMessage(usize),
+}
+
+impl fmt::Display for ErrorMessagesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self.kind {
+            ErrorMessagesErrorKind::MissingMessage => "Example has no '=>' followed by a message"
+                .to_owned(),
+            ErrorMessagesErrorKind::UnterminatedString => "Unterminated string".to_owned(),
+            ErrorMessagesErrorKind::InvalidEscape => "Invalid escape in message".to_owned(),
+            ErrorMessagesErrorKind::TrailingText => "Unexpected text after message".to_owned(),
+            ErrorMessagesErrorKind::UnknownToken(ref n) => {
+                format!("Unknown token '{}'", n)
+            }
+            ErrorMessagesErrorKind::UnknownEntry(ref n) => {
+                format!("'{}' is not the start rule or declared with %entry", n)
+            }
+            ErrorMessagesErrorKind::NoSyntaxError => {
+                "Example does not contain a syntax error".to_owned()
+            }
+            ErrorMessagesErrorKind::EarlySyntaxError => {
+                "Example contains a syntax error before its last token".to_owned()
+            }
+            ErrorMessagesErrorKind::ConflictingMessage(line) => format!(
+                "Example fails in the same way as the example at line {}, but has a different message",
+                line
+            ),
+        };
+        write!(f, "{} at line {}", s, self.line)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cfgrammar::yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind};
+    use lrtable::{from_yacc, Minimiser};
+
+    use super::*;
+    use crate::{parser::test::do_parse, LexParseError, RecoveryKind};
+
+    const LEXS: &str = "[a-z]+ 'ID'
+                        \\( '('
+                        \\) ')'
+                        ; ';'";
+    const GRMS: &str = "%start Stmts
+%%
+Stmts: Stmts Stmt | Stmt;
+Stmt: 'ID' '(' ')' ';';";
+
+    fn grm_stable() -> (YaccGrammar<u16>, StateTable<u16>) {
+        let grm = YaccGrammar::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            GRMS,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        (grm, stable)
+    }
+
+    fn messages(input: &str, msgs: &ErrorMessages<u16>) -> Vec<Option<String>> {
+        let (_, pr) = do_parse(RecoveryKind::CPCTPlus, LEXS, GRMS, input);
+        pr.unwrap_err()
+            .1
+            .iter()
+            .map(|e| match e {
+                LexParseError::ParseError(e) => msgs.message(e).map(|m| m.to_owned()),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_messages() {
+        let (grm, stable) = grm_stable();
+        let msgs = ErrorMessages::from_examples(
+            &grm,
+            &stable,
+            r#"
+// Missing parentheses.
+ID ';' => "Missing '(' in call."
+ID "(" ';' => "Missing ')' in call."
+
+ID '(' => "Missing ')' in call." // A different lookahead, but the same message.
+ID '(' ')' ID => "Missing ';' after call."
+"#,
+        )
+        .unwrap();
+        assert_eq!(msgs.iter().count(), 4);
+        assert_eq!(
+            messages("f;g(;h(", &msgs),
+            vec![
+                Some("Missing '(' in call.".to_owned()),
+                Some("Missing ')' in call.".to_owned()),
+                Some("Missing ')' in call.".to_owned())
+            ]
+        );
+        assert_eq!(
+            messages("f()g();", &msgs),
+            vec![Some("Missing ';' after call.".to_owned())]
+        );
+        // No example fails in this state.
+        assert_eq!(messages("(", &msgs), vec![None]);
+    }
+
+    #[test]
+    fn test_entry() {
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "%start S
+%entry E
+%%
+S: E ';';
+E: 'ID' | E '+' 'ID';",
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let err = |src| {
+            let e = ErrorMessages::from_examples(&grm, &stable, src).unwrap_err();
+            (e.kind, e.line)
+        };
+        // "ID ';'" is valid input for S, but not for E.
+        assert_eq!(
+            err("ID ';' => \"x\""),
+            (ErrorMessagesErrorKind::NoSyntaxError, 1)
+        );
+        let msgs = ErrorMessages::from_examples(
+            &grm,
+            &stable,
+            "E: ID ';' => \"x\"\nS: ID ID => \"y\"\nE: => \"z\"",
+        )
+        .unwrap();
+        assert_eq!(msgs.iter().count(), 3);
+        assert_eq!(
+            err("Y: ID => \"x\""),
+            (ErrorMessagesErrorKind::UnknownEntry("Y".to_owned()), 1)
+        );
+        assert_eq!(err("E:"), (ErrorMessagesErrorKind::MissingMessage, 1));
+    }
+
+    #[test]
+    fn test_errors() {
+        let (grm, stable) = grm_stable();
+        let err = |src| {
+            let e = ErrorMessages::from_examples(&grm, &stable, src).unwrap_err();
+            (e.kind, e.line)
+        };
+        assert_eq!(
+            err("// Comment\nID '('"),
+            (ErrorMessagesErrorKind::MissingMessage, 2)
+        );
+        assert_eq!(
+            err("ID ';' => 'x'"),
+            (ErrorMessagesErrorKind::MissingMessage, 1)
+        );
+        assert_eq!(
+            err("ID ';' => \"x"),
+            (ErrorMessagesErrorKind::UnterminatedString, 1)
+        );
+        assert_eq!(
+            err("ID '; => \"x\""),
+            (ErrorMessagesErrorKind::UnterminatedString, 1)
+        );
+        assert_eq!(
+            err("ID ';' => \"\\x\""),
+            (ErrorMessagesErrorKind::InvalidEscape, 1)
+        );
+        assert_eq!(
+            err("ID ';' => \"x\" y"),
+            (ErrorMessagesErrorKind::TrailingText, 1)
+        );
+        assert_eq!(
+            err("ID '[' => \"x\""),
+            (ErrorMessagesErrorKind::UnknownToken("[".to_owned()), 1)
+        );
+        assert_eq!(
+            err("ID '(' ')' ';' => \"x\""),
+            (ErrorMessagesErrorKind::NoSyntaxError, 1)
+        );
+        assert_eq!(
+            err("ID ';' ID => \"x\""),
+            (ErrorMessagesErrorKind::EarlySyntaxError, 1)
+        );
+        assert_eq!(
+            err("ID ';' => \"x\"\n\nID ';' => \"y\""),
+            (ErrorMessagesErrorKind::ConflictingMessage(1), 3)
+        );
+    }
+}
@@ -0,0 +1,1166 @@
+// This file implements a generalised LR (GLR) parser, in the style of Tomita, which runs over a
+// normal statetable but, whenever a conflict was resolved using Yacc's default rules, explores
+// every alternative (see `StateTable::all_actions`). Alternative parses are represented using a
+// graph-structured stack (GSS), whose nodes at each "level" (i.e. after a given number of lexemes
+// have been shifted) are unique per state. Parse trees are stored in a shared packed parse forest
+// (SPPF), whose nonterminal nodes are unique per (rule, start, end) triple and whose "packed"
+// nodes record the alternative derivations of a given nonterminal node.
+//
+// Reductions at each level are driven by a worklist. When a reduction adds a new node to the
+// current level of the GSS, that node's reductions are queued; when it adds a new edge to an
+// existing node, only those reductions whose paths pass through the new edge are queued (and
+// paths are pruned as soon as they can no longer reach that edge). No path is thus reduced more
+// than once, even for grammars with (hidden) left recursion and empty productions.
+//
+// Once the input has been parsed, alternatives beaten by another alternative with a higher
+// `%dprec` are discarded. Actions can then be run over the forest (see `eval`), with the values
+// of ambiguous nodes combined by `%merge` functions where the grammar specifies them.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+};
+
+use cfgrammar::{yacc::YaccGrammar, PIdx, RIdx, TIdx};
+use lrtable::{Action, StIdx, StateTable};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+use vob::Vob;
+
+use crate::{
+    parser::{AStackType, ActionFn, LexParseError, MergeFn, Node, ParseError},
+    Lexeme, NonStreamingLexer, Span,
+};
+
+/// The index of a node in an [Sppf].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SppfIdx(usize);
+
+impl From<SppfIdx> for usize {
+    fn from(idx: SppfIdx) -> Self {
+        idx.0
+    }
+}
+
+/// A node in an [Sppf].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SppfNode<LexemeT, StorageT> {
+    /// Terminals store a single lexeme.
+    Term { lexeme: LexemeT },
+    /// Nonterminals reference a rule, the span of input they cover, and one or more alternative
+    /// derivations of that input. A nonterminal with more than one alternative is ambiguous.
+    Nonterm {
+        ridx: RIdx<StorageT>,
+        span: Span,
+        alts: Vec<Packed<StorageT>>,
+    },
+}
+
+/// One derivation of an [SppfNode::Nonterm] (a "packed node" in SPPF terminology): the production
+/// which was reduced, and the nodes for each of that production's symbols.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Packed<StorageT> {
+    pidx: PIdx<StorageT>,
+    children: Vec<SppfIdx>,
+}
+
+impl<StorageT: Copy> Packed<StorageT> {
+    /// Return the production this derivation reduced.
+    pub fn pidx(&self) -> PIdx<StorageT> {
+        self.pidx
+    }
+
+    /// Return the nodes for each of the symbols of this derivation's production.
+    pub fn children(&self) -> &[SppfIdx] {
+        &self.children
+    }
+}
+
+/// A shared packed parse forest, as returned by
+/// [RTParserBuilder::parse_sppf](crate::RTParserBuilder::parse_sppf), compactly representing all
+/// the parse trees of an input. Subtrees common to several parse trees are shared; ambiguous
+/// parts of the input are represented by [SppfNode::Nonterm]s with more than one alternative.
+/// Note that grammars with cycles (e.g. `A: A | 'a';`) lead to forests with cycles.
+#[derive(Debug)]
+pub struct Sppf<LexemeT, StorageT> {
+    nodes: Vec<SppfNode<LexemeT, StorageT>>,
+    root: SppfIdx,
+}
+
+impl<LexemeT: Lexeme<StorageT>, StorageT: 'static + PrimInt + Unsigned> Sppf<LexemeT, StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Return the root of this forest (a nonterminal for the rule that was parsed from: normally
+    /// the grammar's start rule).
+    pub fn root(&self) -> SppfIdx {
+        self.root
+    }
+
+    /// Return the node `idx`.
+    pub fn node(&self, idx: SppfIdx) -> &SppfNode<LexemeT, StorageT> {
+        &self.nodes[idx.0]
+    }
+
+    /// Return an iterator over all the nonterminal nodes with more than one alternative.
+    pub fn ambiguities(&self) -> impl Iterator<Item = SppfIdx> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| matches!(n, SppfNode::Nonterm { alts, .. } if alts.len() > 1))
+            .map(|(i, _)| SppfIdx(i))
+    }
+
+    /// Is more than one parse tree represented by this forest?
+    pub fn is_ambiguous(&self) -> bool {
+        self.ambiguities().next().is_some()
+    }
+
+    /// Return a parse tree from this forest, calling `choose` on every ambiguous nonterminal node
+    /// encountered to select which of its alternatives should be used. `choose` is passed the
+    /// node's index and its alternatives, and must return an index into the latter.
+    ///
+    /// # Panics
+    ///
+    /// If `choose` returns an out-of-bounds index. Note also that if `choose` selects alternatives
+    /// which form a cycle, this function will not terminate.
+    pub fn tree<F>(&self, mut choose: F) -> Node<LexemeT, StorageT>
+    where
+        F: FnMut(SppfIdx, &[Packed<StorageT>]) -> usize,
+    {
+        self.tree_from(self.root, &mut choose)
+    }
+
+    fn tree_from<F>(&self, mut idx: SppfIdx, choose: &mut F) -> Node<LexemeT, StorageT>
+    where
+        F: FnMut(SppfIdx, &[Packed<StorageT>]) -> usize,
+    {
+        // Forests can be arbitrarily deep (e.g. a long list parsed by a left recursive rule), so
+        // rather than recursing we keep an explicit stack of the nonterminals whose children are
+        // being built: each entry is the nonterminal's rule, the children of the alternative
+        // chosen for it, and the trees built for those children so far.
+        let mut stack: Vec<(RIdx<StorageT>, &[SppfIdx], Vec<Node<LexemeT, StorageT>>)> = Vec::new();
+        loop {
+            let mut n = match self.node(idx) {
+                SppfNode::Term { lexeme } => Node::Term { lexeme: *lexeme },
+                SppfNode::Nonterm { ridx, alts, .. } => {
+                    let alt = if alts.len() == 1 {
+                        &alts[0]
+                    } else {
+                        &alts[choose(idx, alts)]
+                    };
+                    if let Some(&c) = alt.children.first() {
+                        stack.push((*ridx, &alt.children, Vec::with_capacity(alt.children.len())));
+                        idx = c;
+                        continue;
+                    }
+                    Node::Nonterm {
+                        ridx: *ridx,
+                        nodes: Vec::new(),
+                    }
+                }
+            };
+            // `n` is complete: add it to its parent, which is itself complete if `n` was its last
+            // child.
+            loop {
+                match stack.last_mut() {
+                    None => return n,
+                    Some((_, children, nodes)) => {
+                        nodes.push(n);
+                        if let Some(&c) = children.get(nodes.len()) {
+                            idx = c;
+                            break;
+                        }
+                    }
+                }
+                let (ridx, _, nodes) = stack.pop().unwrap();
+                n = Node::Nonterm { ridx, nodes };
+            }
+        }
+    }
+
+    /// Return every parse tree in this forest. Since forests with cycles represent an infinite
+    /// number of trees, only trees in which no node of the forest is nested within itself are
+    /// returned. Note that the number of parse trees can be exponential in the size of the input.
+    pub fn trees(&self) -> Vec<Node<LexemeT, StorageT>> {
+        // As in `tree_from`, we keep an explicit stack rather than recursing. `on_path` records
+        // which nodes are on the stack, so that we can skip alternatives which would nest a node
+        // within itself.
+        let mut stack: Vec<TreesFrame<LexemeT, StorageT>> = Vec::new();
+        let mut on_path = Vob::from_elem(false, self.nodes.len());
+        let mut idx = self.root;
+        loop {
+            let mut c_trees = match self.node(idx) {
+                SppfNode::Term { lexeme } => vec![Node::Term { lexeme: *lexeme }],
+                SppfNode::Nonterm { .. } => {
+                    on_path.set(idx.0, true);
+                    let mut f = TreesFrame {
+                        idx,
+                        alt: 0,
+                        child: 0,
+                        prefixes: vec![Vec::new()],
+                        trees: Vec::new(),
+                    };
+                    match self.trees_next_child(&mut f, &on_path) {
+                        Some(c) => {
+                            stack.push(f);
+                            idx = c;
+                            continue;
+                        }
+                        None => {
+                            on_path.set(idx.0, false);
+                            f.trees
+                        }
+                    }
+                }
+            };
+            // `c_trees` are the trees of the current child of the top frame.
+            loop {
+                match stack.last_mut() {
+                    None => return c_trees,
+                    Some(f) => {
+                        // The trees of an alternative are the cartesian product of its
+                        // children's trees.
+                        f.prefixes = product(std::mem::take(&mut f.prefixes), c_trees);
+                        f.child += 1;
+                        if let Some(c) = self.trees_next_child(f, &on_path) {
+                            idx = c;
+                            break;
+                        }
+                    }
+                }
+                let f = stack.pop().unwrap();
+                on_path.set(f.idx.0, false);
+                c_trees = f.trees;
+            }
+        }
+    }
+
+    /// Return the next child of `f` whose trees are needed or, if `f` is complete, `None`.
+    fn trees_next_child(
+        &self,
+        f: &mut TreesFrame<LexemeT, StorageT>,
+        on_path: &Vob,
+    ) -> Option<SppfIdx> {
+        let (ridx, alts) = match self.node(f.idx) {
+            SppfNode::Nonterm { ridx, alts, .. } => (*ridx, alts),
+            SppfNode::Term { .. } => unreachable!(),
+        };
+        while let Some(alt) = alts.get(f.alt) {
+            if f.child == 0 && alt.children.iter().any(|c| on_path[c.0]) {
+                f.alt += 1;
+                continue;
+            }
+            if let Some(&c) = alt.children.get(f.child) {
+                return Some(c);
+            }
+            f.trees.extend(
+                f.prefixes
+                    .drain(..)
+                    .map(|nodes| Node::Nonterm { ridx, nodes }),
+            );
+            f.prefixes.push(Vec::new());
+            f.alt += 1;
+            f.child = 0;
+        }
+        None
+    }
+
+    /// Return a pretty-printed version of this forest. Nodes without ambiguities are printed as
+    /// [Node::pp] would print them; each alternative of an ambiguous node is printed in turn.
+    pub fn pp(&self, grm: &YaccGrammar<StorageT>, input: &str) -> String {
+        enum Todo {
+            /// Print node `SppfIdx` at the given indent.
+            Node(SppfIdx, usize),
+            /// Print the header of the `usize`th alternative at the given indent.
+            Alt(usize, usize),
+            /// All of the children of node `SppfIdx` have been printed.
+            Pop(SppfIdx),
+        }
+
+        let mut s = String::new();
+        // Nodes currently being printed, used to detect cycles.
+        let mut on_path = Vob::from_elem(false, self.nodes.len());
+        let mut todo = vec![Todo::Node(self.root, 0)];
+        while let Some(t) = todo.pop() {
+            let (idx, indent) = match t {
+                Todo::Node(idx, indent) => (idx, indent),
+                Todo::Alt(i, indent) => {
+                    s.push_str(&format!("{}|{}\n", " ".repeat(indent), i + 1));
+                    continue;
+                }
+                Todo::Pop(idx) => {
+                    on_path.set(idx.0, false);
+                    continue;
+                }
+            };
+            s.push_str(&" ".repeat(indent));
+            match self.node(idx) {
+                SppfNode::Term { lexeme } => {
+                    let tidx = TIdx(lexeme.tok_id());
+                    let tn = grm.token_name(tidx).unwrap();
+                    let lt = &input[lexeme.span().start()..lexeme.span().end()];
+                    s.push_str(&format!("{} {}\n", tn, lt));
+                }
+                SppfNode::Nonterm { ridx, alts, .. } => {
+                    if on_path[idx.0] {
+                        s.push_str(&format!("{} (cycle)\n", grm.rule_name(*ridx)));
+                        continue;
+                    }
+                    on_path.set(idx.0, true);
+                    todo.push(Todo::Pop(idx));
+                    // Entries are pushed in reverse, so that they are popped in order.
+                    if alts.len() == 1 {
+                        s.push_str(&format!("{}\n", grm.rule_name(*ridx)));
+                        for &c in alts[0].children.iter().rev() {
+                            todo.push(Todo::Node(c, indent + 1));
+                        }
+                    } else {
+                        s.push_str(&format!(
+                            "{} ({} alternatives)\n",
+                            grm.rule_name(*ridx),
+                            alts.len()
+                        ));
+                        for (i, alt) in alts.iter().enumerate().rev() {
+                            for &c in alt.children.iter().rev() {
+                                todo.push(Todo::Node(c, indent + 2));
+                            }
+                            todo.push(Todo::Alt(i, indent + 1));
+                        }
+                    }
+                }
+            }
+        }
+        s
+    }
+}
+
+/// A nonterminal whose trees are being built by [Sppf::trees].
+struct TreesFrame<LexemeT: Lexeme<StorageT>, StorageT> {
+    idx: SppfIdx,
+    /// The alternative whose trees are currently being built.
+    alt: usize,
+    /// The child of the current alternative whose trees are currently being built.
+    child: usize,
+    /// The trees of the current alternative's children processed so far, as the cartesian
+    /// product of each child's trees.
+    prefixes: Vec<Vec<Node<LexemeT, StorageT>>>,
+    /// The trees of the alternatives before `alt`.
+    trees: Vec<Node<LexemeT, StorageT>>,
+}
+
+/// Extend each of `prefixes` with each of `trees` in turn. Elements are only cloned if they are
+/// needed more than once since, for deep trees, cloning is expensive.
+fn product<T: Clone>(mut prefixes: Vec<Vec<T>>, trees: Vec<T>) -> Vec<Vec<T>> {
+    let mut last = match prefixes.pop() {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+    let mut out = Vec::with_capacity((prefixes.len() + 1) * trees.len());
+    for p in prefixes {
+        for t in &trees {
+            let mut p = p.clone();
+            p.push(t.clone());
+            out.push(p);
+        }
+    }
+    let trees_len = trees.len();
+    for (i, t) in trees.into_iter().enumerate() {
+        let mut p = if i + 1 < trees_len {
+            last.clone()
+        } else {
+            std::mem::take(&mut last)
+        };
+        p.push(t);
+        out.push(p);
+    }
+    out
+}
+
+/// A node in the graph-structured stack.
+struct GssNode<StorageT> {
+    stidx: StIdx<StorageT>,
+    /// How many lexemes had been shifted when this node was created?
+    level: usize,
+    /// `(GSS node, SPPF node)` pairs: the nodes below this one in the stack, and the SPPF node for
+    /// the symbol which led from that node to this one.
+    edges: Vec<(usize, SppfIdx)>,
+}
+
+/// Parse `lexemes` starting from `start_state`, returning a forest of all possible parse trees
+/// or, if the input cannot be parsed, a single error reported at the point at which every parse
+/// failed.
+pub(crate) fn parse<
+    LexemeT: Lexeme<StorageT>,
+    StorageT: 'static + Debug + Hash + PrimInt + Unsigned,
+>(
+    grm: &YaccGrammar<StorageT>,
+    stable: &StateTable<StorageT>,
+    start_state: StIdx<StorageT>,
+    lexemes: &[LexemeT],
+) -> (
+    Option<Sppf<LexemeT, StorageT>>,
+    Vec<LexParseError<LexemeT, StorageT>>,
+)
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut sppf = Vec::new();
+    // Nonterminal SPPF nodes are shared by all derivations of a rule over the same lexemes.
+    let mut nonterms = HashMap::new();
+    // The alternatives already added to each SPPF node, so that duplicates can be cheaply found.
+    let mut alts_seen = HashSet::new();
+    let mut gss = vec![GssNode {
+        stidx: start_state,
+        level: 0,
+        edges: Vec::new(),
+    }];
+    // The GSS nodes at the current level.
+    let mut frontier = vec![0];
+    let mut laidx = 0;
+    loop {
+        let la_tidx = if laidx < lexemes.len() {
+            TIdx(lexemes[laidx].tok_id())
+        } else {
+            grm.eof_token_idx()
+        };
+
+        // The GSS nodes at this level, by state.
+        let mut level_nodes = frontier
+            .iter()
+            .map(|&gidx| (gss[gidx].stidx, gidx))
+            .collect::<HashMap<_, _>>();
+        let mut todo = Vec::new();
+        for &gidx in &frontier {
+            queue_reductions(grm, stable, la_tidx, &gss, gidx, None, &mut todo);
+        }
+        while let Some((gidx, pidx, via)) = todo.pop() {
+            let ridx = grm.prod_to_rule(pidx);
+            for (prior, children) in paths(&gss, gidx, grm.prod(pidx).len(), via, laidx) {
+                let start = gss[prior].level;
+                let sidx = *nonterms.entry((ridx, start, laidx)).or_insert_with(|| {
+                    sppf.push(SppfNode::Nonterm {
+                        ridx,
+                        span: span(lexemes, start, laidx),
+                        alts: Vec::new(),
+                    });
+                    SppfIdx(sppf.len() - 1)
+                });
+                let alt = Packed { pidx, children };
+                if alts_seen.insert((sidx, alt.clone())) {
+                    if let SppfNode::Nonterm { ref mut alts, .. } = sppf[sidx.0] {
+                        alts.push(alt);
+                    }
+                }
+
+                let goto_stidx = stable.goto(gss[prior].stidx, ridx).unwrap();
+                match level_nodes.get(&goto_stidx) {
+                    Some(&target) => {
+                        // A state is only ever reached by a single symbol, so there can be at most
+                        // one edge between two GSS nodes. A new edge creates new paths from any
+                        // node at this level which can reach `target`.
+                        if !gss[target].edges.iter().any(|&(x, _)| x == prior) {
+                            gss[target].edges.push((prior, sidx));
+                            for &gidx in &frontier {
+                                queue_reductions(
+                                    grm,
+                                    stable,
+                                    la_tidx,
+                                    &gss,
+                                    gidx,
+                                    Some((target, prior)),
+                                    &mut todo,
+                                );
+                            }
+                        }
+                    }
+                    None => {
+                        gss.push(GssNode {
+                            stidx: goto_stidx,
+                            level: laidx,
+                            edges: vec![(prior, sidx)],
+                        });
+                        let target = gss.len() - 1;
+                        level_nodes.insert(goto_stidx, target);
+                        frontier.push(target);
+                        queue_reductions(grm, stable, la_tidx, &gss, target, None, &mut todo);
+                    }
+                }
+            }
+        }
+
+        if la_tidx == grm.eof_token_idx() {
+            for &gidx in &frontier {
+                if stable
+                    .all_actions(gss[gidx].stidx, la_tidx)
+                    .any(|a| a == Action::Accept)
+                {
+                    // The accepting state is reached from the start state by the start rule, whose
+                    // SPPF node is thus the root.
+                    let root = gss[gidx].edges[0].1;
+                    resolve_dprecs(grm, &mut sppf);
+                    return (Some(prune(sppf, root)), Vec::new());
+                }
+            }
+        }
+
+        let mut next = Vec::new();
+        let mut term = None;
+        for &gidx in &frontier {
+            for a in stable.all_actions(gss[gidx].stidx, la_tidx) {
+                let stidx = match a {
+                    Action::Shift(stidx) => stidx,
+                    _ => continue,
+                };
+                let tidx = *term.get_or_insert_with(|| {
+                    sppf.push(SppfNode::Term {
+                        lexeme: lexemes[laidx],
+                    });
+                    SppfIdx(sppf.len() - 1)
+                });
+                match next.iter().find(|&&x: &&usize| gss[x].stidx == stidx) {
+                    Some(&x) => gss[x].edges.push((gidx, tidx)),
+                    None => {
+                        gss.push(GssNode {
+                            stidx,
+                            level: laidx + 1,
+                            edges: vec![(gidx, tidx)],
+                        });
+                        next.push(gss.len() - 1);
+                    }
+                }
+            }
+        }
+
+        if next.is_empty() {
+            let mut expected = Vec::new();
+            for &gidx in &frontier {
+                expected.extend(stable.expected_tokens(grm, &[gss[gidx].stidx]));
+            }
+            expected.sort();
+            expected.dedup();
+            let lexeme = if laidx < lexemes.len() {
+                lexemes[laidx]
+            } else {
+                // We have to artificially construct a Lexeme for the EOF lexeme.
+                let last_la_end = lexemes.last().map(|l| l.span().end()).unwrap_or(0);
+                Lexeme::new_faulty(
+                    StorageT::from(u32::from(grm.eof_token_idx())).unwrap(),
+                    last_la_end,
+                    0,
+                )
+            };
+            let err = ParseError {
+                stidx: gss[frontier[0]].stidx,
+                lexeme,
+                expected,
+                repairs: Vec::new(),
+            };
+            return (None, vec![err.into()]);
+        }
+        frontier = next;
+        laidx += 1;
+    }
+}
+
+/// A reduction waiting to be performed: reduce production `pidx` along the paths from GSS node
+/// `gidx` or, if the third element is `Some((from, to))`, only along those paths which use the
+/// edge from GSS node `from` to GSS node `to`.
+type Reduction<StorageT> = (usize, PIdx<StorageT>, Option<(usize, usize)>);
+
+/// Add to `todo` the reductions of the GSS node `gidx` with lookahead `la_tidx`, restricted to
+/// paths using the edge `via` if it is `Some`. Empty productions use no edges, so they are only
+/// reduced when `via` is `None` (i.e. when `gidx` is first considered).
+fn queue_reductions<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    stable: &StateTable<StorageT>,
+    la_tidx: TIdx<StorageT>,
+    gss: &[GssNode<StorageT>],
+    gidx: usize,
+    via: Option<(usize, usize)>,
+    todo: &mut Vec<Reduction<StorageT>>,
+) where
+    usize: AsPrimitive<StorageT>,
+{
+    for a in stable.all_actions(gss[gidx].stidx, la_tidx) {
+        if let Action::Reduce(pidx) = a {
+            if via.is_none() || !grm.prod(pidx).is_empty() {
+                todo.push((gidx, pidx, via));
+            }
+        }
+    }
+}
+
+/// Return all the paths of length `len` from the GSS node `gidx` as `(end node, SPPF nodes)`
+/// pairs, with the SPPF nodes in the order they were pushed onto the stack. If `via` is
+/// `Some((from, to))` (where `from` is at level `level`, the current level), only the paths which
+/// use the edge from `from` to `to` are returned.
+fn paths<StorageT>(
+    gss: &[GssNode<StorageT>],
+    gidx: usize,
+    len: usize,
+    via: Option<(usize, usize)>,
+    level: usize,
+) -> Vec<(usize, Vec<SppfIdx>)> {
+    let mut paths = Vec::new();
+    let mut todo = vec![(gidx, via.is_none(), Vec::with_capacity(len))];
+    while let Some((gidx, used, mut sidxs)) = todo.pop() {
+        if sidxs.len() == len {
+            if used {
+                sidxs.reverse();
+                paths.push((gidx, sidxs));
+            }
+            continue;
+        }
+        for &(prior, sidx) in &gss[gidx].edges {
+            let used = used || via == Some((gidx, prior));
+            // Levels never increase along a path, so once a path has left the current level
+            // without using `via`, it never will.
+            if !used && gss[prior].level < level {
+                continue;
+            }
+            let mut sidxs = sidxs.clone();
+            sidxs.push(sidx);
+            todo.push((prior, used, sidxs));
+        }
+    }
+    paths
+}
+
+/// Return the span of the lexemes `start..end`. Empty spans are placed at the end of the lexeme
+/// preceding `start` (or at the start of the input if there is no such lexeme).
+fn span<LexemeT: Lexeme<StorageT>, StorageT: Hash>(
+    lexemes: &[LexemeT],
+    start: usize,
+    end: usize,
+) -> Span {
+    if start < end {
+        Span::new(lexemes[start].span().start(), lexemes[end - 1].span().end())
+    } else if start == 0 {
+        Span::new(0, 0)
+    } else {
+        let off = lexemes[start - 1].span().end();
+        Span::new(off, off)
+    }
+}
+
+/// Discard every alternative for which another alternative of the same node has a higher `%dprec`.
+fn resolve_dprecs<LexemeT, StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    nodes: &mut [SppfNode<LexemeT, StorageT>],
+) where
+    usize: AsPrimitive<StorageT>,
+{
+    for n in nodes {
+        if let SppfNode::Nonterm { ref mut alts, .. } = *n {
+            if let Some(max) = alts.iter().filter_map(|alt| grm.prod_dprec(alt.pidx)).max() {
+                alts.retain(|alt| !matches!(grm.prod_dprec(alt.pidx), Some(d) if d < max));
+            }
+        }
+    }
+}
+
+/// Return a forest rooted at `root` containing only those of `nodes` reachable from `root`.
+fn prune<LexemeT, StorageT>(
+    mut nodes: Vec<SppfNode<LexemeT, StorageT>>,
+    root: SppfIdx,
+) -> Sppf<LexemeT, StorageT> {
+    let mut map = vec![None; nodes.len()];
+    let mut order = Vec::new();
+    let mut todo = vec![root];
+    while let Some(idx) = todo.pop() {
+        if map[idx.0].is_some() {
+            continue;
+        }
+        map[idx.0] = Some(SppfIdx(order.len()));
+        order.push(idx);
+        if let SppfNode::Nonterm { ref alts, .. } = nodes[idx.0] {
+            for alt in alts.iter().rev() {
+                todo.extend(alt.children.iter().rev());
+            }
+        }
+    }
+    for n in &mut nodes {
+        if let SppfNode::Nonterm { ref mut alts, .. } = *n {
+            for alt in alts {
+                for c in &mut alt.children {
+                    if let Some(new) = map[c.0] {
+                        *c = new;
+                    }
+                }
+            }
+        }
+    }
+    let mut nodes = nodes.into_iter().map(Some).collect::<Vec<_>>();
+    Sppf {
+        nodes: order
+            .into_iter()
+            .map(|idx| nodes[idx.0].take().unwrap())
+            .collect(),
+        root: SppfIdx(0),
+    }
+}
+
+/// Execute `actions` over `sppf`, returning the value of the grammar's start rule. See
+/// [RTParserBuilder::parse_sppf_actions](crate::RTParserBuilder::parse_sppf_actions) for how
+/// ambiguities are resolved.
+pub(crate) fn eval<
+    'a,
+    'b: 'a,
+    'input: 'b,
+    LexemeT: Lexeme<StorageT>,
+    StorageT: 'static + PrimInt + Unsigned,
+    ActionT: 'a,
+    ParamT: Copy,
+>(
+    sppf: &Sppf<LexemeT, StorageT>,
+    grm: &YaccGrammar<StorageT>,
+    lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+    actions: &[ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
+    merges: &[Option<MergeFn<'a, ActionT>>],
+    param: ParamT,
+) -> Option<ActionT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let ev = Evaluator {
+        sppf,
+        grm,
+        lexer,
+        actions,
+        merges,
+        param,
+    };
+    match ev.node(sppf.root) {
+        Some(AStackType::ActionType(v)) => Some(v),
+        _ => None,
+    }
+}
+
+struct Evaluator<'a, 'b, 'input, 'x, LexemeT, StorageT: 'static, ActionT, ParamT> {
+    sppf: &'x Sppf<LexemeT, StorageT>,
+    grm: &'x YaccGrammar<StorageT>,
+    lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+    actions: &'x [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
+    merges: &'x [Option<MergeFn<'a, ActionT>>],
+    param: ParamT,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        'input: 'b,
+        'x,
+        LexemeT: Lexeme<StorageT>,
+        StorageT: 'static + PrimInt + Unsigned,
+        ActionT: 'a,
+        ParamT: Copy,
+    > Evaluator<'a, 'b, 'input, 'x, LexemeT, StorageT, ActionT, ParamT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Return the value of node `idx` or, if every derivation of `idx` nests a node within
+    /// itself, `None`.
+    fn node(&self, mut idx: SppfIdx) -> Option<AStackType<LexemeT, ActionT>> {
+        // Forests can be arbitrarily deep, so rather than recursing we keep an explicit stack of
+        // the nonterminals being evaluated. `on_path` records which nodes are on the stack: a
+        // derivation which nests a node within itself has no value.
+        let mut stack = Vec::new();
+        let mut on_path = Vob::from_elem(false, self.sppf.nodes.len());
+        loop {
+            let mut v = match self.sppf.node(idx) {
+                SppfNode::Term { lexeme } => Some(AStackType::Lexeme(*lexeme)),
+                SppfNode::Nonterm { .. } if on_path[idx.0] => None,
+                SppfNode::Nonterm { ridx, span, alts } => {
+                    on_path.set(idx.0, true);
+                    let mut alts = alts.iter().collect::<Vec<_>>();
+                    alts.sort_by_key(|alt| alt.pidx);
+                    let merge = self.grm.prod_merge(alts[0].pidx);
+                    let mut f = EvalFrame {
+                        idx,
+                        ridx: *ridx,
+                        span: *span,
+                        merge: alts.len() > 1
+                            && merge.is_some()
+                            && alts
+                                .iter()
+                                .all(|alt| self.grm.prod_merge(alt.pidx) == merge),
+                        alts,
+                        alt: 0,
+                        args: Vec::new(),
+                        acc: None,
+                    };
+                    match self.advance(&mut f, None) {
+                        Ok(c) => {
+                            stack.push(f);
+                            idx = c;
+                            continue;
+                        }
+                        Err(v) => {
+                            on_path.set(idx.0, false);
+                            v.map(AStackType::ActionType)
+                        }
+                    }
+                }
+            };
+            // `v` is the value of the current child of the top frame.
+            loop {
+                match stack.last_mut() {
+                    None => return v,
+                    Some(f) => match self.advance(f, Some(v)) {
+                        Ok(c) => {
+                            idx = c;
+                            break;
+                        }
+                        Err(fv) => v = fv.map(AStackType::ActionType),
+                    },
+                }
+                let f = stack.pop().unwrap();
+                on_path.set(f.idx.0, false);
+            }
+        }
+    }
+
+    /// Pass `f` the value `v` of its current child (if there is one), returning either the next
+    /// child of `f` to be evaluated (`Ok`) or, if `f` is complete, its value (`Err`). An
+    /// alternative with a child that has no value itself has no value. If `f`'s alternatives are
+    /// to be merged, the values of all its alternatives are combined; otherwise, `f`'s value is
+    /// that of its first alternative which has a value.
+    fn advance(
+        &self,
+        f: &mut EvalFrame<'x, LexemeT, StorageT, ActionT>,
+        v: Option<Option<AStackType<LexemeT, ActionT>>>,
+    ) -> Result<SppfIdx, Option<ActionT>> {
+        match v {
+            Some(Some(v)) => f.args.push(v),
+            Some(None) => {
+                f.args.clear();
+                f.alt += 1;
+            }
+            None => (),
+        }
+        while let Some(alt) = f.alts.get(f.alt) {
+            if let Some(&c) = alt.children.get(f.args.len()) {
+                return Ok(c);
+            }
+            let v = self.actions[usize::from(alt.pidx)](
+                f.ridx,
+                self.lexer,
+                f.span,
+                f.args.drain(..),
+                self.param,
+            );
+            if !f.merge {
+                return Err(Some(v));
+            }
+            f.acc = Some(match f.acc.take() {
+                None => v,
+                Some(acc) => {
+                    let mf = self.merges[usize::from(alt.pidx)]
+                        .expect("No %merge function given for production");
+                    mf(acc, v)
+                }
+            });
+            f.alt += 1;
+        }
+        Err(f.acc.take())
+    }
+}
+
+/// A nonterminal being evaluated by [Evaluator::node].
+struct EvalFrame<'x, LexemeT, StorageT, ActionT> {
+    idx: SppfIdx,
+    ridx: RIdx<StorageT>,
+    span: Span,
+    /// Are the values of all of `alts` to be combined with `%merge` functions?
+    merge: bool,
+    /// The node's alternatives, in the order they are to be evaluated.
+    alts: Vec<&'x Packed<StorageT>>,
+    /// The alternative currently being evaluated.
+    alt: usize,
+    /// The values of the current alternative's children evaluated so far.
+    args: Vec<AStackType<LexemeT, ActionT>>,
+    /// The merged values of the alternatives before `alt`.
+    acc: Option<ActionT>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parser::test::{do_parse_sppf, do_parse_sppf_actions},
+        Lexeme,
+    };
+
+    #[test]
+    fn unambiguous() {
+        let lexs = "a 'a'
+                    \\+ '+'
+                    \\* '*'";
+        let grms = "%start E
+%%
+E: E '+' T | T;
+T: T '*' 'a' | 'a';
+";
+        let (grm, sppf, errs) = do_parse_sppf(lexs, grms, "a+a*a");
+        assert!(errs.is_empty());
+        let sppf = sppf.unwrap();
+        assert!(!sppf.is_ambiguous());
+        let trees = sppf.trees();
+        assert_eq!(trees.len(), 1);
+        assert_eq!(
+            trees[0].pp(&grm, "a+a*a"),
+            "E
+ E
+  T
+   a a
+ + +
+ T
+  T
+   a a
+  * *
+  a a
+"
+        );
+        assert_eq!(sppf.pp(&grm, "a+a*a"), trees[0].pp(&grm, "a+a*a"));
+    }
+
+    #[test]
+    fn ambiguous() {
+        let lexs = "a 'a'
+                    \\+ '+'";
+        let grms = "%start E
+%%
+E: E '+' E | 'a';
+";
+        let (grm, sppf, errs) = do_parse_sppf(lexs, grms, "a+a+a");
+        assert!(errs.is_empty());
+        let sppf = sppf.unwrap();
+        assert!(sppf.is_ambiguous());
+        assert_eq!(sppf.ambiguities().collect::<Vec<_>>(), vec![sppf.root()]);
+        match sppf.node(sppf.root()) {
+            SppfNode::Nonterm { span, alts, .. } => {
+                assert_eq!(*span, Span::new(0, 5));
+                assert_eq!(alts.len(), 2);
+            }
+            _ => panic!(),
+        }
+        let left = "E
+ E
+  E
+   a a
+  + +
+  E
+   a a
+ + +
+ E
+  a a
+";
+        let right = "E
+ E
+  a a
+ + +
+ E
+  E
+   a a
+  + +
+  E
+   a a
+";
+        let mut trees = sppf
+            .trees()
+            .iter()
+            .map(|t| t.pp(&grm, "a+a+a"))
+            .collect::<Vec<_>>();
+        trees.sort();
+        assert_eq!(trees, vec![left, right]);
+
+        // Choosing the alternative whose first child is the longer `E` gives the left-associative
+        // tree.
+        let t = sppf.tree(|_, alts| {
+            alts.iter()
+                .position(|alt| match sppf.node(alt.children()[0]) {
+                    SppfNode::Nonterm { span, .. } => span.len() == 3,
+                    _ => false,
+                })
+                .unwrap()
+        });
+        assert_eq!(t.pp(&grm, "a+a+a"), left);
+
+        let (_, sppf, _) = do_parse_sppf(lexs, grms, "a+a+a+a");
+        assert_eq!(sppf.unwrap().trees().len(), 5);
+    }
+
+    #[test]
+    fn empty_productions() {
+        let lexs = "x 'x'
+                    b 'b'";
+        let grms = "%start S
+%%
+S: A S 'b' | 'x';
+A: ;
+";
+        let (grm, sppf, errs) = do_parse_sppf(lexs, grms, "xbb");
+        assert!(errs.is_empty());
+        let sppf = sppf.unwrap();
+        assert!(!sppf.is_ambiguous());
+        assert_eq!(
+            sppf.pp(&grm, "xbb"),
+            "S
+ A
+ S
+  A
+  S
+   x x
+  b b
+ b b
+"
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        let lexs = "a 'a'
+                    x 'x'";
+        let grms = "%start S
+%%
+S: A 'x';
+A: B | 'a';
+B: A;
+";
+        let (grm, sppf, errs) = do_parse_sppf(lexs, grms, "ax");
+        assert!(errs.is_empty());
+        let sppf = sppf.unwrap();
+        assert!(sppf.is_ambiguous());
+        assert_eq!(sppf.trees().len(), 1);
+        assert_eq!(
+            sppf.pp(&grm, "ax"),
+            "S
+ A (2 alternatives)
+  |1
+   a a
+  |2
+   B
+    A (cycle)
+ x x
+"
+        );
+    }
+
+    #[test]
+    fn dprec() {
+        let lexs = "[0-9]+ 'INT'";
+        let grms = "%start Atom
+%%
+Atom: 'INT' %dprec 1 | Thousands %dprec 2;
+Thousands: 'INT';
+";
+        let (grm, sppf, errs) = do_parse_sppf(lexs, grms, "3");
+        assert!(errs.is_empty());
+        let sppf = sppf.unwrap();
+        assert!(!sppf.is_ambiguous());
+        assert_eq!(
+            sppf.pp(&grm, "3"),
+            "Atom
+ Thousands
+  INT 3
+"
+        );
+    }
+
+    #[test]
+    fn actions() {
+        let lexs = "[0-9]+ 'INT'
+                    \\- '-'";
+        // Without %merge, the alternative with the lowest production index is chosen.
+        let grms = "%start S
+%%
+S: A | B;
+A: 'INT';
+B: 'INT';
+";
+        let (r, errs) = do_parse_sppf_actions("[0-9]+ 'INT'", grms, "3");
+        assert!(errs.is_empty());
+        assert_eq!(r.unwrap(), "S(A(3))");
+
+        let grms = "%start Expr
+%%
+Expr: Expr '-' Expr %merge <m> | Atom;
+Atom: 'INT' %dprec 1 | Thousands %dprec 2;
+Thousands: 'INT';
+";
+        let (r, errs) = do_parse_sppf_actions(lexs, grms, "3-2");
+        assert!(errs.is_empty());
+        assert_eq!(
+            r.unwrap(),
+            "Expr(Expr(Atom(Thousands(3))) - Expr(Atom(Thousands(2))))"
+        );
+        let (r, errs) = do_parse_sppf_actions(lexs, grms, "3-2-1");
+        assert!(errs.is_empty());
+        let left = "Expr(Expr(Expr(Atom(Thousands(3))) - Expr(Atom(Thousands(2)))) - Expr(Atom(Thousands(1))))";
+        let right = "Expr(Expr(Atom(Thousands(3))) - Expr(Expr(Atom(Thousands(2))) - Expr(Atom(Thousands(1)))))";
+        let r = r.unwrap();
+        assert!(r == format!("{} | {}", left, right) || r == format!("{} | {}", right, left));
+
+        let (r, errs) = do_parse_sppf_actions(lexs, grms, "3-");
+        assert!(r.is_none());
+        assert_eq!(errs.len(), 1);
+    }
+
+    #[test]
+    fn cycles_actions() {
+        let lexs = "a 'a'
+                    x 'x'";
+        let grms = "%start S
+%%
+S: A 'x';
+A: B | 'a';
+B: A;
+";
+        let (r, errs) = do_parse_sppf_actions(lexs, grms, "ax");
+        assert!(errs.is_empty());
+        assert_eq!(r.unwrap(), "S(A(a) x)");
+    }
+
+    #[test]
+    fn long_list() {
+        // Forests are as deep as the input is long, so walking them must not recurse.
+        let lexs = "a 'a'";
+        let grms = "%start L
+%%
+L: L 'a' | 'a';
+";
+        let input = "a".repeat(10000);
+        let (grm, sppf, errs) = do_parse_sppf(lexs, grms, &input);
+        assert!(errs.is_empty());
+        let sppf = sppf.unwrap();
+        assert_eq!(sppf.trees().len(), 1);
+        assert!(matches!(sppf.tree(|_, _| 0), Node::Nonterm { .. }));
+        assert_eq!(sppf.pp(&grm, &input).lines().count(), 20000);
+        let (r, errs) = do_parse_sppf_actions(lexs, grms, &input);
+        assert!(errs.is_empty());
+        assert!(r.unwrap().starts_with(&"L(".repeat(10000)));
+    }
+
+    #[test]
+    fn error() {
+        let lexs = "a 'a'
+                    \\+ '+'";
+        let grms = "%start E
+%%
+E: E '+' E | 'a';
+";
+        let (grm, sppf, errs) = do_parse_sppf(lexs, grms, "a+");
+        assert!(sppf.is_none());
+        assert_eq!(errs.len(), 1);
+        match &errs[0] {
+            LexParseError::ParseError(e) => {
+                assert_eq!(e.lexeme().span(), Span::new(2, 2));
+                assert!(e.lexeme().faulty());
+                assert_eq!(e.expected(), &[grm.token_idx("a").unwrap()]);
+                assert!(e.repairs().is_empty());
+            }
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,1263 @@
+// This file implements incremental reparsing, in the style of Wagner and Graham. The aim is that
+// the cost of reparsing after an edit depends on the size of the edit rather than the size of the
+// input. There are three parts to this.
+//
+// First, trees store positions relatively: a node records the positions of its children relative
+// to its own start, so a subtree which moves because of an edit can be reused as-is, its new
+// position being recorded only by its new parent. The absolute positions of lexemes are computed
+// as the tree is traversed (see `IncrementalTree::root`).
+//
+// Second, only the damaged window of the input is relexed. The old lexemes which end before the
+// edit are assumed to be unaffected by it, so lexing restarts at the end of the last of them (the
+// last lexer-safe point) and continues until it produces a lexeme which is the same as an old
+// lexeme after the edit (moved by the edit's change in length). Lexers have no state other than
+// their position in the input, so from there onwards the old lexemes are still correct. The old
+// lexemes themselves are not stored separately: they are found in the old tree when needed.
+//
+// Third, the parser is fed the new lexemes one by one, but whenever it is about to shift (or to
+// reduce an empty production), it first looks for a subtree of the old tree which starts with the
+// next lexeme. Such a subtree can be shifted in one go if:
+//
+//   1. the previous parse was in the same state when it started the subtree;
+//   2. the lexemes the subtree spans lie wholly outside the damaged window;
+//   3. the lexeme following the subtree has the same token as it did in the previous parse.
+//
+// Since an LR parser's actions depend only on the state it is in and the next token, (1) and (2)
+// mean that the parser would perform exactly the same actions over the subtree's lexemes as it did
+// before; (3) means that it would then reduce them to the same subtree.
+//
+// On its own, that would not be enough for lists, which are generally defined by left recursive
+// rules such as `Stmts: Stmts Stmt`. After an edit in the middle of a list, the elements after the
+// edit are nested within a chain of nodes which contains the edit, so each element would have to
+// be shifted, and a new node built for it, separately. Chains of left recursive nodes are thus
+// stored as a balanced tree of their elements (the `Seq` type), which can be split and joined in
+// logarithmic time. When the parser has just reduced a list up to the start of one of the old
+// list's elements, in the state the old list started in, it would then parse the rest of the old
+// list's elements exactly as before: they can thus be appended to the new list in one go.
+//
+// The states of the previous parse are not stored in the tree, but they can be recovered as the
+// tree is traversed: the state before a subtree's first child is the state before the subtree
+// itself, and the state before each later child is the state reached from the state before its
+// predecessor by shifting (for terminals) or by the goto (for nonterminals) of that predecessor.
+// Every element of a list starts in the state reached by the goto of the list's rule from the
+// state before the list.
+
+use std::{cmp, hash::Hash, marker::PhantomData, sync::Arc, vec};
+
+use cfgrammar::{yacc::YaccGrammar, RIdx, TIdx};
+use lrtable::{Action, StIdx, StateTable};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+
+use crate::{
+    parser::{error_lexeme, AStackType, Node, Reuse},
+    LexError, Lexeme, NonStreamingLexer, Span,
+};
+
+/// An edit to the text of an input: the bytes `start..old_end` of the old input were replaced by
+/// the bytes `start..new_end` of the new input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TextEdit {
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+}
+
+impl TextEdit {
+    /// Create a new edit which replaces the bytes `start..old_end` of the old input with the
+    /// bytes `start..new_end` of the new input.
+    ///
+    /// # Panics
+    ///
+    /// If `old_end` or `new_end` is less than `start`.
+    pub fn new(start: usize, old_end: usize, new_end: usize) -> Self {
+        if old_end < start || new_end < start {
+            panic!(
+                "Edit starts ({}) after it ends ({} or {})!",
+                start, old_end, new_end
+            );
+        }
+        TextEdit {
+            start,
+            old_end,
+            new_end,
+        }
+    }
+
+    /// Byte offset of the start of the edit.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset of the end of the replaced text in the old input.
+    pub fn old_end(&self) -> usize {
+        self.old_end
+    }
+
+    /// Byte offset of the end of the replacement text in the new input.
+    pub fn new_end(&self) -> usize {
+        self.new_end
+    }
+
+    /// Return the position in the new input of `pos`, a position in the old input at or after the
+    /// end of the edit.
+    fn moved(&self, pos: usize) -> usize {
+        pos - self.old_end + self.new_end
+    }
+}
+
+/// A parse tree which can be reparsed incrementally after its input is edited (see
+/// [`RTParserBuilder::reparse_incremental`](struct.RTParserBuilder.html#method.reparse_incremental)).
+/// Positions in the tree are stored relative to the nodes containing them, so that the parts of
+/// the tree unaffected by an edit can be shared between the trees before and after it.
+#[derive(Clone)]
+pub struct IncrementalTree<LexemeT, StorageT> {
+    root: Subtree<StorageT>,
+    start_state: StIdx<StorageT>,
+    /// Was the tree produced without errors? Trees with errors cannot be reused by reparsing, as
+    /// they do not record the lexemes deleted by error recovery.
+    reusable: bool,
+    phantom: PhantomData<LexemeT>,
+}
+
+impl<LexemeT: Lexeme<StorageT>, StorageT: 'static + Hash + PrimInt + Unsigned>
+    IncrementalTree<LexemeT, StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    pub(super) fn new(
+        root: Subtree<StorageT>,
+        start_state: StIdx<StorageT>,
+        reusable: bool,
+    ) -> Self {
+        IncrementalTree {
+            root,
+            start_state,
+            reusable,
+            phantom: PhantomData,
+        }
+    }
+
+    pub(super) fn start_state(&self) -> StIdx<StorageT> {
+        self.start_state
+    }
+
+    pub(super) fn reusable(&self) -> bool {
+        self.reusable
+    }
+
+    /// Return the root of the tree. The positions of lexemes are only computed as the tree is
+    /// traversed, so visiting part of the tree takes time proportional to the size of that part.
+    pub fn root(&self) -> IncrementalNode<'_, LexemeT, StorageT> {
+        IncrementalNode::new(&self.root.node, self.root.start)
+    }
+
+    /// Return the tree as a generic parse tree: this is the same tree that
+    /// [`RTParserBuilder::parse_generictree`](struct.RTParserBuilder.html#method.parse_generictree)
+    /// would return for the same input. This takes time proportional to the size of the tree.
+    pub fn node(&self) -> Node<LexemeT, StorageT> {
+        to_node(&self.root.node, self.root.start)
+    }
+}
+
+/// A node of an [IncrementalTree].
+pub struct IncrementalNode<'a, LexemeT, StorageT> {
+    node: &'a INode<StorageT>,
+    start: usize,
+    /// If `node` is a list, the number of its elements which this node contains.
+    items: usize,
+    phantom: PhantomData<LexemeT>,
+}
+
+impl<'a, LexemeT: Lexeme<StorageT>, StorageT: 'static + Hash + PrimInt + Unsigned>
+    IncrementalNode<'a, LexemeT, StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    fn new(node: &'a INode<StorageT>, start: usize) -> Self {
+        let items = match &node.kind {
+            Kind::List { items, .. } => items.len,
+            _ => 0,
+        };
+        IncrementalNode {
+            node,
+            start,
+            items,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Return the rule of this node, or `None` if it is a terminal.
+    pub fn ridx(&self) -> Option<RIdx<StorageT>> {
+        self.node.ridx()
+    }
+
+    /// Return the lexeme of this node, or `None` if it is a nonterminal.
+    pub fn lexeme(&self) -> Option<LexemeT> {
+        match self.node.kind {
+            Kind::Term { tok_id, faulty } => {
+                Some(new_lexeme(tok_id, faulty, self.start, self.node.end))
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the children of this node, in input order.
+    pub fn children(&self) -> Vec<IncrementalNode<'a, LexemeT, StorageT>> {
+        match &self.node.kind {
+            Kind::Term { .. } => Vec::new(),
+            Kind::Nonterm { children, .. } => children
+                .iter()
+                .map(|(off, c)| IncrementalNode::new(c, self.start + off))
+                .collect(),
+            Kind::List {
+                base,
+                items_off,
+                items,
+                ..
+            } => {
+                // This node is a chain's `self.items`th node: its children are the chain's
+                // previous node followed by the children of the chain's `self.items`th element.
+                let mut children = vec![if self.items == 1 {
+                    IncrementalNode::new(base, self.start)
+                } else {
+                    IncrementalNode {
+                        node: self.node,
+                        start: self.start,
+                        items: self.items - 1,
+                        phantom: PhantomData,
+                    }
+                }];
+                let (istart, kids) = nth_item(self.start + items_off, items, self.items - 1);
+                children.extend(
+                    kids.iter()
+                        .map(|(off, c)| IncrementalNode::new(c, istart + off)),
+                );
+                children
+            }
+        }
+    }
+}
+
+/// A node of an [IncrementalTree]. Nodes may be shared between trees, so their positions are
+/// relative: each node has a start (the start of its first lexeme, or, if it has no lexemes, an
+/// arbitrary position) and the starts of its children are stored relative to its own start.
+pub(super) struct INode<StorageT> {
+    /// The number of lexemes in the node.
+    lexemes: usize,
+    /// The end of the node's last lexeme, relative to its start (or 0 if it has no lexemes).
+    end: usize,
+    kind: Kind<StorageT>,
+}
+
+enum Kind<StorageT> {
+    /// A terminal, whose `end` is its length.
+    Term { tok_id: StorageT, faulty: bool },
+    Nonterm {
+        ridx: RIdx<StorageT>,
+        children: Vec<(usize, Arc<INode<StorageT>>)>,
+    },
+    /// A chain of left recursive nodes for the rule `ridx` (e.g. a list defined by `Stmts: Stmts
+    /// Stmt`): `base` is the innermost node (which is not left recursive), and each of `items`
+    /// holds the children, other than the first, of one node of the chain, from the inside out.
+    /// `items` starts at `items_off` relative to the start of the chain (or of `items` itself, if
+    /// `base` has no lexemes).
+    List {
+        ridx: RIdx<StorageT>,
+        base: Arc<INode<StorageT>>,
+        items_off: usize,
+        items: Arc<Seq<StorageT>>,
+    },
+}
+
+impl<StorageT: Copy> INode<StorageT> {
+    fn ridx(&self) -> Option<RIdx<StorageT>> {
+        match self.kind {
+            Kind::Term { .. } => None,
+            Kind::Nonterm { ridx, .. } | Kind::List { ridx, .. } => Some(ridx),
+        }
+    }
+}
+
+/// A subtree of an [IncrementalTree], and the absolute position of its start.
+pub(super) struct Subtree<StorageT> {
+    start: usize,
+    node: Arc<INode<StorageT>>,
+}
+
+impl<StorageT> Clone for Subtree<StorageT> {
+    fn clone(&self) -> Self {
+        Subtree {
+            start: self.start,
+            node: Arc::clone(&self.node),
+        }
+    }
+}
+
+/// A non-empty sequence of list elements, stored as an AVL tree whose leaves are the elements.
+struct Seq<StorageT> {
+    /// The number of elements.
+    len: usize,
+    lexemes: usize,
+    /// The end of the last lexeme, relative to the start (or 0 if there are no lexemes).
+    end: usize,
+    height: usize,
+    kind: SeqKind<StorageT>,
+}
+
+enum SeqKind<StorageT> {
+    /// An element, whose children are stored as those of [Kind::Nonterm] are.
+    Item(Vec<(usize, Arc<INode<StorageT>>)>),
+    /// The elements of the first `Seq` followed by those of the second, which starts at the given
+    /// offset relative to the start of the first (or 0 if the first has no lexemes).
+    Cat(Arc<Seq<StorageT>>, usize, Arc<Seq<StorageT>>),
+}
+
+/// A sequence and the absolute position of its start.
+type PSeq<StorageT> = (usize, Arc<Seq<StorageT>>);
+
+/// Return the start of, and the relative offset of the second of, two adjacent parts (nodes or
+/// sequences) which start at `lstart` and `rstart` and have `llexemes` and `rlexemes` lexemes.
+fn join_offsets(lstart: usize, llexemes: usize, rstart: usize, rlexemes: usize) -> (usize, usize) {
+    if llexemes == 0 {
+        (rstart, 0)
+    } else if rlexemes == 0 {
+        (lstart, 0)
+    } else {
+        (lstart, rstart - lstart)
+    }
+}
+
+/// Return the start of, the relative children of, the number of lexemes in, and the relative end
+/// of a node whose children are `kids`.
+#[allow(clippy::type_complexity)]
+fn relativise<StorageT>(
+    kids: Vec<Subtree<StorageT>>,
+) -> (usize, Vec<(usize, Arc<INode<StorageT>>)>, usize, usize) {
+    let start = kids
+        .iter()
+        .find(|k| k.node.lexemes > 0)
+        .map(|k| k.start)
+        .unwrap_or(0);
+    let mut lexemes = 0;
+    let mut end = 0;
+    let children = kids
+        .into_iter()
+        .map(|k| {
+            if k.node.lexemes == 0 {
+                return (0, k.node);
+            }
+            let off = k.start - start;
+            lexemes += k.node.lexemes;
+            end = off + k.node.end;
+            (off, k.node)
+        })
+        .collect();
+    (start, children, lexemes, end)
+}
+
+fn item<StorageT>(kids: Vec<Subtree<StorageT>>) -> PSeq<StorageT> {
+    let (start, children, lexemes, end) = relativise(kids);
+    (
+        start,
+        Arc::new(Seq {
+            len: 1,
+            lexemes,
+            end,
+            height: 0,
+            kind: SeqKind::Item(children),
+        }),
+    )
+}
+
+fn cat<StorageT>(l: PSeq<StorageT>, r: PSeq<StorageT>) -> PSeq<StorageT> {
+    let (start, off) = join_offsets(l.0, l.1.lexemes, r.0, r.1.lexemes);
+    let end = if r.1.lexemes > 0 {
+        off + r.1.end
+    } else {
+        l.1.end
+    };
+    (
+        start,
+        Arc::new(Seq {
+            len: l.1.len + r.1.len,
+            lexemes: l.1.lexemes + r.1.lexemes,
+            end,
+            height: 1 + cmp::max(l.1.height, r.1.height),
+            kind: SeqKind::Cat(l.1, off, r.1),
+        }),
+    )
+}
+
+/// Split the concatenation `s` into its two parts.
+fn parts<StorageT>(s: &PSeq<StorageT>) -> (PSeq<StorageT>, PSeq<StorageT>) {
+    match &s.1.kind {
+        SeqKind::Cat(l, off, r) => ((s.0, Arc::clone(l)), (s.0 + off, Arc::clone(r))),
+        SeqKind::Item(_) => unreachable!(),
+    }
+}
+
+/// Concatenate `l` and `r`, whose heights differ by at most 2, rebalancing the result.
+fn balance<StorageT>(l: PSeq<StorageT>, r: PSeq<StorageT>) -> PSeq<StorageT> {
+    if l.1.height > r.1.height + 1 {
+        let (ll, lr) = parts(&l);
+        if ll.1.height >= lr.1.height {
+            cat(ll, cat(lr, r))
+        } else {
+            let (lrl, lrr) = parts(&lr);
+            cat(cat(ll, lrl), cat(lrr, r))
+        }
+    } else if r.1.height > l.1.height + 1 {
+        let (rl, rr) = parts(&r);
+        if rr.1.height >= rl.1.height {
+            cat(cat(l, rl), rr)
+        } else {
+            let (rll, rlr) = parts(&rl);
+            cat(cat(l, rll), cat(rlr, rr))
+        }
+    } else {
+        cat(l, r)
+    }
+}
+
+/// Concatenate `l` and `r`, in time proportional to the difference in their heights.
+fn concat<StorageT>(l: PSeq<StorageT>, r: PSeq<StorageT>) -> PSeq<StorageT> {
+    if l.1.height > r.1.height + 1 {
+        let (ll, lr) = parts(&l);
+        balance(ll, concat(lr, r))
+    } else if r.1.height > l.1.height + 1 {
+        let (rl, rr) = parts(&r);
+        balance(concat(l, rl), rr)
+    } else {
+        cat(l, r)
+    }
+}
+
+fn concat_opt<StorageT>(
+    l: Option<PSeq<StorageT>>,
+    r: Option<PSeq<StorageT>>,
+) -> Option<PSeq<StorageT>> {
+    match (l, r) {
+        (Some(l), Some(r)) => Some(concat(l, r)),
+        (l, None) => l,
+        (None, r) => r,
+    }
+}
+
+/// Split `s` into its first `k` elements and the rest.
+#[allow(clippy::type_complexity)]
+fn split<StorageT>(
+    s: PSeq<StorageT>,
+    k: usize,
+) -> (Option<PSeq<StorageT>>, Option<PSeq<StorageT>>) {
+    if k == 0 {
+        return (None, Some(s));
+    } else if k == s.1.len {
+        return (Some(s), None);
+    }
+    let (l, r) = parts(&s);
+    if k <= l.1.len {
+        let (ll, lr) = split(l, k);
+        (ll, concat_opt(lr, Some(r)))
+    } else {
+        let (rl, rr) = split(r, k - l.1.len);
+        (concat_opt(Some(l), rl), rr)
+    }
+}
+
+/// Return the elements `i..j` of `s`.
+fn slice<StorageT>(s: PSeq<StorageT>, i: usize, j: usize) -> PSeq<StorageT> {
+    let (before, _) = split(s, j);
+    split(before.unwrap(), i).1.unwrap()
+}
+
+/// Return the start and children of the `n`th element of the sequence `s` starting at `start`.
+#[allow(clippy::type_complexity)]
+fn nth_item<StorageT>(
+    mut start: usize,
+    mut s: &Seq<StorageT>,
+    mut n: usize,
+) -> (usize, &[(usize, Arc<INode<StorageT>>)]) {
+    loop {
+        match &s.kind {
+            SeqKind::Item(kids) => return (start, kids),
+            SeqKind::Cat(l, off, r) => {
+                if n < l.len {
+                    s = l;
+                } else {
+                    n -= l.len;
+                    start += off;
+                    s = r;
+                }
+            }
+        }
+    }
+}
+
+/// Return the number of lexemes in the first `n` elements of `s`.
+fn lexemes_before<StorageT>(mut s: &Seq<StorageT>, mut n: usize) -> usize {
+    let mut lexemes = 0;
+    while n > 0 {
+        match &s.kind {
+            SeqKind::Item(_) => return lexemes + s.lexemes,
+            SeqKind::Cat(l, _, r) => {
+                if n <= l.len {
+                    s = l;
+                } else {
+                    lexemes += l.lexemes;
+                    n -= l.len;
+                    s = r;
+                }
+            }
+        }
+    }
+    lexemes
+}
+
+/// Return the number of elements at the start of `s` which together contain no more than `n`
+/// lexemes.
+fn items_within<StorageT>(mut s: &Seq<StorageT>, mut n: usize) -> usize {
+    let mut items = 0;
+    loop {
+        match &s.kind {
+            SeqKind::Item(_) => return items + usize::from(s.lexemes <= n),
+            SeqKind::Cat(l, _, r) => {
+                if l.lexemes <= n {
+                    items += l.len;
+                    n -= l.lexemes;
+                    s = r;
+                } else {
+                    s = l;
+                }
+            }
+        }
+    }
+}
+
+/// Return the index, start, index of the first lexeme, and children of the element of the
+/// sequence `s` starting at `start` which contains its `i`th lexeme.
+#[allow(clippy::type_complexity)]
+fn item_containing<StorageT>(
+    mut start: usize,
+    mut s: &Seq<StorageT>,
+    i: usize,
+) -> (usize, usize, usize, &[(usize, Arc<INode<StorageT>>)]) {
+    let (mut idx, mut first) = (0, 0);
+    loop {
+        match &s.kind {
+            SeqKind::Item(kids) => return (idx, start, first, kids),
+            SeqKind::Cat(l, off, r) => {
+                if i < first + l.lexemes {
+                    s = l;
+                } else {
+                    idx += l.len;
+                    first += l.lexemes;
+                    start += off;
+                    s = r;
+                }
+            }
+        }
+    }
+}
+
+fn list<StorageT>(
+    ridx: RIdx<StorageT>,
+    base: Subtree<StorageT>,
+    items: PSeq<StorageT>,
+) -> Subtree<StorageT> {
+    let (start, items_off) = join_offsets(base.start, base.node.lexemes, items.0, items.1.lexemes);
+    let end = if items.1.lexemes > 0 {
+        items_off + items.1.end
+    } else {
+        base.node.end
+    };
+    Subtree {
+        start,
+        node: Arc::new(INode {
+            lexemes: base.node.lexemes + items.1.lexemes,
+            end,
+            kind: Kind::List {
+                ridx,
+                base: base.node,
+                items_off,
+                items: items.1,
+            },
+        }),
+    }
+}
+
+/// Build the node for `ridx` whose children are `astack`, for use as the action of every
+/// production. If the first child is for `ridx` too, the node is added to its chain.
+pub(super) fn reduce<LexemeT: Lexeme<StorageT>, StorageT: Copy + PartialEq>(
+    ridx: RIdx<StorageT>,
+    astack: vec::Drain<AStackType<LexemeT, Subtree<StorageT>>>,
+) -> Subtree<StorageT> {
+    let mut kids = astack
+        .map(|a| match a {
+            AStackType::ActionType(s) => s,
+            AStackType::Lexeme(l) => Subtree {
+                start: l.span().start(),
+                node: Arc::new(INode {
+                    lexemes: 1,
+                    end: l.span().len(),
+                    kind: Kind::Term {
+                        tok_id: l.tok_id(),
+                        faulty: l.faulty(),
+                    },
+                }),
+            },
+        })
+        .collect::<Vec<_>>();
+    if !kids.is_empty() && kids[0].node.ridx() == Some(ridx) {
+        let first = kids.remove(0);
+        let new = item(kids);
+        return match &first.node.kind {
+            Kind::List {
+                base,
+                items_off,
+                items,
+                ..
+            } => {
+                let base = Subtree {
+                    start: first.start,
+                    node: Arc::clone(base),
+                };
+                let items = concat((first.start + items_off, Arc::clone(items)), new);
+                list(ridx, base, items)
+            }
+            _ => list(ridx, first, new),
+        };
+    }
+    let (start, children, lexemes, end) = relativise(kids);
+    Subtree {
+        start,
+        node: Arc::new(INode {
+            lexemes,
+            end,
+            kind: Kind::Nonterm { ridx, children },
+        }),
+    }
+}
+
+fn new_lexeme<LexemeT: Lexeme<StorageT>, StorageT: Copy>(
+    tok_id: StorageT,
+    faulty: bool,
+    start: usize,
+    len: usize,
+) -> LexemeT {
+    if faulty {
+        LexemeT::new_faulty(tok_id, start, len)
+    } else {
+        LexemeT::new(tok_id, start, len)
+    }
+}
+
+fn to_node<LexemeT: Lexeme<StorageT>, StorageT: Copy>(
+    node: &INode<StorageT>,
+    start: usize,
+) -> Node<LexemeT, StorageT> {
+    match &node.kind {
+        Kind::Term { tok_id, faulty } => Node::Term {
+            lexeme: new_lexeme(*tok_id, *faulty, start, node.end),
+        },
+        Kind::Nonterm { ridx, children } => Node::Nonterm {
+            ridx: *ridx,
+            nodes: children
+                .iter()
+                .map(|(off, c)| to_node(c, start + off))
+                .collect(),
+        },
+        Kind::List {
+            ridx,
+            base,
+            items_off,
+            items,
+        } => {
+            let mut n = to_node(base, start);
+            let mut todo = vec![(start + items_off, &**items)];
+            while let Some((s, seq)) = todo.pop() {
+                match &seq.kind {
+                    SeqKind::Item(kids) => {
+                        let mut nodes = Vec::with_capacity(kids.len() + 1);
+                        nodes.push(n);
+                        nodes.extend(kids.iter().map(|(off, c)| to_node(c, s + off)));
+                        n = Node::Nonterm { ridx: *ridx, nodes };
+                    }
+                    SeqKind::Cat(l, off, r) => {
+                        todo.push((s + off, r));
+                        todo.push((s, l));
+                    }
+                }
+            }
+            n
+        }
+    }
+}
+
+/// Return the `i`th lexeme of `node`, which starts at `start`.
+fn lexeme_at<LexemeT: Lexeme<StorageT>, StorageT: Copy>(
+    mut node: &INode<StorageT>,
+    mut start: usize,
+    mut i: usize,
+) -> LexemeT {
+    loop {
+        let children = match &node.kind {
+            Kind::Term { tok_id, faulty } => {
+                return new_lexeme(*tok_id, *faulty, start, node.end);
+            }
+            Kind::Nonterm { children, .. } => &children[..],
+            Kind::List {
+                base,
+                items_off,
+                items,
+                ..
+            } => {
+                if i < base.lexemes {
+                    node = base;
+                    continue;
+                }
+                i -= base.lexemes;
+                let (_, istart, first, kids) = item_containing(start + items_off, items, i);
+                start = istart;
+                i -= first;
+                kids
+            }
+        };
+        for (off, c) in children {
+            if i < c.lexemes {
+                node = c;
+                start += off;
+                break;
+            }
+            i -= c.lexemes;
+        }
+    }
+}
+
+/// Return the number of lexemes at the start of `node`, which starts at `start`, whose ends
+/// satisfy `pred` (which must be true for the ends of a prefix of the lexemes).
+fn count_ending<StorageT>(
+    node: &INode<StorageT>,
+    start: usize,
+    pred: &dyn Fn(usize) -> bool,
+) -> usize {
+    if node.lexemes == 0 || pred(start + node.end) {
+        return node.lexemes;
+    }
+    let count_children = |start: usize, children: &[(usize, Arc<INode<StorageT>>)]| {
+        let mut n = 0;
+        for (off, c) in children {
+            if c.lexemes == 0 {
+                continue;
+            }
+            if !pred(start + off + c.end) {
+                return n + count_ending(c, start + off, pred);
+            }
+            n += c.lexemes;
+        }
+        n
+    };
+    match &node.kind {
+        Kind::Term { .. } => 0,
+        Kind::Nonterm { children, .. } => count_children(start, children),
+        Kind::List {
+            base,
+            items_off,
+            items,
+            ..
+        } => {
+            if base.lexemes > 0 && !pred(start + base.end) {
+                return count_ending(base, start, pred);
+            }
+            let mut n = base.lexemes;
+            let mut s = (start + items_off, &**items);
+            loop {
+                match &s.1.kind {
+                    SeqKind::Item(kids) => return n + count_children(s.0, kids),
+                    SeqKind::Cat(l, off, r) => {
+                        if l.lexemes > 0 && pred(s.0 + l.end) {
+                            n += l.lexemes;
+                            s = (s.0 + off, r);
+                        } else {
+                            s = (s.0, l);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Finds the subtrees of a previous parse tree which can be reused when parsing after an edit,
+/// and supplies the parser with the lexemes of the new input.
+pub(super) struct Reuser<'a, LexemeT, StorageT: 'static> {
+    stable: &'a StateTable<StorageT>,
+    old: &'a IncrementalTree<LexemeT, StorageT>,
+    edit: TextEdit,
+    /// The number of old lexemes before the damaged window.
+    prefix: usize,
+    /// The index of the first old lexeme after the damaged window.
+    resume: usize,
+    /// The new lexemes in the damaged window.
+    window: Vec<LexemeT>,
+}
+
+impl<'a, LexemeT: Lexeme<StorageT>, StorageT: 'static + Hash + PrimInt + Unsigned>
+    Reuser<'a, LexemeT, StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Create a `Reuser` for `old`, a tree produced without errors, where `lexer` lexes the old
+    /// input after `edit`. Only the damaged window of the input is relexed: any lexing errors in it
+    /// are returned, each also being represented in the window by an error lexeme.
+    pub(super) fn new(
+        grm: &YaccGrammar<StorageT>,
+        stable: &'a StateTable<StorageT>,
+        old: &'a IncrementalTree<LexemeT, StorageT>,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+        edit: TextEdit,
+    ) -> (Self, Vec<LexError>) {
+        let root = &old.root;
+        let old_len = root.node.lexemes;
+        let old_lexeme = |i| lexeme_at::<LexemeT, StorageT>(&root.node, root.start, i);
+        // A lexeme which ends immediately before the edit may continue into the new text, so the
+        // last lexer-safe point is the end of the last lexeme which ends before that.
+        let prefix = count_ending(&root.node, root.start, &|end| end < edit.start);
+        let restart = match prefix {
+            0 => 0,
+            _ => old_lexeme(prefix - 1).span().end(),
+        };
+        // The first old lexeme which starts at or after the end of the edit.
+        let mut j = count_ending(&root.node, root.start, &|end| end <= edit.old_end);
+        if j < old_len && old_lexeme(j).span().start() < edit.old_end {
+            j += 1;
+        }
+        let mut window = Vec::new();
+        let mut lex_errs = Vec::new();
+        let mut resume = old_len;
+        for r in lexer.iter_from(restart) {
+            match r {
+                Ok(l) => {
+                    if l.span().start() >= edit.new_end {
+                        while j < old_len
+                            && edit.moved(old_lexeme(j).span().start()) < l.span().start()
+                        {
+                            j += 1;
+                        }
+                        if j < old_len {
+                            let ol = old_lexeme(j);
+                            if edit.moved(ol.span().start()) == l.span().start()
+                                && ol.span().len() == l.span().len()
+                                && ol.tok_id() == l.tok_id()
+                            {
+                                resume = j;
+                                break;
+                            }
+                        }
+                    }
+                    window.push(l);
+                }
+                Err(e) => {
+                    window.push(error_lexeme(grm, &e));
+                    lex_errs.push(e);
+                }
+            }
+        }
+        (
+            Reuser {
+                stable,
+                old,
+                edit,
+                prefix,
+                resume,
+                window,
+            },
+            lex_errs,
+        )
+    }
+
+    /// Return the number of lexemes relexed.
+    #[cfg(test)]
+    pub(super) fn relexed(&self) -> usize {
+        self.window.len()
+    }
+
+    fn old_lexeme(&self, i: usize) -> LexemeT {
+        lexeme_at(&self.old.root.node, self.old.root.start, i)
+    }
+
+    /// Return the `i`th lexeme of the new input, or `None` if there are fewer than `i + 1`.
+    pub(super) fn lexeme(&self, i: usize) -> Option<LexemeT> {
+        if i < self.prefix {
+            Some(self.old_lexeme(i))
+        } else if i < self.prefix + self.window.len() {
+            Some(self.window[i - self.prefix])
+        } else {
+            let j = i - self.prefix - self.window.len() + self.resume;
+            if j < self.old.root.node.lexemes {
+                let l = self.old_lexeme(j);
+                let start = self.edit.moved(l.span().start());
+                Some(new_lexeme(l.tok_id(), l.faulty(), start, l.span().len()))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Return the index of the old lexeme corresponding to the new lexeme at `laidx`, or `None`
+    /// if that lexeme is in the damaged window.
+    fn old_idx(&self, laidx: usize) -> Option<usize> {
+        if laidx < self.prefix {
+            Some(laidx)
+        } else if laidx >= self.prefix + self.window.len() {
+            Some(laidx - self.prefix - self.window.len() + self.resume)
+        } else {
+            None
+        }
+    }
+
+    /// Can a subtree spanning the old lexemes `start..end` be shifted in place of the new lexemes
+    /// starting at `laidx`?
+    fn reusable(&self, start: usize, end: usize, laidx: usize) -> bool {
+        let old_next = match end < self.old.root.node.lexemes {
+            true => Some(self.old_lexeme(end).tok_id()),
+            false => None,
+        };
+        end > start
+            && (end <= self.prefix || start >= self.resume)
+            && old_next == self.lexeme(laidx + end - start).map(|l| l.tok_id())
+    }
+
+    /// Return `s`, a subtree of the old tree spanning old lexemes starting at `oldidx`, at its
+    /// position in the new input.
+    fn moved(&self, s: Subtree<StorageT>, oldidx: usize) -> Subtree<StorageT> {
+        match oldidx >= self.resume {
+            true => Subtree {
+                start: self.edit.moved(s.start),
+                node: s.node,
+            },
+            false => s,
+        }
+    }
+
+    /// Return the state reached from `stidx` after shifting `node`.
+    fn step(&self, stidx: StIdx<StorageT>, node: &INode<StorageT>) -> Option<StIdx<StorageT>> {
+        match node.kind {
+            Kind::Term { tok_id, .. } => match self.stable.action(stidx, TIdx(tok_id)) {
+                Action::Shift(s) => Some(s),
+                _ => None,
+            },
+            Kind::Nonterm { ridx, .. } | Kind::List { ridx, .. } => self.stable.goto(stidx, ridx),
+        }
+    }
+
+    /// If the parser, with parse stack `pstack` and (if it is a value rather than a lexeme) `top`
+    /// on top of its stack, is about to parse the new lexeme at `laidx`, return what it can reuse
+    /// from the old tree instead.
+    pub(super) fn reuse(
+        &self,
+        pstack: &[StIdx<StorageT>],
+        top: Option<&Subtree<StorageT>>,
+        laidx: usize,
+    ) -> Option<Reuse<StorageT, Subtree<StorageT>>> {
+        let oldidx = self.old_idx(laidx)?;
+        if oldidx >= self.old.root.node.lexemes {
+            return None;
+        }
+        let stidx = *pstack.last().unwrap();
+        let below = pstack.len().checked_sub(2).map(|i| pstack[i]);
+        // Walk down the old tree towards the old lexeme at `oldidx`, keeping track of the start,
+        // the index of the first lexeme, and the state before, each node. The largest subtree
+        // starting at that lexeme is found first.
+        let mut node = &self.old.root.node;
+        let mut start = self.old.root.start;
+        let mut first = 0;
+        let mut state = Some(self.old.start_state);
+        loop {
+            if first == oldidx && state == Some(stidx) {
+                if let Some(r) = self.reuse_node(node, start, oldidx, laidx) {
+                    return Some(r);
+                }
+            }
+            let children = match &node.kind {
+                Kind::Term { .. } => return None,
+                Kind::Nonterm { children, .. } => &children[..],
+                Kind::List {
+                    ridx,
+                    base,
+                    items_off,
+                    items,
+                } => {
+                    if oldidx < first + base.lexemes {
+                        node = base;
+                        continue;
+                    }
+                    let ifirst = first + base.lexemes;
+                    let (i, istart, irel, kids) =
+                        item_containing(start + items_off, items, oldidx - ifirst);
+                    let g = state.and_then(|s| self.stable.goto(s, *ridx));
+                    if ifirst + irel == oldidx
+                        && state.is_some()
+                        && below == state
+                        && g == Some(stidx)
+                        && top.and_then(|t| t.node.ridx()) == Some(*ridx)
+                        && (i == 0 || lexemes_before(items, i - 1) < irel)
+                    {
+                        // The parser has just reduced a list up to the start of this element, in
+                        // the same state as the old list: the rest of the list can be appended.
+                        let items = (start + items_off, Arc::clone(items));
+                        if let Some(r) = self.extend(top.unwrap(), items, i, ifirst, laidx) {
+                            return Some(r);
+                        }
+                    }
+                    start = istart;
+                    first = ifirst + irel;
+                    state = g;
+                    kids
+                }
+            };
+            let mut next = None;
+            for (off, c) in children {
+                if oldidx < first + c.lexemes {
+                    next = Some((c, start + off));
+                    break;
+                }
+                first += c.lexemes;
+                state = state.and_then(|s| self.step(s, c));
+            }
+            let (c, cstart) = next?;
+            node = c;
+            start = cstart;
+        }
+    }
+
+    /// Return `node`, which starts at `start` and whose first lexeme is the old lexeme at
+    /// `oldidx`, or the largest part of it which is a node in its own right, if it can be reused
+    /// in place of the new lexemes starting at `laidx`.
+    fn reuse_node(
+        &self,
+        node: &Arc<INode<StorageT>>,
+        start: usize,
+        oldidx: usize,
+        laidx: usize,
+    ) -> Option<Reuse<StorageT, Subtree<StorageT>>> {
+        let ridx = node.ridx()?;
+        let shift = |s: Subtree<StorageT>| {
+            let len = s.node.lexemes;
+            let s = self.moved(s, oldidx);
+            let span = Span::new(s.start, s.start + s.node.end);
+            Some(Reuse::Shift(ridx, s, len, span))
+        };
+        if self.reusable(oldidx, oldidx + node.lexemes, laidx) {
+            return shift(Subtree {
+                start,
+                node: Arc::clone(node),
+            });
+        }
+        if let Kind::List {
+            base,
+            items_off,
+            items,
+            ..
+        } = &node.kind
+        {
+            // The nodes in the chain which end before the damaged window start at the same place.
+            let ifirst = oldidx + base.lexemes;
+            if ifirst > self.prefix {
+                return None;
+            }
+            let k = items_within(items, self.prefix - ifirst);
+            for k in (k.saturating_sub(1)..=k).rev().filter(|&k| k > 0) {
+                if self.reusable(oldidx, ifirst + lexemes_before(items, k), laidx) {
+                    let base = Subtree {
+                        start,
+                        node: Arc::clone(base),
+                    };
+                    let (before, _) = split((start + items_off, Arc::clone(items)), k);
+                    return shift(list(ridx, base, before.unwrap()));
+                }
+            }
+        }
+        None
+    }
+
+    /// Append the elements of `items` from the `i`th onwards, as many as can be reused in place of
+    /// the new lexemes starting at `laidx`, to `top`. `items` is the sequence of elements of an old
+    /// list whose first lexeme is the old lexeme at `ifirst`.
+    fn extend(
+        &self,
+        top: &Subtree<StorageT>,
+        items: PSeq<StorageT>,
+        i: usize,
+        ifirst: usize,
+        laidx: usize,
+    ) -> Option<Reuse<StorageT, Subtree<StorageT>>> {
+        let oldidx = ifirst + lexemes_before(&items.1, i);
+        let j = match oldidx >= self.resume {
+            true => items.1.len,
+            false if ifirst > self.prefix => return None,
+            false => items_within(&items.1, self.prefix - ifirst),
+        };
+        let j = (i + 1..=j)
+            .rev()
+            .take(2)
+            .find(|&j| self.reusable(oldidx, ifirst + lexemes_before(&items.1, j), laidx))?;
+        let run = slice(items, i, j);
+        let run = match oldidx >= self.resume {
+            true => (self.edit.moved(run.0), run.1),
+            false => run,
+        };
+        let len = run.1.lexemes;
+        let end = run.0 + run.1.end;
+        let ridx = top.node.ridx().unwrap();
+        let new = match &top.node.kind {
+            Kind::List {
+                base,
+                items_off,
+                items,
+                ..
+            } => {
+                let base = Subtree {
+                    start: top.start,
+                    node: Arc::clone(base),
+                };
+                list(
+                    ridx,
+                    base,
+                    concat((top.start + items_off, Arc::clone(items)), run),
+                )
+            }
+            _ => list(ridx, top.clone(), run),
+        };
+        Some(Reuse::Extend(new, len, end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::test::do_reparse;
+
+    const LEXS: &str = "[a-z]+ 'ID'
+                        [0-9]+ 'INT'
+                        = '='
+                        ; ';'
+                        \\+ '+'
+                        \\( '('
+                        \\) ')'";
+    const GRMS: &str = "%start Stmts
+%%
+Stmts : Stmts Stmt | ;
+Stmt : 'ID' '=' Expr ';';
+Expr : Expr '+' Term | Term;
+Term : 'ID' | 'INT' | '(' Expr ')';";
+
+    /// Replace the bytes `start..end` of `old` with `new`, reparse, and check that the result is
+    /// the same as parsing from scratch, returning the number of lexemes covered by reused subtrees.
+    fn check_reparse(old: &str, start: usize, end: usize, new: &str) -> usize {
+        let new_input = format!("{}{}{}", &old[..start], new, &old[end..]);
+        let edit = TextEdit::new(start, end, start + new.len());
+        let (reparsed, fresh, counts) = do_reparse(LEXS, GRMS, "Stmts", old, &new_input, edit);
+        assert_eq!(reparsed, fresh);
+        counts.reused_lexemes
+    }
+
+    #[test]
+    fn reuse_before_edit() {
+        let old = "a=1;b=2+c;d=(3);";
+        assert!(check_reparse(old, old.len(), old.len(), "e=4;") > 0);
+        assert!(check_reparse(old, 12, 15, "4+5") > 0);
+    }
+
+    #[test]
+    fn reuse_after_edit() {
+        let old = "a=1;b=2+c;d=(3);";
+        assert!(check_reparse(old, 0, 0, "z=0;") > 0);
+        assert!(check_reparse(old, 6, 7, "2+5") > 0);
+        assert!(check_reparse(old, 6, 9, "e") > 0);
+    }
+
+    #[test]
+    fn changed_lookahead() {
+        // Subtrees whose following token has changed are reparsed.
+        let old = "a=1;b=2+c;";
+        check_reparse(old, 9, 9, "+d");
+        check_reparse(old, 3, 3, "+b");
+        check_reparse(old, 0, old.len(), "");
+        check_reparse("", 0, 0, "a=1;");
+    }
+
+    #[test]
+    fn relex_window() {
+        // Lexemes next to the edit are relexed, so that they can merge with the new text.
+        let old = "ab=1;cd=2;";
+        check_reparse(old, 2, 2, "x");
+        check_reparse(old, 0, 0, "x");
+        check_reparse(old, 5, 5, "x");
+        check_reparse(old, 4, 5, "");
+        check_reparse(old, 6, 7, "123");
+    }
+
+    #[test]
+    fn reuse_lists() {
+        let old = "a=1+2+3+4;b=5;c=6+7;d=8;";
+        // Edits in the middle of lists, including the list of expressions within a statement.
+        for (start, end, new) in [
+            (4, 5, "9"),
+            (4, 5, "9+0"),
+            (6, 7, ""),
+            (11, 14, ""),
+            (14, 14, "x=1;"),
+            (10, 10, "x=1;"),
+        ] {
+            assert!(check_reparse(old, start, end, new) > 0);
+        }
+    }
+
+    #[test]
+    fn reuse_large() {
+        // Reparsing a large list after an edit in its middle only reshifts the lexemes around the
+        // edit: the elements before it are reused as one subtree, and those after it are appended
+        // to the new list in one go.
+        let old = (0..2000)
+            .map(|i| format!("x{}=1+{};", "x".repeat(i % 3), i))
+            .collect::<String>();
+        let start = old.len() / 2 + old[old.len() / 2..].find('=').unwrap() + 1;
+        let new_input = format!("{}(2+3){}", &old[..start], &old[start + 1..]);
+        let edit = TextEdit::new(start, start + 1, start + 5);
+        let (reparsed, fresh, counts) = do_reparse(LEXS, GRMS, "Stmts", &old, &new_input, edit);
+        assert_eq!(reparsed, fresh);
+        // Only `=(2+3)` is relexed.
+        assert_eq!(counts.relexed, 6);
+        assert!(counts.reused <= 4);
+        assert!(counts.pulled <= 20);
+        assert!(counts.reused_lexemes >= 12000 + 4 - 12);
+    }
+
+    #[test]
+    fn reuse_from_entry() {
+        let grms = "%start Stmts
+%entry Expr
+%%
+Stmts : Stmts Stmt | ;
+Stmt : 'ID' '=' Expr ';';
+Expr : Expr '+' Term | Term;
+Term : 'ID' | 'INT' | '(' Expr ')';";
+        let old = "a+(1+2)+b";
+        let new = "a+(1+2)+c+b";
+        let (reparsed, fresh, counts) =
+            do_reparse(LEXS, grms, "Expr", old, new, TextEdit::new(8, 8, 10));
+        assert_eq!(reparsed, fresh);
+        assert!(counts.reused_lexemes > 0);
+    }
+
+    #[test]
+    fn errors() {
+        // Error recovery chooses arbitrarily amongst equally good repairs, so these edits have only
+        // one best repair.
+        let old = "a=1;b=2+c;d=(3);";
+        check_reparse(old, 5, 6, "");
+        check_reparse(old, 12, 13, "");
+        check_reparse(old, 1, 2, "");
+        check_reparse(old, 15, 16, "");
+    }
+}
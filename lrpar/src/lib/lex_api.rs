@@ -79,6 +79,26 @@ pub trait NonStreamingLexer<'input, LexemeT: Lexeme<StorageT>, StorageT: Hash +
     /// If these requirements are not respected this function may panic or return unexpected
     /// portions of the input.
     fn line_col(&self, span: Span) -> ((usize, usize), (usize, usize));
+
+    /// Iterate over the lexemes (and lexing errors) in this lexer which start at or after byte
+    /// `start`, which must be a lexer-safe point: the end of a lexeme such that at most one
+    /// character after it was looked at when it was lexed, or 0. Lexing from a lexer-safe point
+    /// produces the same lexemes as lexing the whole input does from that point onwards.
+    ///
+    /// The default implementation lexes the whole input, discarding the lexemes before `start`:
+    /// lexers which can start lexing at an arbitrary point should override it.
+    fn iter_from<'a>(
+        &'a self,
+        start: usize,
+    ) -> Box<dyn Iterator<Item = Result<LexemeT, LexError>> + 'a>
+    where
+        LexemeT: 'a,
+    {
+        Box::new(self.iter().filter(move |r| match r {
+            Ok(l) => l.span().start() >= start,
+            Err(e) => e.span().start() >= start,
+        }))
+    }
 }
 
 /// A lexeme represents a segment of the user's input that conforms to a known type: this trait
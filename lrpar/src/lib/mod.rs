@@ -165,18 +165,18 @@
 //!
 //! ```text
 //! >>> 2 + + 3
-//! Parsing error at line 1 column 5. Repair sequences found:
+//! Parsing error at line 1 column 5. Expected one of `INT`, `(`. Repair sequences found:
 //!    1: Delete +
 //!    2: Insert INT
 //! Result: 5
 //! >>> 2 + 3 3
-//! Parsing error at line 1 column 7. Repair sequences found:
+//! Parsing error at line 1 column 7. Expected one of `+`, `*`, end of input. Repair sequences found:
 //!    1: Insert *
 //!    2: Insert +
 //!    3: Delete 3
 //! Result: 11
 //! >>> 2 + 3 4 5
-//! Parsing error at line 1 column 7. Repair sequences found:
+//! Parsing error at line 1 column 7. Expected one of `+`, `*`, end of input. Repair sequences found:
 //!    1: Insert *, Delete 4
 //!    2: Insert +, Delete 4
 //!    3: Delete 4, Delete 5
@@ -193,6 +193,9 @@ mod cpctplus;
 #[doc(hidden)]
 pub mod ctbuilder;
 mod dijkstra;
+mod error_messages;
+mod glr;
+mod incremental;
 #[doc(hidden)]
 pub mod lex_api;
 #[doc(hidden)]
@@ -201,9 +204,15 @@ pub mod parser;
 mod test_utils;
 
 pub use crate::{
-    ctbuilder::{CTParser, CTParserBuilder, Visibility},
+    ctbuilder::{CTParser, CTParserBuilder, TablesKind, Visibility},
+    error_messages::{ErrorMessages, ErrorMessagesError, ErrorMessagesErrorKind},
+    glr::{Packed, Sppf, SppfIdx, SppfNode},
+    incremental::{IncrementalNode, IncrementalTree, TextEdit},
     lex_api::{LexError, Lexeme, Lexer, NonStreamingLexer},
-    parser::{LexParseError, Node, ParseError, ParseRepair, RTParserBuilder, RecoveryKind},
+    parser::{
+        LexParseError, Node, ParseError, ParseRepair, PushParser, RTParserBuilder, RecoveryBudget,
+        RecoveryContext, RecoveryKind, RecoveryStrategy, RepairEdit,
+    },
 };
 
 /// A convenience macro for including statically compiled `.y` files. A file `src/a/b/c.ly
@@ -1,18 +1,26 @@
 use std::{
+    cell::Cell,
+    cmp,
+    collections::VecDeque,
     error::Error,
     fmt::{self, Debug, Display},
     hash::Hash,
     marker::PhantomData,
+    ops::Index,
     time::{Duration, Instant},
     vec,
 };
 
 use cactus::Cactus;
-use cfgrammar::{yacc::YaccGrammar, RIdx, TIdx};
+use cfgrammar::{yacc::YaccGrammar, PIdx, RIdx, TIdx};
 use lrtable::{Action, StIdx, StateTable};
 use num_traits::{AsPrimitive, PrimInt, Unsigned};
 
-use crate::{cpctplus, LexError, Lexeme, NonStreamingLexer, Span};
+use crate::{
+    cpctplus, glr,
+    incremental::{self, IncrementalTree, Reuser, Subtree, TextEdit},
+    LexError, Lexeme, NonStreamingLexer, Span, Sppf,
+};
 
 #[cfg(test)]
 const RECOVERY_TIME_BUDGET: u64 = 60_000; // milliseconds
@@ -63,15 +71,33 @@ where
     }
 }
 
-type PStack = Vec<StIdx>; // Parse stack
+type PStack<StorageT> = Vec<StIdx<StorageT>>; // Parse stack
 type TokenCostFn<'a, StorageT> = &'a (dyn Fn(TIdx<StorageT>) -> u8 + 'a);
-type ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT> = &'a dyn Fn(
-    RIdx<StorageT>,
-    &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
-    Span,
-    vec::Drain<AStackType<LexemeT, ActionT>>,
-    ParamT,
-) -> ActionT;
+pub(crate) type ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT> =
+    &'a dyn Fn(
+        RIdx<StorageT>,
+        &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+        Span,
+        vec::Drain<AStackType<LexemeT, ActionT>>,
+        ParamT,
+    ) -> ActionT;
+pub(crate) type MergeFn<'a, ActionT> = &'a dyn Fn(ActionT, ActionT) -> ActionT;
+/// A function which, given the parse stack, the value on top of the stack (if it is a value rather
+/// than a lexeme), and the index of the next lexeme, can return a value from a previous parse to
+/// be used in place of some of the lexemes starting at the next lexeme.
+pub(super) type ReuseFn<'a, StorageT, ActionT> =
+    &'a dyn Fn(&[StIdx<StorageT>], Option<&ActionT>, usize) -> Option<Reuse<StorageT, ActionT>>;
+
+/// A value from a previous parse returned by a [ReuseFn].
+pub(super) enum Reuse<StorageT, ActionT> {
+    /// Shift the value, as if the next `usize` lexemes, which span `Span`, had been parsed and
+    /// reduced to the rule `RIdx`.
+    Shift(RIdx<StorageT>, ActionT, usize, Span),
+    /// Replace the value on top of the stack with this one, as if the next `usize` lexemes, which
+    /// end at the given byte, had been parsed and then reduced along with the value on top of the
+    /// stack, leaving the parser in the same state as before.
+    Extend(ActionT, usize, usize),
+}
 
 #[derive(Debug)]
 pub enum AStackType<LexemeT, ActionT> {
@@ -79,6 +105,101 @@ pub enum AStackType<LexemeT, ActionT> {
     Lexeme(LexemeT),
 }
 
+/// The state of an in-progress LR parse, allowing [Parser::lr] to be resumed.
+struct LrState<LexemeT: Lexeme<StorageT>, StorageT: Hash, ActionT> {
+    laidx: usize,
+    pstack: PStack<StorageT>,
+    astack: Vec<AStackType<LexemeT, ActionT>>,
+    spans: Vec<Span>,
+    errors: Vec<LexParseError<LexemeT, StorageT>>,
+    recovery_budget: RecoveryBudget,
+    /// An error which panic mode recovery is waiting for more lexemes to recover from.
+    panicking: Option<ParseError<LexemeT, StorageT>>,
+    /// The position of the lookahead after the most recent panic mode recovery: if another error
+    /// occurs there, at least one lexeme must be discarded so that the parser makes progress.
+    panic_laidx: Option<usize>,
+}
+
+impl<LexemeT: Lexeme<StorageT>, StorageT: 'static + Hash + PrimInt + Unsigned, ActionT>
+    LrState<LexemeT, StorageT, ActionT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    fn new(start_state: StIdx<StorageT>, recovery_budget: RecoveryBudget) -> Self {
+        LrState {
+            laidx: 0,
+            pstack: vec![start_state],
+            astack: Vec::new(),
+            spans: Vec::new(),
+            errors: Vec::new(),
+            recovery_budget,
+            panicking: None,
+            panic_laidx: None,
+        }
+    }
+}
+
+/// The lexemes a parser currently has available. Lexemes are indexed by their position in the
+/// input, but those which the parser can no longer need are dropped, so that the input's lexemes
+/// need never be held in memory in their entirety.
+pub(super) struct Lexemes<LexemeT> {
+    /// The position in the input of `buf[0]`.
+    off: usize,
+    buf: VecDeque<LexemeT>,
+}
+
+impl<LexemeT> Lexemes<LexemeT> {
+    fn new() -> Self {
+        Lexemes {
+            off: 0,
+            buf: VecDeque::new(),
+        }
+    }
+
+    /// Return the position in the input of the lexeme after the last lexeme available.
+    pub(super) fn len(&self) -> usize {
+        self.off + self.buf.len()
+    }
+
+    fn push(&mut self, lexeme: LexemeT) {
+        self.buf.push_back(lexeme);
+    }
+
+    /// Drop all lexemes before position `laidx`.
+    fn drop_before(&mut self, laidx: usize) {
+        let n = laidx.saturating_sub(self.off).min(self.buf.len());
+        self.buf.drain(..n);
+        self.off += n;
+    }
+
+    /// If position `laidx` is after the last lexeme available, drop all lexemes, so that the next
+    /// lexeme pushed is at position `laidx`.
+    fn skip_to(&mut self, laidx: usize) {
+        if laidx > self.len() {
+            self.buf.clear();
+            self.off = laidx;
+        }
+    }
+}
+
+impl<LexemeT> Index<usize> for Lexemes<LexemeT> {
+    type Output = LexemeT;
+
+    fn index(&self, laidx: usize) -> &LexemeT {
+        &self.buf[laidx - self.off]
+    }
+}
+
+/// The outcome of [Parser::lr].
+enum LrOutcome<ActionT> {
+    /// The parse reached an accept state.
+    Accept(ActionT),
+    /// Some of the input could not be parsed, even after possibly making repairs.
+    Fail,
+    /// More lexemes are needed before parsing can continue.
+    NeedInput,
+}
+
 pub(super) struct Parser<
     'a,
     'b: 'a,
@@ -89,15 +210,16 @@ pub(super) struct Parser<
     ParamT: Copy,
 > {
     rcvry_kind: RecoveryKind,
+    rcvry_budget: RecoveryBudget,
+    rcvry_strategy: Option<&'a dyn RecoveryStrategy<LexemeT, StorageT>>,
     pub(super) grm: &'a YaccGrammar<StorageT>,
     pub(super) token_cost: Box<TokenCostFn<'a, StorageT>>,
     pub(super) stable: &'a StateTable<StorageT>,
     lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
-    // In the long term, we should remove the `lexemes` field entirely, as the `NonStreamingLexer` API is
-    // powerful enough to allow us to incrementally obtain lexemes and buffer them when necessary.
-    pub(super) lexemes: Vec<LexemeT>,
+    pub(super) lexemes: Lexemes<LexemeT>,
     actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
     param: ParamT,
+    reuse: Option<ReuseFn<'a, StorageT, ActionT>>,
 }
 
 impl<
@@ -112,11 +234,13 @@ where
 {
     fn parse_generictree(
         rcvry_kind: RecoveryKind,
+        rcvry_budget: RecoveryBudget,
+        rcvry_strategy: Option<&'a dyn RecoveryStrategy<LexemeT, StorageT>>,
         grm: &YaccGrammar<StorageT>,
         token_cost: TokenCostFn<'a, StorageT>,
         stable: &StateTable<StorageT>,
+        start_state: StIdx<StorageT>,
         lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
-        lexemes: Vec<LexemeT>,
     ) -> (
         Option<Node<LexemeT, StorageT>>,
         Vec<LexParseError<LexemeT, StorageT>>,
@@ -130,20 +254,18 @@ where
         actions.resize(usize::from(grm.prods_len()), &Parser::generic_ptree);
         let psr = Parser {
             rcvry_kind,
+            rcvry_budget,
+            rcvry_strategy,
             grm,
             token_cost: Box::new(token_cost),
             stable,
             lexer,
-            lexemes,
+            lexemes: Lexemes::new(),
             actions: actions.as_slice(),
             param: (),
+            reuse: None,
         };
-        let mut pstack = vec![stable.start_state()];
-        let mut astack = Vec::new();
-        let mut errors = Vec::new();
-        let mut spans = Vec::new();
-        let accpt = psr.lr(0, &mut pstack, &mut astack, &mut errors, &mut spans);
-        (accpt, errors)
+        psr.lr_stream(start_state)
     }
 
     fn generic_ptree(
@@ -164,6 +286,126 @@ where
     }
 }
 
+impl<
+        'a,
+        'b: 'a,
+        'input: 'b,
+        LexemeT: Lexeme<StorageT>,
+        StorageT: 'static + Debug + Hash + PrimInt + Unsigned,
+    > Parser<'a, 'b, 'input, LexemeT, StorageT, Subtree<StorageT>, ()>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    fn parse_incremental(
+        rcvry_kind: RecoveryKind,
+        rcvry_budget: RecoveryBudget,
+        rcvry_strategy: Option<&'a dyn RecoveryStrategy<LexemeT, StorageT>>,
+        grm: &YaccGrammar<StorageT>,
+        token_cost: TokenCostFn<'a, StorageT>,
+        stable: &StateTable<StorageT>,
+        start_state: StIdx<StorageT>,
+        lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+    ) -> (
+        Option<Subtree<StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        for tidx in grm.iter_tidxs() {
+            assert!(token_cost(tidx) > 0);
+        }
+        let mut actions: Vec<ActionFn<'a, 'b, 'input, LexemeT, StorageT, Subtree<StorageT>, ()>> =
+            Vec::new();
+        actions.resize(usize::from(grm.prods_len()), &Parser::incremental_ptree);
+        let psr = Parser {
+            rcvry_kind,
+            rcvry_budget,
+            rcvry_strategy,
+            grm,
+            token_cost: Box::new(token_cost),
+            stable,
+            lexer,
+            lexemes: Lexemes::new(),
+            actions: actions.as_slice(),
+            param: (),
+            reuse: None,
+        };
+        psr.lr_stream(start_state)
+    }
+
+    /// Parse the lexemes returned by `lexeme` (which returns the lexeme at a given position in
+    /// the input, or `None` if the input has fewer lexemes) starting from `start_state`, using
+    /// the values `reuse` returns rather than reparsing the lexemes they cover. Lexemes are only
+    /// requested when the parser needs them, so those covered by reused values need not be
+    /// requested at all.
+    fn reparse_incremental(
+        rcvry_kind: RecoveryKind,
+        rcvry_budget: RecoveryBudget,
+        rcvry_strategy: Option<&'a dyn RecoveryStrategy<LexemeT, StorageT>>,
+        grm: &YaccGrammar<StorageT>,
+        token_cost: TokenCostFn<'a, StorageT>,
+        stable: &StateTable<StorageT>,
+        start_state: StIdx<StorageT>,
+        lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+        lexeme: &dyn Fn(usize) -> Option<LexemeT>,
+        reuse: ReuseFn<'_, StorageT, Subtree<StorageT>>,
+    ) -> (
+        Option<Subtree<StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        for tidx in grm.iter_tidxs() {
+            assert!(token_cost(tidx) > 0);
+        }
+        let mut actions: Vec<ActionFn<'a, 'b, 'input, LexemeT, StorageT, Subtree<StorageT>, ()>> =
+            Vec::new();
+        actions.resize(usize::from(grm.prods_len()), &Parser::incremental_ptree);
+        let mut psr = Parser {
+            rcvry_kind,
+            rcvry_budget,
+            rcvry_strategy,
+            grm,
+            token_cost: Box::new(token_cost),
+            stable,
+            lexer,
+            lexemes: Lexemes::new(),
+            actions: actions.as_slice(),
+            param: (),
+            reuse: Some(reuse),
+        };
+        let mut st = LrState::new(start_state, rcvry_budget);
+        let mut complete = false;
+        loop {
+            let r = match psr.lr(&mut st, complete) {
+                LrOutcome::Accept(v) => Some(v),
+                LrOutcome::Fail => None,
+                LrOutcome::NeedInput => {
+                    psr.drop_consumed(&st);
+                    // A reused value may have moved the lookahead beyond the lexemes available,
+                    // in which case the lexeme before the lookahead is requested first (see
+                    // [Parser::drop_consumed]).
+                    let laidx = cmp::max(psr.lexemes.len(), st.laidx.saturating_sub(1));
+                    psr.lexemes.skip_to(laidx);
+                    match lexeme(laidx) {
+                        Some(l) => psr.lexemes.push(l),
+                        None => complete = true,
+                    }
+                    continue;
+                }
+            };
+            sort_errors(&mut st.errors);
+            return (r, st.errors);
+        }
+    }
+
+    fn incremental_ptree(
+        ridx: RIdx<StorageT>,
+        _lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+        _span: Span,
+        astack: vec::Drain<AStackType<LexemeT, Subtree<StorageT>>>,
+        _param: (),
+    ) -> Subtree<StorageT> {
+        incremental::reduce(ridx, astack)
+    }
+}
+
 impl<
         'a,
         'b: 'a,
@@ -176,11 +418,13 @@ where
 {
     fn parse_noaction(
         rcvry_kind: RecoveryKind,
+        rcvry_budget: RecoveryBudget,
+        rcvry_strategy: Option<&'a dyn RecoveryStrategy<LexemeT, StorageT>>,
         grm: &YaccGrammar<StorageT>,
         token_cost: TokenCostFn<'a, StorageT>,
         stable: &StateTable<StorageT>,
+        start_state: StIdx<StorageT>,
         lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
-        lexemes: Vec<LexemeT>,
     ) -> Vec<LexParseError<LexemeT, StorageT>> {
         for tidx in grm.iter_tidxs() {
             assert!(token_cost(tidx) > 0);
@@ -189,20 +433,18 @@ where
         actions.resize(usize::from(grm.prods_len()), &Parser::noaction);
         let psr = Parser {
             rcvry_kind,
+            rcvry_budget,
+            rcvry_strategy,
             grm,
             token_cost: Box::new(token_cost),
             stable,
             lexer,
-            lexemes,
+            lexemes: Lexemes::new(),
             actions: actions.as_slice(),
             param: (),
+            reuse: None,
         };
-        let mut pstack = vec![stable.start_state()];
-        let mut astack = Vec::new();
-        let mut errors = Vec::new();
-        let mut spans = Vec::new();
-        psr.lr(0, &mut pstack, &mut astack, &mut errors, &mut spans);
-        errors
+        psr.lr_stream(start_state).1
     }
 
     fn noaction(
@@ -229,11 +471,13 @@ where
 {
     fn parse_actions(
         rcvry_kind: RecoveryKind,
+        rcvry_budget: RecoveryBudget,
+        rcvry_strategy: Option<&'a dyn RecoveryStrategy<LexemeT, StorageT>>,
         grm: &'a YaccGrammar<StorageT>,
         token_cost: TokenCostFn<'a, StorageT>,
         stable: &'a StateTable<StorageT>,
+        start_state: StIdx<StorageT>,
         lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
-        lexemes: Vec<LexemeT>,
         actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
         param: ParamT,
     ) -> (Option<ActionT>, Vec<LexParseError<LexemeT, StorageT>>) {
@@ -242,143 +486,414 @@ where
         }
         let psr = Parser {
             rcvry_kind,
+            rcvry_budget,
+            rcvry_strategy,
             grm,
             token_cost: Box::new(token_cost),
             stable,
             lexer,
-            lexemes,
+            lexemes: Lexemes::new(),
             actions,
             param,
+            reuse: None,
         };
-        let mut pstack = vec![stable.start_state()];
-        let mut astack = Vec::new();
-        let mut errors = Vec::new();
-        let mut spans = Vec::new();
-        let accpt = psr.lr(0, &mut pstack, &mut astack, &mut errors, &mut spans);
-        (accpt, errors)
+        psr.lr_stream(start_state)
     }
 
-    /// Start parsing text at `laidx` (using the lexeme in `lexeme_prefix`, if it is not `None`,
-    /// as the first lexeme) up to (but excluding) `end_laidx` (if it's specified). Parsing
-    /// continues as long as possible (assuming that any errors encountered can be recovered from)
-    /// unless `end_laidx` is `None`, at which point this function returns as soon as it
-    /// encounters an error.
-    ///
-    /// Note that if `lexeme_prefix` is specified, `laidx` will still be incremented, and thus
-    /// `end_laidx` *must* be set to `laidx + 1` in order that the parser doesn't skip the real
-    /// lexeme at position `laidx`.
-    ///
-    /// Return `Some(value)` if the parse reached an accept state (i.e. all the input was consumed,
+    /// Parse the lexemes produced by `self.lexer` starting from `start_state`, returning
+    /// `Some(value)` if the parse reached an accept state (i.e. all the input was consumed,
     /// possibly after making repairs) or `None` (i.e. some of the input was not consumed, even
-    /// after possibly making repairs) otherwise.
+    /// after possibly making repairs) otherwise, and any errors encountered. Lexemes are obtained
+    /// from the lexer only when the parser needs them, and dropped as soon as the parser can no
+    /// longer need them. If the lexer produces an error and error recovery is turned on, the input
+    /// it could not lex is passed to the parser as an error lexeme (see [error_lexeme]), which
+    /// causes a parsing error that the recoverer repairs like any other (e.g. CPCT+ can delete
+    /// it): lexing and parsing errors are returned together, in the order in which they occur in
+    /// the input. Without error recovery, parsing stops at the first lexing error.
+    fn lr_stream(
+        mut self,
+        start_state: StIdx<StorageT>,
+    ) -> (Option<ActionT>, Vec<LexParseError<LexemeT, StorageT>>) {
+        let mut st = LrState::new(start_state, self.rcvry_budget);
+        let lexer = self.lexer;
+        let mut iter = lexer.iter();
+        let mut complete = false;
+        loop {
+            let r = match self.lr(&mut st, complete) {
+                LrOutcome::Accept(v) => Some(v),
+                LrOutcome::Fail => None,
+                LrOutcome::NeedInput => {
+                    self.drop_consumed(&st);
+                    match iter.next() {
+                        Some(Ok(l)) => self.lexemes.push(l),
+                        Some(Err(e)) => {
+                            if !self.recovers() {
+                                return (None, vec![e.into()]);
+                            }
+                            self.lexemes.push(error_lexeme(self.grm, &e));
+                            st.errors.push(e.into());
+                        }
+                        None => complete = true,
+                    }
+                    continue;
+                }
+            };
+            sort_errors(&mut st.errors);
+            return (r, st.errors);
+        }
+    }
+
+    /// Drop the lexemes which the parse described by `st` can no longer need. The lexeme before
+    /// the lookahead is kept, as the span of an EOF lexeme is derived from it.
+    fn drop_consumed(&mut self, st: &LrState<LexemeT, StorageT, ActionT>) {
+        self.lexemes.drop_before(st.laidx.saturating_sub(1));
+    }
+
+    /// Continue the parse described by `st`. Parsing continues as long as possible (assuming that
+    /// any errors encountered can be recovered from). If `complete` is false, `self.lexemes` may
+    /// later be extended: rather than treating the end of `self.lexemes` as the end of the input,
+    /// parsing stops when the lookahead runs out, or when an error is encountered without enough
+    /// lexemes for the recoverer to look ahead as far as it would on complete input, and
+    /// [LrOutcome::NeedInput] is returned. Calling this function again (with more lexemes) then
+    /// continues the parse exactly as if all the lexemes had been available from the start.
     fn lr(
         &self,
-        mut laidx: usize,
-        pstack: &mut PStack,
-        astack: &mut Vec<AStackType<LexemeT, ActionT>>,
-        errors: &mut Vec<LexParseError<LexemeT, StorageT>>,
-        spans: &mut Vec<Span>,
-    ) -> Option<ActionT> {
+        st: &mut LrState<LexemeT, StorageT, ActionT>,
+        complete: bool,
+    ) -> LrOutcome<ActionT> {
         let mut recoverer = None;
-        let mut recovery_budget = Duration::from_millis(RECOVERY_TIME_BUDGET);
         loop {
-            debug_assert_eq!(astack.len(), spans.len());
-            let stidx = *pstack.last().unwrap();
-            let la_tidx = self.next_tidx(laidx);
+            debug_assert_eq!(st.astack.len(), st.spans.len());
+            if !complete && st.laidx >= self.lexemes.len() {
+                return LrOutcome::NeedInput;
+            }
+            let stidx = *st.pstack.last().unwrap();
+            let la_tidx = self.next_tidx(st.laidx);
 
             match self.stable.action(stidx, la_tidx) {
                 Action::Reduce(pidx) => {
-                    let ridx = self.grm.prod_to_rule(pidx);
-                    let pop_idx = pstack.len() - self.grm.prod(pidx).len();
-
-                    pstack.drain(pop_idx..);
-                    let prior = *pstack.last().unwrap();
-                    pstack.push(self.stable.goto(prior, ridx).unwrap());
-
-                    let span = if spans.is_empty() {
-                        Span::new(0, 0)
-                    } else if pop_idx - 1 < spans.len() {
-                        Span::new(spans[pop_idx - 1].start(), spans[spans.len() - 1].end())
-                    } else {
-                        Span::new(spans[spans.len() - 1].start(), spans[spans.len() - 1].end())
-                    };
-                    spans.truncate(pop_idx - 1);
-                    spans.push(span);
-
-                    let v = AStackType::ActionType(self.actions[usize::from(pidx)](
-                        ridx,
-                        self.lexer,
-                        span,
-                        astack.drain(pop_idx - 1..),
-                        self.param,
-                    ));
-                    astack.push(v);
+                    // A reused value may start with the empty production about to be reduced.
+                    if self.grm.prod(pidx).is_empty() && self.shift_reused(st) {
+                        continue;
+                    }
+                    self.reduce(st, pidx)
                 }
                 Action::Shift(state_id) => {
-                    let la_lexeme = self.next_lexeme(laidx);
-                    pstack.push(state_id);
-                    astack.push(AStackType::Lexeme(la_lexeme));
+                    if self.shift_reused(st) {
+                        continue;
+                    }
+                    let la_lexeme = self.next_lexeme(st.laidx);
+                    st.pstack.push(state_id);
+                    st.astack.push(AStackType::Lexeme(la_lexeme));
 
-                    spans.push(la_lexeme.span());
-                    laidx += 1;
+                    st.spans.push(la_lexeme.span());
+                    st.laidx += 1;
                 }
                 Action::Accept => {
                     debug_assert_eq!(la_tidx, self.grm.eof_token_idx());
-                    debug_assert_eq!(astack.len(), 1);
-                    match astack.drain(..).next().unwrap() {
-                        AStackType::ActionType(v) => return Some(v),
+                    debug_assert_eq!(st.astack.len(), 1);
+                    match st.astack.drain(..).next().unwrap() {
+                        AStackType::ActionType(v) => return LrOutcome::Accept(v),
                         _ => unreachable!(),
                     }
                 }
                 Action::Error => {
-                    if recoverer.is_none() {
-                        recoverer = Some(match self.rcvry_kind {
-                            RecoveryKind::CPCTPlus => cpctplus::recoverer(self),
-                            RecoveryKind::None => {
-                                let la_lexeme = self.next_lexeme(laidx);
-                                errors.push(
-                                    ParseError {
-                                        stidx,
-                                        lexeme: la_lexeme,
-                                        repairs: vec![],
+                    let err = match st.panicking.take() {
+                        Some(err) => err,
+                        None => {
+                            if !complete
+                                && (self.rcvry_strategy.is_some()
+                                    || matches!(self.rcvry_kind, RecoveryKind::CPCTPlus))
+                                && self.lexemes.len() < st.laidx + cpctplus::LOOKAHEAD
+                            {
+                                return LrOutcome::NeedInput;
+                            }
+                            // The recoverer alters the parse stack, so we have to work out what
+                            // tokens were expected before it runs.
+                            let mut err = ParseError {
+                                stidx,
+                                lexeme: self.next_lexeme(st.laidx),
+                                expected: self.stable.expected_tokens(self.grm, &st.pstack),
+                                repairs: vec![],
+                            };
+                            match (self.rcvry_strategy, self.rcvry_kind) {
+                                (Some(strategy), _) => {
+                                    let repairs = self.recover_with_strategy(strategy, st);
+                                    if !repairs.is_empty() {
+                                        err.repairs = repairs;
+                                        st.errors.push(err.into());
+                                        continue;
                                     }
-                                    .into(),
-                                );
-                                return None;
+                                    // The strategy couldn't find any repairs, so fall back to
+                                    // panic mode recovery.
+                                }
+                                (None, RecoveryKind::CPCTPlus) => {
+                                    if recoverer.is_none() {
+                                        recoverer = Some(cpctplus::recoverer(self));
+                                    }
+                                    let allowance = RecoveryAllowance::new(st.recovery_budget);
+                                    let (new_laidx, repairs) =
+                                        recoverer.as_ref().unwrap().as_ref().recover(
+                                            &allowance,
+                                            self,
+                                            st.laidx,
+                                            &mut st.pstack,
+                                            &mut st.astack,
+                                            &mut st.spans,
+                                        );
+                                    st.recovery_budget = allowance.remaining();
+                                    if !repairs.is_empty() {
+                                        err.repairs = repairs;
+                                        st.errors.push(err.into());
+                                        st.laidx = new_laidx;
+                                        continue;
+                                    }
+                                    // CPCT+ couldn't find any repairs, so fall back to panic
+                                    // mode recovery.
+                                }
+                                (None, RecoveryKind::Panic) => (),
+                                (None, RecoveryKind::None) => {
+                                    st.errors.push(err.into());
+                                    return LrOutcome::Fail;
+                                }
                             }
-                        });
-                    }
-
-                    let before = Instant::now();
-                    let finish_by = before + recovery_budget;
-                    let (new_laidx, repairs) = recoverer
-                        .as_ref()
-                        .unwrap()
-                        .as_ref()
-                        .recover(finish_by, self, laidx, pstack, astack, spans);
-                    let after = Instant::now();
-                    recovery_budget = recovery_budget
-                        .checked_sub(after - before)
-                        .unwrap_or_else(|| Duration::new(0, 0));
-                    let keep_going = !repairs.is_empty();
-                    let la_lexeme = self.next_lexeme(laidx);
-                    errors.push(
-                        ParseError {
-                            stidx,
-                            lexeme: la_lexeme,
-                            repairs,
+                            err
+                        }
+                    };
+                    match self.panic(st, complete) {
+                        PanicOutcome::Recovered => st.errors.push(err.into()),
+                        PanicOutcome::NeedInput => {
+                            st.panicking = Some(err);
+                            return LrOutcome::NeedInput;
+                        }
+                        PanicOutcome::Fail => {
+                            st.errors.push(err.into());
+                            return LrOutcome::Fail;
                         }
-                        .into(),
-                    );
-                    if !keep_going {
-                        return None;
                     }
-                    laidx = new_laidx;
                 }
             }
         }
     }
 
+    /// If `self.reuse` returns a value from a previous parse to be used in place of the lexemes
+    /// at the lookahead of the parse described by `st`, update `st` as if those lexemes had been
+    /// parsed, and return true.
+    fn shift_reused(&self, st: &mut LrState<LexemeT, StorageT, ActionT>) -> bool {
+        let reuse = match self.reuse {
+            Some(f) => f,
+            None => return false,
+        };
+        let top = match st.astack.last() {
+            Some(AStackType::ActionType(v)) => Some(v),
+            _ => None,
+        };
+        match reuse(&st.pstack, top, st.laidx) {
+            Some(Reuse::Shift(ridx, v, len, span)) => {
+                let stidx = *st.pstack.last().unwrap();
+                st.pstack.push(self.stable.goto(stidx, ridx).unwrap());
+                st.astack.push(AStackType::ActionType(v));
+                st.spans.push(span);
+                st.laidx += len;
+            }
+            Some(Reuse::Extend(v, len, end)) => {
+                *st.astack.last_mut().unwrap() = AStackType::ActionType(v);
+                let span = st.spans.last_mut().unwrap();
+                *span = Span::new(span.start(), end);
+                st.laidx += len;
+            }
+            None => return false,
+        }
+        true
+    }
+
+    /// Ask `strategy` how to recover from a syntax error at the lookahead of the parse described
+    /// by `st`, and apply the first repair sequence it returns which makes progress (see
+    /// [RecoveryContext::makes_progress]). Returns the repair sequences which make progress, in
+    /// the order `strategy` returned them: if there are none, `st` is left unchanged.
+    fn recover_with_strategy(
+        &self,
+        strategy: &dyn RecoveryStrategy<LexemeT, StorageT>,
+        st: &mut LrState<LexemeT, StorageT, ActionT>,
+    ) -> Vec<Vec<ParseRepair<LexemeT, StorageT>>> {
+        let allowance = RecoveryAllowance::new(st.recovery_budget);
+        let end_laidx = cmp::min(st.laidx + cpctplus::LOOKAHEAD, self.lexemes.len() + 1);
+        let ctx = RecoveryContext {
+            grm: self.grm,
+            stable: self.stable,
+            pstack: &st.pstack,
+            lookahead: (st.laidx..end_laidx)
+                .map(|laidx| self.next_lexeme(laidx))
+                .collect(),
+            allowance: &allowance,
+        };
+        let repairs = strategy
+            .recover(&ctx)
+            .into_iter()
+            .filter(|rprs| ctx.makes_progress(rprs))
+            .collect::<Vec<_>>();
+        st.recovery_budget = allowance.remaining();
+        if let Some(rprs) = repairs.first() {
+            st.laidx = cpctplus::apply_repairs(
+                self,
+                st.laidx,
+                &mut st.pstack,
+                &mut Some(&mut st.astack),
+                &mut Some(&mut st.spans),
+                rprs,
+            );
+        }
+        repairs
+    }
+
+    /// Reduce production `pidx` in the parse described by `st`, executing its action.
+    fn reduce(&self, st: &mut LrState<LexemeT, StorageT, ActionT>, pidx: PIdx<StorageT>) {
+        let ridx = self.grm.prod_to_rule(pidx);
+        let pop_idx = st.pstack.len() - self.grm.prod(pidx).len();
+
+        st.pstack.drain(pop_idx..);
+        let prior = *st.pstack.last().unwrap();
+        st.pstack.push(self.stable.goto(prior, ridx).unwrap());
+
+        let spans = &mut st.spans;
+        let span = if spans.is_empty() {
+            Span::new(0, 0)
+        } else if pop_idx - 1 < spans.len() {
+            Span::new(spans[pop_idx - 1].start(), spans[spans.len() - 1].end())
+        } else {
+            Span::new(spans[spans.len() - 1].start(), spans[spans.len() - 1].end())
+        };
+        spans.truncate(pop_idx - 1);
+        spans.push(span);
+
+        let v = AStackType::ActionType(self.actions[usize::from(pidx)](
+            ridx,
+            self.lexer,
+            span,
+            st.astack.drain(pop_idx - 1..),
+            self.param,
+        ));
+        st.astack.push(v);
+    }
+
+    /// Recover from an error in the parse described by `st` in the style of Yacc: pop states until
+    /// one is found which can shift the `error` token; then discard lexemes until one is found
+    /// which can follow the `error` token in the resulting state; and finally shift an `error`
+    /// lexeme whose span covers the input represented by the popped states and the discarded
+    /// lexemes. If the grammar doesn't use the `error` token, no state can shift it, or the end of
+    /// the input is reached while discarding lexemes, recovery fails.
+    ///
+    /// Before any states are popped, states whose only actions are reductions by a single
+    /// production have that reduction performed (as Yacc's "default reductions" would have done
+    /// before the error was detected), so that complete constructs at the top of the parse stack
+    /// are kept rather than being swallowed by the `error` token.
+    fn panic(&self, st: &mut LrState<LexemeT, StorageT, ActionT>, complete: bool) -> PanicOutcome {
+        let err_tidx = match self.grm.error_token_idx() {
+            Some(x) => x,
+            None => return PanicOutcome::Fail,
+        };
+        // Default reductions of empty and unit productions need not shrink the stack, so in some
+        // grammars they can cycle forever. We thus cap the number of reductions at the stack's
+        // depth plus the number of productions, which is ample for sequences which don't cycle.
+        let mut budget = st.pstack.len() + usize::from(self.grm.prods_len());
+        while let Some(pidx) = self.default_reduction(*st.pstack.last().unwrap()) {
+            if budget == 0 {
+                break;
+            }
+            budget -= 1;
+            self.reduce(st, pidx);
+            if self
+                .stable
+                .action(*st.pstack.last().unwrap(), self.next_tidx(st.laidx))
+                != Action::Error
+            {
+                // The lookahead is valid after the reductions, so there's nothing to skip.
+                return PanicOutcome::Recovered;
+            }
+        }
+        // Find the topmost state which can shift the error token: `keep` is the number of states
+        // that remain on the parse stack.
+        let mut keep = st.pstack.len();
+        let err_stidx = loop {
+            if keep == 0 {
+                return PanicOutcome::Fail;
+            }
+            if let Action::Shift(stidx) = self.stable.action(st.pstack[keep - 1], err_tidx) {
+                break stidx;
+            }
+            keep -= 1;
+        };
+
+        // Discard lexemes until one is valid after the error token. If the previous recovery
+        // ended at this lexeme, it must be discarded, as otherwise we could loop forever.
+        let mut laidx = st.laidx;
+        if st.panic_laidx == Some(laidx) {
+            if laidx == self.lexemes.len() {
+                return if complete {
+                    PanicOutcome::Fail
+                } else {
+                    PanicOutcome::NeedInput
+                };
+            }
+            laidx += 1;
+        }
+        loop {
+            if laidx == self.lexemes.len() && !complete {
+                return PanicOutcome::NeedInput;
+            }
+            match self.stable.action(err_stidx, self.next_tidx(laidx)) {
+                Action::Error if laidx == self.lexemes.len() => return PanicOutcome::Fail,
+                Action::Error => laidx += 1,
+                _ => break,
+            }
+        }
+
+        let popped = &st.spans[keep - 1..];
+        let start = match popped.first() {
+            Some(span) => span.start(),
+            None => self.next_lexeme(st.laidx).span().start(),
+        };
+        let end = if laidx > st.laidx {
+            self.lexemes[laidx - 1].span().end()
+        } else {
+            popped.last().map(|span| span.end()).unwrap_or(start)
+        };
+        st.pstack.truncate(keep);
+        st.pstack.push(err_stidx);
+        st.astack.truncate(keep - 1);
+        st.astack.push(AStackType::Lexeme(Lexeme::new_faulty(
+            StorageT::from(u32::from(err_tidx)).unwrap(),
+            start,
+            end - start,
+        )));
+        st.spans.truncate(keep - 1);
+        st.spans.push(Span::new(start, end));
+        st.laidx = laidx;
+        st.panic_laidx = Some(laidx);
+        PanicOutcome::Recovered
+    }
+
+    /// Is any form of error recovery turned on?
+    fn recovers(&self) -> bool {
+        self.rcvry_strategy.is_some() || !matches!(self.rcvry_kind, RecoveryKind::None)
+    }
+
+    /// If all the actions of state `stidx` are reductions by the same production, return that
+    /// production.
+    fn default_reduction(&self, stidx: StIdx<StorageT>) -> Option<PIdx<StorageT>> {
+        let mut default = None;
+        for tidx in self.stable.state_actions(stidx) {
+            match self.stable.action(stidx, tidx) {
+                Action::Reduce(pidx) if default.is_none() || default == Some(pidx) => {
+                    default = Some(pidx)
+                }
+                Action::Error => (),
+                _ => return None,
+            }
+        }
+        default
+    }
+
     /// Parse from `laidx` up to (but excluding) `end_laidx` mutating `pstack` as parsing occurs.
     /// Returns the index of the token it parsed up to (by definition <= end_laidx: can be less if
     /// the input is < end_laidx, or if an error is encountered). Does not do any form of error
@@ -388,7 +903,7 @@ where
         lexeme_prefix: Option<LexemeT>,
         mut laidx: usize,
         end_laidx: usize,
-        pstack: &mut PStack,
+        pstack: &mut PStack<StorageT>,
         astack: &mut Option<&mut Vec<AStackType<LexemeT, ActionT>>>,
         spans: &mut Option<&mut Vec<Span>>,
     ) -> usize {
@@ -515,9 +1030,9 @@ where
         lexeme_prefix: Option<LexemeT>,
         mut laidx: usize,
         end_laidx: usize,
-        mut pstack: Cactus<StIdx>,
+        mut pstack: Cactus<StIdx<StorageT>>,
         tstack: &mut Option<&mut Vec<Node<LexemeT, StorageT>>>,
-    ) -> (usize, Cactus<StIdx>) {
+    ) -> (usize, Cactus<StIdx<StorageT>>) {
         assert!(lexeme_prefix.is_none() || end_laidx == laidx + 1);
         while laidx != end_laidx {
             let stidx = *pstack.val().unwrap();
@@ -581,80 +1096,359 @@ pub(super) trait Recoverer<
 {
     fn recover(
         &self,
-        finish_by: Instant,
+        allowance: &RecoveryAllowance,
         parser: &Parser<LexemeT, StorageT, ActionT, ParamT>,
         in_laidx: usize,
-        in_pstack: &mut PStack,
+        in_pstack: &mut PStack<StorageT>,
         astack: &mut Vec<AStackType<LexemeT, ActionT>>,
         spans: &mut Vec<Span>,
     ) -> (usize, Vec<Vec<ParseRepair<LexemeT, StorageT>>>);
 }
 
+/// The result of [Parser::panic].
+enum PanicOutcome {
+    /// The parse can continue.
+    Recovered,
+    /// More lexemes are needed to find one which can follow the `error` token.
+    NeedInput,
+    /// The error cannot be recovered from.
+    Fail,
+}
+
 /// What recovery algorithm should be used when a syntax error is encountered?
 #[derive(Clone, Copy, Debug)]
 pub enum RecoveryKind {
     /// The CPCT+ algorithm from Diekmann/Tratt "Don't Panic! Better, Fewer, Syntax Errors for LR
-    /// Parsers".
+    /// Parsers". If CPCT+ cannot find any repair sequences, and the grammar has productions which
+    /// use the `error` token, recovery falls back to that of `Panic`.
     CPCTPlus,
-    /// Don't use error recovery: return as soon as the first syntax error is encountered.
+    /// Yacc-style panic mode recovery using productions which contain the `error` token (e.g.
+    /// `Stmt: error ';'`): states are popped from the parse stack until one is found which can
+    /// shift `error`; then lexemes are discarded until one is found which can follow `error`. The
+    /// `error` token is passed to actions as a faulty lexeme whose span covers the skipped input.
+    /// The resulting [ParseError] has no repair sequences. If no state can shift `error`, parsing
+    /// stops.
+    Panic,
+    /// Don't use error recovery: return as soon as the first lexing or syntax error is
+    /// encountered.
     None,
 }
 
-/// A lexing or parsing error. Although the two are quite distinct in terms of what can be reported
-/// to users, both can (at least conceptually) occur at any point of the intertwined lexing/parsing
-/// process.
-#[derive(Debug)]
-pub enum LexParseError<LexemeT: Lexeme<StorageT>, StorageT: Hash> {
-    LexError(LexError),
-    ParseError(ParseError<LexemeT, StorageT>),
+/// How much work error recovery may do, over the course of an entire parse, before it gives up.
+/// Whatever one recovery uses is unavailable to later recoveries in the same parse.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RecoveryBudget {
+    /// Recovery may take at most this much wall-clock time. Note that the repairs found (and
+    /// whether recovery succeeds at all) then depend on how fast the machine, and how heavily
+    /// loaded it is.
+    Time(Duration),
+    /// Recovery may explore at most this many nodes in its search for repairs. Parsing identical
+    /// input thus always leads to identical repairs, no matter how fast the machine is.
+    Nodes(usize),
 }
 
-impl<LexemeT: Lexeme<StorageT>, StorageT: Hash + PrimInt + Unsigned>
-    LexParseError<LexemeT, StorageT>
-{
-    /// A pretty-printer of a lexer/parser error. This isn't suitable for all purposes, but it's
-    /// often good enough. The output format is not guaranteed to be stable but is likely to be of
-    /// the form:
-    ///
-    /// ```text
-    /// Parsing error at line 3 column 8. Repair sequences found:
-    ///   1: Insert ID
-    ///   2: Delete +, Shift 3
-    /// ```
-    ///
-    /// If you are using the compile-time parse system, your `grm_y` module exposes a suitable
-    /// [`epp`](../ctbuilder/struct.CTParserBuilder.html#method.process_file) function; if you are
-    /// using the run-time system,
-    /// [`YaccGrammar`](../../cfgrammar/yacc/grammar/struct.YaccGrammar.html) exposes a suitable
-    /// [`epp`](../../cfgrammar/yacc/grammar/struct.YaccGrammar.html#method.token_epp) function,
-    /// though you will have to wrap it in a closure e.g. `&|t| grm.token_epp(t)`.
-    pub fn pp<'a>(
-        &self,
-        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
-        epp: &dyn Fn(TIdx<StorageT>) -> Option<&'a str>,
-    ) -> String {
-        match self {
-            LexParseError::LexError(e) => {
-                let ((line, col), _) = lexer.line_col(e.span());
-                format!("Lexing error at line {} column {}.", line, col)
+impl Default for RecoveryBudget {
+    /// A time budget of 0.5 seconds.
+    fn default() -> Self {
+        RecoveryBudget::Time(Duration::from_millis(RECOVERY_TIME_BUDGET))
+    }
+}
+
+/// The part of a [RecoveryBudget] available to a single invocation of a recoverer.
+pub(super) struct RecoveryAllowance {
+    finish_by: Option<Instant>,
+    nodes: Option<Cell<usize>>,
+}
+
+impl RecoveryAllowance {
+    fn new(budget: RecoveryBudget) -> Self {
+        match budget {
+            RecoveryBudget::Time(d) => RecoveryAllowance {
+                finish_by: Some(Instant::now() + d),
+                nodes: None,
+            },
+            RecoveryBudget::Nodes(n) => RecoveryAllowance {
+                finish_by: None,
+                nodes: Some(Cell::new(n)),
+            },
+        }
+    }
+
+    /// Spend the budget for exploring one node, returning `false` if the budget is exhausted.
+    pub(super) fn explore_node(&self) -> bool {
+        if self.timed_out() {
+            return false;
+        }
+        if let Some(ref nodes) = self.nodes {
+            if nodes.get() == 0 {
+                return false;
             }
-            LexParseError::ParseError(e) => {
-                let ((line, col), _) = lexer.line_col(e.lexeme().span());
-                let mut out = format!("Parsing error at line {} column {}.", line, col);
-                let repairs_len = e.repairs().len();
-                if repairs_len == 0 {
-                    out.push_str(" No repair sequences found.");
-                } else {
-                    out.push_str(" Repair sequences found:");
-                    for (i, rs) in e.repairs().iter().enumerate() {
-                        let padding = ((repairs_len as f64).log10() as usize)
-                            - (((i + 1) as f64).log10() as usize)
-                            + 1;
-                        out.push_str(&format!("\n  {}{}: ", " ".repeat(padding), i + 1));
-                        let mut rs_out = Vec::new();
-                        for r in rs {
-                            match r {
-                                ParseRepair::Insert(tidx) => {
+            nodes.set(nodes.get() - 1);
+        }
+        true
+    }
+
+    /// Has a time budget run out? A node budget is only consumed by
+    /// [explore_node](RecoveryAllowance::explore_node), so this is always `false` for one.
+    pub(super) fn timed_out(&self) -> bool {
+        matches!(self.finish_by, Some(finish_by) if Instant::now() >= finish_by)
+    }
+
+    /// Return the budget that remains for later recoveries.
+    fn remaining(&self) -> RecoveryBudget {
+        match (self.finish_by, &self.nodes) {
+            (Some(finish_by), _) => {
+                RecoveryBudget::Time(finish_by.saturating_duration_since(Instant::now()))
+            }
+            (None, Some(nodes)) => RecoveryBudget::Nodes(nodes.get()),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+/// A custom error recovery strategy, which can be given to a parser with
+/// [RTParserBuilder::recovery_strategy]. When the parser encounters a syntax error, it calls
+/// [recover](RecoveryStrategy::recover), which proposes ways of repairing the input in the form of
+/// [ParseRepair] sequences. The parser then applies the first repair sequence which makes progress
+/// (see [RecoveryContext::makes_progress]) and continues parsing. If no repair sequence makes
+/// progress, the parser falls back to panic mode recovery (see [RecoveryKind::Panic]).
+pub trait RecoveryStrategy<LexemeT: Lexeme<StorageT>, StorageT: Hash> {
+    /// Return the repair sequences, best first, which could fix the syntax error described by
+    /// `ctx`. Repair sequences apply to the lexemes in [RecoveryContext::lookahead]:
+    /// [ParseRepair::Delete] and [ParseRepair::Shift] respectively skip and parse the next such
+    /// lexeme (whatever lexeme they contain), and [ParseRepair::Insert] parses a token which is not
+    /// present in the input. The repair sequences which make progress are recorded, in order, in
+    /// the resulting [ParseError].
+    fn recover(
+        &self,
+        ctx: &RecoveryContext<LexemeT, StorageT>,
+    ) -> Vec<Vec<ParseRepair<LexemeT, StorageT>>>;
+}
+
+/// The state of a parse at a syntax error, as given to a [RecoveryStrategy].
+pub struct RecoveryContext<'a, LexemeT: Lexeme<StorageT>, StorageT: 'static + Hash> {
+    grm: &'a YaccGrammar<StorageT>,
+    stable: &'a StateTable<StorageT>,
+    pstack: &'a [StIdx<StorageT>],
+    lookahead: Vec<LexemeT>,
+    allowance: &'a RecoveryAllowance,
+}
+
+impl<'a, LexemeT: Lexeme<StorageT>, StorageT: 'static + Hash + PrimInt + Unsigned>
+    RecoveryContext<'a, LexemeT, StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Return the grammar being parsed.
+    pub fn grm(&self) -> &'a YaccGrammar<StorageT> {
+        self.grm
+    }
+
+    /// Return the state table being used to parse.
+    pub fn stable(&self) -> &'a StateTable<StorageT> {
+        self.stable
+    }
+
+    /// Return the parse stack: the last element is the state in which the error was detected.
+    pub fn pstack(&self) -> &[StIdx<StorageT>] {
+        self.pstack
+    }
+
+    /// Return the lexemes from the point of the error onwards: the first is the lexeme at which
+    /// the error was detected. Only a bounded number of lexemes are available, so that recovery
+    /// can happen without waiting for the rest of the input. If the input ends within that bound,
+    /// the final lexeme is a zero-length lexeme for the EOF token (see
+    /// [`eof_token_idx`](../../cfgrammar/yacc/grammar/struct.YaccGrammar.html#method.eof_token_idx)).
+    /// Input which the lexer could not lex is represented by a lexeme whose token index is
+    /// [`tokens_len`](../../cfgrammar/yacc/grammar/struct.YaccGrammar.html#method.tokens_len):
+    /// no state has an action for it, so a repair sequence must delete it.
+    pub fn lookahead(&self) -> &[LexemeT] {
+        &self.lookahead
+    }
+
+    /// Spend the [RecoveryBudget] for exploring one possible repair, returning `false` if the
+    /// budget is exhausted, in which case the strategy should return the repairs it has found so
+    /// far.
+    pub fn explore_node(&self) -> bool {
+        self.allowance.explore_node()
+    }
+
+    /// Return how many of the lexemes in [lookahead](RecoveryContext::lookahead) are consumed if
+    /// `repairs` are applied and parsing then continues until either another error is found or
+    /// the lookahead runs out (accepting the input consumes the EOF lexeme). Returns `None` if
+    /// `repairs` cannot be applied: i.e. if one of them causes a syntax error, inserts the EOF or
+    /// `error` tokens, or deletes or shifts the EOF lexeme or a lexeme beyond the lookahead.
+    pub fn parse_distance(&self, repairs: &[ParseRepair<LexemeT, StorageT>]) -> Option<usize> {
+        self.distances(repairs).map(|(_, dist)| dist)
+    }
+
+    /// Does applying `repairs` allow parsing to make progress, i.e. can at least one more lexeme
+    /// be parsed once the lexemes deleted or shifted by `repairs` have been consumed? Repair
+    /// sequences which do not make progress are ignored by the parser, as applying them could
+    /// lead to the same error being encountered forever.
+    pub fn makes_progress(&self, repairs: &[ParseRepair<LexemeT, StorageT>]) -> bool {
+        matches!(self.distances(repairs), Some((consumed, dist)) if dist > consumed)
+    }
+
+    /// Apply `repairs` to a copy of the parse stack, returning how many lexemes of the lookahead
+    /// `repairs` consume and the parse distance (see [RecoveryContext::parse_distance]).
+    fn distances(&self, repairs: &[ParseRepair<LexemeT, StorageT>]) -> Option<(usize, usize)> {
+        let eof_tidx = self.grm.eof_token_idx();
+        let mut pstack = self.pstack.to_owned();
+        let mut consumed = 0;
+        for r in repairs {
+            match *r {
+                ParseRepair::Insert(tidx) => {
+                    if tidx == eof_tidx
+                        || Some(tidx) == self.grm.error_token_idx()
+                        || !self.feed(&mut pstack, tidx)
+                    {
+                        return None;
+                    }
+                }
+                ParseRepair::Delete(_) | ParseRepair::Shift(_) => {
+                    let tidx = TIdx(self.lookahead.get(consumed)?.tok_id());
+                    if tidx == eof_tidx {
+                        return None;
+                    }
+                    if let ParseRepair::Shift(_) = *r {
+                        if !self.feed(&mut pstack, tidx) {
+                            return None;
+                        }
+                    }
+                    consumed += 1;
+                }
+            }
+        }
+        let mut dist = consumed;
+        while dist < self.lookahead.len()
+            && self.feed(&mut pstack, TIdx(self.lookahead[dist].tok_id()))
+        {
+            dist += 1;
+        }
+        Some((consumed, dist))
+    }
+
+    /// Parse the token `tidx` with the parse stack `pstack`, making whatever reductions are
+    /// necessary before it is shifted. Returns `false` if `tidx` causes a syntax error.
+    fn feed(&self, pstack: &mut Vec<StIdx<StorageT>>, tidx: TIdx<StorageT>) -> bool {
+        loop {
+            match self.stable.action(*pstack.last().unwrap(), tidx) {
+                Action::Reduce(pidx) => {
+                    let ridx = self.grm.prod_to_rule(pidx);
+                    pstack.truncate(pstack.len() - self.grm.prod(pidx).len());
+                    let prior = *pstack.last().unwrap();
+                    pstack.push(self.stable.goto(prior, ridx).unwrap());
+                }
+                Action::Shift(stidx) => {
+                    pstack.push(stidx);
+                    return true;
+                }
+                Action::Accept => return true,
+                Action::Error => return false,
+            }
+        }
+    }
+}
+
+/// Return a lexeme representing the input at `e`'s span, which the lexer could not lex. Its token
+/// index is one beyond the grammar's tokens, so no state has an action for it and the parser
+/// treats it as a syntax error, which error recovery can repair by deleting the lexeme.
+pub(super) fn error_lexeme<LexemeT: Lexeme<StorageT>, StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    e: &LexError,
+) -> LexemeT
+where
+    usize: AsPrimitive<StorageT>,
+{
+    LexemeT::new_faulty(
+        StorageT::from(u32::from(grm.tokens_len())).unwrap(),
+        e.span().start(),
+        e.span().len(),
+    )
+}
+
+/// Sort `errors` into the order in which they occur in the input. A lexing error is placed before
+/// a parsing error at the same position, as the latter is often a consequence of the former.
+fn sort_errors<LexemeT: Lexeme<StorageT>, StorageT: Hash>(
+    errors: &mut [LexParseError<LexemeT, StorageT>],
+) {
+    errors.sort_by_key(|e| match e {
+        LexParseError::LexError(e) => (e.span().start(), 0),
+        LexParseError::ParseError(e) => (e.lexeme.span().start(), 1),
+    });
+}
+
+/// A lexing or parsing error. Although the two are quite distinct in terms of what can be reported
+/// to users, both can (at least conceptually) occur at any point of the intertwined lexing/parsing
+/// process.
+#[derive(Debug)]
+pub enum LexParseError<LexemeT: Lexeme<StorageT>, StorageT: Hash> {
+    LexError(LexError),
+    ParseError(ParseError<LexemeT, StorageT>),
+}
+
+impl<LexemeT: Lexeme<StorageT>, StorageT: Hash + PrimInt + Unsigned>
+    LexParseError<LexemeT, StorageT>
+{
+    /// A pretty-printer of a lexer/parser error. This isn't suitable for all purposes, but it's
+    /// often good enough. The output format is not guaranteed to be stable but is likely to be of
+    /// the form:
+    ///
+    /// ```text
+    /// Parsing error at line 3 column 8. Expected one of `ID`, `(`. Repair sequences found:
+    ///   1: Insert ID
+    ///   2: Delete +, Shift 3
+    /// ```
+    ///
+    /// If you are using the compile-time parse system, your `grm_y` module exposes a suitable
+    /// [`epp`](../ctbuilder/struct.CTParserBuilder.html#method.process_file) function; if you are
+    /// using the run-time system,
+    /// [`YaccGrammar`](../../cfgrammar/yacc/grammar/struct.YaccGrammar.html) exposes a suitable
+    /// [`epp`](../../cfgrammar/yacc/grammar/struct.YaccGrammar.html#method.token_epp) function,
+    /// though you will have to wrap it in a closure e.g. `&|t| grm.token_epp(t)`.
+    pub fn pp<'a>(
+        &self,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+        epp: &dyn Fn(TIdx<StorageT>) -> Option<&'a str>,
+    ) -> String {
+        match self {
+            LexParseError::LexError(e) => {
+                let ((line, col), _) = lexer.line_col(e.span());
+                format!("Lexing error at line {} column {}.", line, col)
+            }
+            LexParseError::ParseError(e) => {
+                let ((line, col), _) = lexer.line_col(e.lexeme().span());
+                let mut out = format!("Parsing error at line {} column {}.", line, col);
+                // The only token without a pretty-printed value which can be expected is the EOF
+                // token.
+                let expected = e
+                    .expected()
+                    .iter()
+                    .map(|&tidx| match epp(tidx) {
+                        Some(s) => format!("`{}`", s),
+                        None => "end of input".to_owned(),
+                    })
+                    .collect::<Vec<_>>();
+                match expected.len() {
+                    0 => (),
+                    1 => out.push_str(&format!(" Expected {}.", expected[0])),
+                    _ => out.push_str(&format!(" Expected one of {}.", expected.join(", "))),
+                }
+                let repairs_len = e.repairs().len();
+                if repairs_len == 0 {
+                    out.push_str(" No repair sequences found.");
+                } else {
+                    out.push_str(" Repair sequences found:");
+                    for (i, rs) in e.repairs().iter().enumerate() {
+                        let padding = ((repairs_len as f64).log10() as usize)
+                            - (((i + 1) as f64).log10() as usize)
+                            + 1;
+                        out.push_str(&format!("\n  {}{}: ", " ".repeat(padding), i + 1));
+                        let mut rs_out = Vec::new();
+                        for r in rs {
+                            match r {
+                                ParseRepair::Insert(tidx) => {
                                     rs_out.push(format!("Insert {}", epp(*tidx).unwrap()));
                                 }
                                 ParseRepair::Shift(l) | ParseRepair::Delete(l) => {
@@ -706,10 +1500,12 @@ impl<LexemeT: Lexeme<StorageT>, StorageT: Hash> From<ParseError<LexemeT, Storage
 }
 
 /// A run-time parser builder.
-pub struct RTParserBuilder<'a, LexemeT: Lexeme<StorageT>, StorageT: Eq + Hash> {
+pub struct RTParserBuilder<'a, LexemeT: Lexeme<StorageT>, StorageT: 'static + Eq + Hash> {
     grm: &'a YaccGrammar<StorageT>,
     stable: &'a StateTable<StorageT>,
     recoverer: RecoveryKind,
+    recovery_budget: RecoveryBudget,
+    recovery_strategy: Option<&'a dyn RecoveryStrategy<LexemeT, StorageT>>,
     term_costs: &'a dyn Fn(TIdx<StorageT>) -> u8,
     phantom: PhantomData<(LexemeT, StorageT)>,
 }
@@ -725,6 +1521,8 @@ where
             grm,
             stable,
             recoverer: RecoveryKind::CPCTPlus,
+            recovery_budget: RecoveryBudget::default(),
+            recovery_strategy: None,
             term_costs: &|_| 1,
             phantom: PhantomData,
         }
@@ -736,11 +1534,47 @@ where
         self
     }
 
+    /// Set the work the recoverer may do over the course of a parse to `rb`. Defaults to
+    /// `RecoveryBudget::default()`.
+    pub fn recovery_budget(mut self, rb: RecoveryBudget) -> Self {
+        self.recovery_budget = rb;
+        self
+    }
+
+    /// Recover from syntax errors using the custom strategy `rs` rather than the recoverer set by
+    /// [`recoverer`](#method.recoverer). If `rs` finds no repairs which make progress, the parser
+    /// falls back to panic mode recovery (see [RecoveryKind::Panic]). `rs` is given the same
+    /// [RecoveryBudget] as the built-in recoverers.
+    pub fn recovery_strategy(mut self, rs: &'a dyn RecoveryStrategy<LexemeT, StorageT>) -> Self {
+        self.recovery_strategy = Some(rs);
+        self
+    }
+
     pub fn term_costs(mut self, f: &'a dyn Fn(TIdx<StorageT>) -> u8) -> Self {
         self.term_costs = f;
         self
     }
 
+    /// Return the start state of the entry point for rule `ridx`.
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`.
+    fn entry_state(&self, ridx: RIdx<StorageT>) -> StIdx<StorageT> {
+        match self
+            .grm
+            .entries()
+            .iter()
+            .position(|&(e_ridx, _)| e_ridx == ridx)
+        {
+            Some(i) => self.stable.start_states()[i],
+            None => panic!(
+                "Rule '{}' is not the start rule and was not declared with %entry",
+                self.grm.rule_name(ridx)
+            ),
+        }
+    }
+
     /// Parse input, and (if possible) return a generic parse tree. See the arguments for
     /// [`parse_actions`](#method.parse_actions) for more details about the return value.
     pub fn parse_generictree(
@@ -750,43 +1584,178 @@ where
         Option<Node<LexemeT, StorageT>>,
         Vec<LexParseError<LexemeT, StorageT>>,
     ) {
-        let mut lexemes = vec![];
-        for e in lexer.iter().collect::<Vec<_>>() {
-            match e {
-                Ok(l) => lexemes.push(l),
-                Err(e) => return (None, vec![e.into()]),
-            }
-        }
+        self.parse_generictree_from(self.grm.entries()[0].0, lexer)
+    }
+
+    /// Parse input as an instance of rule `ridx`, which must be the grammar's start rule or a
+    /// rule declared with `%entry`, and (if possible) return a generic parse tree. See the
+    /// arguments for [`parse_actions`](#method.parse_actions) for more details about the return
+    /// value.
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`.
+    pub fn parse_generictree_from(
+        &self,
+        ridx: RIdx<StorageT>,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+    ) -> (
+        Option<Node<LexemeT, StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
         Parser::<LexemeT, StorageT, Node<LexemeT, StorageT>, ()>::parse_generictree(
             self.recoverer,
+            self.recovery_budget,
+            self.recovery_strategy,
             self.grm,
             self.term_costs,
             self.stable,
+            self.entry_state(ridx),
             lexer,
-            lexemes,
         )
     }
 
+    /// Parse input, and (if possible) return a parse tree which can later be reparsed
+    /// incrementally after the input is edited (see
+    /// [`reparse_incremental`](#method.reparse_incremental)). See the arguments for
+    /// [`parse_actions`](#method.parse_actions) for more details about the return value.
+    pub fn parse_incremental(
+        &self,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+    ) -> (
+        Option<IncrementalTree<LexemeT, StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        self.parse_incremental_from(self.grm.entries()[0].0, lexer)
+    }
+
+    /// Parse input as an instance of rule `ridx`, which must be the grammar's start rule or a
+    /// rule declared with `%entry`, and (if possible) return a parse tree which can later be
+    /// reparsed incrementally. See [`parse_incremental`](#method.parse_incremental) for more
+    /// details.
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`.
+    pub fn parse_incremental_from(
+        &self,
+        ridx: RIdx<StorageT>,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+    ) -> (
+        Option<IncrementalTree<LexemeT, StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        self.parse_incremental_state(self.entry_state(ridx), lexer)
+    }
+
+    fn parse_incremental_state(
+        &self,
+        start_state: StIdx<StorageT>,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+    ) -> (
+        Option<IncrementalTree<LexemeT, StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        let (root, errs) = Parser::<LexemeT, StorageT, Subtree<StorageT>, ()>::parse_incremental(
+            self.recoverer,
+            self.recovery_budget,
+            self.recovery_strategy,
+            self.grm,
+            self.term_costs,
+            self.stable,
+            start_state,
+            lexer,
+        );
+        let tree = root.map(|r| IncrementalTree::new(r, start_state, errs.is_empty()));
+        (tree, errs)
+    }
+
+    /// Reparse input after an edit, reusing the parts of the previous parse tree unaffected by
+    /// the edit rather than reparsing them, and (if possible) return a new parse tree. `old` must
+    /// be the tree returned by [`parse_incremental`](#method.parse_incremental) (or by this
+    /// function) for the input before `edit`, as an instance of the same rule, and `lexer` must
+    /// lex the input after `edit`. The result is the same as that of
+    /// [`parse_incremental`](#method.parse_incremental) for the new input (though error recovery
+    /// may choose a different repair amongst equally good repairs). See the arguments for
+    /// [`parse_actions`](#method.parse_actions) for more details about the return value.
+    ///
+    /// Only the part of the input around the edit is relexed (using
+    /// [`NonStreamingLexer::iter_from`]), and a subtree of `old` is reused, along with the
+    /// positions of its lexemes relative to its start, if the parser would certainly reduce the
+    /// same lexemes to the same subtree. Lists defined by left recursive rules are stored so that
+    /// the elements after an edit can be appended to a new list in one go. The time taken thus
+    /// depends mostly on the size of the edit rather than that of the input. If `old` was
+    /// produced with errors, the input is parsed from scratch.
+    pub fn reparse_incremental(
+        &self,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+        old: &IncrementalTree<LexemeT, StorageT>,
+        edit: TextEdit,
+    ) -> (
+        Option<IncrementalTree<LexemeT, StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        if !old.reusable() {
+            return self.parse_incremental_state(old.start_state(), lexer);
+        }
+        let (reuser, lex_errs) = Reuser::new(self.grm, self.stable, old, lexer, edit);
+        let (root, mut errs) =
+            Parser::<LexemeT, StorageT, Subtree<StorageT>, ()>::reparse_incremental(
+                self.recoverer,
+                self.recovery_budget,
+                self.recovery_strategy,
+                self.grm,
+                self.term_costs,
+                self.stable,
+                old.start_state(),
+                lexer,
+                &|laidx| reuser.lexeme(laidx),
+                &|pstack, top, laidx| reuser.reuse(pstack, top, laidx),
+            );
+        errs.extend(lex_errs.into_iter().map(LexParseError::from));
+        sort_errors(&mut errs);
+        if self.recovery_strategy.is_none() && matches!(self.recoverer, RecoveryKind::None) {
+            // Without error recovery, parsing stops at the first error, be it a lexing or a
+            // parsing error.
+            if !errs.is_empty() {
+                errs.truncate(1);
+                return (None, errs);
+            }
+        }
+        let tree = root.map(|r| IncrementalTree::new(r, old.start_state(), errs.is_empty()));
+        (tree, errs)
+    }
+
     /// Parse input, returning any errors found. See the arguments for
     /// [`parse_actions`](#method.parse_actions) for more details about the return value.
     pub fn parse_noaction(
         &self,
         lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
     ) -> Vec<LexParseError<LexemeT, StorageT>> {
-        let mut lexemes = vec![];
-        for e in lexer.iter().collect::<Vec<_>>() {
-            match e {
-                Ok(l) => lexemes.push(l),
-                Err(e) => return vec![e.into()],
-            }
-        }
+        self.parse_noaction_from(self.grm.entries()[0].0, lexer)
+    }
+
+    /// Parse input as an instance of rule `ridx`, which must be the grammar's start rule or a
+    /// rule declared with `%entry`, returning any errors found. See the arguments for
+    /// [`parse_actions`](#method.parse_actions) for more details about the return value.
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`.
+    pub fn parse_noaction_from(
+        &self,
+        ridx: RIdx<StorageT>,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+    ) -> Vec<LexParseError<LexemeT, StorageT>> {
         Parser::<LexemeT, StorageT, (), ()>::parse_noaction(
             self.recoverer,
+            self.recovery_budget,
+            self.recovery_strategy,
             self.grm,
             self.term_costs,
             self.stable,
+            self.entry_state(ridx),
             lexer,
-            lexemes,
         )
     }
 
@@ -796,63 +1765,297 @@ where
     /// (`None, [...]`), errors and a value (`Some(...), [...]`), as well as a value and no errors
     /// (`Some(...), []`). Errors are sorted by the position they were found in the input and can
     /// be a mix of lexing and parsing errors.
+    ///
+    /// If error recovery is turned on, input which the lexer could not lex is passed to the parser
+    /// as a faulty lexeme whose token is not part of the grammar, so lexing errors do not stop
+    /// parsing: each also leads to a parsing error, whose repair sequences (e.g. those found by
+    /// CPCT+) delete the unlexable input, and is reported immediately after it. Without error
+    /// recovery (see [RecoveryKind::None]), parsing stops at the first lexing error.
     pub fn parse_actions<'b: 'a, 'input: 'b, ActionT: 'a, ParamT: Copy>(
         &self,
         lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
         actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
         param: ParamT,
     ) -> (Option<ActionT>, Vec<LexParseError<LexemeT, StorageT>>) {
-        let mut lexemes = vec![];
-        for e in lexer.iter().collect::<Vec<_>>() {
-            match e {
-                Ok(l) => lexemes.push(l),
-                Err(e) => return (None, vec![e.into()]),
-            }
-        }
+        self.parse_actions_from(self.grm.entries()[0].0, lexer, actions, param)
+    }
+
+    /// Parse input as an instance of rule `ridx`, which must be the grammar's start rule or a
+    /// rule declared with `%entry`, execute actions, and return the associated value (if
+    /// possible) and/or any lexing/parsing errors encountered. The value is that produced by
+    /// `ridx`'s action. See [`parse_actions`](#method.parse_actions) for more details about the
+    /// return value.
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`.
+    pub fn parse_actions_from<'b: 'a, 'input: 'b, ActionT: 'a, ParamT: Copy>(
+        &self,
+        ridx: RIdx<StorageT>,
+        lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+        actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
+        param: ParamT,
+    ) -> (Option<ActionT>, Vec<LexParseError<LexemeT, StorageT>>) {
         Parser::parse_actions(
             self.recoverer,
+            self.recovery_budget,
+            self.recovery_strategy,
             self.grm,
             self.term_costs,
             self.stable,
+            self.entry_state(ridx),
             lexer,
-            lexemes,
             actions,
             param,
         )
     }
-}
-
-/// After a parse error is encountered, the parser attempts to find a way of recovering. Each entry
-/// in the sequence of repairs is represented by a `ParseRepair`.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub enum ParseRepair<LexemeT: Lexeme<StorageT>, StorageT: Hash> {
-    /// Insert a `Symbol::Token`.
-    Insert(TIdx<StorageT>),
-    /// Delete a symbol.
-    Delete(LexemeT),
-    /// Shift a symbol.
-    Shift(LexemeT),
-}
-
-/// Records a single parse error.
-#[derive(Clone, Debug, PartialEq)]
-pub struct ParseError<LexemeT: Lexeme<StorageT>, StorageT: Hash> {
-    stidx: StIdx,
-    lexeme: LexemeT,
-    repairs: Vec<Vec<ParseRepair<LexemeT, StorageT>>>,
-}
 
-impl<LexemeT: Lexeme<StorageT>, StorageT: Debug + Hash> Display for ParseError<LexemeT, StorageT> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Parse error at lexeme {:?}", self.lexeme)
+    /// Create a push parser, which is given the input's lexemes one at a time (see
+    /// [PushParser::push]) rather than obtaining them from a lexer. Actions, spans, and error
+    /// recovery are identical to those of [`parse_actions`](#method.parse_actions). `lexer` is
+    /// passed to actions (e.g. so that they can call [NonStreamingLexer::span_str]), but its
+    /// lexemes are not used: `lexer` need only be able to answer questions about the lexemes
+    /// pushed so far.
+    pub fn push_parser<'b: 'a, 'input: 'b, ActionT: 'a, ParamT: Copy>(
+        &self,
+        lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+        actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
+        param: ParamT,
+    ) -> PushParser<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT> {
+        self.push_parser_from(self.grm.entries()[0].0, lexer, actions, param)
     }
-}
 
-impl<LexemeT: Lexeme<StorageT>, StorageT: Debug + Hash> Error for ParseError<LexemeT, StorageT> {}
+    /// Create a push parser (see [`push_parser`](#method.push_parser)) which parses its input as
+    /// an instance of rule `ridx`, which must be the grammar's start rule or a rule declared with
+    /// `%entry`.
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`.
+    pub fn push_parser_from<'b: 'a, 'input: 'b, ActionT: 'a, ParamT: Copy>(
+        &self,
+        ridx: RIdx<StorageT>,
+        lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+        actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
+        param: ParamT,
+    ) -> PushParser<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT> {
+        let start_state = self.entry_state(ridx);
+        for tidx in self.grm.iter_tidxs() {
+            assert!((self.term_costs)(tidx) > 0);
+        }
+        PushParser {
+            psr: Parser {
+                rcvry_kind: self.recoverer,
+                rcvry_budget: self.recovery_budget,
+                rcvry_strategy: self.recovery_strategy,
+                grm: self.grm,
+                token_cost: Box::new(self.term_costs),
+                stable: self.stable,
+                lexer,
+                lexemes: Lexemes::new(),
+                actions,
+                param,
+                reuse: None,
+            },
+            st: LrState::new(start_state, self.recovery_budget),
+            failed: false,
+        }
+    }
+
+    /// Parse input with a generalised LR (GLR) parser and (if possible) return a shared packed
+    /// parse forest of all the parse trees for the input. Unlike the other `parse_*` functions,
+    /// conflicts in the statetable are not resolved in favour of one action: every action (see
+    /// [StateTable::all_actions]) is explored, so ambiguous grammars can be parsed without
+    /// distorting them. The recoverer is not used: if the input cannot be parsed, a single error
+    /// (with no repairs) is returned.
+    pub fn parse_sppf(
+        &self,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+    ) -> (
+        Option<Sppf<LexemeT, StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        self.parse_sppf_from(self.grm.entries()[0].0, lexer)
+    }
+
+    /// Parse input as an instance of rule `ridx`, which must be the grammar's start rule or a
+    /// rule declared with `%entry`, with a generalised LR (GLR) parser (see
+    /// [`parse_sppf`](#method.parse_sppf)).
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`.
+    pub fn parse_sppf_from(
+        &self,
+        ridx: RIdx<StorageT>,
+        lexer: &dyn NonStreamingLexer<LexemeT, StorageT>,
+    ) -> (
+        Option<Sppf<LexemeT, StorageT>>,
+        Vec<LexParseError<LexemeT, StorageT>>,
+    ) {
+        let start_state = self.entry_state(ridx);
+        let mut lexemes = vec![];
+        for e in lexer.iter().collect::<Vec<_>>() {
+            match e {
+                Ok(l) => lexemes.push(l),
+                Err(e) => return (None, vec![e.into()]),
+            }
+        }
+        glr::parse(self.grm, self.stable, start_state, &lexemes)
+    }
+
+    /// Parse input with a generalised LR (GLR) parser (see
+    /// [`parse_sppf`](#method.parse_sppf)), execute actions over the resulting forest, and return
+    /// the associated value (if possible) and/or any lexing/parsing errors encountered.
+    ///
+    /// `merges` maps each production to the function named by its `%merge` annotation (if any).
+    /// When every alternative of an ambiguous part of the input names the same `%merge` function,
+    /// the alternatives' values are combined, in production order, using that function; otherwise
+    /// the alternative with the lowest production index is used (mirroring Yacc's default
+    /// resolution of reduce/reduce conflicts). Alternatives beaten by a higher `%dprec` have
+    /// already been discarded from the forest. Actions for parts of the input shared between
+    /// alternatives are executed once per alternative.
+    ///
+    /// # Panics
+    ///
+    /// If the alternatives of an ambiguity are to be merged, but `merges` contains no function
+    /// for one of their productions.
+    pub fn parse_sppf_actions<'b: 'a, 'input: 'b, ActionT: 'a, ParamT: Copy>(
+        &self,
+        lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+        actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
+        merges: &'a [Option<MergeFn<'a, ActionT>>],
+        param: ParamT,
+    ) -> (Option<ActionT>, Vec<LexParseError<LexemeT, StorageT>>) {
+        self.parse_sppf_actions_from(self.grm.entries()[0].0, lexer, actions, merges, param)
+    }
+
+    /// Parse input as an instance of rule `ridx`, which must be the grammar's start rule or a
+    /// rule declared with `%entry`, with a generalised LR (GLR) parser and execute actions over
+    /// the resulting forest (see [`parse_sppf_actions`](#method.parse_sppf_actions)).
+    ///
+    /// # Panics
+    ///
+    /// If `ridx` is neither the grammar's start rule nor declared with `%entry`, or as
+    /// [`parse_sppf_actions`](#method.parse_sppf_actions).
+    pub fn parse_sppf_actions_from<'b: 'a, 'input: 'b, ActionT: 'a, ParamT: Copy>(
+        &self,
+        ridx: RIdx<StorageT>,
+        lexer: &'b dyn NonStreamingLexer<'input, LexemeT, StorageT>,
+        actions: &'a [ActionFn<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>],
+        merges: &'a [Option<MergeFn<'a, ActionT>>],
+        param: ParamT,
+    ) -> (Option<ActionT>, Vec<LexParseError<LexemeT, StorageT>>) {
+        match self.parse_sppf_from(ridx, lexer) {
+            (Some(sppf), errs) => (
+                glr::eval(&sppf, self.grm, lexer, actions, merges, param),
+                errs,
+            ),
+            (None, errs) => (None, errs),
+        }
+    }
+}
+
+/// A parser which is given the input's lexemes one at a time, created by
+/// [RTParserBuilder::push_parser]. Parsing proceeds as far as each lexeme allows: actions are
+/// executed as soon as their lookahead is known, and error recovery runs as soon as enough
+/// lexemes have been pushed for it to look as far ahead as it would have done with the complete
+/// input (or, failing that, when [PushParser::finish] is called).
+pub struct PushParser<
+    'a,
+    'b: 'a,
+    'input: 'b,
+    LexemeT: Lexeme<StorageT>,
+    StorageT: 'static + Eq + Hash,
+    ActionT: 'a,
+    ParamT: Copy,
+> {
+    psr: Parser<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>,
+    st: LrState<LexemeT, StorageT, ActionT>,
+    /// Has the parse failed (in which case later lexemes are ignored)?
+    failed: bool,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        'input: 'b,
+        LexemeT: Lexeme<StorageT>,
+        StorageT: 'static + Debug + Hash + PrimInt + Unsigned,
+        ActionT: 'a,
+        ParamT: Copy,
+    > PushParser<'a, 'b, 'input, LexemeT, StorageT, ActionT, ParamT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Give the parser the next lexeme of the input. A lexeme whose token is the grammar's EOF
+    /// token is ignored: the end of the input can only be signalled by calling
+    /// [PushParser::finish].
+    pub fn push(&mut self, lexeme: LexemeT) {
+        if self.failed || TIdx(lexeme.tok_id()) == self.psr.grm.eof_token_idx() {
+            return;
+        }
+        self.psr.lexemes.push(lexeme);
+        match self.psr.lr(&mut self.st, false) {
+            LrOutcome::NeedInput => self.psr.drop_consumed(&self.st),
+            LrOutcome::Fail => self.failed = true,
+            // Only the EOF token can lead to an accept state, and we never push that.
+            LrOutcome::Accept(_) => unreachable!(),
+        }
+    }
+
+    /// Return the errors encountered so far.
+    pub fn errors(&self) -> &[LexParseError<LexemeT, StorageT>] {
+        &self.st.errors
+    }
+
+    /// Signal the end of the input, returning the associated value (if possible) and/or any
+    /// parsing errors encountered, as [RTParserBuilder::parse_actions] does.
+    pub fn finish(mut self) -> (Option<ActionT>, Vec<LexParseError<LexemeT, StorageT>>) {
+        if self.failed {
+            return (None, self.st.errors);
+        }
+        let accpt = match self.psr.lr(&mut self.st, true) {
+            LrOutcome::Accept(v) => Some(v),
+            LrOutcome::Fail => None,
+            LrOutcome::NeedInput => unreachable!(),
+        };
+        (accpt, self.st.errors)
+    }
+}
+
+/// After a parse error is encountered, the parser attempts to find a way of recovering. Each entry
+/// in the sequence of repairs is represented by a `ParseRepair`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ParseRepair<LexemeT: Lexeme<StorageT>, StorageT: Hash> {
+    /// Insert a `Symbol::Token`.
+    Insert(TIdx<StorageT>),
+    /// Delete a symbol.
+    Delete(LexemeT),
+    /// Shift a symbol.
+    Shift(LexemeT),
+}
+
+/// Records a single parse error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError<LexemeT: Lexeme<StorageT>, StorageT: Hash> {
+    pub(super) stidx: StIdx<StorageT>,
+    pub(super) lexeme: LexemeT,
+    pub(super) expected: Vec<TIdx<StorageT>>,
+    pub(super) repairs: Vec<Vec<ParseRepair<LexemeT, StorageT>>>,
+}
+
+impl<LexemeT: Lexeme<StorageT>, StorageT: Debug + Hash> Display for ParseError<LexemeT, StorageT> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Parse error at lexeme {:?}", self.lexeme)
+    }
+}
+
+impl<LexemeT: Lexeme<StorageT>, StorageT: Debug + Hash> Error for ParseError<LexemeT, StorageT> {}
 
 impl<LexemeT: Lexeme<StorageT>, StorageT: Hash + PrimInt + Unsigned> ParseError<LexemeT, StorageT> {
     /// Return the state table index where this error was detected.
-    pub fn stidx(&self) -> StIdx {
+    pub fn stidx(&self) -> StIdx<StorageT> {
         self.stidx
     }
 
@@ -861,16 +2064,159 @@ impl<LexemeT: Lexeme<StorageT>, StorageT: Hash + PrimInt + Unsigned> ParseError<
         &self.lexeme
     }
 
+    /// Return the tokens, in order, which would have been valid in place of [ParseError::lexeme]
+    /// (see `StateTable::expected_tokens`). These can be turned into user-friendly names with
+    /// [`epp`](../../cfgrammar/yacc/grammar/struct.YaccGrammar.html#method.token_epp) e.g. to
+    /// report "expected one of `)`, `,`".
+    pub fn expected(&self) -> &[TIdx<StorageT>] {
+        &self.expected
+    }
+
     /// Return the repairs found that would fix this error. Note that there are infinite number of
-    /// possible repairs for any error, so this is by definition a (finite) subset.
+    /// possible repairs for any error, so this is by definition a (finite) subset. If the error
+    /// was recovered from using the `error` token (see [RecoveryKind::Panic]), no repairs are
+    /// returned.
     pub fn repairs(&self) -> &Vec<Vec<ParseRepair<LexemeT, StorageT>>> {
         &self.repairs
     }
+
+    /// Return the edits to `input` (the text which was lexed and parsed) which apply the repair
+    /// sequence `self.repairs()[i]`, in the order they occur in `input`. Deleted lexemes are
+    /// removed, and the text for inserted tokens is obtained from `epp`: the grammar's `%epp`
+    /// entries (see [LexParseError::pp]) or a caller-supplied printer (e.g. one which returns a
+    /// placeholder identifier for an `ID` token). Inserted tokens are separated from each other,
+    /// and from the input either side, by a space if they would otherwise join two alphanumeric
+    /// characters together. Returns `None` if `epp` returns `None` for an inserted token.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is not a valid index into [ParseError::repairs].
+    pub fn repair_edits<'a>(
+        &self,
+        i: usize,
+        input: &str,
+        epp: &dyn Fn(TIdx<StorageT>) -> Option<&'a str>,
+    ) -> Option<Vec<RepairEdit>> {
+        // Adjacent inserts and deletes are merged into a single edit.
+        let mut edits: Vec<(Span, Vec<&str>)> = Vec::new();
+        let mut pos = self.lexeme.span().start();
+        for r in &self.repairs[i] {
+            let (span, tok) = match *r {
+                ParseRepair::Insert(tidx) => (Span::new(pos, pos), Some(epp(tidx)?)),
+                ParseRepair::Delete(l) => {
+                    pos = l.span().end();
+                    (l.span(), None)
+                }
+                ParseRepair::Shift(l) => {
+                    pos = l.span().end();
+                    continue;
+                }
+            };
+            match edits.last_mut() {
+                Some((last_span, toks)) if last_span.end() == span.start() => {
+                    *last_span = Span::new(last_span.start(), span.end());
+                    toks.extend(tok);
+                }
+                _ => edits.push((span, tok.into_iter().collect())),
+            }
+        }
+        Some(
+            edits
+                .into_iter()
+                .map(|(span, toks)| {
+                    let mut text = String::new();
+                    let mut prev = input[..span.start()].chars().next_back();
+                    for tok in toks {
+                        if is_word_char(prev) && is_word_char(tok.chars().next()) {
+                            text.push(' ');
+                        }
+                        text.push_str(tok);
+                        prev = tok.chars().next_back().or(prev);
+                    }
+                    if !text.is_empty()
+                        && is_word_char(prev)
+                        && is_word_char(input[span.end()..].chars().next())
+                    {
+                        text.push(' ');
+                    }
+                    RepairEdit { span, text }
+                })
+                .collect(),
+        )
+    }
+
+    /// Return `input` (the text which was lexed and parsed) with the repair sequence
+    /// `self.repairs()[i]` applied to it (see [ParseError::repair_edits]). Returns `None` if `epp`
+    /// returns `None` for an inserted token.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is not a valid index into [ParseError::repairs].
+    pub fn repaired_source<'a>(
+        &self,
+        i: usize,
+        input: &str,
+        epp: &dyn Fn(TIdx<StorageT>) -> Option<&'a str>,
+    ) -> Option<String> {
+        Some(RepairEdit::apply(input, &self.repair_edits(i, input, epp)?))
+    }
+}
+
+/// Is `c` a character which could be part of an identifier or a number?
+fn is_word_char(c: Option<char>) -> bool {
+    matches!(c, Some(c) if c.is_alphanumeric() || c == '_')
+}
+
+/// An edit to the input which is part of applying a repair sequence: the text at `span` is
+/// replaced by `text`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RepairEdit {
+    span: Span,
+    text: String,
+}
+
+impl RepairEdit {
+    /// Return the span of the input which this edit replaces (for an insertion, this has a length
+    /// of zero).
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Return the replacement text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Return `input` with `edits` applied to it. `edits` must be in the order they occur in
+    /// `input` and must not overlap: the edits of each of the errors returned by a parse (see
+    /// [ParseError::repair_edits]) can thus be concatenated and applied in one go, so as to
+    /// repair every error in `input`.
+    ///
+    /// # Panics
+    ///
+    /// If `edits` are out of order or overlap, or if an edit's span exceeds `input`.
+    pub fn apply(input: &str, edits: &[RepairEdit]) -> String {
+        let mut out = String::with_capacity(input.len());
+        let mut i = 0;
+        for e in edits {
+            if e.span.start() < i {
+                panic!("Edit at {:?} overlaps a previous edit", e.span);
+            }
+            out.push_str(&input[i..e.span.start()]);
+            out.push_str(&e.text);
+            i = e.span.end();
+        }
+        out.push_str(&input[i..]);
+        out
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod test {
-    use std::collections::HashMap;
+    use std::{
+        cell::{Cell, RefCell},
+        collections::HashMap,
+    };
 
     use cfgrammar::yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind};
     use lrtable::{from_yacc, Minimiser};
@@ -878,7 +2224,7 @@ pub(crate) mod test {
     use regex::Regex;
 
     use super::*;
-    use crate::{test_utils::TestLexeme, Lexeme, Lexer, Span};
+    use crate::{test_utils::TestLexeme, IncrementalNode, Lexeme, Lexer, Span};
 
     pub(crate) fn do_parse(
         rcvry_kind: RecoveryKind,
@@ -895,11 +2241,38 @@ pub(crate) mod test {
             ),
         >,
     ) {
-        do_parse_with_costs(rcvry_kind, lexs, grms, input, &HashMap::new())
+        do_parse_with_costs(
+            rcvry_kind,
+            RecoveryBudget::default(),
+            lexs,
+            grms,
+            input,
+            &HashMap::new(),
+        )
+    }
+
+    pub(crate) fn do_parse_with_budget(
+        rcvry_kind: RecoveryKind,
+        rcvry_budget: RecoveryBudget,
+        lexs: &str,
+        grms: &str,
+        input: &str,
+    ) -> (
+        YaccGrammar<u16>,
+        Result<
+            Node<TestLexeme, u16>,
+            (
+                Option<Node<TestLexeme, u16>>,
+                Vec<LexParseError<TestLexeme, u16>>,
+            ),
+        >,
+    ) {
+        do_parse_with_costs(rcvry_kind, rcvry_budget, lexs, grms, input, &HashMap::new())
     }
 
     fn do_parse_with_costs(
         rcvry_kind: RecoveryKind,
+        rcvry_budget: RecoveryBudget,
         lexs: &str,
         grms: &str,
         input: &str,
@@ -934,6 +2307,7 @@ pub(crate) mod test {
             .collect::<HashMap<_, _>>();
         let (r, errs) = RTParserBuilder::new(&grm, &stable)
             .recoverer(rcvry_kind)
+            .recovery_budget(rcvry_budget)
             .term_costs(&|tidx| **costs_tidx.get(&tidx).unwrap_or(&&1))
             .parse_generictree(&lexer);
         if r.is_some() && errs.is_empty() {
@@ -945,6 +2319,189 @@ pub(crate) mod test {
         }
     }
 
+    pub(crate) fn do_parse_sppf(
+        lexs: &str,
+        grms: &str,
+        input: &str,
+    ) -> (
+        YaccGrammar<u16>,
+        Option<Sppf<TestLexeme, u16>>,
+        Vec<LexParseError<TestLexeme, u16>>,
+    ) {
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect();
+        let lexer_rules = small_lexer(lexs, rule_ids);
+        let lexemes = small_lex(lexer_rules, input);
+        let lexer = SmallLexer { lexemes };
+        let (sppf, errs) = RTParserBuilder::new(&grm, &stable).parse_sppf(&lexer);
+        (grm, sppf, errs)
+    }
+
+    /// Parse `input` with a GLR parser, executing actions which return a bracketed string of the
+    /// form `Rule(child1 child2 ...)`. Productions with a `%merge` join alternatives with ` | `.
+    pub(crate) fn do_parse_sppf_actions(
+        lexs: &str,
+        grms: &str,
+        input: &str,
+    ) -> (Option<String>, Vec<LexParseError<TestLexeme, u16>>) {
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect();
+        let lexer_rules = small_lexer(lexs, rule_ids);
+        let lexemes = small_lex(lexer_rules, input);
+        let lexer = SmallLexer { lexemes };
+        let action = |ridx: RIdx<u16>,
+                      _: &dyn NonStreamingLexer<TestLexeme, u16>,
+                      _: Span,
+                      astack: vec::Drain<AStackType<TestLexeme, String>>,
+                      _: ()| {
+            let children = astack
+                .map(|a| match a {
+                    AStackType::ActionType(s) => s,
+                    AStackType::Lexeme(l) => input[l.span().start()..l.span().end()].to_owned(),
+                })
+                .collect::<Vec<_>>();
+            format!("{}({})", grm.rule_name(ridx), children.join(" "))
+        };
+        let merge = |x: String, y: String| format!("{} | {}", x, y);
+        let actions: Vec<ActionFn<TestLexeme, u16, String, ()>> =
+            vec![&action; usize::from(grm.prods_len())];
+        let merges = grm
+            .iter_pidxs()
+            .map(|pidx| grm.prod_merge(pidx).map(|_| &merge as MergeFn<String>))
+            .collect::<Vec<_>>();
+        RTParserBuilder::new(&grm, &stable).parse_sppf_actions(&lexer, &actions, &merges, ())
+    }
+
+    /// What [do_reparse] counted while reparsing.
+    pub(crate) struct ReparseCounts {
+        /// The number of lexemes relexed.
+        pub(crate) relexed: usize,
+        /// The number of times a subtree, or a run of list elements, was reused.
+        pub(crate) reused: usize,
+        /// The number of lexemes covered by reused subtrees and runs of list elements.
+        pub(crate) reused_lexemes: usize,
+        /// The number of lexemes the parser requested.
+        pub(crate) pulled: usize,
+    }
+
+    /// Return the generic parse tree equivalent to the lazy view `n` of an incremental tree.
+    fn lazy_node(n: IncrementalNode<TestLexeme, u16>) -> Node<TestLexeme, u16> {
+        match n.lexeme() {
+            Some(lexeme) => Node::Term { lexeme },
+            None => Node::Nonterm {
+                ridx: n.ridx().unwrap(),
+                nodes: n.children().into_iter().map(lazy_node).collect(),
+            },
+        }
+    }
+
+    /// Parse `old_input` as an instance of the rule `entry`, then reparse its tree after `edit`
+    /// turns it into `new_input`, returning the pretty-printed result of the reparse, the
+    /// pretty-printed result of parsing `new_input` from scratch, and counts of what the reparse
+    /// did.
+    pub(crate) fn do_reparse(
+        lexs: &str,
+        grms: &str,
+        entry: &str,
+        old_input: &str,
+        new_input: &str,
+        edit: TextEdit,
+    ) -> (String, String, ReparseCounts) {
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect::<HashMap<_, _>>();
+        let old_lexer = SmallLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids.clone()), old_input),
+        };
+        let new_lexer = SmallLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids), new_input),
+        };
+        let ridx = grm.rule_idx(entry).unwrap();
+        let builder = RTParserBuilder::new(&grm, &stable);
+        let (old, errs) = builder.parse_incremental_from(ridx, &old_lexer);
+        assert!(errs.is_empty());
+        let old = old.unwrap();
+        assert_eq!(
+            Some(old.node()),
+            builder.parse_generictree_from(ridx, &old_lexer).0
+        );
+        // Reparse as `RTParserBuilder::reparse_incremental` does, but count what is reused.
+        let (reuser, lex_errs) = Reuser::new(&grm, &stable, &old, &new_lexer, edit);
+        assert!(lex_errs.is_empty());
+        let (reused, reused_lexemes, pulled) = (Cell::new(0), Cell::new(0), Cell::new(0));
+        let (new, errs) = Parser::<TestLexeme, u16, Subtree<u16>, ()>::reparse_incremental(
+            RecoveryKind::CPCTPlus,
+            RecoveryBudget::default(),
+            None,
+            &grm,
+            &|_| 1,
+            &stable,
+            builder.entry_state(ridx),
+            &new_lexer,
+            &|laidx| {
+                pulled.set(pulled.get() + 1);
+                reuser.lexeme(laidx)
+            },
+            &|pstack, top, laidx| {
+                let r = reuser.reuse(pstack, top, laidx);
+                if let Some(Reuse::Shift(_, _, n, _)) | Some(Reuse::Extend(_, n, _)) = r {
+                    reused.set(reused.get() + 1);
+                    reused_lexemes.set(reused_lexemes.get() + n);
+                }
+                r
+            },
+        );
+        let new = new.map(|r| IncrementalTree::new(r, builder.entry_state(ridx), errs.is_empty()));
+        let (fresh, fresh_errs) = builder.parse_generictree_from(ridx, &new_lexer);
+        assert_eq!(errs.len(), fresh_errs.len());
+        let (new2, _) = builder.reparse_incremental(&new_lexer, &old, edit);
+        if new_input.len() < 1000 {
+            // The lazy view is traversed recursively, so it is only checked on small trees.
+            if let Some(new) = &new {
+                assert_eq!(lazy_node(new.root()), new.node());
+            }
+        }
+        let new = new.map(|t| t.node());
+        assert_eq!(new, new2.map(|t| t.node()));
+        let pp = |n: Option<Node<TestLexeme, u16>>| n.map(|n| n.pp(&grm, new_input));
+        (
+            pp(new).unwrap_or_default(),
+            pp(fresh).unwrap_or_default(),
+            ReparseCounts {
+                relexed: reuser.relexed(),
+                reused: reused.get(),
+                reused_lexemes: reused_lexemes.get(),
+                pulled: pulled.get(),
+            },
+        )
+    }
+
     fn check_parse_output(lexs: &str, grms: &str, input: &str, expected: &str) {
         let (grm, pt) = do_parse(RecoveryKind::CPCTPlus, lexs, grms, input);
         assert_eq!(expected, pt.unwrap().pp(&grm, &input));
@@ -976,6 +2533,17 @@ pub(crate) mod test {
         fn line_col(&self, _: Span) -> ((usize, usize), (usize, usize)) {
             unreachable!();
         }
+
+        fn iter_from<'a>(
+            &'a self,
+            start: usize,
+        ) -> Box<dyn Iterator<Item = Result<TestLexeme, LexError>> + 'a>
+        where
+            TestLexeme: 'a,
+        {
+            let i = self.lexemes.partition_point(|l| l.span().start() < start);
+            Box::new(self.lexemes[i..].iter().map(|x| Ok(*x)))
+        }
     }
 
     fn small_lexer(lexs: &str, ids_map: HashMap<String, u16>) -> Vec<(u16, Regex)> {
@@ -1145,6 +2713,7 @@ Call: 'ID' '(' ')';";
             LexParseError::ParseError(e) => {
                 assert_eq!(e.lexeme(), &Lexeme::new_faulty(err_tok_id, 2, 0));
                 assert!(e.lexeme().faulty());
+                assert_eq!(e.expected(), &[grm.token_idx(")").unwrap()]);
             }
             _ => unreachable!(),
         }
@@ -1157,8 +2726,819 @@ Call: 'ID' '(' ')';";
             LexParseError::ParseError(e) => {
                 assert_eq!(e.lexeme(), &Lexeme::new(err_tok_id, 2, 1));
                 assert!(!e.lexeme().faulty());
+                assert_eq!(e.expected(), &[grm.token_idx(")").unwrap()]);
             }
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn repair_edits() {
+        let lexs = "\\( '('
+                    \\) ')'
+                    ; ';'
+                    [a-zA-Z_][a-zA-Z_0-9]* 'ID'";
+        let grms = "%start Stmts
+%epp ID 'x'
+%%
+Stmts: Stmts Stmt | Stmt;
+Stmt: 'ID' '(' ')' ';' | 'ID' 'ID' ';';";
+        let parse = |input: &str| {
+            let (grm, pr) = do_parse(RecoveryKind::CPCTPlus, lexs, grms, input);
+            let errs = pr
+                .unwrap_err()
+                .1
+                .into_iter()
+                .map(|e| match e {
+                    LexParseError::ParseError(e) => e,
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            (grm, errs)
+        };
+
+        // An insertion at the end of the input.
+        let (grm, errs) = parse("f(");
+        assert_eq!(errs.len(), 1);
+        let e = &errs[0];
+        let i = e.repairs().iter().position(|rs| rs.len() == 2).unwrap();
+        assert_eq!(
+            e.repair_edits(i, "f(", &|t| grm.token_epp(t)),
+            Some(vec![RepairEdit {
+                span: Span::new(2, 2),
+                text: ");".to_owned()
+            }])
+        );
+        assert_eq!(
+            e.repaired_source(i, "f(", &|t| grm.token_epp(t)),
+            Some("f();".to_owned())
+        );
+        assert_eq!(e.repaired_source(i, "f(", &|_| None), None);
+
+        // Inserted identifiers are kept apart from adjacent identifiers, and deletions and
+        // insertions at the same point are merged.
+        let (grm, errs) = parse("int;f(;");
+        assert_eq!(errs.len(), 2);
+        let mut edits = vec![];
+        for e in &errs {
+            let i = e
+                .repairs()
+                .iter()
+                .position(|rs| matches!(rs[0], ParseRepair::Insert(_)))
+                .unwrap();
+            edits.extend(e.repair_edits(i, "int;f(;", &|t| grm.token_epp(t)).unwrap());
+        }
+        assert_eq!(
+            edits,
+            vec![
+                RepairEdit {
+                    span: Span::new(3, 3),
+                    text: " x".to_owned()
+                },
+                RepairEdit {
+                    span: Span::new(6, 6),
+                    text: ")".to_owned()
+                }
+            ]
+        );
+        assert_eq!(RepairEdit::apply("int;f(;", &edits), "int x;f();");
+
+        let (grm, errs) = parse("f)(;");
+        assert_eq!(errs.len(), 1);
+        let e = &errs[0];
+        let i = e
+            .repairs()
+            .iter()
+            .position(|rs| matches!(rs[..], [ParseRepair::Delete(_), ParseRepair::Shift(_), ..]))
+            .unwrap();
+        assert_eq!(
+            e.repaired_source(i, "f)(;", &|t| grm.token_epp(t)),
+            Some("f();".to_owned())
+        );
+
+        // An insertion followed by a deletion at the same point becomes a replacement.
+        let rparen = grm.token_idx(")").unwrap();
+        let semi = TestLexeme::new(u32::from(grm.token_idx(";").unwrap()) as u16, 2, 1);
+        let e = ParseError {
+            stidx: e.stidx(),
+            lexeme: semi,
+            expected: vec![rparen],
+            repairs: vec![vec![ParseRepair::Insert(rparen), ParseRepair::Delete(semi)]],
+        };
+        assert_eq!(
+            e.repair_edits(0, "f(;", &|t| grm.token_epp(t)),
+            Some(vec![RepairEdit {
+                span: Span::new(2, 3),
+                text: ")".to_owned()
+            }])
+        );
+    }
+
+    /// Parse `input` both normally and by pushing one lexeme at a time into a push parser, and
+    /// check that the results are the same.
+    fn check_push_parse(rcvry_kind: RecoveryKind, lexs: &str, grms: &str, input: &str) {
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect();
+        let lexer_rules = small_lexer(lexs, rule_ids);
+        let lexemes = small_lex(lexer_rules, input);
+        let lexer = SmallLexer { lexemes };
+        let builder = RTParserBuilder::new(&grm, &stable).recoverer(rcvry_kind);
+        let (pt, errs) = builder.parse_generictree(&lexer);
+
+        let actions: Vec<ActionFn<TestLexeme, u16, Node<TestLexeme, u16>, ()>> =
+            vec![&Parser::generic_ptree; usize::from(grm.prods_len())];
+        let mut pp = builder.push_parser(&lexer, &actions, ());
+        for &l in &lexer.lexemes {
+            pp.push(l);
+        }
+        let (push_pt, push_errs) = pp.finish();
+
+        // When there are several equally good repairs, which is chosen is nondeterministic, so we
+        // can only compare parse trees when there were no errors.
+        assert_eq!(pt.is_some(), push_pt.is_some());
+        if errs.is_empty() {
+            assert_eq!(
+                pt.unwrap().pp(&grm, input),
+                push_pt.unwrap().pp(&grm, input)
+            );
+        }
+        assert_eq!(errs.len(), push_errs.len());
+        for (e, push_e) in errs.iter().zip(push_errs.iter()) {
+            match (e, push_e) {
+                (LexParseError::ParseError(e), LexParseError::ParseError(push_e)) => {
+                    assert_eq!(e.stidx(), push_e.stidx());
+                    assert_eq!(e.lexeme(), push_e.lexeme());
+                    assert_eq!(e.expected(), push_e.expected());
+                    let mut rprs = e.repairs().clone();
+                    let mut push_rprs = push_e.repairs().clone();
+                    rprs.sort_by_key(|x| format!("{:?}", x));
+                    push_rprs.sort_by_key(|x| format!("{:?}", x));
+                    assert_eq!(rprs, push_rprs);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn push_parser() {
+        let lexs = "\\( '('
+                    \\) ')'
+                    \\+ '+'
+                    \\* '*'
+                    [0-9]+ 'INT'";
+        let grms = "%start Expr
+%%
+Expr : Expr '+' Term | Term;
+Term : Term '*' Factor | Factor;
+Factor : '(' Expr ')' | 'INT';";
+        for rk in &[RecoveryKind::CPCTPlus, RecoveryKind::None] {
+            for input in &[
+                "", "2", "2+3*4", "(2+3)*4", "2+", "(2+3", "2+3)*4", "2++3**4", "2)+(3",
+            ] {
+                check_push_parse(*rk, lexs, grms, input);
+            }
+        }
+    }
+
+    #[test]
+    fn panic_recovery() {
+        let lexs = "[a-z]+ 'ID'
+                    ; ';'
+                    \\+ '+'";
+        let grms = "%start Stmts
+%token '+'
+%%
+Stmts : Stmts Stmt | Stmt;
+Stmt : 'ID' ';' | error ';';";
+
+        let (grm, pr) = do_parse(RecoveryKind::Panic, lexs, grms, "a;b+c;d;");
+        let (pt, errs) = pr.unwrap_err();
+        assert_eq!(
+            pt.unwrap().pp(&grm, "a;b+c;d;"),
+            "Stmts
+ Stmts
+  Stmts
+   Stmt
+    ID a
+    ; ;
+  Stmt
+   error b+c
+   ; ;
+ Stmt
+  ID d
+  ; ;
+"
+        );
+        assert_eq!(errs.len(), 1);
+        match &errs[0] {
+            LexParseError::ParseError(e) => {
+                assert_eq!(e.lexeme().span(), Span::new(3, 4));
+                assert!(e.repairs().is_empty());
+            }
+            _ => unreachable!(),
+        }
+
+        // Nothing needs to be popped or discarded: the error token is zero-length.
+        let (grm, pr) = do_parse(RecoveryKind::Panic, lexs, grms, "a;;");
+        let (pt, errs) = pr.unwrap_err();
+        assert_eq!(errs.len(), 1);
+        match pt.unwrap() {
+            Node::Nonterm { nodes, .. } => match &nodes[1] {
+                Node::Nonterm { nodes, .. } => match nodes[0] {
+                    Node::Term { lexeme } => {
+                        assert_eq!(
+                            lexeme.tok_id(),
+                            u32::from(grm.error_token_idx().unwrap()) as u16
+                        );
+                        assert_eq!(lexeme.span(), Span::new(2, 2));
+                        assert!(lexeme.faulty());
+                    }
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        // Reaching the end of the input while discarding lexemes means that recovery fails.
+        let (_, pr) = do_parse(RecoveryKind::Panic, lexs, grms, "a;b+c");
+        let (pt, errs) = pr.unwrap_err();
+        assert!(pt.is_none());
+        assert_eq!(errs.len(), 1);
+
+        // Each error must lead to at least one lexeme being discarded if recovery would otherwise
+        // end at the same place as the previous recovery.
+        let (_, pr) = do_parse(RecoveryKind::Panic, lexs, grms, "a;+;+;");
+        let (pt, errs) = pr.unwrap_err();
+        assert!(pt.is_some());
+        assert_eq!(errs.len(), 2);
+
+        // CPCT+ falls back to panic mode recovery if it can't find any repairs.
+        let (grm, pr) = do_parse_with_budget(
+            RecoveryKind::CPCTPlus,
+            RecoveryBudget::Nodes(0),
+            lexs,
+            grms,
+            "a;b+c;d;",
+        );
+        let (pt, errs) = pr.unwrap_err();
+        assert!(pt.unwrap().pp(&grm, "a;b+c;d;").contains("error b+c"));
+        assert_eq!(errs.len(), 1);
+
+        // Grammars without the error token can't use panic mode recovery.
+        let (_, pr) = do_parse(
+            RecoveryKind::Panic,
+            lexs,
+            "%start Stmts
+%token '+'
+%%
+Stmts : Stmts Stmt | Stmt;
+Stmt : 'ID' ';';",
+            "a;b+c;d;",
+        );
+        let (pt, errs) = pr.unwrap_err();
+        assert!(pt.is_none());
+        assert_eq!(errs.len(), 1);
+    }
+
+    #[test]
+    fn push_parser_panic() {
+        let lexs = "[a-z]+ 'ID'
+                    ; ';'
+                    \\+ '+'";
+        let grms = "%start Stmts
+%token '+'
+%%
+Stmts : Stmts Stmt | Stmt;
+Stmt : 'ID' ';' | error ';';";
+        for rk in &[RecoveryKind::CPCTPlus, RecoveryKind::Panic] {
+            for input in &["a;", "a;b+c;d;", "a;;", "a;b+c", "+;+;+", "a;+;+;"] {
+                check_push_parse(*rk, lexs, grms, input);
+            }
+        }
+    }
+
+    #[test]
+    fn push_parser_errors() {
+        let lexs = "a 'a'
+                    b 'b'";
+        let grms = "%start S
+%%
+S : 'a' 'b';";
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect();
+        let lexer = SmallLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids), "ba"),
+        };
+        let actions: Vec<ActionFn<TestLexeme, u16, Node<TestLexeme, u16>, ()>> =
+            vec![&Parser::generic_ptree; usize::from(grm.prods_len())];
+
+        // Without recovery, errors are reported as soon as they are encountered.
+        let builder = RTParserBuilder::new(&grm, &stable).recoverer(RecoveryKind::None);
+        let mut pp = builder.push_parser(&lexer, &actions, ());
+        pp.push(lexer.lexemes[0]);
+        assert_eq!(pp.errors().len(), 1);
+        pp.push(lexer.lexemes[1]);
+        let (pt, errs) = pp.finish();
+        assert!(pt.is_none());
+        assert_eq!(errs.len(), 1);
+
+        // With recovery, errors are only reported once enough lookahead is available.
+        let builder = RTParserBuilder::new(&grm, &stable);
+        let mut pp = builder.push_parser(&lexer, &actions, ());
+        pp.push(lexer.lexemes[0]);
+        pp.push(lexer.lexemes[1]);
+        assert!(pp.errors().is_empty());
+        let (pt, errs) = pp.finish();
+        assert!(pt.is_some());
+        assert_eq!(errs.len(), 1);
+
+        // Pushing a lexeme with the EOF token doesn't end the input.
+        let lexer = SmallLexer {
+            lexemes: vec![lexer.lexemes[1], lexer.lexemes[0]],
+        };
+        let eof = TestLexeme::new(u32::from(grm.eof_token_idx()) as u16, 1, 0);
+        let mut pp = builder.push_parser(&lexer, &actions, ());
+        pp.push(lexer.lexemes[0]);
+        pp.push(eof);
+        pp.push(lexer.lexemes[1]);
+        let (pt, errs) = pp.finish();
+        assert!(pt.is_some());
+        assert!(errs.is_empty());
+    }
+
+    #[test]
+    fn push_parser_from() {
+        let lexs = "a 'a'
+                    b 'b'";
+        let grms = "%start S
+%entry T
+%%
+S : 'a' T;
+T : 'b';";
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect();
+        let lexer = SmallLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids), "b"),
+        };
+        let actions: Vec<ActionFn<TestLexeme, u16, Node<TestLexeme, u16>, ()>> =
+            vec![&Parser::generic_ptree; usize::from(grm.prods_len())];
+        let builder = RTParserBuilder::new(&grm, &stable).recoverer(RecoveryKind::None);
+        let mut pp = builder.push_parser_from(grm.rule_idx("T").unwrap(), &lexer, &actions, ());
+        pp.push(lexer.lexemes[0]);
+        let (pt, errs) = pp.finish();
+        assert_eq!(pt.unwrap().pp(&grm, "b"), "T\n b b\n");
+        assert!(errs.is_empty());
+
+        // The same input isn't a valid instance of the start rule.
+        let mut pp = builder.push_parser(&lexer, &actions, ());
+        pp.push(lexer.lexemes[0]);
+        let (pt, errs) = pp.finish();
+        assert!(pt.is_none());
+        assert_eq!(errs.len(), 1);
+    }
+
+    // A recovery strategy which resynchronises on the next ';'.
+    struct SemiColonSync;
+
+    impl RecoveryStrategy<TestLexeme, u16> for SemiColonSync {
+        fn recover(
+            &self,
+            ctx: &RecoveryContext<TestLexeme, u16>,
+        ) -> Vec<Vec<ParseRepair<TestLexeme, u16>>> {
+            let semi_tidx = ctx.grm().token_idx(";").unwrap();
+            match ctx
+                .lookahead()
+                .iter()
+                .position(|l| TIdx(l.tok_id()) == semi_tidx)
+            {
+                Some(i) => {
+                    assert!(ctx.explore_node());
+                    // The empty repair sequence makes no progress, so the parser must skip it.
+                    vec![
+                        vec![],
+                        ctx.lookahead()[..i]
+                            .iter()
+                            .map(|&l| ParseRepair::Delete(l))
+                            .collect(),
+                    ]
+                }
+                None => vec![],
+            }
+        }
+    }
+
+    #[test]
+    fn custom_recovery_strategy() {
+        let lexs = "[a-z]+ 'ID'
+                    ; ';'
+                    \\+ '+'";
+        let grms = "%start Stmts
+%token '+'
+%%
+Stmts : Stmts Stmt | Stmt;
+Stmt : 'ID' ';';";
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect::<HashMap<_, _>>();
+        let lexer = SmallLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids.clone()), "a;b++;e;"),
+        };
+        let builder = RTParserBuilder::new(&grm, &stable)
+            .recoverer(RecoveryKind::None)
+            .recovery_strategy(&SemiColonSync);
+        let (pt, errs) = builder.parse_generictree(&lexer);
+        assert_eq!(
+            pt.unwrap().pp(&grm, "a;b++;e;"),
+            "Stmts
+ Stmts
+  Stmts
+   Stmt
+    ID a
+    ; ;
+  Stmt
+   ID b
+   ; ;
+ Stmt
+  ID e
+  ; ;
+"
+        );
+        assert_eq!(errs.len(), 1);
+        match &errs[0] {
+            LexParseError::ParseError(e) => {
+                assert_eq!(e.lexeme().span(), Span::new(3, 4));
+                assert_eq!(e.repairs().len(), 1);
+                let spans = e.repairs()[0]
+                    .iter()
+                    .map(|r| match r {
+                        ParseRepair::Delete(l) => l.span(),
+                        _ => unreachable!(),
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(spans, vec![Span::new(3, 4), Span::new(4, 5)]);
+            }
+            _ => unreachable!(),
+        }
+
+        // If the strategy finds no repairs, the parser falls back to panic mode, which fails
+        // without productions which use the `error` token.
+        let lexer = SmallLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids), "a;b+"),
+        };
+        let (pt, errs) = builder.parse_generictree(&lexer);
+        assert!(pt.is_none());
+        assert_eq!(errs.len(), 1);
+    }
+
+    #[test]
+    fn recovery_context() {
+        let grms = "%start Stmts
+%%
+Stmts : Stmts Stmt | Stmt;
+Stmt : 'ID' ';';";
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let id_tidx = grm.token_idx("ID").unwrap();
+        let semi_tidx = grm.token_idx(";").unwrap();
+        let lexeme = |tidx: TIdx<u16>, start| TestLexeme::new(u32::from(tidx) as u16, start, 1);
+        // The parse of "a b;" at the error at "b".
+        let mut pstack = vec![stable.start_state()];
+        let stidx = stable.action(pstack[0], id_tidx);
+        match stidx {
+            Action::Shift(stidx) => pstack.push(stidx),
+            _ => unreachable!(),
+        }
+        let allowance = RecoveryAllowance::new(RecoveryBudget::Nodes(1));
+        let ctx = RecoveryContext {
+            grm: &grm,
+            stable: &stable,
+            pstack: &pstack,
+            lookahead: vec![
+                lexeme(id_tidx, 2),
+                lexeme(semi_tidx, 3),
+                TestLexeme::new_faulty(u32::from(grm.eof_token_idx()) as u16, 4, 0),
+            ],
+            allowance: &allowance,
+        };
+        let b = ParseRepair::Delete(ctx.lookahead()[0]);
+        let semi = ParseRepair::Shift(ctx.lookahead()[1]);
+        assert_eq!(ctx.parse_distance(&[]), Some(0));
+        assert!(!ctx.makes_progress(&[]));
+        assert_eq!(ctx.parse_distance(std::slice::from_ref(&b)), Some(3));
+        assert!(ctx.makes_progress(std::slice::from_ref(&b)));
+        assert_eq!(
+            ctx.parse_distance(&[ParseRepair::Insert(semi_tidx)]),
+            Some(3)
+        );
+        assert_eq!(ctx.parse_distance(&[b.clone(), semi.clone()]), Some(3));
+        assert_eq!(ctx.parse_distance(&[semi]), None);
+        assert_eq!(ctx.parse_distance(&[ParseRepair::Insert(id_tidx)]), None);
+        assert_eq!(
+            ctx.parse_distance(&[ParseRepair::Insert(grm.eof_token_idx())]),
+            None
+        );
+        assert_eq!(ctx.parse_distance(&[b.clone(), b.clone(), b.clone()]), None);
+        assert!(ctx.explore_node());
+        assert!(!ctx.explore_node());
+    }
+
+    // A lexer which records how many lexemes the parser has pulled from it.
+    struct CountingLexer {
+        lexemes: Vec<TestLexeme>,
+        pulled: Cell<usize>,
+    }
+
+    impl Lexer<TestLexeme, u16> for CountingLexer {
+        fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = Result<TestLexeme, LexError>> + 'a> {
+            Box::new(self.lexemes.iter().map(move |x| {
+                self.pulled.set(self.pulled.get() + 1);
+                Ok(*x)
+            }))
+        }
+    }
+
+    impl<'input> NonStreamingLexer<'input, TestLexeme, u16> for CountingLexer {
+        fn span_str(&self, _: Span) -> &'input str {
+            unreachable!();
+        }
+
+        fn span_lines_str(&self, _: Span) -> &'input str {
+            unreachable!();
+        }
+
+        fn line_col(&self, _: Span) -> ((usize, usize), (usize, usize)) {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let lexs = "a 'a'";
+        let grms = "%start S
+%%
+S : S 'a' | 'a';";
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect();
+        let lexer = CountingLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids), "aaaa"),
+            pulled: Cell::new(0),
+        };
+        // Each action records how many lexemes had been pulled from the lexer when it was
+        // executed: a lexeme is only pulled once the parser needs it as lookahead.
+        let pulled = RefCell::new(Vec::new());
+        let action = |_: RIdx<u16>,
+                      _: &dyn NonStreamingLexer<TestLexeme, u16>,
+                      _: Span,
+                      _: vec::Drain<AStackType<TestLexeme, ()>>,
+                      _: ()| {
+            pulled.borrow_mut().push(lexer.pulled.get());
+        };
+        let actions: Vec<ActionFn<TestLexeme, u16, (), ()>> =
+            vec![&action; usize::from(grm.prods_len())];
+        let (r, errs) = RTParserBuilder::new(&grm, &stable).parse_actions(&lexer, &actions, ());
+        assert!(r.is_some());
+        assert!(errs.is_empty());
+        assert_eq!(*pulled.borrow(), vec![2, 3, 4, 4]);
+    }
+
+    // A lexer which returns a predetermined sequence of lexemes and lexing errors.
+    struct ErrLexer {
+        lexemes: Vec<Result<TestLexeme, LexError>>,
+    }
+
+    impl Lexer<TestLexeme, u16> for ErrLexer {
+        fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = Result<TestLexeme, LexError>> + 'a> {
+            Box::new(self.lexemes.iter().cloned())
+        }
+    }
+
+    impl<'input> NonStreamingLexer<'input, TestLexeme, u16> for ErrLexer {
+        fn span_str(&self, _: Span) -> &'input str {
+            unreachable!();
+        }
+
+        fn span_lines_str(&self, _: Span) -> &'input str {
+            unreachable!();
+        }
+
+        fn line_col(&self, _: Span) -> ((usize, usize), (usize, usize)) {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn lex_errors() {
+        let grms = "%start Stmts
+%%
+Stmts : Stmts Stmt | Stmt;
+Stmt : 'ID' ';';";
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let id = u32::from(grm.token_idx("ID").unwrap()) as u16;
+        let semi = u32::from(grm.token_idx(";").unwrap()) as u16;
+        // The lexemes of "a;§b;": the unlexable "§" becomes an error lexeme, which CPCT+ deletes,
+        // so parsing succeeds.
+        let lexer = ErrLexer {
+            lexemes: vec![
+                Ok(TestLexeme::new(id, 0, 1)),
+                Ok(TestLexeme::new(semi, 1, 1)),
+                Err(LexError::new(Span::new(2, 4))),
+                Ok(TestLexeme::new(id, 4, 1)),
+                Ok(TestLexeme::new(semi, 5, 1)),
+            ],
+        };
+        let builder = RTParserBuilder::new(&grm, &stable);
+        let (pt, errs) = builder.parse_generictree(&lexer);
+        assert_eq!(
+            pt.unwrap().pp(&grm, "a;§b;"),
+            "Stmts
+ Stmts
+  Stmt
+   ID a
+   ; ;
+ Stmt
+  ID b
+  ; ;
+"
+        );
+        assert_eq!(errs.len(), 2);
+        assert!(matches!(&errs[0], LexParseError::LexError(e) if e.span() == Span::new(2, 4)));
+        match &errs[1] {
+            LexParseError::ParseError(e) => {
+                assert_eq!(e.lexeme().span(), Span::new(2, 4));
+                assert!(e.lexeme().faulty());
+                assert_eq!(
+                    e.repairs(),
+                    &vec![vec![ParseRepair::Delete(TestLexeme::new_faulty(
+                        u32::from(grm.tokens_len()) as u16,
+                        2,
+                        2
+                    ))]]
+                );
+            }
+            _ => panic!(),
+        }
+
+        // The lexemes of "a b;c;§d;e;": the lexing error is pulled from the lexer before the
+        // parsing error at "b" is found, but is reported after it, followed by the parsing error
+        // its error lexeme causes.
+        let lexer = ErrLexer {
+            lexemes: vec![
+                Ok(TestLexeme::new(id, 0, 1)),
+                Ok(TestLexeme::new(id, 2, 1)),
+                Ok(TestLexeme::new(semi, 3, 1)),
+                Ok(TestLexeme::new(id, 4, 1)),
+                Ok(TestLexeme::new(semi, 5, 1)),
+                Err(LexError::new(Span::new(6, 8))),
+                Ok(TestLexeme::new(id, 8, 1)),
+                Ok(TestLexeme::new(semi, 9, 1)),
+                Ok(TestLexeme::new(id, 10, 1)),
+                Ok(TestLexeme::new(semi, 11, 1)),
+            ],
+        };
+        let (pt, errs) = builder.parse_generictree(&lexer);
+        assert!(pt.is_some());
+        assert_eq!(errs.len(), 3);
+        assert!(
+            matches!(&errs[0], LexParseError::ParseError(e) if e.lexeme().span() == Span::new(2, 3))
+        );
+        assert!(matches!(&errs[1], LexParseError::LexError(e) if e.span() == Span::new(6, 8)));
+        assert!(
+            matches!(&errs[2], LexParseError::ParseError(e) if e.lexeme().span() == Span::new(6, 8))
+        );
+
+        // Without error recovery, parsing stops at the first error: in "a;§b;" that's the lexing
+        // error, even though the rest of the input parses.
+        let lexer = ErrLexer {
+            lexemes: vec![
+                Ok(TestLexeme::new(id, 0, 1)),
+                Ok(TestLexeme::new(semi, 1, 1)),
+                Err(LexError::new(Span::new(2, 4))),
+                Ok(TestLexeme::new(id, 4, 1)),
+                Ok(TestLexeme::new(semi, 5, 1)),
+            ],
+        };
+        let (pt, errs) = builder
+            .recoverer(RecoveryKind::None)
+            .parse_generictree(&lexer);
+        assert!(pt.is_none());
+        assert_eq!(errs.len(), 1);
+        assert!(matches!(&errs[0], LexParseError::LexError(e) if e.span() == Span::new(2, 4)));
+    }
+
+    #[test]
+    fn parse_from_entry() {
+        let lexs = "[a-z] 'ID'
+\\+ '+'
+; ';'";
+        let grms = "%start S
+%entry E
+%%
+S : E ';' | S E ';';
+E : E '+' 'ID' | 'ID';";
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            grms,
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let rule_ids = grm
+            .tokens_map()
+            .iter()
+            .map(|(&n, &i)| (n.to_owned(), u32::from(i).to_u16().unwrap()))
+            .collect::<HashMap<_, _>>();
+        let lex = |input| SmallLexer {
+            lexemes: small_lex(small_lexer(lexs, rule_ids.clone()), input),
+        };
+        let e_ridx = grm.rule_idx("E").unwrap();
+        let pb = RTParserBuilder::new(&grm, &stable);
+
+        let (r, errs) = pb.parse_generictree_from(e_ridx, &lex("a+b"));
+        assert!(errs.is_empty());
+        assert_eq!(
+            r.unwrap().pp(&grm, "a+b"),
+            "E
+ E
+  ID a
+ + +
+ ID b
+"
+        );
+        assert!(pb.parse_noaction_from(e_ridx, &lex("a+b")).is_empty());
+        // A statement is not an expression...
+        assert!(!pb.parse_noaction_from(e_ridx, &lex("a;")).is_empty());
+        // ...and an expression is not a statement.
+        assert!(!pb.parse_noaction(&lex("a")).is_empty());
+        assert!(pb.parse_noaction(&lex("a;")).is_empty());
+        assert!(pb
+            .parse_noaction_from(grm.rule_idx("S").unwrap(), &lex("a;b+c;"))
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn parse_from_non_entry() {
+        let grm = YaccGrammar::<u16>::new_with_storaget(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "%start S %% S : T; T : 'a';",
+        )
+        .unwrap();
+        let (_, stable) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let lexer = SmallLexer { lexemes: vec![] };
+        RTParserBuilder::new(&grm, &stable).parse_noaction_from(grm.rule_idx("T").unwrap(), &lexer);
+    }
 }
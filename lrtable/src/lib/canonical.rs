@@ -0,0 +1,174 @@
+use std::{collections::hash_map::HashMap, hash::Hash};
+
+use cfgrammar::{yacc::YaccGrammar, PIdx, SIdx, Symbol};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+use vob::Vob;
+
+use crate::{
+    itemset::{Ctx, Itemset},
+    stategraph::{new_stidx, StateGraph},
+    StIdx, StateTableError,
+};
+
+// This file creates canonical LR(1) stategraphs from grammars, as described in:
+//
+//   On the Translation of Languages from Left to Right
+//     Donald E. Knuth, Information and Control 8, 607--639, 1965
+//
+// Unlike Pager's algorithm, no states are ever merged: two core states are only considered the
+// same if they have identical items *and* identical contexts. This can lead to very large
+// stategraphs for even moderately sized grammars, but the conflicts reported are exactly those
+// of the grammar under LR(1).
+
+/// A hashable, order independent, representation of a core state.
+type CoreKey<StorageT> = Vec<(PIdx<StorageT>, SIdx<StorageT>, Ctx)>;
+
+fn core_key<StorageT: Hash + PrimInt + Unsigned>(is: &Itemset<StorageT>) -> CoreKey<StorageT> {
+    let mut k = is
+        .items
+        .iter()
+        .map(|(&(pidx, dot), ctx)| (pidx, dot, ctx.clone()))
+        .collect::<Vec<_>>();
+    k.sort_unstable_by_key(|&(pidx, dot, _)| (pidx, dot));
+    k
+}
+
+/// Create a canonical LR(1) `StateGraph` from 'grm'.
+pub fn canonical_stategraph<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+) -> Result<StateGraph<StorageT>, StateTableError<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let firsts = grm.firsts();
+    let mut core_states = Vec::new();
+    let mut closed_states = Vec::new();
+    let mut edges: Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>> = Vec::new();
+    // A map from core states to their index in core_states.
+    let mut seen = HashMap::new();
+
+    // Each of the grammar's entry points has its own start state.
+    let mut start_states = Vec::with_capacity(grm.entries().len());
+    let mut ctx = Vob::from_elem(false, usize::from(grm.tokens_len()));
+    ctx.set(usize::from(grm.eof_token_idx()), true);
+    for &(_, pidx) in grm.entries() {
+        let mut state = Itemset::new(grm);
+        state.add(pidx, SIdx(StorageT::zero()), &ctx);
+        let stidx = new_stidx(grm, core_states.len())?;
+        seen.insert(core_key(&state), stidx);
+        start_states.push(stidx);
+        core_states.push(state);
+        edges.push(HashMap::new());
+    }
+
+    // We maintain two lists of which rules and tokens we've seen; when processing a given
+    // state there's no point processing a rule or token more than once.
+    let mut seen_rules = Vob::from_elem(false, usize::from(grm.rules_len()));
+    let mut seen_tokens = Vob::from_elem(false, usize::from(grm.tokens_len()));
+    // Since states are only ever appended, every state before `state_i` has been fully processed.
+    let mut state_i = 0;
+    while state_i < core_states.len() {
+        let cl_state = core_states[state_i].close(grm, &firsts);
+        seen_rules.set_all(false);
+        seen_tokens.set_all(false);
+        for &(pidx, dot) in cl_state.items.keys() {
+            if dot == grm.prod_len(pidx) {
+                continue;
+            }
+            let sym = grm.prod(pidx)[usize::from(dot)];
+            match sym {
+                Symbol::Rule(s_ridx) => {
+                    if seen_rules[usize::from(s_ridx)] {
+                        continue;
+                    }
+                    seen_rules.set(usize::from(s_ridx), true);
+                }
+                Symbol::Token(s_tidx) => {
+                    if seen_tokens[usize::from(s_tidx)] {
+                        continue;
+                    }
+                    seen_tokens.set(usize::from(s_tidx), true);
+                }
+            }
+            let nstate = cl_state.goto(grm, &sym);
+            let k = core_key(&nstate);
+            let stidx = match seen.get(&k) {
+                Some(&stidx) => stidx,
+                None => {
+                    let stidx = new_stidx(grm, core_states.len())?;
+                    seen.insert(k, stidx);
+                    core_states.push(nstate);
+                    edges.push(HashMap::new());
+                    stidx
+                }
+            };
+            edges[state_i].insert(sym, stidx);
+        }
+        closed_states.push(cl_state);
+        state_i += 1;
+    }
+
+    // Every state is reachable from a start state by construction, so unlike Pager's algorithm
+    // there is nothing to garbage collect.
+    Ok(StateGraph::new(
+        core_states.into_iter().zip(closed_states).collect(),
+        start_states,
+        edges,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use cfgrammar::{
+        yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind},
+        SIdx, Symbol,
+    };
+
+    use super::canonical_stategraph;
+    use crate::{pager::pager_stategraph, stategraph::state_exists, StIdx, StateTable};
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_canonical_no_merging() {
+        // A grammar where Pager's algorithm merges two states that canonical LR(1) keeps apart.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'a' A 'b' | A 'c';
+            A: 'x';
+          ").unwrap();
+        let sg = canonical_stategraph(&grm).unwrap();
+        assert_eq!(sg.all_states_len(), StIdx(9));
+        assert_eq!(pager_stategraph(&grm).unwrap().all_states_len(), StIdx(8));
+
+        let s0 = sg.start_state();
+        let s2 = sg.edge(s0, Symbol::Token(grm.token_idx("a").unwrap())).unwrap();
+        let s4 = sg.edge(s0, Symbol::Token(grm.token_idx("x").unwrap())).unwrap();
+        let s6 = sg.edge(s2, Symbol::Token(grm.token_idx("x").unwrap())).unwrap();
+        assert_ne!(s4, s6);
+        state_exists(&grm, sg.closed_state(s4), "A", 0, SIdx(1), vec!["c"]);
+        state_exists(&grm, sg.closed_state(s6), "A", 0, SIdx(1), vec!["b"]);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_canonical_lr1_not_lalr1() {
+        // This grammar is LR(1) but not LALR(1): merging the states reached by 'a' 'c' and
+        // 'b' 'c' would lead to a reduce/reduce conflict.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'a' A 'd' | 'b' B 'd' | 'a' B 'e' | 'b' A 'e';
+            A: 'c';
+            B: 'c';
+          ").unwrap();
+        let sg = canonical_stategraph(&grm).unwrap();
+        assert_eq!(sg.all_states_len(), StIdx(14));
+        let st = StateTable::new(&grm, &sg).unwrap();
+        assert!(st.conflicts().is_none());
+    }
+}
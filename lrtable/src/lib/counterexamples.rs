@@ -0,0 +1,867 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::HashMap, BinaryHeap},
+    hash::Hash,
+};
+
+use cfgrammar::{yacc::YaccGrammar, PIdx, SIdx, Symbol, TIdx};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+
+use crate::{stategraph::StateGraph, StIdx};
+
+// This file creates counterexamples for conflicts, in a similar spirit to (though much less
+// sophisticated than) the algorithm in:
+//
+//   Finding Counterexamples from Parsing Conflicts
+//     Chinawat Isradisaikul and Andrew C. Myers, PLDI 2015
+//
+// For each of the two items involved in a conflict we find the shortest "lookahead sensitive
+// path" to that item: a path through the stategraph, descending into productions as necessary,
+// which guarantees that the conflicting token can follow the item. Each path gives us an example
+// sentential form and its derivation; a path's length counts the symbols on both sides of the
+// example's dot, so that examples do not contain unnecessary trailing symbols. If the two examples have identical symbols, we have found
+// an ambiguity (a "unifying" counterexample); otherwise we report the two examples separately (a
+// "non-unifying" counterexample). Unlike Isradisaikul and Myers, we do not search beyond the
+// shortest path to each item, so we sometimes report a non-unifying counterexample for a conflict
+// which is caused by an ambiguity.
+
+/// A (partial) derivation tree. Symbols which are not expanded further can be either tokens or
+/// rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Derivation<StorageT> {
+    /// A symbol which is not expanded further.
+    Symbol(Symbol<StorageT>),
+    /// The point in the input at which the conflict occurs.
+    Dot,
+    /// A production, and the derivations of each of its symbols.
+    Prod(PIdx<StorageT>, Vec<Derivation<StorageT>>),
+}
+
+impl<StorageT: 'static + Hash + PrimInt + Unsigned> Derivation<StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    fn leaves(&self, syms: &mut Vec<Symbol<StorageT>>, dot: &mut Option<usize>) {
+        match self {
+            Derivation::Symbol(sym) => syms.push(*sym),
+            Derivation::Dot => *dot = Some(syms.len()),
+            Derivation::Prod(_, ds) => {
+                for d in ds {
+                    d.leaves(syms, dot);
+                }
+            }
+        }
+    }
+
+    /// Returns a pretty-printed version of this derivation. Each production is printed as
+    /// `R -> [...]`.
+    pub fn pp(&self, grm: &YaccGrammar<StorageT>) -> String {
+        match self {
+            Derivation::Symbol(sym) => pp_sym(grm, *sym),
+            Derivation::Dot => "•".to_owned(),
+            Derivation::Prod(pidx, ds) => format!(
+                "{} -> [{}]",
+                grm.rule_name(grm.prod_to_rule(*pidx)),
+                ds.iter().map(|d| d.pp(grm)).collect::<Vec<_>>().join(" ")
+            ),
+        }
+    }
+}
+
+/// An example sentential form which leads to a conflict, and its derivation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Example<StorageT> {
+    /// The (token and rule) symbols of the example.
+    pub symbols: Vec<Symbol<StorageT>>,
+    /// The conflict occurs when `symbols[..dot]` have been parsed.
+    pub dot: usize,
+    /// How the start rule derives `symbols`.
+    pub derivation: Vec<Derivation<StorageT>>,
+}
+
+impl<StorageT: 'static + Hash + PrimInt + Unsigned> Example<StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    fn new(derivation: Vec<Derivation<StorageT>>) -> Self {
+        let mut symbols = Vec::new();
+        let mut dot = None;
+        for d in &derivation {
+            d.leaves(&mut symbols, &mut dot);
+        }
+        Example {
+            symbols,
+            dot: dot.unwrap(),
+            derivation,
+        }
+    }
+
+    /// Returns a pretty-printed version of this example's symbols.
+    pub fn pp(&self, grm: &YaccGrammar<StorageT>) -> String {
+        let mut syms = self
+            .symbols
+            .iter()
+            .map(|sym| pp_sym(grm, *sym))
+            .collect::<Vec<_>>();
+        syms.insert(self.dot, "•".to_owned());
+        syms.join(" ")
+    }
+
+    /// Returns a pretty-printed version of this example's derivation.
+    pub fn pp_derivation(&self, grm: &YaccGrammar<StorageT>) -> String {
+        self.derivation
+            .iter()
+            .map(|d| d.pp(grm))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A counterexample for a conflict. The first example always corresponds to the first action
+/// of the conflict (i.e. the reduction for a shift/reduce conflict, and the first reduction for
+/// a reduce/reduce conflict) and the second example to the second action.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Counterexample<StorageT> {
+    /// The two examples have the same symbols but different derivations: the grammar is
+    /// ambiguous.
+    Unifying(Example<StorageT>, Example<StorageT>),
+    /// The two examples have different symbols.
+    NonUnifying(Example<StorageT>, Example<StorageT>),
+}
+
+pub(crate) fn pp_sym<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    sym: Symbol<StorageT>,
+) -> String
+where
+    usize: AsPrimitive<StorageT>,
+{
+    match sym {
+        Symbol::Rule(ridx) => grm.rule_name(ridx).to_owned(),
+        Symbol::Token(tidx) => format!("\"{}\"", grm.token_name(tidx).unwrap_or("<unknown>")),
+    }
+}
+
+/// Searches for examples leading to conflicts on the token `tidx`.
+struct Searcher<'a, StorageT: 'static + Eq + Hash> {
+    grm: &'a YaccGrammar<StorageT>,
+    sg: &'a StateGraph<StorageT>,
+    tidx: TIdx<StorageT>,
+    /// For each rule, if it can derive the empty string, a production that does so without
+    /// recursing infinitely.
+    eps_prods: Vec<Option<PIdx<StorageT>>>,
+    /// For each rule, if it can derive a string starting with `tidx`, a production and the
+    /// offset of the symbol within it which does so without recursing infinitely.
+    tfirst_prods: Vec<Option<(PIdx<StorageT>, usize)>>,
+    /// The index of the first node of each state. Each item in a state has two nodes: one where
+    /// we do not require that `tidx` follows the item's production, and one where we do.
+    offsets: Vec<usize>,
+    /// A map from each item in each state to its offset from that state's first node.
+    items: Vec<HashMap<(PIdx<StorageT>, SIdx<StorageT>), usize>>,
+}
+
+impl<'a, StorageT: 'static + Hash + PrimInt + Unsigned> Searcher<'a, StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    fn new(
+        grm: &'a YaccGrammar<StorageT>,
+        sg: &'a StateGraph<StorageT>,
+        tidx: TIdx<StorageT>,
+    ) -> Self {
+        let mut eps_prods = vec![None; usize::from(grm.rules_len())];
+        let mut tfirst_prods = vec![None; usize::from(grm.rules_len())];
+        // Only accept a production for a rule if it only relies on rules which have already been
+        // accepted: this guarantees that expanding derivations always terminates.
+        loop {
+            let mut changed = false;
+            for pidx in grm.iter_pidxs() {
+                let ridx = usize::from(grm.prod_to_rule(pidx));
+                if eps_prods[ridx].is_none()
+                    && grm.prod(pidx).iter().all(|sym| match sym {
+                        Symbol::Rule(s_ridx) => eps_prods[usize::from(*s_ridx)].is_some(),
+                        Symbol::Token(_) => false,
+                    })
+                {
+                    eps_prods[ridx] = Some(pidx);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        loop {
+            let mut changed = false;
+            for pidx in grm.iter_pidxs() {
+                let ridx = usize::from(grm.prod_to_rule(pidx));
+                if tfirst_prods[ridx].is_some() {
+                    continue;
+                }
+                for (i, sym) in grm.prod(pidx).iter().enumerate() {
+                    match *sym {
+                        Symbol::Token(s_tidx) => {
+                            if s_tidx == tidx {
+                                tfirst_prods[ridx] = Some((pidx, i));
+                                changed = true;
+                            }
+                            break;
+                        }
+                        Symbol::Rule(s_ridx) => {
+                            if tfirst_prods[usize::from(s_ridx)].is_some() {
+                                tfirst_prods[ridx] = Some((pidx, i));
+                                changed = true;
+                                break;
+                            }
+                            if eps_prods[usize::from(s_ridx)].is_none() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut offsets = Vec::with_capacity(usize::from(sg.all_states_len()));
+        let mut items = Vec::with_capacity(usize::from(sg.all_states_len()));
+        let mut off = 0;
+        for stidx in sg.iter_stidxs() {
+            offsets.push(off);
+            let mut m = HashMap::new();
+            for &k in sg.closed_state(stidx).items.keys() {
+                m.insert(k, m.len() * 2);
+            }
+            off += m.len() * 2;
+            items.push(m);
+        }
+
+        Searcher {
+            grm,
+            sg,
+            tidx,
+            eps_prods,
+            tfirst_prods,
+            offsets,
+            items,
+        }
+    }
+
+    fn nullable(&self, syms: &[Symbol<StorageT>]) -> bool {
+        syms.iter().all(|sym| match sym {
+            Symbol::Rule(ridx) => self.eps_prods[usize::from(*ridx)].is_some(),
+            Symbol::Token(_) => false,
+        })
+    }
+
+    /// If `syms` can derive a string starting with `self.tidx`, return the offset of the symbol
+    /// which does so.
+    fn tfirst(&self, syms: &[Symbol<StorageT>]) -> Option<usize> {
+        for (i, sym) in syms.iter().enumerate() {
+            match *sym {
+                Symbol::Token(tidx) => return if tidx == self.tidx { Some(i) } else { None },
+                Symbol::Rule(ridx) => {
+                    if self.tfirst_prods[usize::from(ridx)].is_some() {
+                        return Some(i);
+                    }
+                    // If `ridx` isn't nullable, nothing after it can start with `self.tidx`.
+                    self.eps_prods[usize::from(ridx)]?;
+                }
+            }
+        }
+        None
+    }
+
+    fn node(
+        &self,
+        stidx: StIdx<StorageT>,
+        pidx: PIdx<StorageT>,
+        dot: SIdx<StorageT>,
+        need: bool,
+    ) -> usize {
+        self.offsets[usize::from(stidx)]
+            + self.items[usize::from(stidx)][&(pidx, dot)]
+            + usize::from(need)
+    }
+
+    /// Find the shortest lookahead sensitive path to an item in state `stidx` satisfying
+    /// `is_target`, where a path's length is the number of symbols in the example it leads to.
+    /// Returns a list of `(pidx, dot, need)` triples, one for each step of the path.
+    fn path<F>(
+        &self,
+        stidx: StIdx<StorageT>,
+        is_target: &F,
+    ) -> Option<Vec<(PIdx<StorageT>, SIdx<StorageT>, bool)>>
+    where
+        F: Fn(PIdx<StorageT>, SIdx<StorageT>, bool) -> bool,
+    {
+        let grm = self.grm;
+        let nodes_len = self.offsets.last().unwrap() + self.items.last().unwrap().len() * 2;
+        let mut parents = vec![None; nodes_len];
+        let mut done = vec![false; nodes_len];
+        // A Dijkstra search: moving the dot over a symbol has a cost of 1, and descending into a
+        // production costs the number of symbols which follow it in the parent production (since
+        // these end up after the dot in the example). `entries` holds each step we have queued,
+        // and `todo` orders their indexes by cost (ties are broken in the order steps were
+        // queued).
+        let mut entries = Vec::new();
+        let mut todo = BinaryHeap::new();
+        for (&(_, pidx), &start_stidx) in grm.entries().iter().zip(self.sg.start_states()) {
+            let start = (start_stidx, pidx, SIdx(StorageT::zero()));
+            todo.push(Reverse((0, entries.len())));
+            entries.push((start, false, None));
+            if self.tidx == grm.eof_token_idx() {
+                todo.push(Reverse((0, entries.len())));
+                entries.push((start, true, None));
+            }
+        }
+        while let Some(Reverse((cost, i))) = todo.pop() {
+            let ((c_stidx, pidx, dot), need, parent) = entries[i];
+            let n = self.node(c_stidx, pidx, dot, need);
+            if done[n] {
+                continue;
+            }
+            done[n] = true;
+            parents[n] = parent;
+            if c_stidx == stidx && is_target(pidx, dot, need) {
+                let mut path = vec![(pidx, dot, need)];
+                let mut cur = parent;
+                while let Some(((p_stidx, p_pidx, p_dot), p_need)) = cur {
+                    path.push((p_pidx, p_dot, p_need));
+                    cur = parents[self.node(p_stidx, p_pidx, p_dot, p_need)];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if dot == grm.prod_len(pidx) {
+                continue;
+            }
+            let prod = grm.prod(pidx);
+            let sym = prod[usize::from(dot)];
+            let cur = Some(((c_stidx, pidx, dot), need));
+            let n_stidx = self.sg.edge(c_stidx, sym).unwrap();
+            todo.push(Reverse((cost + 1, entries.len())));
+            entries.push((
+                (n_stidx, pidx, SIdx(dot.as_storaget() + StorageT::one())),
+                need,
+                cur,
+            ));
+            if let Symbol::Rule(ridx) = sym {
+                let rest = &prod[usize::from(dot) + 1..];
+                let c_need = self.tfirst(rest).is_some() || (need && self.nullable(rest));
+                for &c_pidx in grm.rule_to_prods(ridx) {
+                    let c_item = (c_stidx, c_pidx, SIdx(StorageT::zero()));
+                    todo.push(Reverse((cost + rest.len(), entries.len())));
+                    entries.push((c_item, false, cur));
+                    if c_need {
+                        todo.push(Reverse((cost + rest.len(), entries.len())));
+                        entries.push((c_item, true, cur));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Find lookahead sensitive paths which move the dot over exactly the symbols in `prefix`
+    /// and end at an item satisfying `is_target`. Since there can be exponentially many such
+    /// paths, the search gives up after `PREFIX_SEARCH_BUDGET` steps.
+    fn paths_with_prefix<F>(
+        &self,
+        prefix: &[Symbol<StorageT>],
+        is_target: &F,
+    ) -> Vec<Vec<(PIdx<StorageT>, SIdx<StorageT>, bool)>>
+    where
+        F: Fn(PIdx<StorageT>, SIdx<StorageT>, bool) -> bool,
+    {
+        let mut paths = Vec::new();
+        let mut budget = PREFIX_SEARCH_BUDGET;
+        let mut path = Vec::new();
+        for (&(_, pidx), &start_stidx) in self.grm.entries().iter().zip(self.sg.start_states()) {
+            let start = (start_stidx, pidx, SIdx(StorageT::zero()));
+            self.paths_with_prefix_rec(
+                start,
+                false,
+                0,
+                prefix,
+                is_target,
+                &mut budget,
+                &mut path,
+                &mut paths,
+            );
+            if self.tidx == self.grm.eof_token_idx() {
+                self.paths_with_prefix_rec(
+                    start,
+                    true,
+                    0,
+                    prefix,
+                    is_target,
+                    &mut budget,
+                    &mut path,
+                    &mut paths,
+                );
+            }
+        }
+        paths
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn paths_with_prefix_rec<F>(
+        &self,
+        (stidx, pidx, dot): (StIdx<StorageT>, PIdx<StorageT>, SIdx<StorageT>),
+        need: bool,
+        pos: usize,
+        prefix: &[Symbol<StorageT>],
+        is_target: &F,
+        budget: &mut usize,
+        path: &mut Vec<(PIdx<StorageT>, SIdx<StorageT>, bool)>,
+        paths: &mut Vec<Vec<(PIdx<StorageT>, SIdx<StorageT>, bool)>>,
+    ) where
+        F: Fn(PIdx<StorageT>, SIdx<StorageT>, bool) -> bool,
+    {
+        if *budget == 0 {
+            return;
+        }
+        *budget -= 1;
+        // Descending into productions doesn't move the dot, so we have to be careful not to
+        // descend into the same item (e.g. via left recursion) forever.
+        if dot == SIdx(StorageT::zero())
+            && path
+                .iter()
+                .rev()
+                .take_while(|&&(_, d, _)| d == SIdx(StorageT::zero()))
+                .any(|&x| x == (pidx, dot, need))
+        {
+            return;
+        }
+        path.push((pidx, dot, need));
+        if pos == prefix.len() && is_target(pidx, dot, need) {
+            paths.push(path.clone());
+        }
+        if dot < self.grm.prod_len(pidx) {
+            let prod = self.grm.prod(pidx);
+            let sym = prod[usize::from(dot)];
+            if pos < prefix.len() && sym == prefix[pos] {
+                let n_stidx = self.sg.edge(stidx, sym).unwrap();
+                self.paths_with_prefix_rec(
+                    (n_stidx, pidx, SIdx(dot.as_storaget() + StorageT::one())),
+                    need,
+                    pos + 1,
+                    prefix,
+                    is_target,
+                    budget,
+                    path,
+                    paths,
+                );
+            }
+            if let Symbol::Rule(ridx) = sym {
+                let rest = &prod[usize::from(dot) + 1..];
+                let c_need = self.tfirst(rest).is_some() || (need && self.nullable(rest));
+                for &c_pidx in self.grm.rule_to_prods(ridx) {
+                    let c_item = (stidx, c_pidx, SIdx(StorageT::zero()));
+                    self.paths_with_prefix_rec(
+                        c_item, false, pos, prefix, is_target, budget, path, paths,
+                    );
+                    if c_need {
+                        self.paths_with_prefix_rec(
+                            c_item, true, pos, prefix, is_target, budget, path, paths,
+                        );
+                    }
+                }
+            }
+        }
+        path.pop();
+    }
+
+    /// Return a derivation of `ridx` which derives the empty string.
+    fn eps_derivation(&self, ridx: usize) -> Derivation<StorageT> {
+        let pidx = self.eps_prods[ridx].unwrap();
+        Derivation::Prod(
+            pidx,
+            self.grm
+                .prod(pidx)
+                .iter()
+                .map(|sym| match sym {
+                    Symbol::Rule(s_ridx) => self.eps_derivation(usize::from(*s_ridx)),
+                    Symbol::Token(_) => unreachable!(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Return derivations for `syms` which derive a string starting with `self.tidx`.
+    fn tfirst_derivations(&self, syms: &[Symbol<StorageT>]) -> Vec<Derivation<StorageT>> {
+        let i = self.tfirst(syms).unwrap();
+        let mut ds = syms[..i]
+            .iter()
+            .map(|sym| match sym {
+                Symbol::Rule(ridx) => self.eps_derivation(usize::from(*ridx)),
+                Symbol::Token(_) => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        match syms[i] {
+            Symbol::Token(_) => ds.push(Derivation::Symbol(syms[i])),
+            Symbol::Rule(ridx) => {
+                let (pidx, _) = self.tfirst_prods[usize::from(ridx)].unwrap();
+                ds.push(Derivation::Prod(
+                    pidx,
+                    self.tfirst_derivations(self.grm.prod(pidx)),
+                ));
+            }
+        }
+        ds.extend(syms[i + 1..].iter().map(|sym| Derivation::Symbol(*sym)));
+        ds
+    }
+
+    /// Turn a path (as returned by `path`) into an example.
+    fn example(&self, path: &[(PIdx<StorageT>, SIdx<StorageT>, bool)]) -> Example<StorageT> {
+        let grm = self.grm;
+        // Each frame is a production, the derivations of the symbols we have moved the dot over,
+        // and whether we require that `self.tidx` follows the production.
+        let mut frames = vec![(path[0].0, Vec::new(), path[0].2)];
+        for &(pidx, dot, need) in &path[1..] {
+            if dot == SIdx(StorageT::zero()) {
+                frames.push((pidx, Vec::new(), need));
+            } else {
+                let sym = grm.prod(pidx)[usize::from(dot) - 1];
+                frames.last_mut().unwrap().1.push(Derivation::Symbol(sym));
+            }
+        }
+
+        let (pidx, mut ds, mut need) = frames.pop().unwrap();
+        let &(_, dot, _) = path.last().unwrap();
+        ds.push(Derivation::Dot);
+        ds.extend(
+            grm.prod(pidx)[usize::from(dot)..]
+                .iter()
+                .map(|sym| Derivation::Symbol(*sym)),
+        );
+        if dot < grm.prod_len(pidx) {
+            // The example is for a shift, so we don't care what follows this production.
+            need = false;
+        }
+        let mut d = Derivation::Prod(pidx, ds);
+        while let Some((pidx, mut ds, f_need)) = frames.pop() {
+            let rest = &grm.prod(pidx)[ds.len() + 1..];
+            ds.push(d);
+            if need {
+                if self.tfirst(rest).is_some() {
+                    ds.extend(self.tfirst_derivations(rest));
+                    need = false;
+                } else {
+                    ds.extend(rest.iter().map(|sym| match sym {
+                        Symbol::Rule(ridx) => self.eps_derivation(usize::from(*ridx)),
+                        Symbol::Token(_) => unreachable!(),
+                    }));
+                    need = f_need;
+                }
+            } else {
+                ds.extend(rest.iter().map(|sym| Derivation::Symbol(*sym)));
+            }
+            d = Derivation::Prod(pidx, ds);
+        }
+        match d {
+            Derivation::Prod(_, ds) => Example::new(ds),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The maximum number of steps [Searcher::paths_with_prefix] may take.
+const PREFIX_SEARCH_BUDGET: usize = 100_000;
+
+fn unifies<StorageT: PartialEq>(ex1: &Example<StorageT>, ex2: &Example<StorageT>) -> bool {
+    ex1.symbols == ex2.symbols && ex1.dot == ex2.dot && ex1.derivation != ex2.derivation
+}
+
+/// Return a counterexample for a conflict in `stidx` between the items satisfying `is_target1`
+/// and `is_target2`.
+fn counterexample<StorageT: 'static + Hash + PrimInt + Unsigned, F1, F2>(
+    s: &Searcher<StorageT>,
+    stidx: StIdx<StorageT>,
+    is_target1: F1,
+    is_target2: F2,
+) -> Option<Counterexample<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+    F1: Fn(PIdx<StorageT>, SIdx<StorageT>, bool) -> bool,
+    F2: Fn(PIdx<StorageT>, SIdx<StorageT>, bool) -> bool,
+{
+    let ex1 = s.example(&s.path(stidx, &is_target1)?);
+    let ex2 = s.example(&s.path(stidx, &is_target2)?);
+    if unifies(&ex1, &ex2) {
+        return Some(Counterexample::Unifying(ex1, ex2));
+    }
+    // The shortest examples for the two items don't unify, but the conflict may still be caused
+    // by an ambiguity: see if we can find an example for one item with the same symbols as the
+    // other item's example.
+    for path in s.paths_with_prefix(&ex1.symbols[..ex1.dot], &is_target2) {
+        let ex = s.example(&path);
+        if unifies(&ex1, &ex) {
+            return Some(Counterexample::Unifying(ex1, ex));
+        }
+    }
+    for path in s.paths_with_prefix(&ex2.symbols[..ex2.dot], &is_target1) {
+        let ex = s.example(&path);
+        if unifies(&ex, &ex2) {
+            return Some(Counterexample::Unifying(ex, ex2));
+        }
+    }
+    Some(Counterexample::NonUnifying(ex1, ex2))
+}
+
+/// Return a counterexample for a shift/reduce conflict on `tidx` with production `pidx` in
+/// state `stidx`.
+pub(crate) fn sr_counterexample<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    sg: &StateGraph<StorageT>,
+    tidx: TIdx<StorageT>,
+    pidx: PIdx<StorageT>,
+    stidx: StIdx<StorageT>,
+) -> Option<Counterexample<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    counterexample(
+        &Searcher::new(grm, sg, tidx),
+        stidx,
+        |p, dot, need| p == pidx && dot == grm.prod_len(pidx) && need,
+        |p, dot, _| dot < grm.prod_len(p) && grm.prod(p)[usize::from(dot)] == Symbol::Token(tidx),
+    )
+}
+
+/// Return a counterexample for a reduce/reduce conflict on `tidx` between productions `pidx` and
+/// `r_pidx` in state `stidx`.
+pub(crate) fn rr_counterexample<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    sg: &StateGraph<StorageT>,
+    tidx: TIdx<StorageT>,
+    pidx: PIdx<StorageT>,
+    r_pidx: PIdx<StorageT>,
+    stidx: StIdx<StorageT>,
+) -> Option<Counterexample<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    counterexample(
+        &Searcher::new(grm, sg, tidx),
+        stidx,
+        |p, dot, need| p == pidx && dot == grm.prod_len(pidx) && need,
+        |p, dot, need| p == r_pidx && dot == grm.prod_len(r_pidx) && need,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use cfgrammar::{
+        yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind},
+        Symbol,
+    };
+
+    use super::Counterexample;
+    use crate::{from_yacc, Minimiser};
+
+    #[test]
+    fn test_sr_unifying() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start E
+            %%
+            E: E '+' E | 'id';
+          ",
+        )
+        .unwrap();
+        let (sg, st) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let c = st.conflicts().unwrap();
+        assert_eq!(c.sr_len(), 1);
+        let &(tidx, pidx, stidx) = c.sr_conflicts().next().unwrap();
+        match c.sr_counterexample(&grm, &sg, tidx, pidx, stidx).unwrap() {
+            Counterexample::Unifying(reduce, shift) => {
+                assert_eq!(reduce.pp(&grm), "E \"+\" E • \"+\" E");
+                assert_eq!(shift.pp(&grm), "E \"+\" E • \"+\" E");
+                assert_eq!(
+                    reduce.pp_derivation(&grm),
+                    "E -> [E -> [E \"+\" E •] \"+\" E]"
+                );
+                assert_eq!(
+                    shift.pp_derivation(&grm),
+                    "E -> [E \"+\" E -> [E • \"+\" E]]"
+                );
+            }
+            c => panic!("{:?}", c),
+        }
+    }
+
+    #[test]
+    fn test_sr_minimal() {
+        // The shortest path to each item shouldn't drag in symbols from other productions after
+        // the dot (e.g. `E "+" E • "+" E "*" E`).
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start E
+            %%
+            E: E '+' E | E '*' E | 'a';
+          ",
+        )
+        .unwrap();
+        let (sg, st) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let c = st.conflicts().unwrap();
+        assert_eq!(c.sr_len(), 4);
+        for &(tidx, pidx, stidx) in c.sr_conflicts() {
+            match c.sr_counterexample(&grm, &sg, tidx, pidx, stidx).unwrap() {
+                Counterexample::Unifying(reduce, _) => {
+                    let op = match grm.prod(pidx)[1] {
+                        Symbol::Token(op) => op,
+                        Symbol::Rule(_) => unreachable!(),
+                    };
+                    assert_eq!(
+                        reduce.pp(&grm),
+                        format!(
+                            "E \"{}\" E • \"{}\" E",
+                            grm.token_name(op).unwrap(),
+                            grm.token_name(tidx).unwrap()
+                        )
+                    );
+                }
+                c => panic!("{:?}", c),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sr_dangling_else() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'if' E 'then' S 'else' S | 'if' E 'then' S | 'x';
+            E: 'e';
+          ",
+        )
+        .unwrap();
+        let (sg, st) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let c = st.conflicts().unwrap();
+        let &(tidx, pidx, stidx) = c.sr_conflicts().next().unwrap();
+        match c.sr_counterexample(&grm, &sg, tidx, pidx, stidx).unwrap() {
+            Counterexample::Unifying(reduce, shift) => {
+                assert_eq!(
+                    reduce.pp(&grm),
+                    "\"if\" E \"then\" \"if\" E \"then\" S • \"else\" S"
+                );
+                assert_eq!(reduce.symbols, shift.symbols);
+                assert_ne!(reduce.derivation, shift.derivation);
+            }
+            c => panic!("{:?}", c),
+        }
+    }
+
+    #[test]
+    fn test_rr_nonunifying() {
+        // The reduce/reduce conflict here isn't caused by an ambiguity: 'a' 'b' can only be
+        // parsed as A, but we can't tell that with one token of lookahead.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: A 'b' 'c' | B 'b' 'd';
+            A: 'a';
+            B: 'a';
+          ",
+        )
+        .unwrap();
+        let (sg, st) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let c = st.conflicts().unwrap();
+        assert_eq!(c.rr_len(), 1);
+        let &(pidx, r_pidx, stidx) = c.rr_conflicts().next().unwrap();
+        let tidx = grm.token_idx("b").unwrap();
+        match c
+            .rr_counterexample(&grm, &sg, tidx, pidx, r_pidx, stidx)
+            .unwrap()
+        {
+            Counterexample::NonUnifying(ex1, ex2) => {
+                assert_eq!(ex1.pp(&grm), "\"a\" • \"b\" \"c\"");
+                assert_eq!(ex1.pp_derivation(&grm), "S -> [A -> [\"a\" •] \"b\" \"c\"]");
+                assert_eq!(ex2.pp(&grm), "\"a\" • \"b\" \"d\"");
+                assert_eq!(ex2.pp_derivation(&grm), "S -> [B -> [\"a\" •] \"b\" \"d\"]");
+            }
+            c => panic!("{:?}", c),
+        }
+    }
+
+    #[test]
+    fn test_rr_lalr_pp() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'a' A 'd' | 'b' B 'd' | 'a' B 'e' | 'b' A 'e';
+            A: 'c';
+            B: 'c';
+          ",
+        )
+        .unwrap();
+        let (sg, st) = from_yacc(&grm, Minimiser::LALR).unwrap();
+        let c = st.conflicts().unwrap();
+        // The conflict occurs on both 'd' and 'e', and each is reported once along with its
+        // token.
+        assert_eq!(c.rr_len(), 2);
+        let pp = c.pp_rr_counterexamples(&grm, &sg);
+        let pp = pp.lines().collect::<Vec<_>>();
+        assert_eq!(pp[0], "Reduce/Reduce conflicts:");
+        assert_eq!(pp[1], "   State 6: Reduce(A: \"c\") / Reduce(B: \"c\")");
+        assert_eq!(pp[2], "      After \"a\" \"c\", on \"d\"");
+        assert_eq!(pp[3], "      First example: \"a\" \"c\" • \"d\"");
+        assert_eq!(pp[5], "      Second example: \"b\" \"c\" • \"d\"");
+        assert_eq!(pp[7], "      After \"a\" \"c\", on \"e\"");
+        assert_eq!(pp[8], "      First example: \"b\" \"c\" • \"e\"");
+        assert_eq!(pp[10], "      Second example: \"a\" \"c\" • \"e\"");
+        assert_eq!(pp.len(), 12);
+    }
+
+    #[test]
+    fn test_rr_nullable() {
+        // Both the lookahead and the ambiguity are only visible once nullable rules have been
+        // expanded.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: A C 'x' | B D 'x';
+            A: 'a';
+            B: 'a';
+            C: ;
+            D: ;
+          ",
+        )
+        .unwrap();
+        let (sg, st) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        let c = st.conflicts().unwrap();
+        let &(pidx, r_pidx, stidx) = c.rr_conflicts().next().unwrap();
+        let tidx = grm.token_idx("x").unwrap();
+        match c
+            .rr_counterexample(&grm, &sg, tidx, pidx, r_pidx, stidx)
+            .unwrap()
+        {
+            Counterexample::Unifying(ex1, ex2) => {
+                assert_eq!(ex1.pp(&grm), "\"a\" • \"x\"");
+                assert_eq!(
+                    ex1.pp_derivation(&grm),
+                    "S -> [A -> [\"a\" •] C -> [] \"x\"]"
+                );
+                assert_eq!(
+                    ex2.pp_derivation(&grm),
+                    "S -> [B -> [\"a\" •] D -> [] \"x\"]"
+                );
+            }
+            c => panic!("{:?}", c),
+        }
+    }
+}
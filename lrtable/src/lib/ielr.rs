@@ -0,0 +1,383 @@
+use std::{collections::hash_map::HashMap, hash::Hash};
+
+use cfgrammar::{yacc::YaccGrammar, PIdx, SIdx, Symbol};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+use vob::Vob;
+
+use crate::{
+    itemset::Itemset,
+    lalr::{digraph, lalr_stategraph, nullable_suffixes},
+    stategraph::{new_stidx, StateGraph},
+    StIdx, StateTableError,
+};
+
+// This file creates IELR(1) stategraphs from grammars, following the approach of:
+//
+//   The IELR(1) algorithm for generating minimal LR(1) parser tables for non-LR(1) grammars
+//   with conflict resolution
+//     Joel E. Denny and Brian A. Malloy, Science of Computer Programming 75(11), 943--979, 2010
+//
+// The aim is a stategraph whose parser behaves exactly as a canonical LR(1) parser does, even
+// when conflicts are resolved by precedence, but which is (nearly) as small as an LALR(1)
+// stategraph. As in the paper, we start from the LALR(1) stategraph and work out which of its
+// states are "inadequate" (i.e. have a conflict on a token, whether or not that conflict is later
+// resolved by precedence). Every token on which a state is inadequate is then propagated
+// backwards, along the paths which lookaheads flow forwards, to every item whose lookahead might
+// end up there: we call these an item's "relevant" tokens. Finally, we build the LR(1)
+// stategraph, but when a new state has the same core as an existing state (an "isocore"), we
+// merge the two if their lookaheads agree on all relevant tokens. Any lookahead which differs
+// between the two can thus never reach a conflict, so merging cannot change the parser's
+// behaviour.
+//
+// Our test for merging isocores is somewhat more conservative than the paper's (which merges
+// isocores if they would lead to the same action being chosen for each conflict, even if the
+// lookaheads leading to that action differ), so we can occasionally create a few more states
+// than Bison would. When the LALR(1) stategraph has no conflicts, no tokens are relevant, and we
+// create exactly the LALR(1) stategraph.
+
+/// Return, for each state in `sg` (which must be an LALR(1) stategraph), a map from each core
+/// item to its relevant tokens.
+fn relevant_tokens<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    sg: &StateGraph<StorageT>,
+) -> Vec<HashMap<(PIdx<StorageT>, SIdx<StorageT>), Vob>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let tokens_len = usize::from(grm.tokens_len());
+    let firsts = grm.firsts();
+    let nullable_from = nullable_suffixes(grm, &firsts);
+
+    // Number every item in every closed state.
+    let mut nodes = HashMap::new();
+    for stidx in sg.iter_stidxs() {
+        for &k in sg.closed_state(stidx).items.keys() {
+            let len = nodes.len();
+            nodes.insert((stidx, k), len);
+        }
+    }
+
+    // Each item's relevant tokens start off as the tokens on which its state is inadequate (if
+    // the item is a reduction). `rel` records how lookaheads flow from one item to another,
+    // either by advancing the dot or by closure.
+    let mut f = vec![Vob::from_elem(false, tokens_len); nodes.len()];
+    let mut rel = vec![Vec::new(); nodes.len()];
+    for stidx in sg.iter_stidxs() {
+        let state = sg.closed_state(stidx);
+        let mut inadequate = Vob::from_elem(false, tokens_len);
+        for tidx in grm.iter_tidxs() {
+            let mut actions = usize::from(sg.edge(stidx, Symbol::Token(tidx)).is_some());
+            for (&(pidx, dot), ctx) in &state.items {
+                if dot == grm.prod_len(pidx) && ctx[usize::from(tidx)] {
+                    actions += 1;
+                }
+            }
+            if actions > 1 {
+                inadequate.set(usize::from(tidx), true);
+            }
+        }
+        for &(pidx, dot) in state.items.keys() {
+            let node = nodes[&(stidx, (pidx, dot))];
+            if dot == grm.prod_len(pidx) {
+                f[node] = inadequate.clone();
+                continue;
+            }
+            let sym = grm.prod(pidx)[usize::from(dot)];
+            let next = sg.edge(stidx, sym).unwrap();
+            rel[node].push(nodes[&(next, (pidx, SIdx(dot.as_storaget() + StorageT::one())))]);
+            if let Symbol::Rule(ridx) = sym {
+                if usize::from(dot) + 1 >= nullable_from[usize::from(pidx)] {
+                    for &c_pidx in grm.rule_to_prods(ridx) {
+                        rel[node].push(nodes[&(stidx, (c_pidx, SIdx(StorageT::zero())))]);
+                    }
+                }
+            }
+        }
+    }
+    digraph(&rel, &mut f);
+
+    sg.iter_stidxs()
+        .map(|stidx| {
+            sg.core_state(stidx)
+                .items
+                .keys()
+                .map(|&k| (k, f[nodes[&(stidx, k)]].clone()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Return true if `is1` and `is2` (which must have the same core) have the same lookaheads for
+/// every token in `relevant`.
+fn compatible<StorageT: Hash + PrimInt + Unsigned>(
+    is1: &Itemset<StorageT>,
+    is2: &Itemset<StorageT>,
+    relevant: &HashMap<(PIdx<StorageT>, SIdx<StorageT>), Vob>,
+) -> bool {
+    relevant.iter().all(|(k, r)| {
+        let mut ctx1 = is1.items[k].clone();
+        ctx1.and(r);
+        let mut ctx2 = is2.items[k].clone();
+        ctx2.and(r);
+        ctx1 == ctx2
+    })
+}
+
+/// Create an IELR(1) `StateGraph` from 'grm'.
+pub fn ielr_stategraph<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+) -> Result<StateGraph<StorageT>, StateTableError<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let lalr_sg = lalr_stategraph(grm)?;
+    let relevant = relevant_tokens(grm, &lalr_sg);
+
+    let firsts = grm.firsts();
+    let mut core_states = Vec::new();
+    // The LALR(1) state with the same core as each of our states.
+    let mut lalr_stidxs = Vec::new();
+    let mut edges: Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>> = Vec::new();
+    // A map from LALR(1) states to all the isocores we have created for them.
+    let mut isocores: HashMap<StIdx<StorageT>, Vec<StIdx<StorageT>>> = HashMap::new();
+
+    // Each of the grammar's entry points has its own start state.
+    let mut start_states = Vec::with_capacity(grm.entries().len());
+    let mut ctx = Vob::from_elem(false, usize::from(grm.tokens_len()));
+    ctx.set(usize::from(grm.eof_token_idx()), true);
+    for (&(_, pidx), &lalr_stidx) in grm.entries().iter().zip(lalr_sg.start_states()) {
+        let mut state = Itemset::new(grm);
+        state.add(pidx, SIdx(StorageT::zero()), &ctx);
+        let stidx = new_stidx(grm, core_states.len())?;
+        start_states.push(stidx);
+        core_states.push(state);
+        lalr_stidxs.push(lalr_stidx);
+        edges.push(HashMap::new());
+        isocores.insert(lalr_stidx, vec![stidx]);
+    }
+
+    let mut seen_rules = Vob::from_elem(false, usize::from(grm.rules_len()));
+    let mut seen_tokens = Vob::from_elem(false, usize::from(grm.tokens_len()));
+    let mut state_i = 0;
+    while state_i < core_states.len() {
+        let cl_state = core_states[state_i].close(grm, &firsts);
+        seen_rules.set_all(false);
+        seen_tokens.set_all(false);
+        for &(pidx, dot) in cl_state.items.keys() {
+            if dot == grm.prod_len(pidx) {
+                continue;
+            }
+            let sym = grm.prod(pidx)[usize::from(dot)];
+            match sym {
+                Symbol::Rule(s_ridx) => {
+                    if seen_rules[usize::from(s_ridx)] {
+                        continue;
+                    }
+                    seen_rules.set(usize::from(s_ridx), true);
+                }
+                Symbol::Token(s_tidx) => {
+                    if seen_tokens[usize::from(s_tidx)] {
+                        continue;
+                    }
+                    seen_tokens.set(usize::from(s_tidx), true);
+                }
+            }
+            let nstate = cl_state.goto(grm, &sym);
+            let lalr_stidx = lalr_sg.edge(lalr_stidxs[state_i], sym).unwrap();
+            let rel = &relevant[usize::from(lalr_stidx)];
+            let isos = isocores.entry(lalr_stidx).or_default();
+            let stidx = match isos
+                .iter()
+                .find(|&&stidx| compatible(&core_states[usize::from(stidx)], &nstate, rel))
+            {
+                Some(&stidx) => {
+                    // The lookaheads we're merging in can't affect any conflicts, but they still
+                    // need to be propagated to stidx's successors: we do that below, once the
+                    // stategraph has been completely built.
+                    for (&(pidx, dot), ctx) in &nstate.items {
+                        core_states[usize::from(stidx)].add(pidx, dot, ctx);
+                    }
+                    stidx
+                }
+                None => {
+                    let stidx = new_stidx(grm, core_states.len())?;
+                    isos.push(stidx);
+                    core_states.push(nstate);
+                    lalr_stidxs.push(lalr_stidx);
+                    edges.push(HashMap::new());
+                    stidx
+                }
+            };
+            edges[state_i].insert(sym, stidx);
+        }
+        state_i += 1;
+    }
+
+    // Propagate lookaheads from merged states to their successors until we reach a fixed point.
+    let mut todo = (0..core_states.len()).rev().collect::<Vec<_>>();
+    let mut in_todo = Vob::from_elem(true, core_states.len());
+    while let Some(state_i) = todo.pop() {
+        in_todo.set(state_i, false);
+        let cl_state = core_states[state_i].close(grm, &firsts);
+        for (sym, &stidx) in &edges[state_i] {
+            let mut changed = false;
+            for (&(pidx, dot), ctx) in &cl_state.goto(grm, sym).items {
+                changed |= core_states[usize::from(stidx)].add(pidx, dot, ctx);
+            }
+            if changed && !in_todo[usize::from(stidx)] {
+                in_todo.set(usize::from(stidx), true);
+                todo.push(usize::from(stidx));
+            }
+        }
+    }
+
+    let closed_states = core_states
+        .iter()
+        .map(|core_state| core_state.close(grm, &firsts))
+        .collect::<Vec<_>>();
+    Ok(StateGraph::new(
+        core_states.into_iter().zip(closed_states).collect(),
+        start_states,
+        edges,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::hash_map::HashMap;
+
+    use cfgrammar::yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind};
+
+    use super::ielr_stategraph;
+    use crate::{
+        canonical::canonical_stategraph, lalr::lalr_stategraph, Action, StIdx, StateGraph,
+        StateTable,
+    };
+
+    /// Check that the parser described by `sg` behaves as the canonical LR(1) parser for `grm`
+    /// does, other than (as with LALR(1)) possibly reducing where the canonical parser would
+    /// have already detected an error. Returns false if the two differ.
+    fn behaves_as_canonical(grm: &YaccGrammar<u32>, sg: &StateGraph<u32>) -> bool {
+        let st = StateTable::new(grm, sg).unwrap();
+        let canonical_sg = canonical_stategraph(grm).unwrap();
+        let canonical_st = StateTable::new(grm, &canonical_sg).unwrap();
+        // Walk both stategraphs in parallel, mapping each canonical state to a state in `sg`.
+        let mut map = HashMap::new();
+        let mut todo = vec![(canonical_sg.start_state(), sg.start_state())];
+        while let Some((c_stidx, stidx)) = todo.pop() {
+            if let Some(&m_stidx) = map.get(&c_stidx) {
+                assert_eq!(m_stidx, stidx);
+                continue;
+            }
+            map.insert(c_stidx, stidx);
+            for tidx in grm.iter_tidxs() {
+                match (canonical_st.action(c_stidx, tidx), st.action(stidx, tidx)) {
+                    (Action::Error, Action::Error) | (Action::Error, Action::Reduce(_)) => (),
+                    (Action::Shift(_), Action::Shift(_)) | (Action::Accept, Action::Accept) => (),
+                    (Action::Reduce(c_pidx), Action::Reduce(pidx)) if c_pidx == pidx => (),
+                    _ => return false,
+                }
+            }
+            for (&sym, &c_next) in canonical_sg.edges(c_stidx) {
+                todo.push((c_next, sg.edge(stidx, sym).unwrap()));
+            }
+        }
+        true
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_ielr_mysterious_conflict() {
+        // LALR(1) merges the states reached by 'a' 'c' and 'b' 'c', leading to reduce/reduce
+        // conflicts: IELR(1) splits them.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'a' A 'd' | 'b' B 'd' | 'a' B 'e' | 'b' A 'e';
+            A: 'c';
+            B: 'c';
+          ").unwrap();
+        let sg = ielr_stategraph(&grm).unwrap();
+        assert_eq!(sg.all_states_len(), StIdx(14));
+        assert!(StateTable::new(&grm, &sg).unwrap().conflicts().is_none());
+        assert!(behaves_as_canonical(&grm, &sg));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_ielr_lalr_sized() {
+        // For an LALR(1) grammar, IELR(1) creates exactly the LALR(1) stategraph.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start E
+            %%
+            E: E '+' T | T;
+            T: T '*' F | F;
+            F: '(' E ')' | 'id';
+          ").unwrap();
+        let sg = ielr_stategraph(&grm).unwrap();
+        assert_eq!(sg.all_states_len(), lalr_stategraph(&grm).unwrap().all_states_len());
+        assert!(usize::from(canonical_stategraph(&grm).unwrap().all_states_len()) > usize::from(sg.all_states_len()));
+        assert!(behaves_as_canonical(&grm, &sg));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_ielr_precedence() {
+        // LALR(1) merges the states reached by 'a' 'a' and 'b' 'a'. Only the former has a
+        // shift/reduce conflict on 'a' (which `%left 'a'` resolves in favour of reducing), but
+        // the merged state reduces on 'a' in both cases, so the LALR(1) parser rejects the valid
+        // input "baab".
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %left 'a'
+            %%
+            S: 'a' A 'a' | 'b' A 'b';
+            A: 'a' 'a' | 'a';
+          ").unwrap();
+        assert!(!behaves_as_canonical(&grm, &lalr_stategraph(&grm).unwrap()));
+        let sg = ielr_stategraph(&grm).unwrap();
+        assert!(behaves_as_canonical(&grm, &sg));
+        assert!(usize::from(sg.all_states_len()) > usize::from(lalr_stategraph(&grm).unwrap().all_states_len()));
+        assert!(usize::from(sg.all_states_len()) < usize::from(canonical_stategraph(&grm).unwrap().all_states_len()));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_ielr_nullable() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %left 'c'
+            %%
+            S: A B C 'x' | B 'y' C | 'y' A 'c' | 'x' B 'c' 'c';
+            A: 'a' | ;
+            B: 'b' A | ;
+            C: 'c' | '(' S ')' C | ;
+          ").unwrap();
+        assert!(behaves_as_canonical(&grm, &ielr_stategraph(&grm).unwrap()));
+
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start E
+            %nonassoc '='
+            %left '+' '-'
+            %left '*'
+            %right '^'
+            %%
+            E: E '=' E | E '+' E | E '-' E | E '*' E | E '^' E | '-' E | '(' E ')' | F;
+            F: 'id' | 'id' '(' Args ')';
+            Args: Args ',' E | E | ;
+          ").unwrap();
+        let sg = ielr_stategraph(&grm).unwrap();
+        assert!(behaves_as_canonical(&grm, &sg));
+        assert_eq!(sg.all_states_len(), lalr_stategraph(&grm).unwrap().all_states_len());
+    }
+}
@@ -0,0 +1,453 @@
+use std::{cmp::min, collections::hash_map::HashMap, hash::Hash};
+
+use cfgrammar::{
+    yacc::{firsts::YaccFirsts, YaccGrammar},
+    PIdx, SIdx, Symbol,
+};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+use vob::Vob;
+
+use crate::{
+    itemset::Itemset,
+    stategraph::{new_stidx, StateGraph},
+    StIdx, StateTableError,
+};
+
+// This file creates LALR(1) stategraphs from grammars. We first build an LR(0) automaton and then
+// calculate lookaheads for it using the relations (reads, includes, and lookback) and the
+// `Digraph` algorithm from:
+//
+//   Efficient Computation of LALR(1) Look-Ahead Sets
+//     Frank DeRemer and Thomas Pennello, TOPLAS 4(4), 615--649, 1982
+//
+// This is the approach taken by Bison (and most other Yacc-alikes), so grammars written for those
+// tools will see the same conflicts (and can thus use the same %expect / %expect-rr values) here.
+//
+// In the paper, the start production is `S' -> S $`; grmtools' start production does not include
+// the EOF token. We therefore pretend that the start production is followed by EOF by seeding
+// the direct reads of the transition on the start production's final symbol with EOF.
+
+/// The (unclosed) items of an LR(0) state, sorted so that they can be used as a key.
+type Lr0Items<StorageT> = Vec<(PIdx<StorageT>, SIdx<StorageT>)>;
+
+/// Return a closed version of the LR(0) items `core`.
+fn lr0_close<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    core: &[(PIdx<StorageT>, SIdx<StorageT>)],
+) -> Lr0Items<StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut items = core.to_vec();
+    let mut seen_rules = Vob::from_elem(false, usize::from(grm.rules_len()));
+    let mut i = 0;
+    while i < items.len() {
+        let (pidx, dot) = items[i];
+        i += 1;
+        if dot == grm.prod_len(pidx) {
+            continue;
+        }
+        if let Symbol::Rule(ridx) = grm.prod(pidx)[usize::from(dot)] {
+            if !seen_rules[usize::from(ridx)] {
+                seen_rules.set(usize::from(ridx), true);
+                for &pidx in grm.rule_to_prods(ridx) {
+                    items.push((pidx, SIdx(StorageT::zero())));
+                }
+            }
+        }
+    }
+    items
+}
+
+/// Starting at `stidx`, follow the edges for each symbol of production `pidx`, returning every
+/// state visited (including `stidx` itself).
+fn walk<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    edges: &[HashMap<Symbol<StorageT>, StIdx<StorageT>>],
+    stidx: StIdx<StorageT>,
+    pidx: PIdx<StorageT>,
+) -> Vec<StIdx<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut path = Vec::with_capacity(grm.prod(pidx).len() + 1);
+    path.push(stidx);
+    let mut cur = stidx;
+    for sym in grm.prod(pidx) {
+        cur = edges[usize::from(cur)][sym];
+        path.push(cur);
+    }
+    path
+}
+
+/// For each production, return the index of the first symbol of its nullable suffix (i.e. the
+/// production's length if its last symbol is not nullable).
+pub(crate) fn nullable_suffixes<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    firsts: &YaccFirsts<StorageT>,
+) -> Vec<usize>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    grm.iter_pidxs()
+        .map(|pidx| {
+            grm.prod(pidx)
+                .iter()
+                .rposition(|sym| match *sym {
+                    Symbol::Rule(ridx) => !firsts.is_epsilon_set(ridx),
+                    Symbol::Token(_) => true,
+                })
+                .map(|i| i + 1)
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Set `f[x]` to `f[x] ∪ f[y]`.
+fn vob_union(f: &mut [Vob], x: usize, y: usize) {
+    if x < y {
+        let (l, r) = f.split_at_mut(y);
+        l[x].or(&r[0]);
+    } else if x > y {
+        let (l, r) = f.split_at_mut(x);
+        r[0].or(&l[y]);
+    }
+}
+
+/// DeRemer and Pennello's `Digraph` algorithm: on return, `f[x]` is the union of the input
+/// `f[x]` and the input `f[y]` of every `y` reachable from `x` via `rel`. This is an iterative,
+/// rather than the paper's recursive, version so that large grammars can't exhaust the stack.
+pub(crate) fn digraph(rel: &[Vec<usize>], f: &mut [Vob]) {
+    // `n[x]` is 0 if `x` has not yet been visited, `usize::MAX` if `x` has been completely
+    // processed, or the depth of the stack at which `x` was first visited otherwise.
+    let mut n = vec![0; rel.len()];
+    let mut stack = Vec::new();
+    // The explicit call stack: (node, offset of the next relation to process, depth).
+    let mut calls: Vec<(usize, usize, usize)> = Vec::new();
+    for root in 0..rel.len() {
+        if n[root] != 0 {
+            continue;
+        }
+        stack.push(root);
+        n[root] = stack.len();
+        calls.push((root, 0, stack.len()));
+        while let Some(&(x, i, d)) = calls.last() {
+            if let Some(&y) = rel[x].get(i) {
+                calls.last_mut().unwrap().1 += 1;
+                if n[y] == 0 {
+                    stack.push(y);
+                    n[y] = stack.len();
+                    calls.push((y, 0, stack.len()));
+                } else {
+                    n[x] = min(n[x], n[y]);
+                    vob_union(f, x, y);
+                }
+                continue;
+            }
+            calls.pop();
+            if n[x] == d {
+                // `x` is the root of a strongly connected component: every member of the
+                // component has the same set.
+                loop {
+                    let z = stack.pop().unwrap();
+                    n[z] = usize::MAX;
+                    if z == x {
+                        break;
+                    }
+                    f[z] = f[x].clone();
+                }
+            }
+            if let Some(&(p, _, _)) = calls.last() {
+                n[p] = min(n[p], n[x]);
+                vob_union(f, p, x);
+            }
+        }
+    }
+}
+
+/// Create an LALR(1) `StateGraph` from 'grm'.
+pub fn lalr_stategraph<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+) -> Result<StateGraph<StorageT>, StateTableError<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    // Build the LR(0) automaton, starting with one start state for each of the grammar's entry
+    // points.
+    let mut start_states = Vec::with_capacity(grm.entries().len());
+    let mut core_items: Vec<Lr0Items<StorageT>> = Vec::new();
+    let mut closed_items = Vec::new();
+    let mut edges: Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>> = Vec::new();
+    let mut seen = HashMap::new();
+    for &(_, pidx) in grm.entries() {
+        let stidx = new_stidx(grm, core_items.len())?;
+        let items = vec![(pidx, SIdx(StorageT::zero()))];
+        seen.insert(items.clone(), stidx);
+        start_states.push(stidx);
+        core_items.push(items);
+        edges.push(HashMap::new());
+    }
+    let mut state_i = 0;
+    while state_i < core_items.len() {
+        let cl_items = lr0_close(grm, &core_items[state_i]);
+        // Group the items by the symbol after the dot, in the order we first encounter each
+        // symbol, so that state numbering is deterministic.
+        let mut syms = Vec::new();
+        let mut gotos: HashMap<Symbol<StorageT>, Lr0Items<StorageT>> = HashMap::new();
+        for &(pidx, dot) in &cl_items {
+            if dot == grm.prod_len(pidx) {
+                continue;
+            }
+            let sym = grm.prod(pidx)[usize::from(dot)];
+            gotos
+                .entry(sym)
+                .or_insert_with(|| {
+                    syms.push(sym);
+                    Vec::new()
+                })
+                .push((pidx, SIdx(dot.as_storaget() + StorageT::one())));
+        }
+        for sym in syms {
+            let mut nitems = gotos.remove(&sym).unwrap();
+            nitems.sort_unstable();
+            let stidx = match seen.get(&nitems) {
+                Some(&stidx) => stidx,
+                None => {
+                    let stidx = new_stidx(grm, core_items.len())?;
+                    seen.insert(nitems.clone(), stidx);
+                    core_items.push(nitems);
+                    edges.push(HashMap::new());
+                    stidx
+                }
+            };
+            edges[state_i].insert(sym, stidx);
+        }
+        closed_items.push(cl_items);
+        state_i += 1;
+    }
+
+    // Number the nonterminal transitions `(p, A)`.
+    let mut trans = Vec::new();
+    let mut trans_map = HashMap::new();
+    for (i, es) in edges.iter().enumerate() {
+        for (&sym, &to) in es {
+            if let Symbol::Rule(ridx) = sym {
+                trans_map.insert((StIdx(i.as_()), ridx), trans.len());
+                trans.push((StIdx(i.as_()), ridx, to));
+            }
+        }
+    }
+
+    let firsts = grm.firsts();
+    let nullable_from = nullable_suffixes(grm, &firsts);
+
+    // Direct reads and the reads relation.
+    let tokens_len = usize::from(grm.tokens_len());
+    let mut read_sets = vec![Vob::from_elem(false, tokens_len); trans.len()];
+    let mut reads = vec![Vec::new(); trans.len()];
+    for (i, &(_, _, to)) in trans.iter().enumerate() {
+        for &sym in edges[usize::from(to)].keys() {
+            match sym {
+                Symbol::Token(tidx) => {
+                    read_sets[i].set(usize::from(tidx), true);
+                }
+                Symbol::Rule(ridx) => {
+                    if firsts.is_epsilon_set(ridx) {
+                        reads[i].push(trans_map[&(to, ridx)]);
+                    }
+                }
+            }
+        }
+    }
+    // See the comment at the top of this file for why we seed EOF here.
+    let start_paths = grm
+        .entries()
+        .iter()
+        .zip(&start_states)
+        .map(|(&(_, pidx), &stidx)| (pidx, walk(grm, &edges, stidx, pidx)))
+        .collect::<Vec<_>>();
+    for (pidx, start_path) in &start_paths {
+        if let Some(&Symbol::Rule(ridx)) = grm.prod(*pidx).last() {
+            let p = start_path[start_path.len() - 2];
+            read_sets[trans_map[&(p, ridx)]].set(usize::from(grm.eof_token_idx()), true);
+        }
+    }
+    digraph(&reads, &mut read_sets);
+
+    // The includes relation: `(p, A) includes (p', B)` iff `B -> β A γ`, `γ` is nullable, and
+    // `p' --β--> p`.
+    let mut includes = vec![Vec::new(); trans.len()];
+    for (i, &(stidx, ridx, _)) in trans.iter().enumerate() {
+        for &pidx in grm.rule_to_prods(ridx) {
+            let path = walk(grm, &edges, stidx, pidx);
+            for (j, sym) in grm
+                .prod(pidx)
+                .iter()
+                .enumerate()
+                .skip(nullable_from[usize::from(pidx)].saturating_sub(1))
+            {
+                if let Symbol::Rule(a_ridx) = *sym {
+                    includes[trans_map[&(path[j], a_ridx)]].push(i);
+                }
+            }
+        }
+    }
+    let mut follow_sets = read_sets;
+    digraph(&includes, &mut follow_sets);
+
+    // Calculate the lookaheads for every item in every state. The lookahead of `A -> α . β` in
+    // state `q` is the union of Follow(p, A) for every `p` such that `p --α--> q`: in other words,
+    // the lookback relation generalised to items whose dot is not at the end.
+    let mut core_states = Vec::with_capacity(core_items.len());
+    let mut closed_states = Vec::with_capacity(core_items.len());
+    let empty = Vob::from_elem(false, tokens_len);
+    for cl_items in &closed_items {
+        let mut is = Itemset::new(grm);
+        for &(pidx, dot) in cl_items {
+            is.add(pidx, dot, &empty);
+        }
+        closed_states.push(is);
+    }
+    let mut eof_ctx = empty.clone();
+    eof_ctx.set(usize::from(grm.eof_token_idx()), true);
+    for (pidx, start_path) in &start_paths {
+        for (dot, &stidx) in start_path.iter().enumerate() {
+            closed_states[usize::from(stidx)].add(*pidx, SIdx(dot.as_()), &eof_ctx);
+        }
+    }
+    for (i, &(stidx, ridx, _)) in trans.iter().enumerate() {
+        for &pidx in grm.rule_to_prods(ridx) {
+            let path = walk(grm, &edges, stidx, pidx);
+            for (dot, &q) in path.iter().enumerate() {
+                closed_states[usize::from(q)].add(pidx, SIdx(dot.as_()), &follow_sets[i]);
+            }
+        }
+    }
+    for (cr_items, cl_state) in core_items.iter().zip(closed_states.iter()) {
+        let mut is = Itemset::new(grm);
+        for k in cr_items {
+            is.add(k.0, k.1, &cl_state.items[k]);
+        }
+        core_states.push(is);
+    }
+
+    Ok(StateGraph::new(
+        core_states.into_iter().zip(closed_states).collect(),
+        start_states,
+        edges,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::hash_map::HashMap;
+
+    use cfgrammar::{
+        yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind},
+        PIdx, SIdx,
+    };
+    use vob::Vob;
+
+    use super::lalr_stategraph;
+    use crate::{canonical::canonical_stategraph, itemset::Itemset, StIdx, StateTable};
+
+    fn core_key(is: &Itemset<u32>) -> Vec<(PIdx<u32>, SIdx<u32>)> {
+        let mut k = is.items.keys().cloned().collect::<Vec<_>>();
+        k.sort_unstable();
+        k
+    }
+
+    /// Check that the LALR(1) stategraph for `grm` is identical to the canonical LR(1) stategraph
+    /// with all states with the same core merged (which is the definition of LALR(1)).
+    fn check_against_canonical(grm: &YaccGrammar<u32>) {
+        let lalr_sg = lalr_stategraph(grm).unwrap();
+        let canonical_sg = canonical_stategraph(grm).unwrap();
+        let mut merged: HashMap<_, HashMap<(PIdx<u32>, SIdx<u32>), Vob>> = HashMap::new();
+        for stidx in canonical_sg.iter_stidxs() {
+            let m = merged
+                .entry(core_key(canonical_sg.core_state(stidx)))
+                .or_default();
+            for (&k, ctx) in &canonical_sg.closed_state(stidx).items {
+                m.entry(k).or_insert_with(|| ctx.clone()).or(ctx);
+            }
+        }
+        assert_eq!(usize::from(lalr_sg.all_states_len()), merged.len());
+        for stidx in lalr_sg.iter_stidxs() {
+            let m = &merged[&core_key(lalr_sg.core_state(stidx))];
+            let cl = &lalr_sg.closed_state(stidx).items;
+            assert_eq!(cl.len(), m.len());
+            for (k, ctx) in cl {
+                assert_eq!(ctx, &m[k]);
+            }
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_lalr_mysterious_conflict() {
+        // This grammar is LR(1) but not LALR(1): merging the states reached by 'a' 'c' and
+        // 'b' 'c' leads to reduce/reduce conflicts on 'd' and 'e'.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'a' A 'd' | 'b' B 'd' | 'a' B 'e' | 'b' A 'e';
+            A: 'c';
+            B: 'c';
+          ").unwrap();
+        let sg = lalr_stategraph(&grm).unwrap();
+        assert_eq!(sg.all_states_len(), StIdx(13));
+        let st = StateTable::new(&grm, &sg).unwrap();
+        let c = st.conflicts().unwrap();
+        assert_eq!(c.sr_len(), 0);
+        assert_eq!(c.rr_len(), 2);
+        check_against_canonical(&grm);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_lalr_expr() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start E
+            %%
+            E: E '+' T | T;
+            T: T '*' F | F;
+            F: '(' E ')' | 'id';
+          ").unwrap();
+        let sg = lalr_stategraph(&grm).unwrap();
+        assert_eq!(sg.all_states_len(), StIdx(12));
+        assert!(StateTable::new(&grm, &sg).unwrap().conflicts().is_none());
+        check_against_canonical(&grm);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_lalr_nullable() {
+        // Nullable rules exercise both the reads and the includes relations.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: A B C 'x' | B 'y' C | A;
+            A: 'a' | ;
+            B: 'b' A | ;
+            C: 'c' | '(' S ')' C | ;
+          ").unwrap();
+        check_against_canonical(&grm);
+
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: L '=' R | R;
+            L: '*' R | 'id';
+            R: L;
+          ").unwrap();
+        check_against_canonical(&grm);
+    }
+}
@@ -7,57 +7,83 @@ use std::{hash::Hash, mem::size_of};
 use num_traits::{AsPrimitive, PrimInt, Unsigned};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use static_assertions::const_assert;
 
+mod canonical;
+mod counterexamples;
+mod ielr;
 mod itemset;
+mod lalr;
 mod pager;
+mod report;
+mod sparse;
 mod stategraph;
 pub mod statetable;
 
 pub use crate::{
-    stategraph::StateGraph,
-    statetable::{Action, StateTable, StateTableError, StateTableErrorKind},
+    counterexamples::{Counterexample, Derivation, Example},
+    report::Report,
+    sparse::StaticUInts,
+    stategraph::{DotOptions, StateGraph},
+    statetable::{
+        Action, StateTable, StateTableError, StateTableErrorKind, StateTableWarning,
+        StaticStateTable,
+    },
 };
 use cfgrammar::yacc::YaccGrammar;
 
-/// The type of the inner value of an StIdx.
-pub type StIdxStorageT = u16;
-
-/// StIdx is a wrapper for a state index. Its internal type is `StIdxStorageT`. The only guarantee
-/// we make about `StIdx' is that it can be infallibly converted to usize.
+/// StIdx is a wrapper for a state index. Its internal type is `StorageT`, the same storage type
+/// as the grammar whose stategraph the state is part of: if a stategraph has more states than
+/// `StorageT` can represent, [from_yacc] returns a [StateTableError] rather than creating the
+/// stategraph. The only guarantee we make about `StIdx` is that it can be infallibly converted to
+/// usize.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StIdx<StorageT>(StorageT);
 
-pub struct StIdx(StIdxStorageT);
-
-impl StIdx {
-    fn max_value() -> StIdx {
-        StIdx(StIdxStorageT::max_value())
+impl<StorageT> From<StorageT> for StIdx<StorageT> {
+    fn from(v: StorageT) -> Self {
+        StIdx(v)
     }
 }
 
-impl From<StIdxStorageT> for StIdx {
-    fn from(v: StIdxStorageT) -> Self {
-        StIdx(v)
+impl<StorageT: PrimInt + Unsigned> From<StIdx<StorageT>> for usize {
+    fn from(st: StIdx<StorageT>) -> Self {
+        debug_assert!(size_of::<usize>() >= size_of::<StorageT>());
+        num_traits::cast(st.0).unwrap()
     }
 }
 
-impl From<StIdx> for usize {
-    fn from(st: StIdx) -> Self {
-        const_assert!(size_of::<usize>() >= size_of::<StIdxStorageT>());
-        st.0 as usize
+impl<StorageT: PrimInt + Unsigned> StIdx<StorageT> {
+    pub fn as_storaget(&self) -> StorageT {
+        self.0
     }
 }
 
-impl From<StIdx> for StIdxStorageT {
-    fn from(st: StIdx) -> Self {
-        st.0 as StIdxStorageT
+impl<StorageT> StIdx<StorageT> {
+    /// Create a `StIdx` in a `const` context. This interface is unstable and should only be used
+    /// by code generated by grmtools itself.
+    #[doc(hidden)]
+    pub const fn from_storaget(v: StorageT) -> Self {
+        StIdx(v)
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Minimiser {
+    /// Pager's algorithm, which merges compatible LR(1) states, typically leading to stategraphs
+    /// close in size to LALR(1) without introducing LALR(1)'s spurious conflicts.
     Pager,
+    /// Knuth's canonical LR(1), which never merges states. Stategraphs can be very large, but
+    /// this is useful as a reference point when investigating conflicts.
+    Canonical,
+    /// LALR(1), with lookaheads calculated using DeRemer and Pennello's algorithm. This is what
+    /// Bison and most other Yacc-alikes use, so conflicts (and thus `%expect` / `%expect-rr`
+    /// values) are the same as those tools report.
+    LALR,
+    /// IELR(1), which creates stategraphs whose parsers behave as canonical LR(1) parsers do
+    /// (including when conflicts are resolved by precedence), but which are typically no bigger
+    /// than, or only slightly bigger than, LALR(1) stategraphs.
+    IELR,
 }
 
 pub fn from_yacc<StorageT: 'static + Hash + PrimInt + Unsigned>(
@@ -69,7 +95,22 @@ where
 {
     match m {
         Minimiser::Pager => {
-            let sg = pager::pager_stategraph(grm);
+            let sg = pager::pager_stategraph(grm)?;
+            let st = StateTable::new(grm, &sg)?;
+            Ok((sg, st))
+        }
+        Minimiser::Canonical => {
+            let sg = canonical::canonical_stategraph(grm)?;
+            let st = StateTable::new(grm, &sg)?;
+            Ok((sg, st))
+        }
+        Minimiser::LALR => {
+            let sg = lalr::lalr_stategraph(grm)?;
+            let st = StateTable::new(grm, &sg)?;
+            Ok((sg, st))
+        }
+        Minimiser::IELR => {
+            let sg = ielr::ielr_stategraph(grm)?;
             let st = StateTable::new(grm, &sg)?;
             Ok((sg, st))
         }
@@ -4,10 +4,14 @@ use std::{
 };
 
 use cfgrammar::{yacc::YaccGrammar, SIdx, Symbol};
-use num_traits::{AsPrimitive, PrimInt, Unsigned, Zero};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
 use vob::Vob;
 
-use crate::{itemset::Itemset, stategraph::StateGraph, StIdx, StIdxStorageT};
+use crate::{
+    itemset::Itemset,
+    stategraph::{new_stidx, StateGraph},
+    StIdx, StateTableError,
+};
 
 // This file creates stategraphs from grammars. Unfortunately there is no perfect guide to how to
 // do this that I know of -- certainly not one that talks about sensible ways to arrange data and
@@ -115,7 +119,7 @@ fn vob_intersect(v1: &Vob, v2: &Vob) -> bool {
 /// Create a `StateGraph` from 'grm'.
 pub fn pager_stategraph<StorageT: 'static + Hash + PrimInt + Unsigned>(
     grm: &YaccGrammar<StorageT>,
-) -> StateGraph<StorageT>
+) -> Result<StateGraph<StorageT>, StateTableError<StorageT>>
 where
     usize: AsPrimitive<StorageT>,
 {
@@ -128,16 +132,21 @@ where
     // closed_states also implicitly serves as a todo list.
     let mut closed_states = Vec::new();
     let mut core_states = Vec::new();
-    let mut edges: Vec<HashMap<Symbol<StorageT>, StIdx>> = Vec::new();
+    let mut edges: Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>> = Vec::new();
 
-    let start_state = StIdx::from(StIdxStorageT::zero());
-    let mut state0 = Itemset::new(grm);
+    // Each of the grammar's entry points has its own start state: these are states
+    // 0..grm.entries().len(), with the start rule's state first.
+    let mut start_states = Vec::with_capacity(grm.entries().len());
     let mut ctx = Vob::from_elem(false, usize::from(grm.tokens_len()));
     ctx.set(usize::from(grm.eof_token_idx()), true);
-    state0.add(grm.start_prod(), SIdx(StorageT::zero()), &ctx);
-    closed_states.push(None);
-    core_states.push(state0);
-    edges.push(HashMap::new());
+    for &(_, pidx) in grm.entries() {
+        let mut state = Itemset::new(grm);
+        state.add(pidx, SIdx(StorageT::zero()), &ctx);
+        start_states.push(new_stidx(grm, core_states.len())?);
+        closed_states.push(None);
+        core_states.push(state);
+        edges.push(HashMap::new());
+    }
 
     // We maintain two lists of which rules and tokens we've seen; when processing a given
     // state there's no point processing a rule or token more than once.
@@ -147,11 +156,12 @@ where
     let mut new_states = Vec::new();
     // cnd_[rule|token]_weaklies represent which states are possible weakly compatible
     // matches for a given symbol.
-    let mut cnd_rule_weaklies: Vec<Vec<StIdx>> = vec![Vec::new(); usize::from(grm.rules_len())];
-    let mut cnd_token_weaklies: Vec<Vec<StIdx>> =
+    let mut cnd_rule_weaklies: Vec<Vec<StIdx<StorageT>>> =
+        vec![Vec::new(); usize::from(grm.rules_len())];
+    let mut cnd_token_weaklies: Vec<Vec<StIdx<StorageT>>> =
         vec![Vec::new(); usize::from(grm.tokens_len()).checked_add(1).unwrap()];
 
-    let mut todo = 1; // How many None values are there in closed_states?
+    let mut todo = start_states.len(); // How many None values are there in closed_states?
     let mut todo_off = 0; // Offset in closed states to start searching for the next todo.
     while todo > 0 {
         debug_assert_eq!(core_states.len(), closed_states.len());
@@ -250,9 +260,7 @@ where
                     }
                 }
                 None => {
-                    assert!(core_states.len() <= usize::from(StIdxStorageT::max_value()));
-                    // The assert above guarantees that the cast below is safe.
-                    let stidx = StIdx(core_states.len() as StIdxStorageT);
+                    let stidx = new_stidx(grm, core_states.len())?;
                     match sym {
                         Symbol::Rule(s_ridx) => {
                             cnd_rule_weaklies[usize::from(s_ridx)].push(stidx);
@@ -282,26 +290,30 @@ where
             .drain(..)
             .zip(closed_states.drain(..).map(Option::unwrap))
             .collect(),
-        start_state,
+        &start_states,
         edges,
     );
-    StateGraph::new(gc_states, start_state, gc_edges)
+    Ok(StateGraph::new(gc_states, start_states, gc_edges))
 }
 
 /// Garbage collect `zip_states` (of `(core_states, closed_state)`) and `edges`. Returns a new pair
 /// with unused states and their corresponding edges removed.
-fn gc<StorageT: Eq + Hash + PrimInt>(
+fn gc<StorageT: 'static + Eq + Hash + PrimInt + Unsigned>(
     mut states: Vec<(Itemset<StorageT>, Itemset<StorageT>)>,
-    start_state: StIdx,
-    mut edges: Vec<HashMap<Symbol<StorageT>, StIdx>>,
+    start_states: &[StIdx<StorageT>],
+    mut edges: Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>>,
 ) -> (
     Vec<(Itemset<StorageT>, Itemset<StorageT>)>,
-    Vec<HashMap<Symbol<StorageT>, StIdx>>,
-) {
+    Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>>,
+)
+where
+    usize: AsPrimitive<StorageT>,
+{
     // First of all, do a simple pass over all states. All state indexes reachable from the
-    // start state will be inserted into the 'seen' set.
+    // start states will be inserted into the 'seen' set. Since the start states are the first
+    // states and are always seen, their indexes are unchanged by garbage collection.
     let mut todo = HashSet::new();
-    todo.insert(start_state);
+    todo.extend(start_states.iter().cloned());
     let mut seen = HashSet::new();
     while !todo.is_empty() {
         // XXX This is the clumsy way we're forced to do what we'd prefer to be:
@@ -341,13 +353,13 @@ fn gc<StorageT: Eq + Hash + PrimInt>(
     for (state_i, zstate) in states
         .drain(..)
         .enumerate()
-        // edges goes from 0..states_len(), and we know the latter can safely fit into an
-        // StIdxStorageT, so the cast is safe.
-        .map(|(x, y)| (StIdx(x as StIdxStorageT), y))
+        // edges goes from 0..states_len(), and we know the latter can safely fit into a
+        // StorageT, so the cast is safe.
+        .map(|(x, y)| (StIdx(x.as_()), y))
     {
-        // state_i <= states_len(), which fits in StIdxStorageT, so state_i - offset must also be
+        // state_i <= states_len(), which fits in StorageT, so state_i - offset must also be
         // <= states_len, making the cast safe
-        offsets.push(StIdx((usize::from(state_i) - offset) as StIdxStorageT));
+        offsets.push(StIdx((usize::from(state_i) - offset).as_()));
         if !seen.contains(&state_i) {
             offset += 1;
             continue;
@@ -361,9 +373,9 @@ fn gc<StorageT: Eq + Hash + PrimInt>(
     for (st_edge_i, st_edges) in edges
         .drain(..)
         .enumerate()
-        // edges goes from 0..states_len(), and we know the latter can safely fit into an
-        // StIdxStorageT, so the cast is safe.
-        .map(|(x, y)| (StIdx(x as StIdxStorageT), y))
+        // edges goes from 0..states_len(), and we know the latter can safely fit into a
+        // StorageT, so the cast is safe.
+        .map(|(x, y)| (StIdx(x.as_()), y))
     {
         if !seen.contains(&st_edge_i) {
             continue;
@@ -441,7 +453,7 @@ mod test {
     #[rustfmt::skip]
     fn test_stategraph() {
         let grm = grammar3();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
 
         assert_eq!(sg.all_states_len(), StIdx(10));
         assert_eq!(sg.all_edges_len(), 10);
@@ -521,7 +533,7 @@ mod test {
 
     #[rustfmt::skip]
     fn test_pager_graph(grm: &YaccGrammar) {
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
 
         assert_eq!(sg.all_states_len(), StIdx(23));
         assert_eq!(sg.all_edges_len(), 27);
@@ -673,7 +685,7 @@ mod test {
     #[rustfmt::skip]
     fn test_pager_graph_core_states() {
         let grm = grammar_pager();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
 
         // State 0
         assert_eq!(sg.core_state(sg.start_state()).items.len(), 1);
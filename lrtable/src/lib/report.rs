@@ -0,0 +1,618 @@
+use std::{cmp::Ordering, hash::Hash};
+
+use cfgrammar::{
+    yacc::{AssocKind, YaccGrammar},
+    PIdx, RIdx, Symbol, TIdx,
+};
+use num_traits::{AsPrimitive, PrimInt, Unsigned};
+use vob::Vob;
+
+use crate::{
+    stategraph::{pp_item, StateGraph},
+    Action, StIdx, StateTable, StateTableWarning,
+};
+
+/// A detailed report about a grammar and the stategraph and statetable created from it, similar
+/// to those produced by Bison's `--report=all` (see [Report::pp]) and `--xml` (see
+/// [Report::to_xml]) options. For each state the report lists its items (with lookaheads), its
+/// actions and gotos, and any shift/reduce conflicts that were resolved by precedence (and how).
+/// It also lists the grammar's unresolved conflicts, its unused rules and tokens, and any
+/// [StateTableWarning]s.
+pub struct Report<'a, StorageT: 'static + Eq + Hash> {
+    grm: &'a YaccGrammar<StorageT>,
+    sg: &'a StateGraph<StorageT>,
+    st: &'a StateTable<StorageT>,
+}
+
+/// A shift/reduce conflict on `tidx` between a shift and a reduction of `pidx` in state `stidx`,
+/// which was resolved by precedence in favour of `action`.
+pub(crate) struct PrecResolution<StorageT> {
+    pub(crate) stidx: StIdx<StorageT>,
+    pub(crate) tidx: TIdx<StorageT>,
+    pub(crate) pidx: PIdx<StorageT>,
+    pub(crate) action: Action<StorageT>,
+}
+
+impl<'a, StorageT: 'static + Hash + PrimInt + Unsigned> Report<'a, StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Create a report for `grm`, where `sg` is the stategraph created from `grm` and `st` the
+    /// statetable created from `sg`.
+    pub fn new(
+        grm: &'a YaccGrammar<StorageT>,
+        sg: &'a StateGraph<StorageT>,
+        st: &'a StateTable<StorageT>,
+    ) -> Self {
+        Report { grm, sg, st }
+    }
+
+    /// Return the rules which can not be reached from the start rule, in order.
+    pub fn unused_rules(&self) -> Vec<RIdx<StorageT>> {
+        let used = used_rules(self.grm);
+        self.grm
+            .iter_rules()
+            .filter(|ridx| !used[usize::from(*ridx)])
+            .collect()
+    }
+
+    /// Return the tokens (other than the end of file token) which are not referenced by any
+    /// production, in order.
+    pub fn unused_tokens(&self) -> Vec<TIdx<StorageT>> {
+        let mut used = Vob::from_elem(false, usize::from(self.grm.tokens_len()));
+        used.set(usize::from(self.grm.eof_token_idx()), true);
+        for pidx in self.grm.iter_pidxs() {
+            for sym in self.grm.prod(pidx) {
+                if let Symbol::Token(tidx) = *sym {
+                    used.set(usize::from(tidx), true);
+                }
+            }
+        }
+        self.grm
+            .iter_tidxs()
+            .filter(|tidx| !used[usize::from(*tidx)])
+            .collect()
+    }
+
+    /// Return a human readable version of this report.
+    pub fn pp(&self) -> String {
+        let grm = self.grm;
+        let mut o = String::new();
+
+        o.push_str("Grammar\n\n");
+        for pidx in grm.iter_pidxs() {
+            o.push_str(&format!(
+                "{:>5} {}\n",
+                usize::from(pidx),
+                pp_prod(grm, pidx)
+            ));
+        }
+
+        let unused_rules = self.unused_rules();
+        if !unused_rules.is_empty() {
+            o.push_str("\nUnused rules\n\n");
+            for ridx in unused_rules {
+                o.push_str(&format!("    {}\n", grm.rule_name(ridx)));
+            }
+        }
+
+        let unused_tokens = self.unused_tokens();
+        if !unused_tokens.is_empty() {
+            o.push_str("\nUnused tokens\n\n");
+            for tidx in unused_tokens {
+                o.push_str(&format!("    {}\n", pp_token(grm, tidx)));
+            }
+        }
+
+        if let Some(c) = self.st.conflicts() {
+            o.push_str("\nConflicts\n\n");
+            for &(tidx, pidx, stidx) in c.sr_conflicts() {
+                o.push_str(&format!(
+                    "    State {}: shift/reduce conflict between production {} and token {}\n",
+                    usize::from(stidx),
+                    usize::from(pidx),
+                    pp_token(grm, tidx)
+                ));
+            }
+            let mut seen = Vec::new();
+            for &(pidx, r_pidx, stidx) in c.rr_conflicts() {
+                if seen.contains(&(pidx, r_pidx, stidx)) {
+                    continue;
+                }
+                seen.push((pidx, r_pidx, stidx));
+                o.push_str(&format!(
+                    "    State {}: reduce/reduce conflict between productions {} and {}\n",
+                    usize::from(stidx),
+                    usize::from(pidx),
+                    usize::from(r_pidx)
+                ));
+            }
+        }
+
+        if !self.st.warnings().is_empty() {
+            o.push_str("\nWarnings\n\n");
+            for w in self.st.warnings() {
+                o.push_str(&format!("    {}\n", w.pp(grm)));
+            }
+        }
+
+        let resolutions = prec_resolutions(grm, self.sg, self.st);
+        for stidx in self.sg.iter_stidxs() {
+            o.push_str(&format!("\n\nState {}\n\n", usize::from(stidx)));
+            for (pidx, sidx, ctx) in sorted_items(self.sg, stidx) {
+                o.push_str(&format!("    {}\n", pp_item(grm, pidx, sidx, Some(ctx))));
+            }
+
+            let actions = self.actions(stidx);
+            if !actions.is_empty() {
+                o.push('\n');
+                let width = actions.iter().map(|(s, _)| s.len()).max().unwrap();
+                for (sym, desc) in actions {
+                    o.push_str(&format!("    {:width$}  {}\n", sym, desc, width = width));
+                }
+            }
+
+            let mut seen_resolution = false;
+            for r in resolutions.iter().filter(|r| r.stidx == stidx) {
+                if !seen_resolution {
+                    o.push('\n');
+                    seen_resolution = true;
+                }
+                o.push_str(&format!(
+                    "    Conflict between production {} and token {} resolved as {} ({}).\n",
+                    usize::from(r.pidx),
+                    pp_token(grm, r.tidx),
+                    pp_resolved_action(r.action),
+                    resolution_reason(grm, r.tidx, r.pidx)
+                ));
+            }
+        }
+        o
+    }
+
+    /// Return a machine readable, XML, version of this report.
+    pub fn to_xml(&self) -> String {
+        let grm = self.grm;
+        let mut o = String::new();
+        o.push_str("<?xml version=\"1.0\"?>\n");
+        o.push_str("<grmtools-report>\n");
+
+        o.push_str("  <grammar>\n");
+        o.push_str("    <rules>\n");
+        let used = used_rules(grm);
+        for ridx in grm.iter_rules() {
+            o.push_str(&format!(
+                "      <rule number=\"{}\" name=\"{}\" usage=\"{}\">\n",
+                usize::from(ridx),
+                xml_escape(grm.rule_name(ridx)),
+                if used[usize::from(ridx)] {
+                    "used"
+                } else {
+                    "unused"
+                }
+            ));
+            for &pidx in grm.rule_to_prods(ridx) {
+                o.push_str(&format!(
+                    "        <production number=\"{}\">\n",
+                    usize::from(pidx)
+                ));
+                for sym in grm.prod(pidx) {
+                    match *sym {
+                        Symbol::Rule(s_ridx) => o.push_str(&format!(
+                            "          <rule>{}</rule>\n",
+                            xml_escape(grm.rule_name(s_ridx))
+                        )),
+                        Symbol::Token(s_tidx) => o.push_str(&format!(
+                            "          <token>{}</token>\n",
+                            xml_escape(&token_name(grm, s_tidx))
+                        )),
+                    }
+                }
+                o.push_str("        </production>\n");
+            }
+            o.push_str("      </rule>\n");
+        }
+        o.push_str("    </rules>\n");
+        o.push_str("    <tokens>\n");
+        let unused_tokens = self.unused_tokens();
+        for tidx in grm.iter_tidxs() {
+            o.push_str(&format!(
+                "      <token number=\"{}\" name=\"{}\" usage=\"{}\"/>\n",
+                usize::from(tidx),
+                xml_escape(&token_name(grm, tidx)),
+                if unused_tokens.contains(&tidx) {
+                    "unused"
+                } else {
+                    "used"
+                }
+            ));
+        }
+        o.push_str("    </tokens>\n");
+        o.push_str("  </grammar>\n");
+
+        o.push_str("  <conflicts>\n");
+        if let Some(c) = self.st.conflicts() {
+            for &(tidx, pidx, stidx) in c.sr_conflicts() {
+                o.push_str(&format!(
+                    "    <shift-reduce state=\"{}\" token=\"{}\" production=\"{}\"/>\n",
+                    usize::from(stidx),
+                    xml_escape(&token_name(grm, tidx)),
+                    usize::from(pidx)
+                ));
+            }
+            let mut seen = Vec::new();
+            for &(pidx, r_pidx, stidx) in c.rr_conflicts() {
+                if seen.contains(&(pidx, r_pidx, stidx)) {
+                    continue;
+                }
+                seen.push((pidx, r_pidx, stidx));
+                o.push_str(&format!(
+                    "    <reduce-reduce state=\"{}\" production=\"{}\" other-production=\"{}\"/>\n",
+                    usize::from(stidx),
+                    usize::from(pidx),
+                    usize::from(r_pidx)
+                ));
+            }
+        }
+        o.push_str("  </conflicts>\n");
+
+        o.push_str("  <warnings>\n");
+        for w in self.st.warnings() {
+            match *w {
+                StateTableWarning::NeverReduced(pidx) => o.push_str(&format!(
+                    "    <never-reduced production=\"{}\"/>\n",
+                    usize::from(pidx)
+                )),
+                StateTableWarning::NeverShifted(tidx) => o.push_str(&format!(
+                    "    <never-shifted token=\"{}\"/>\n",
+                    xml_escape(&token_name(grm, tidx))
+                )),
+                StateTableWarning::UnusedPrecedence(tidx) => o.push_str(&format!(
+                    "    <unused-precedence token=\"{}\"/>\n",
+                    xml_escape(&token_name(grm, tidx))
+                )),
+            }
+        }
+        o.push_str("  </warnings>\n");
+
+        o.push_str("  <automaton>\n");
+        let resolutions = prec_resolutions(grm, self.sg, self.st);
+        for stidx in self.sg.iter_stidxs() {
+            o.push_str(&format!("    <state number=\"{}\">\n", usize::from(stidx)));
+            o.push_str("      <itemset>\n");
+            for (pidx, sidx, ctx) in sorted_items(self.sg, stidx) {
+                o.push_str(&format!(
+                    "        <item production=\"{}\" dot=\"{}\">\n",
+                    usize::from(pidx),
+                    usize::from(sidx)
+                ));
+                for bidx in ctx.iter_set_bits(..) {
+                    // Since ctx is exactly tokens_len bits long, the call to as_ is safe.
+                    let tidx = TIdx(bidx.as_());
+                    o.push_str(&format!(
+                        "          <lookahead>{}</lookahead>\n",
+                        xml_escape(&token_name(grm, tidx))
+                    ));
+                }
+                o.push_str("        </item>\n");
+            }
+            o.push_str("      </itemset>\n");
+
+            o.push_str("      <actions>\n");
+            for tidx in self.st.state_actions(stidx) {
+                let tok = xml_escape(&token_name(grm, tidx));
+                match self.st.action(stidx, tidx) {
+                    Action::Shift(s_stidx) => o.push_str(&format!(
+                        "        <shift token=\"{}\" state=\"{}\"/>\n",
+                        tok,
+                        usize::from(s_stidx)
+                    )),
+                    Action::Reduce(pidx) => o.push_str(&format!(
+                        "        <reduce token=\"{}\" production=\"{}\"/>\n",
+                        tok,
+                        usize::from(pidx)
+                    )),
+                    Action::Accept => o.push_str(&format!("        <accept token=\"{}\"/>\n", tok)),
+                    Action::Error => (),
+                }
+            }
+            for ridx in grm.iter_rules() {
+                if let Some(g_stidx) = self.st.goto(stidx, ridx) {
+                    o.push_str(&format!(
+                        "        <goto rule=\"{}\" state=\"{}\"/>\n",
+                        xml_escape(grm.rule_name(ridx)),
+                        usize::from(g_stidx)
+                    ));
+                }
+            }
+            o.push_str("      </actions>\n");
+
+            o.push_str("      <resolutions>\n");
+            for r in resolutions.iter().filter(|r| r.stidx == stidx) {
+                o.push_str(&format!(
+                    "        <resolution token=\"{}\" production=\"{}\" action=\"{}\" reason=\"{}\"/>\n",
+                    xml_escape(&token_name(grm, r.tidx)),
+                    usize::from(r.pidx),
+                    match r.action {
+                        Action::Shift(_) => "shift",
+                        Action::Reduce(_) => "reduce",
+                        _ => "error",
+                    },
+                    xml_escape(resolution_reason(grm, r.tidx, r.pidx))
+                ));
+            }
+            o.push_str("      </resolutions>\n");
+            o.push_str("    </state>\n");
+        }
+        o.push_str("  </automaton>\n");
+        o.push_str("</grmtools-report>\n");
+        o
+    }
+
+    /// Return `(symbol, description)` pairs for all the actions and gotos of `stidx`.
+    fn actions(&self, stidx: StIdx<StorageT>) -> Vec<(String, String)> {
+        let grm = self.grm;
+        let mut actions = Vec::new();
+        for tidx in self.st.state_actions(stidx) {
+            let desc = match self.st.action(stidx, tidx) {
+                Action::Shift(s_stidx) => {
+                    format!("shift, and go to state {}", usize::from(s_stidx))
+                }
+                Action::Reduce(pidx) => format!(
+                    "reduce using production {} ({})",
+                    usize::from(pidx),
+                    pp_prod(grm, pidx)
+                ),
+                Action::Accept => "accept".to_owned(),
+                // Tokens made non-associative by precedence declarations lead to errors.
+                Action::Error => continue,
+            };
+            actions.push((pp_token(grm, tidx), desc));
+        }
+        for ridx in grm.iter_rules() {
+            if let Some(g_stidx) = self.st.goto(stidx, ridx) {
+                actions.push((
+                    grm.rule_name(ridx).to_owned(),
+                    format!("go to state {}", usize::from(g_stidx)),
+                ));
+            }
+        }
+        actions
+    }
+}
+
+/// Return the shift/reduce conflicts in `st` which were resolved by precedence (i.e. where both
+/// the token and the production have a precedence).
+pub(crate) fn prec_resolutions<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    sg: &StateGraph<StorageT>,
+    st: &StateTable<StorageT>,
+) -> Vec<PrecResolution<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut resolutions = Vec::new();
+    for stidx in sg.iter_stidxs() {
+        for tidx in grm.iter_tidxs() {
+            if sg.edge(stidx, Symbol::Token(tidx)).is_none() {
+                continue;
+            }
+            // As in `StateTable::new`, reduce/reduce conflicts are resolved in favour of the
+            // earliest production before any shift/reduce conflict is considered.
+            let pidx = sg
+                .closed_state(stidx)
+                .items
+                .iter()
+                .filter(|&(&(pidx, sidx), ctx)| {
+                    sidx == grm.prod_len(pidx) && ctx[usize::from(tidx)]
+                })
+                .map(|(&(pidx, _), _)| pidx)
+                .min();
+            if let Some(pidx) = pidx {
+                if grm.token_precedence(tidx).is_some() && grm.prod_precedence(pidx).is_some() {
+                    resolutions.push(PrecResolution {
+                        stidx,
+                        tidx,
+                        pidx,
+                        action: st.action(stidx, tidx),
+                    });
+                }
+            }
+        }
+    }
+    resolutions
+}
+
+/// Return a vob where each rule reachable from the start rule (or any other entry point) is set
+/// to true.
+fn used_rules<StorageT: 'static + PrimInt + Unsigned>(grm: &YaccGrammar<StorageT>) -> Vob
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut used = Vob::from_elem(false, usize::from(grm.rules_len()));
+    let mut todo = Vec::new();
+    for &(_, pidx) in grm.entries() {
+        let ridx = grm.prod_to_rule(pidx);
+        used.set(usize::from(ridx), true);
+        todo.push(ridx);
+    }
+    while let Some(ridx) = todo.pop() {
+        for &pidx in grm.rule_to_prods(ridx) {
+            for sym in grm.prod(pidx) {
+                if let Symbol::Rule(s_ridx) = *sym {
+                    if !used[usize::from(s_ridx)] {
+                        used.set(usize::from(s_ridx), true);
+                        todo.push(s_ridx);
+                    }
+                }
+            }
+        }
+    }
+    used
+}
+
+/// Return the items of the closed state `stidx` sorted by production and dot position.
+fn sorted_items<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    sg: &StateGraph<StorageT>,
+    stidx: StIdx<StorageT>,
+) -> Vec<(PIdx<StorageT>, cfgrammar::SIdx<StorageT>, &Vob)>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut items = sg
+        .closed_state(stidx)
+        .items
+        .iter()
+        .map(|(&(pidx, sidx), ctx)| (pidx, sidx, ctx))
+        .collect::<Vec<_>>();
+    items.sort_unstable_by_key(|&(pidx, sidx, _)| (pidx, sidx));
+    items
+}
+
+fn token_name<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    tidx: TIdx<StorageT>,
+) -> String
+where
+    usize: AsPrimitive<StorageT>,
+{
+    if tidx == grm.eof_token_idx() {
+        "$".to_owned()
+    } else {
+        grm.token_name(tidx).unwrap_or("").to_owned()
+    }
+}
+
+fn pp_token<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    tidx: TIdx<StorageT>,
+) -> String
+where
+    usize: AsPrimitive<StorageT>,
+{
+    format!("'{}'", token_name(grm, tidx))
+}
+
+/// Pretty print production `pidx` in the form `R: 'a' S`.
+fn pp_prod<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    pidx: PIdx<StorageT>,
+) -> String
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut o = format!("{}:", grm.rule_name(grm.prod_to_rule(pidx)));
+    if grm.prod(pidx).is_empty() {
+        o.push_str(" %empty");
+    }
+    for sym in grm.prod(pidx) {
+        match *sym {
+            Symbol::Rule(ridx) => o.push_str(&format!(" {}", grm.rule_name(ridx))),
+            Symbol::Token(tidx) => o.push_str(&format!(" {}", pp_token(grm, tidx))),
+        }
+    }
+    o
+}
+
+fn pp_resolved_action<StorageT>(action: Action<StorageT>) -> &'static str {
+    match action {
+        Action::Shift(_) => "shift",
+        Action::Reduce(_) => "reduce",
+        _ => "an error",
+    }
+}
+
+/// Explain why the shift/reduce conflict between `tidx` and `pidx` was resolved as it was.
+fn resolution_reason<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    tidx: TIdx<StorageT>,
+    pidx: PIdx<StorageT>,
+) -> &'static str
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let token_prec = grm.token_precedence(tidx).unwrap();
+    let prod_prec = grm.prod_precedence(pidx).unwrap();
+    match token_prec.level.cmp(&prod_prec.level) {
+        Ordering::Greater => "the token has a higher precedence than the production",
+        Ordering::Less => "the production has a higher precedence than the token",
+        Ordering::Equal => match token_prec.kind {
+            AssocKind::Left => "%left",
+            AssocKind::Right => "%right",
+            AssocKind::Nonassoc => "%nonassoc",
+        },
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod test {
+    use cfgrammar::yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind};
+
+    use super::Report;
+    use crate::{pager::pager_stategraph, StateTable};
+
+    #[test]
+    fn test_report() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start E
+            %token 'UNUSED'
+            %left '+'
+            %left '*'
+            %%
+            E: E '+' E | E '*' E | 'INT' | 'IF' E 'THEN' E | 'IF' E 'THEN' E 'ELSE' E;
+            U: 'INT';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let st = StateTable::new(&grm, &sg).unwrap();
+        let r = Report::new(&grm, &sg, &st);
+        assert_eq!(r.unused_rules(), vec![grm.rule_idx("U").unwrap()]);
+        assert_eq!(r.unused_tokens(), vec![grm.token_idx("UNUSED").unwrap()]);
+
+        let pp = r.pp();
+        assert!(pp.starts_with("Grammar\n\n    0 E: E '+' E\n    1 E: E '*' E\n"));
+        assert!(pp.contains("\nUnused rules\n\n    U\n"));
+        assert!(pp.contains("\nUnused tokens\n\n    'UNUSED'\n"));
+        assert!(pp.contains("shift/reduce conflict between production 3 and token 'ELSE'\n"));
+        assert!(pp.contains(
+            "    Conflict between production 0 and token '+' resolved as reduce (%left).\n"
+        ));
+        assert!(pp.contains(
+            "    Conflict between production 0 and token '*' resolved as shift (the token has a \
+             higher precedence than the production).\n"
+        ));
+        assert!(pp.contains(
+            "    Conflict between production 1 and token '+' resolved as reduce (the production \
+             has a higher precedence than the token).\n"
+        ));
+        assert!(!pp.contains("\nWarnings\n"));
+        assert!(pp.contains("    'INT'  shift, and go to state "));
+        assert!(pp.contains("reduce using production 2 (E: 'INT')\n"));
+
+        let xml = r.to_xml();
+        assert!(xml.starts_with("<?xml version=\"1.0\"?>\n<grmtools-report>\n"));
+        assert!(xml.ends_with("</grmtools-report>\n"));
+        assert!(xml.contains("  <warnings>\n  </warnings>\n"));
+        assert!(xml.contains("<rule number=\"2\" name=\"U\" usage=\"unused\">"));
+        assert!(xml.contains("name=\"UNUSED\" usage=\"unused\"/>"));
+        assert!(xml.contains("token=\"ELSE\" production=\"3\"/>"));
+        assert!(xml.contains(
+            "<resolution token=\"+\" production=\"0\" action=\"reduce\" reason=\"%left\"/>"
+        ));
+        assert_eq!(
+            xml.matches("<state ").count(),
+            usize::from(sg.all_states_len())
+        );
+    }
+}
@@ -0,0 +1,342 @@
+// The compressed tables a `StateTable` is made up of. Both are stored as `StaticSlice`s, so that a
+// `StateTable` embedded in generated code can borrow them directly from `static` arrays, without
+// any decoding at run-time. Integers are stored using the narrowest type that can hold them,
+// which keeps the tables (and binaries containing them) small.
+
+use std::collections::{BTreeSet, HashMap};
+
+use cfgrammar::StaticSlice;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A slice of unsigned integers, stored using the narrowest of `u8`, `u16`, `u32`, and `u64`
+/// which can represent all of its values.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) enum UIntSlice {
+    U8(StaticSlice<u8>),
+    U16(StaticSlice<u16>),
+    U32(StaticSlice<u32>),
+    U64(StaticSlice<u64>),
+}
+
+impl UIntSlice {
+    fn new(v: &[usize]) -> Self {
+        let max = v.iter().copied().max().unwrap_or(0);
+        if max <= usize::from(u8::MAX) {
+            UIntSlice::U8(v.iter().map(|&x| x as u8).collect())
+        } else if max <= usize::from(u16::MAX) {
+            UIntSlice::U16(v.iter().map(|&x| x as u16).collect())
+        } else if max <= u32::MAX as usize {
+            UIntSlice::U32(v.iter().map(|&x| x as u32).collect())
+        } else {
+            UIntSlice::U64(v.iter().map(|&x| x as u64).collect())
+        }
+    }
+
+    pub(crate) const fn from_static(s: StaticUInts<'static>) -> Self {
+        match s {
+            StaticUInts::U8(s) => UIntSlice::U8(StaticSlice::Borrowed(s)),
+            StaticUInts::U16(s) => UIntSlice::U16(StaticSlice::Borrowed(s)),
+            StaticUInts::U32(s) => UIntSlice::U32(StaticSlice::Borrowed(s)),
+            StaticUInts::U64(s) => UIntSlice::U64(StaticSlice::Borrowed(s)),
+        }
+    }
+
+    pub(crate) fn to_static(&self) -> StaticUInts<'_> {
+        match self {
+            UIntSlice::U8(s) => StaticUInts::U8(s),
+            UIntSlice::U16(s) => StaticUInts::U16(s),
+            UIntSlice::U32(s) => StaticUInts::U32(s),
+            UIntSlice::U64(s) => StaticUInts::U64(s),
+        }
+    }
+
+    fn get(&self, i: usize) -> usize {
+        match self {
+            UIntSlice::U8(s) => usize::from(s[i]),
+            UIntSlice::U16(s) => usize::from(s[i]),
+            UIntSlice::U32(s) => s[i] as usize,
+            UIntSlice::U64(s) => s[i] as usize,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            UIntSlice::U8(s) => s.len(),
+            UIntSlice::U16(s) => s.len(),
+            UIntSlice::U32(s) => s.len(),
+            UIntSlice::U64(s) => s.len(),
+        }
+    }
+}
+
+/// A slice of unsigned integers, as stored in a [StaticStateTable](crate::StaticStateTable). Each
+/// slice uses the narrowest integer type which can represent all of its values. This interface
+/// is unstable and should only be used by grmtools itself.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub enum StaticUInts<'a> {
+    U8(&'a [u8]),
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+    U64(&'a [u64]),
+}
+
+/// A two-dimensional table of `usize`s compressed using row displacement. Which entries of the
+/// table are non-empty is recorded by the table's user (in a [BitSet]), and only non-empty
+/// entries can be looked up. This allows two optimisations. First, a row whose non-empty entries
+/// all have the same value is stored as that value alone (in `uniform`). Second, other rows'
+/// entries are stored at `data[displacement[row] + col]`, with rows interleaved such that a slot
+/// of `data` is shared by the entries of any number of rows, provided they have the same value.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct SparseTable {
+    pub(crate) row_len: usize,
+    /// For each row, `0` if its entries are stored in `data`, or `x + 1` if all its non-empty
+    /// entries are `x`.
+    pub(crate) uniform: UIntSlice,
+    pub(crate) displacement: UIntSlice,
+    pub(crate) data: UIntSlice,
+}
+
+impl SparseTable {
+    /// Compress the `rows * row_len` table `dense`, stored in row-major order, whose non-empty
+    /// entries are those whose offset into `dense` is set in `mask`.
+    pub(crate) fn new(dense: &[usize], row_len: usize, mask: &BitSet) -> Self {
+        let rows_len = dense.len().checked_div(row_len).unwrap_or(0);
+        let mut uniform = vec![0; rows_len];
+        let mut cols = Vec::with_capacity(rows_len);
+        for (r, u) in uniform.iter_mut().enumerate() {
+            let row = &dense[r * row_len..(r + 1) * row_len];
+            let mut cs = mask
+                .iter_set_bits(r * row_len, (r + 1) * row_len)
+                .map(|i| i - r * row_len)
+                .collect::<Vec<_>>();
+            if let Some(&c) = cs.first() {
+                if cs.iter().all(|&x| row[x] == row[c]) {
+                    *u = row[c] + 1;
+                    cs.clear();
+                }
+            }
+            cols.push(cs);
+        }
+        // Placing the densest rows first means that the sparser rows can fill in the gaps they
+        // leave, which typically leads to the smallest tables.
+        let mut order = (0..rows_len).collect::<Vec<_>>();
+        order.sort_by_key(|&r| std::cmp::Reverse(cols[r].len()));
+
+        let mut displacement = vec![0; rows_len];
+        let mut data = Vec::new();
+        // Which slots of `data` hold at least one row's entry.
+        let mut used = Vec::new();
+        // For each used slot `i`, `skip[i]` is a slot `j > i` such that all the slots in `i..j`
+        // are used, so that finding an unused slot doesn't require visiting every used slot.
+        let mut skip = Vec::new();
+        // The used slots holding each value.
+        let mut slots: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        for r in order {
+            let cs = &cols[r];
+            if cs.is_empty() {
+                continue;
+            }
+            let row = &dense[r * row_len..(r + 1) * row_len];
+            // A row can only be placed at a displacement which puts its first entry in a slot
+            // which is either unused or already holds the same value, so we only check the rest
+            // of the row's entries at such displacements.
+            let mut i = cs[0];
+            let d = loop {
+                let f = match (
+                    next_unused(&used, &mut skip, i),
+                    slots.get(&row[cs[0]]).and_then(|s| s.range(i..).next()),
+                ) {
+                    (f, Some(&g)) if g < f => g,
+                    (f, _) => f,
+                };
+                let d = f - cs[0];
+                if cs[1..]
+                    .iter()
+                    .all(|&c| !used.get(d + c).copied().unwrap_or(false) || data[d + c] == row[c])
+                {
+                    break d;
+                }
+                i = f + 1;
+            };
+            let end = d + cs.last().unwrap() + 1;
+            if end > used.len() {
+                data.resize(end, 0);
+                used.resize(end, false);
+                skip.resize(end, 0);
+            }
+            for &c in cs {
+                if !used[d + c] {
+                    data[d + c] = row[c];
+                    used[d + c] = true;
+                    skip[d + c] = d + c + 1;
+                    slots.entry(row[c]).or_default().insert(d + c);
+                }
+            }
+            displacement[r] = d;
+        }
+        SparseTable {
+            row_len,
+            uniform: UIntSlice::new(&uniform),
+            displacement: UIntSlice::new(&displacement),
+            data: UIntSlice::new(&data),
+        }
+    }
+
+    /// Return the entry at (`row`, `col`), which must be non-empty.
+    pub(crate) fn get(&self, row: usize, col: usize) -> usize {
+        debug_assert!(col < self.row_len);
+        match self.uniform.get(row) {
+            0 => self.data.get(self.displacement.get(row) + col),
+            u => u - 1,
+        }
+    }
+
+    /// Return the number of entries (empty or not) in the table.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.displacement.len() * self.row_len
+    }
+}
+
+/// Return the first unused slot at or after `i` (which may be `used.len()`), shortening the
+/// paths through `skip` that were followed to find it.
+fn next_unused(used: &[bool], skip: &mut [usize], i: usize) -> usize {
+    let mut j = i;
+    while j < used.len() && used[j] {
+        j = skip[j];
+    }
+    let mut k = i;
+    while k < j {
+        let n = skip[k];
+        skip[k] = j;
+        k = n;
+    }
+    j
+}
+
+/// A fixed-length vector of bits, stored in blocks of 64. Since a `BitSet`'s length is always
+/// known by its user, it is not recorded: any bits beyond it in the last block are unset.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct BitSet {
+    pub(crate) blocks: StaticSlice<u64>,
+}
+
+impl BitSet {
+    /// Create a `BitSet` of `len` bits, all of which are unset.
+    pub(crate) fn new(len: usize) -> Self {
+        BitSet {
+            blocks: vec![0; len.div_ceil(64)].into(),
+        }
+    }
+
+    pub(crate) fn get(&self, i: usize) -> bool {
+        self.blocks[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Set bit `i`, returning `true` if it was previously unset.
+    pub(crate) fn set(&mut self, i: usize) -> bool {
+        match self.blocks {
+            StaticSlice::Owned(ref mut v) => {
+                let old = v[i / 64];
+                v[i / 64] |= 1 << (i % 64);
+                old != v[i / 64]
+            }
+            StaticSlice::Borrowed(_) => panic!("Can't mutate a static BitSet"),
+        }
+    }
+
+    /// Return an iterator over the indexes of the set bits in `start..end`.
+    pub(crate) fn iter_set_bits(&self, start: usize, end: usize) -> IterSetBits<'_> {
+        debug_assert!(start <= end && end <= self.blocks.len() * 64);
+        IterSetBits {
+            blocks: &self.blocks,
+            next: start,
+            end,
+        }
+    }
+}
+
+pub(crate) struct IterSetBits<'a> {
+    blocks: &'a [u64],
+    next: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for IterSetBits<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.next < self.end {
+            // Mask out the bits below `self.next` and skip straight to the next set bit, if
+            // there is one, in this block.
+            let b = self.blocks[self.next / 64] >> (self.next % 64);
+            if b == 0 {
+                self.next = (self.next / 64 + 1) * 64;
+                continue;
+            }
+            let i = self.next + b.trailing_zeros() as usize;
+            if i >= self.end {
+                break;
+            }
+            self.next = i + 1;
+            return Some(i);
+        }
+        self.next = self.end;
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BitSet, SparseTable};
+
+    #[test]
+    fn test_sparse_table() {
+        let dense = vec![
+            0, 1, 0, 0, //
+            2, 0, 3, 0, //
+            0, 0, 0, 0, //
+            0, 4, 0, 5, //
+        ];
+        let mut mask = BitSet::new(dense.len());
+        for (i, &x) in dense.iter().enumerate() {
+            if x != 0 {
+                mask.set(i);
+            }
+        }
+        // An entry which is non-empty, even though it has the same value as empty entries.
+        mask.set(8);
+        let st = SparseTable::new(&dense, 4, &mask);
+        for i in mask.iter_set_bits(0, dense.len()) {
+            assert_eq!(st.get(i / 4, i % 4), dense[i]);
+        }
+        // The rows have been interleaved rather than stored end to end, and the first and third
+        // rows, each of whose non-empty entries all have the same value, aren't stored in `data`.
+        assert_eq!(st.uniform.get(0), 2);
+        assert_eq!(st.uniform.get(2), 1);
+        assert!(st.data.len() < dense.len());
+    }
+
+    #[test]
+    fn test_bitset() {
+        let mut b = BitSet::new(130);
+        assert!(b.set(0));
+        assert!(b.set(63));
+        assert!(b.set(64));
+        assert!(b.set(129));
+        assert!(!b.set(64));
+        assert!(b.get(63) && !b.get(62));
+        assert_eq!(
+            b.iter_set_bits(0, 130).collect::<Vec<_>>(),
+            vec![0, 63, 64, 129]
+        );
+        assert_eq!(b.iter_set_bits(1, 129).collect::<Vec<_>>(), vec![63, 64]);
+        assert_eq!(b.iter_set_bits(65, 129).count(), 0);
+    }
+}
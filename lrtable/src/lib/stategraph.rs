@@ -1,18 +1,45 @@
-use std::{collections::hash_map::HashMap, hash::Hash};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::HashMap, BinaryHeap},
+    hash::Hash,
+};
 
-use cfgrammar::{yacc::YaccGrammar, Symbol, TIdx};
+use cfgrammar::{yacc::YaccGrammar, PIdx, RIdx, SIdx, Symbol, TIdx};
 use num_traits::{AsPrimitive, PrimInt, Unsigned};
-use try_from::TryFrom;
+use vob::Vob;
 
-use crate::{itemset::Itemset, StIdx, StIdxStorageT};
+use crate::{itemset::Itemset, statetable::Conflicts, StIdx, StateTableError, StateTableErrorKind};
+
+/// Options for [StateGraph::to_dot].
+pub struct DotOptions<'a, StorageT: 'static> {
+    /// If true, closed states (i.e. including the items added by closure) are rendered; if
+    /// false, only core states are rendered.
+    pub closed_states: bool,
+    /// If true, each item is rendered with its lookahead set.
+    pub lookaheads: bool,
+    /// If set, states with conflicts are highlighted and annotated with their conflicting actions.
+    pub conflicts: Option<&'a Conflicts<StorageT>>,
+}
+
+impl<'a, StorageT: 'static> Default for DotOptions<'a, StorageT> {
+    fn default() -> Self {
+        DotOptions {
+            closed_states: false,
+            lookaheads: true,
+            conflicts: None,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct StateGraph<StorageT: Eq + Hash> {
     /// A vector of `(core_states, closed_states)` tuples.
     states: Vec<(Itemset<StorageT>, Itemset<StorageT>)>,
-    start_state: StIdx,
+    /// The start state of each of the grammar's entry points, in the same order as
+    /// `YaccGrammar::entries`.
+    start_states: Vec<StIdx<StorageT>>,
     /// For each state in `states`, edges is a hashmap from symbols to state offsets.
-    edges: Vec<HashMap<Symbol<StorageT>, StIdx>>,
+    edges: Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>>,
 }
 
 impl<StorageT: 'static + Hash + PrimInt + Unsigned> StateGraph<StorageT>
@@ -21,34 +48,41 @@ where
 {
     pub(crate) fn new(
         states: Vec<(Itemset<StorageT>, Itemset<StorageT>)>,
-        start_state: StIdx,
-        edges: Vec<HashMap<Symbol<StorageT>, StIdx>>,
+        start_states: Vec<StIdx<StorageT>>,
+        edges: Vec<HashMap<Symbol<StorageT>, StIdx<StorageT>>>,
     ) -> Self {
-        // states.len() needs to fit into StIdxStorageT; however we don't need to worry about
+        // states.len() needs to fit into StorageT; however we don't need to worry about
         // edges.len() (which merely needs to fit in a usize)
-        assert!(StIdxStorageT::try_from(states.len()).is_ok());
+        assert!(num_traits::cast::<usize, StorageT>(states.len()).is_some());
+        assert!(!start_states.is_empty());
         StateGraph {
             states,
-            start_state,
+            start_states,
             edges,
         }
     }
 
     /// Return this state graph's start state.
-    pub fn start_state(&self) -> StIdx {
-        self.start_state
+    pub fn start_state(&self) -> StIdx<StorageT> {
+        self.start_states[0]
+    }
+
+    /// Return the start state of each of the grammar's entry points, in the same order as
+    /// [YaccGrammar::entries] (so the first element is always [start_state](Self::start_state)).
+    pub fn start_states(&self) -> &[StIdx<StorageT>] {
+        &self.start_states
     }
 
     /// Return an iterator which produces (in order from `0..self.rules_len()`) all this
     /// grammar's valid `RIdx`s.
-    pub fn iter_stidxs(&self) -> Box<dyn Iterator<Item = StIdx>> {
+    pub fn iter_stidxs(&self) -> Box<dyn Iterator<Item = StIdx<StorageT>>> {
         // We can use as safely, because we know that we're only generating integers from
-        // 0..self.states.len() which we've already checked fits within StIdxStorageT.
-        Box::new((0..self.states.len()).map(|x| StIdx(x as StIdxStorageT)))
+        // 0..self.states.len() which we've already checked fits within StorageT.
+        Box::new((0..self.states.len()).map(|x| StIdx(x.as_())))
     }
 
     /// Return the itemset for closed state `stidx`. Panics if `stidx` doesn't exist.
-    pub fn closed_state(&self, stidx: StIdx) -> &Itemset<StorageT> {
+    pub fn closed_state(&self, stidx: StIdx<StorageT>) -> &Itemset<StorageT> {
         &self.states[usize::from(stidx)].1
     }
 
@@ -60,7 +94,7 @@ where
     }
 
     /// Return the itemset for core state `stidx` or `None` if it doesn't exist.
-    pub fn core_state(&self, stidx: StIdx) -> &Itemset<StorageT> {
+    pub fn core_state(&self, stidx: StIdx<StorageT>) -> &Itemset<StorageT> {
         &self.states[usize::from(stidx)].0
     }
 
@@ -71,13 +105,13 @@ where
 
     /// How many states does this `StateGraph` contain? NB: By definition the `StateGraph` contains
     /// the same number of core and closed states.
-    pub fn all_states_len(&self) -> StIdx {
-        // We checked in the constructor that self.states.len() can fit into StIdxStorageT
-        StIdx::from(self.states.len() as StIdxStorageT)
+    pub fn all_states_len(&self) -> StIdx<StorageT> {
+        // We checked in the constructor that self.states.len() can fit into StorageT
+        StIdx(self.states.len().as_())
     }
 
     /// Return the state pointed to by `sym` from `stidx` or `None` otherwise.
-    pub fn edge(&self, stidx: StIdx, sym: Symbol<StorageT>) -> Option<StIdx> {
+    pub fn edge(&self, stidx: StIdx<StorageT>, sym: Symbol<StorageT>) -> Option<StIdx<StorageT>> {
         self.edges
             .get(usize::from(stidx))
             .and_then(|x| x.get(&sym))
@@ -85,7 +119,7 @@ where
     }
 
     /// Return the edges for state `stidx`. Panics if `stidx` doesn't exist.
-    pub fn edges(&self, stidx: StIdx) -> &HashMap<Symbol<StorageT>, StIdx> {
+    pub fn edges(&self, stidx: StIdx<StorageT>) -> &HashMap<Symbol<StorageT>, StIdx<StorageT>> {
         &self.edges[usize::from(stidx)]
     }
 
@@ -94,43 +128,88 @@ where
         self.edges.iter().fold(0, |a, x| a + x.len())
     }
 
+    /// Return a shortest sequence of tokens which leads from a start state to `stidx`, and the
+    /// entry rule (see [YaccGrammar::entries]) whose start state the sequence begins at. Edges
+    /// labelled with rules are expanded using `SentenceGenerator::min_sentence`, and path length
+    /// is measured in the number of tokens after expansion. Panics if `stidx` doesn't exist.
+    pub fn shortest_path(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        stidx: StIdx<StorageT>,
+    ) -> (RIdx<StorageT>, Vec<TIdx<StorageT>>) {
+        let sg = grm.sentence_generator(|_| 1);
+        let sym_cost = |sym: Symbol<StorageT>| match sym {
+            Symbol::Rule(ridx) => usize::from(sg.min_sentence_cost(ridx)),
+            Symbol::Token(_) => 1,
+        };
+
+        // A straightforward Dijkstra search from the start states. `preds[i]` records the state
+        // and symbol by which state `i` was first reached with minimal cost.
+        let mut costs = vec![usize::MAX; self.states.len()];
+        let mut preds: Vec<Option<(StIdx<StorageT>, Symbol<StorageT>)>> =
+            vec![None; self.states.len()];
+        let mut todo = BinaryHeap::new();
+        for &start_stidx in &self.start_states {
+            costs[usize::from(start_stidx)] = 0;
+            todo.push(Reverse((0, usize::from(start_stidx))));
+        }
+        while let Some(Reverse((cost, st_i))) = todo.pop() {
+            if st_i == usize::from(stidx) {
+                break;
+            }
+            if cost > costs[st_i] {
+                continue;
+            }
+            for (&sym, &e_stidx) in self.edges[st_i].iter() {
+                let e_cost = cost + sym_cost(sym);
+                if e_cost < costs[usize::from(e_stidx)] {
+                    costs[usize::from(e_stidx)] = e_cost;
+                    // st_i < self.states.len() so the cast is safe.
+                    preds[usize::from(e_stidx)] = Some((StIdx(st_i.as_()), sym));
+                    todo.push(Reverse((e_cost, usize::from(e_stidx))));
+                }
+            }
+        }
+
+        let mut syms = Vec::new();
+        let mut cur = stidx;
+        while let Some((p_stidx, sym)) = preds[usize::from(cur)] {
+            syms.push(sym);
+            cur = p_stidx;
+        }
+        // `start_states` is in the same order as `grm.entries()`.
+        let entry_i = self.start_states.iter().position(|&x| x == cur).unwrap();
+        let mut toks = Vec::new();
+        for sym in syms.into_iter().rev() {
+            match sym {
+                Symbol::Rule(ridx) => toks.extend(sg.min_sentence(ridx)),
+                Symbol::Token(tidx) => toks.push(tidx),
+            }
+        }
+        (grm.entries()[entry_i].0, toks)
+    }
+
     /// Pretty print this stategraph as a `String`. If `core_states` is set to true, only the core
     /// states are pretty printed; if set to false, all states (including non-core states) are
     /// pretty printed.
     pub fn pp(&self, grm: &YaccGrammar<StorageT>, core_states: bool) -> String {
-        fn num_digits(i: StIdx) -> usize {
-            if usize::from(i) == 0 {
+        fn num_digits(i: usize) -> usize {
+            if i == 0 {
                 1
             } else {
-                ((usize::from(i) as f64).log10() as usize) + 1
-            }
-        }
-
-        fn fmt_sym<StorageT: 'static + PrimInt + Unsigned>(
-            grm: &YaccGrammar<StorageT>,
-            sym: Symbol<StorageT>,
-        ) -> String
-        where
-            usize: AsPrimitive<StorageT>,
-        {
-            match sym {
-                Symbol::Rule(ridx) => grm.rule_name(ridx).to_string(),
-                Symbol::Token(tidx) => format!("'{}'", grm.token_name(tidx).unwrap_or("")),
+                ((i as f64).log10() as usize) + 1
             }
         }
 
         let mut o = String::new();
         for (stidx, &(ref core_st, ref closed_st)) in self.iter_stidxs().zip(self.states.iter()) {
-            if stidx != self.start_state {
+            if stidx != self.start_state() {
                 o.push('\n');
             }
             {
-                let padding = num_digits(self.all_states_len()) - num_digits(stidx);
-                o.push_str(&format!(
-                    "{}:{}",
-                    StIdxStorageT::from(stidx),
-                    " ".repeat(padding)
-                ));
+                let padding =
+                    num_digits(usize::from(self.all_states_len())) - num_digits(usize::from(stidx));
+                o.push_str(&format!("{}:{}", usize::from(stidx), " ".repeat(padding)));
             }
 
             let st = if core_states { core_st } else { closed_st };
@@ -139,44 +218,18 @@ where
                     0
                 } else {
                     o.push_str("\n "); // Extra space to compensate for ":" printed above
-                    num_digits(self.all_states_len())
+                    num_digits(usize::from(self.all_states_len()))
                 };
                 o.push_str(&format!(
-                    "{} [{} ->",
+                    "{} [{}]",
                     " ".repeat(padding),
-                    grm.rule_name(grm.prod_to_rule(pidx))
+                    pp_item(grm, pidx, sidx, Some(ctx))
                 ));
-                for (i_sidx, i_ssym) in grm.prod(pidx).iter().enumerate() {
-                    if i_sidx == usize::from(sidx) {
-                        o.push_str(" .");
-                    }
-                    o.push_str(&format!(" {}", fmt_sym(grm, *i_ssym)));
-                }
-                if usize::from(sidx) == grm.prod(pidx).len() {
-                    o.push_str(" .");
-                }
-                o.push_str(", {");
-                let mut seen_b = false;
-                for bidx in ctx.iter_set_bits(..) {
-                    if seen_b {
-                        o.push_str(", ");
-                    } else {
-                        seen_b = true;
-                    }
-                    // Since ctx is exactly tokens_len bits long, the call to as_ is safe.
-                    let tidx = TIdx(bidx.as_());
-                    if tidx == grm.eof_token_idx() {
-                        o.push_str("'$'");
-                    } else {
-                        o.push_str(&format!("'{}'", grm.token_name(tidx).unwrap()));
-                    }
-                }
-                o.push_str("}]");
             }
             for (esym, e_stidx) in self.edges(stidx).iter() {
                 o.push_str(&format!(
                     "\n{}{} -> {}",
-                    " ".repeat(num_digits(self.all_states_len()) + 2),
+                    " ".repeat(num_digits(usize::from(self.all_states_len())) + 2),
                     fmt_sym(grm, *esym),
                     usize::from(*e_stidx)
                 ));
@@ -194,10 +247,159 @@ where
     pub fn pp_closed_states(&self, grm: &YaccGrammar<StorageT>) -> String {
         self.pp(grm, false)
     }
+
+    /// Return a representation of this stategraph in Graphviz's DOT format, with each state
+    /// rendered as a node (labelled with its items) and each edge as an arc (labelled with its
+    /// symbol). What is rendered is determined by `options`.
+    pub fn to_dot(&self, grm: &YaccGrammar<StorageT>, options: &DotOptions<StorageT>) -> String {
+        let mut o = String::new();
+        o.push_str("digraph StateGraph {\n");
+        o.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        for stidx in self.iter_stidxs() {
+            let st = if options.closed_states {
+                self.closed_state(stidx)
+            } else {
+                self.core_state(stidx)
+            };
+            // Itemsets are unordered, so sort the items to make the output deterministic.
+            let mut items = st.items.iter().collect::<Vec<_>>();
+            items.sort_unstable_by_key(|&(&(pidx, sidx), _)| (pidx, sidx));
+            let mut label = format!("{}:\\l", usize::from(stidx));
+            for (&(pidx, sidx), ctx) in items {
+                let ctx = if options.lookaheads { Some(ctx) } else { None };
+                label.push_str(&dot_escape(&pp_item(grm, pidx, sidx, ctx)));
+                label.push_str("\\l");
+            }
+            let mut conflicted = false;
+            if let Some(c) = options.conflicts {
+                for &(tidx, pidx, _) in c.sr_conflicts().filter(|x| x.2 == stidx) {
+                    conflicted = true;
+                    label.push_str(&dot_escape(&format!(
+                        "Shift/Reduce: Shift('{}') / Reduce({})",
+                        grm.token_name(tidx).unwrap_or(""),
+                        grm.pp_prod(pidx)
+                    )));
+                    label.push_str("\\l");
+                }
+                // A reduce/reduce conflict is recorded once per token it occurs on, but we only
+                // want to show it once.
+                let mut seen = Vec::new();
+                for &(pidx, r_pidx, _) in c.rr_conflicts().filter(|x| x.2 == stidx) {
+                    if seen.contains(&(pidx, r_pidx)) {
+                        continue;
+                    }
+                    seen.push((pidx, r_pidx));
+                    conflicted = true;
+                    label.push_str(&dot_escape(&format!(
+                        "Reduce/Reduce: Reduce({}) / Reduce({})",
+                        grm.pp_prod(pidx),
+                        grm.pp_prod(r_pidx)
+                    )));
+                    label.push_str("\\l");
+                }
+            }
+            o.push_str(&format!("    {} [label=\"{}\"", usize::from(stidx), label));
+            if conflicted {
+                o.push_str(", color=red, penwidth=2");
+            }
+            o.push_str("];\n");
+        }
+        for stidx in self.iter_stidxs() {
+            let mut edges = self.edges(stidx).iter().collect::<Vec<_>>();
+            edges.sort_unstable_by_key(|&(_, &e_stidx)| usize::from(e_stidx));
+            for (&sym, &e_stidx) in edges {
+                o.push_str(&format!(
+                    "    {} -> {} [label=\"{}\"];\n",
+                    usize::from(stidx),
+                    usize::from(e_stidx),
+                    dot_escape(&fmt_sym(grm, sym))
+                ));
+            }
+        }
+        o.push_str("}\n");
+        o
+    }
 }
 
-#[cfg(test)]
-use cfgrammar::SIdx;
+fn fmt_sym<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    sym: Symbol<StorageT>,
+) -> String
+where
+    usize: AsPrimitive<StorageT>,
+{
+    match sym {
+        Symbol::Rule(ridx) => grm.rule_name(ridx).to_string(),
+        Symbol::Token(tidx) => format!("'{}'", grm.token_name(tidx).unwrap_or("")),
+    }
+}
+
+/// Pretty print the item `pidx` with the dot at `sidx` and, if `ctx` is not `None`, its
+/// lookahead set, e.g. `A -> 'a' . B, {'b', '$'}`.
+pub(crate) fn pp_item<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    pidx: PIdx<StorageT>,
+    sidx: SIdx<StorageT>,
+    ctx: Option<&Vob>,
+) -> String
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let mut o = format!("{} ->", grm.rule_name(grm.prod_to_rule(pidx)));
+    for (i_sidx, i_ssym) in grm.prod(pidx).iter().enumerate() {
+        if i_sidx == usize::from(sidx) {
+            o.push_str(" .");
+        }
+        o.push_str(&format!(" {}", fmt_sym(grm, *i_ssym)));
+    }
+    if usize::from(sidx) == grm.prod(pidx).len() {
+        o.push_str(" .");
+    }
+    if let Some(ctx) = ctx {
+        o.push_str(", {");
+        let mut seen_b = false;
+        for bidx in ctx.iter_set_bits(..) {
+            if seen_b {
+                o.push_str(", ");
+            } else {
+                seen_b = true;
+            }
+            // Since ctx is exactly tokens_len bits long, the call to as_ is safe.
+            let tidx = TIdx(bidx.as_());
+            if tidx == grm.eof_token_idx() {
+                o.push_str("'$'");
+            } else {
+                o.push_str(&format!("'{}'", grm.token_name(tidx).unwrap()));
+            }
+        }
+        o.push('}');
+    }
+    o
+}
+
+/// Escape `s` so that it can be used within a double-quoted DOT string.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Return the `StIdx` for a new state which will be stored at offset `i` of a stategraph for `grm`,
+/// or an error if the stategraph would then have more states than `StorageT` can represent.
+pub(crate) fn new_stidx<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    i: usize,
+) -> Result<StIdx<StorageT>, StateTableError<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    // Both `i` and the number of states (`i + 1`) must be representable as a StorageT.
+    if i >= num_traits::cast::<StorageT, usize>(StorageT::max_value()).unwrap_or(usize::MAX) {
+        return Err(StateTableError {
+            kind: StateTableErrorKind::TooManyStates,
+            pidx: grm.start_prod(),
+        });
+    }
+    Ok(StIdx(i.as_()))
+}
 
 #[cfg(test)]
 pub fn state_exists<StorageT: 'static + Hash + PrimInt + Unsigned>(
@@ -244,7 +446,8 @@ pub fn state_exists<StorageT: 'static + Hash + PrimInt + Unsigned>(
 
 #[cfg(test)]
 mod test {
-    use crate::{pager::pager_stategraph, StIdx};
+    use super::DotOptions;
+    use crate::{pager::pager_stategraph, StIdx, StateTable};
     use cfgrammar::{
         yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind},
         Symbol,
@@ -264,7 +467,7 @@ mod test {
              | 'b';
           "
         ).unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         assert_eq!(sg.all_states_len(), StIdx(7));
         assert_eq!(sg.states.iter().fold(0, |a, x| a + x.0.items.len()), 7);
         assert_eq!(sg.all_edges_len(), 9);
@@ -281,4 +484,134 @@ mod test {
         let s4 = sg.edge(s5, Symbol::Rule(grm.rule_idx("A").unwrap())).unwrap();
         sg.edge(s4, Symbol::Token(grm.token_idx("CLOSE_BRACKET").unwrap())).unwrap(); // s6
     }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_shortest_path() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'IF' E 'THEN' S | 'IF' E 'THEN' S 'ELSE' S | 'X';
+            E: 'LP' E 'RP' | 'ID';
+          "
+        ).unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let path = |stidx| {
+            sg.shortest_path(&grm, stidx).1
+              .into_iter()
+              .map(|tidx| grm.token_name(tidx).unwrap())
+              .collect::<Vec<_>>()
+        };
+        assert!(path(sg.start_state()).is_empty());
+
+        let tok = |n| Symbol::Token(grm.token_idx(n).unwrap());
+        let s_if = sg.edge(sg.start_state(), tok("IF")).unwrap();
+        let s_e = sg.edge(s_if, Symbol::Rule(grm.rule_idx("E").unwrap())).unwrap();
+        let s_then = sg.edge(s_e, tok("THEN")).unwrap();
+        let s_s = sg.edge(s_then, Symbol::Rule(grm.rule_idx("S").unwrap())).unwrap();
+        let s_else = sg.edge(s_s, tok("ELSE")).unwrap();
+        assert_eq!(path(s_if), vec!["IF"]);
+        assert_eq!(path(s_e), vec!["IF", "ID"]);
+        assert_eq!(path(s_s), vec!["IF", "ID", "THEN", "X"]);
+        assert_eq!(path(s_else), vec!["IF", "ID", "THEN", "X", "ELSE"]);
+        let s_lp = sg.edge(s_if, tok("LP")).unwrap();
+        let s_rp = sg.edge(sg.edge(s_lp, Symbol::Rule(grm.rule_idx("E").unwrap())).unwrap(),
+                           tok("RP")).unwrap();
+        assert_eq!(path(s_rp), vec!["IF", "LP", "ID", "RP"]);
+    }
+
+    #[test]
+    fn test_shortest_path_entries() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %entry T
+            %%
+            S: T ';';
+            T: 'a' | 'a' '+' T;
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let (s_ridx, t_ridx) = (grm.rule_idx("S").unwrap(), grm.rule_idx("T").unwrap());
+        assert_eq!(sg.shortest_path(&grm, sg.start_state()), (s_ridx, vec![]));
+        assert_eq!(
+            sg.shortest_path(&grm, sg.start_states()[1]),
+            (t_ridx, vec![])
+        );
+        // The state reached after a complete `T` from `T`'s start state can only be reached by
+        // parsing `T` as an entry rule.
+        let t_end = sg.edge(sg.start_states()[1], Symbol::Rule(t_ridx)).unwrap();
+        assert_eq!(
+            sg.shortest_path(&grm, t_end),
+            (t_ridx, vec![grm.token_idx("a").unwrap()])
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+            %start S
+            %%
+            S: 'IF' 'E' 'THEN' S | 'IF' 'E' 'THEN' S 'ELSE' S | 'X';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let st = StateTable::new(&grm, &sg).unwrap();
+        let tok = |n| Symbol::Token(grm.token_idx(n).unwrap());
+        let s_if = sg.edge(sg.start_state(), tok("IF")).unwrap();
+        let s_e = sg.edge(s_if, tok("E")).unwrap();
+        let s_then = sg.edge(s_e, tok("THEN")).unwrap();
+        let s_s = sg
+            .edge(s_then, Symbol::Rule(grm.rule_idx("S").unwrap()))
+            .unwrap();
+
+        let dot = sg.to_dot(&grm, &DotOptions::default());
+        assert!(dot.starts_with("digraph StateGraph {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains(&format!(
+            "    {} [label=\"{}:\\lS -> 'IF' . 'E' 'THEN' S, {{'ELSE', '$'}}\\l\
+             S -> 'IF' . 'E' 'THEN' S 'ELSE' S, {{'ELSE', '$'}}\\l\"];\n",
+            usize::from(s_if),
+            usize::from(s_if)
+        )));
+        assert!(dot.contains(&format!(
+            "    {} -> {} [label=\"'E'\"];\n",
+            usize::from(s_if),
+            usize::from(s_e)
+        )));
+        assert!(!dot.contains("color=red"));
+
+        let dot = sg.to_dot(
+            &grm,
+            &DotOptions {
+                closed_states: true,
+                lookaheads: false,
+                conflicts: st.conflicts(),
+            },
+        );
+        assert!(dot.contains(&format!(
+            "    {} [label=\"{}:\\lS -> . 'IF' 'E' 'THEN' S\\lS -> 'IF' 'E' 'THEN' . S\\l\
+             S -> . 'IF' 'E' 'THEN' S 'ELSE' S\\lS -> 'IF' 'E' 'THEN' . S 'ELSE' S\\l\
+             S -> . 'X'\\l\"];\n",
+            usize::from(s_then),
+            usize::from(s_then)
+        )));
+        assert!(dot.contains(
+            "Shift/Reduce: Shift('ELSE') / Reduce(S: \\\"IF\\\" \\\"E\\\" \\\"THEN\\\" \\\"S\\\")\\l\", \
+             color=red, penwidth=2];\n"
+        ));
+        assert_eq!(dot.matches("color=red").count(), 1);
+        assert!(dot.contains(&format!(
+            "    {} [label=\"{}:",
+            usize::from(s_s),
+            usize::from(s_s)
+        )));
+    }
 }
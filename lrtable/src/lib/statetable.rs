@@ -1,4 +1,5 @@
 use std::{
+    any::type_name,
     cmp::Ordering,
     collections::hash_map::HashMap,
     error::Error,
@@ -9,21 +10,25 @@ use std::{
 
 use cfgrammar::{
     yacc::{AssocKind, YaccGrammar},
-    PIdx, RIdx, Symbol, TIdx,
+    PIdx, RIdx, StaticSlice, Symbol, TIdx,
 };
 use num_traits::{AsPrimitive, PrimInt, Unsigned};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use sparsevec::SparseVec;
-use vob::{IterSetBits, Vob};
+use vob::Vob;
 
-use crate::{stategraph::StateGraph, StIdx, StIdxStorageT};
+use crate::{
+    counterexamples::{pp_sym, rr_counterexample, sr_counterexample, Counterexample},
+    sparse::{BitSet, IterSetBits, SparseTable, StaticUInts, UIntSlice},
+    stategraph::StateGraph,
+    StIdx,
+};
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug)]
-pub struct Conflicts<StorageT> {
-    reduce_reduce: Vec<(PIdx<StorageT>, PIdx<StorageT>, StIdx)>,
-    shift_reduce: Vec<(TIdx<StorageT>, PIdx<StorageT>, StIdx)>,
+pub struct Conflicts<StorageT: 'static> {
+    reduce_reduce: StaticSlice<(PIdx<StorageT>, PIdx<StorageT>, StIdx<StorageT>)>,
+    shift_reduce: StaticSlice<(TIdx<StorageT>, PIdx<StorageT>, StIdx<StorageT>)>,
 }
 
 impl<StorageT: 'static + Hash + PrimInt + Unsigned> Conflicts<StorageT>
@@ -31,12 +36,16 @@ where
     usize: AsPrimitive<StorageT>,
 {
     /// Return an iterator over all reduce/reduce conflicts.
-    pub fn rr_conflicts(&self) -> impl Iterator<Item = &(PIdx<StorageT>, PIdx<StorageT>, StIdx)> {
+    pub fn rr_conflicts(
+        &self,
+    ) -> impl Iterator<Item = &(PIdx<StorageT>, PIdx<StorageT>, StIdx<StorageT>)> {
         self.reduce_reduce.iter()
     }
 
     /// Return an iterator over all shift/reduce conflicts.
-    pub fn sr_conflicts(&self) -> impl Iterator<Item = &(TIdx<StorageT>, PIdx<StorageT>, StIdx)> {
+    pub fn sr_conflicts(
+        &self,
+    ) -> impl Iterator<Item = &(TIdx<StorageT>, PIdx<StorageT>, StIdx<StorageT>)> {
         self.shift_reduce.iter()
     }
 
@@ -92,12 +101,212 @@ where
         }
         s
     }
+
+    /// Return a counterexample for the shift/reduce conflict on `tidx` with production `pidx` in
+    /// state `stidx` (as returned by [Conflicts::sr_conflicts]). `sg` must be the stategraph from
+    /// which the statetable was created. Returns `None` if no counterexample could be found.
+    pub fn sr_counterexample(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        sg: &StateGraph<StorageT>,
+        tidx: TIdx<StorageT>,
+        pidx: PIdx<StorageT>,
+        stidx: StIdx<StorageT>,
+    ) -> Option<Counterexample<StorageT>> {
+        sr_counterexample(grm, sg, tidx, pidx, stidx)
+    }
+
+    /// Return a counterexample for the reduce/reduce conflict between productions `pidx` and
+    /// `r_pidx` in state `stidx` (as returned by [Conflicts::rr_conflicts]) when the next token
+    /// is `tidx`. `sg` must be the stategraph from which the statetable was created. Returns
+    /// `None` if no counterexample could be found.
+    pub fn rr_counterexample(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        sg: &StateGraph<StorageT>,
+        tidx: TIdx<StorageT>,
+        pidx: PIdx<StorageT>,
+        r_pidx: PIdx<StorageT>,
+        stidx: StIdx<StorageT>,
+    ) -> Option<Counterexample<StorageT>> {
+        rr_counterexample(grm, sg, tidx, pidx, r_pidx, stidx)
+    }
+
+    /// Returns a pretty-printed version of the reduce/reduce conflicts, each followed by a
+    /// shortest input leading to the conflicting state and a counterexample for every token on
+    /// which the conflict occurs. Tokens with identical counterexamples are reported together.
+    /// `sg` must be the stategraph from which the statetable was created.
+    pub fn pp_rr_counterexamples(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        sg: &StateGraph<StorageT>,
+    ) -> String {
+        let mut s = String::new();
+        if self.rr_len() > 0 {
+            s.push_str("Reduce/Reduce conflicts:\n");
+            let mut seen = Vec::new();
+            for &(pidx, r_pidx, stidx) in self.rr_conflicts() {
+                if seen.contains(&(pidx, r_pidx, stidx)) {
+                    continue;
+                }
+                seen.push((pidx, r_pidx, stidx));
+                s.push_str(&format!(
+                    "   State {:?}: Reduce({}) / Reduce({})\n",
+                    usize::from(stidx),
+                    grm.pp_prod(pidx),
+                    grm.pp_prod(r_pidx)
+                ));
+                let items = &sg.closed_state(stidx).items;
+                let mut ctx = items[&(pidx, grm.prod_len(pidx))].clone();
+                ctx.and(&items[&(r_pidx, grm.prod_len(r_pidx))]);
+                // Each distinct pretty-printed counterexample, and the tokens it was found for.
+                let mut ces: Vec<(String, Vec<TIdx<StorageT>>)> = Vec::new();
+                for tidx in ctx.iter_set_bits(..) {
+                    let tidx = TIdx(tidx.as_());
+                    let mut ce = String::new();
+                    pp_counterexample(
+                        grm,
+                        self.rr_counterexample(grm, sg, tidx, pidx, r_pidx, stidx),
+                        ("First reduce", "Second reduce"),
+                        &mut ce,
+                    );
+                    match ces.iter_mut().find(|(x, _)| *x == ce) {
+                        Some((_, tidxs)) => tidxs.push(tidx),
+                        None => ces.push((ce, vec![tidx])),
+                    }
+                }
+                let path = pp_shortest_path(grm, sg, stidx);
+                for (ce, tidxs) in ces {
+                    s.push_str(&format!(
+                        "      {}, on {}\n",
+                        path,
+                        tidxs
+                            .into_iter()
+                            .map(|tidx| pp_sym(grm, Symbol::Token(tidx)))
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    ));
+                    s.push_str(&ce);
+                }
+            }
+        }
+        s
+    }
+
+    /// Returns a pretty-printed version of the shift/reduce conflicts, each followed by a
+    /// shortest input leading to the conflicting state and a counterexample. `sg` must be the
+    /// stategraph from which the statetable was created.
+    pub fn pp_sr_counterexamples(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        sg: &StateGraph<StorageT>,
+    ) -> String {
+        let mut s = String::new();
+        if self.sr_len() > 0 {
+            s.push_str("Shift/Reduce conflicts:\n");
+            for &(tidx, pidx, stidx) in self.sr_conflicts() {
+                s.push_str(&format!(
+                    "   State {:?}: Shift(\"{}\") / Reduce({})\n",
+                    usize::from(stidx),
+                    grm.token_name(tidx).unwrap(),
+                    grm.pp_prod(pidx)
+                ));
+                s.push_str(&format!(
+                    "      {}, on {}\n",
+                    pp_shortest_path(grm, sg, stidx),
+                    pp_sym(grm, Symbol::Token(tidx))
+                ));
+                pp_counterexample(
+                    grm,
+                    self.sr_counterexample(grm, sg, tidx, pidx, stidx),
+                    ("Reduce", "Shift"),
+                    &mut s,
+                );
+            }
+        }
+        s
+    }
+}
+
+/// Pretty-print a shortest input which leads from a start state to `stidx`. If the grammar has more
+/// than one entry rule, the entry rule whose start state the input begins at is included.
+fn pp_shortest_path<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    sg: &StateGraph<StorageT>,
+    stidx: StIdx<StorageT>,
+) -> String
+where
+    usize: AsPrimitive<StorageT>,
+{
+    let (ridx, toks) = sg.shortest_path(grm, stidx);
+    let mut s = if toks.is_empty() {
+        "At the start of the input".to_owned()
+    } else {
+        format!(
+            "After {}",
+            toks.into_iter()
+                .map(|tidx| pp_sym(grm, Symbol::Token(tidx)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    };
+    if grm.entries().len() > 1 {
+        s.push_str(&format!(" (parsing entry rule {})", grm.rule_name(ridx)));
+    }
+    s
+}
+
+/// Pretty-print the (possibly absent) counterexample `ce` into `s`, using `labels` to describe
+/// the first and second derivations.
+fn pp_counterexample<StorageT: 'static + Hash + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    ce: Option<Counterexample<StorageT>>,
+    labels: (&str, &str),
+    s: &mut String,
+) where
+    usize: AsPrimitive<StorageT>,
+{
+    match ce {
+        Some(Counterexample::Unifying(ex1, ex2)) => {
+            s.push_str(&format!("      Ambiguous example: {}\n", ex1.pp(grm)));
+            s.push_str(&format!(
+                "         {} derivation: {}\n",
+                labels.0,
+                ex1.pp_derivation(grm)
+            ));
+            s.push_str(&format!(
+                "         {} derivation: {}\n",
+                labels.1,
+                ex2.pp_derivation(grm)
+            ));
+        }
+        Some(Counterexample::NonUnifying(ex1, ex2)) => {
+            s.push_str(&format!("      First example: {}\n", ex1.pp(grm)));
+            s.push_str(&format!(
+                "         {} derivation: {}\n",
+                labels.0,
+                ex1.pp_derivation(grm)
+            ));
+            s.push_str(&format!("      Second example: {}\n", ex2.pp(grm)));
+            s.push_str(&format!(
+                "         {} derivation: {}\n",
+                labels.1,
+                ex2.pp_derivation(grm)
+            ));
+        }
+        None => s.push_str("      No counterexample found.\n"),
+    }
 }
 
 /// The various different possible Yacc parser errors.
 #[derive(Debug)]
 pub enum StateTableErrorKind {
+    /// Production `pidx` can be reduced where the grammar's input should be accepted.
     AcceptReduceConflict,
+    /// The grammar's stategraph has more states than can be represented by its `StorageT`, in
+    /// which case `pidx` is the grammar's start production. A wider `StorageT` (e.g. `u32`) allows
+    /// more states.
+    TooManyStates,
 }
 
 /// Any error from the Yacc parser returns an instance of this struct.
@@ -111,36 +320,125 @@ impl<StorageT: Debug> Error for StateTableError<StorageT> {}
 
 impl<StorageT> fmt::Display for StateTableError<StorageT> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let s;
         match self.kind {
-            StateTableErrorKind::AcceptReduceConflict => s = "Accept/reduce conflict",
+            StateTableErrorKind::AcceptReduceConflict => write!(f, "Accept/reduce conflict"),
+            StateTableErrorKind::TooManyStates => write!(
+                f,
+                "Too many states for the storage type {}: use a larger storage type (e.g. u32)",
+                type_name::<StorageT>()
+            ),
+        }
+    }
+}
+
+/// A warning about a grammar, found while creating its [StateTable]. Warnings do not prevent a
+/// statetable from being created, but often indicate a mistake in the grammar.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StateTableWarning<StorageT> {
+    /// Production `pidx` could be reduced in at least one state, but is never reduced in any: each
+    /// time, a conflict was resolved in favour of another action.
+    NeverReduced(PIdx<StorageT>),
+    /// Token `tidx` could be shifted in at least one state, but is never shifted in any: each
+    /// time, a shift/reduce conflict was resolved in favour of a reduction (or, for `%nonassoc`
+    /// tokens, an error).
+    NeverShifted(TIdx<StorageT>),
+    /// Token `tidx` has a precedence (from `%left`, `%right`, or `%nonassoc`) which is never used
+    /// to resolve a shift/reduce conflict, either as the conflicting token or as the source of a
+    /// production's precedence. The declaration can be removed without changing the statetable.
+    UnusedPrecedence(TIdx<StorageT>),
+}
+
+impl<StorageT: 'static + PrimInt + Unsigned> StateTableWarning<StorageT>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    /// Returns a pretty-printed version of this warning.
+    pub fn pp(&self, grm: &YaccGrammar<StorageT>) -> String {
+        match *self {
+            StateTableWarning::NeverReduced(pidx) => format!(
+                "Production {} is never reduced due to conflicts",
+                grm.pp_prod(pidx)
+            ),
+            StateTableWarning::NeverShifted(tidx) => format!(
+                "Token \"{}\" is never shifted due to conflicts",
+                grm.token_name(tidx).unwrap()
+            ),
+            StateTableWarning::UnusedPrecedence(tidx) => format!(
+                "The precedence of token \"{}\" is never used to resolve a conflict",
+                grm.token_name(tidx).unwrap()
+            ),
         }
-        write!(f, "{}", s)
     }
 }
 
 /// A representation of a `StateTable` for a grammar. `actions` and `gotos` are split into two
-/// separate hashmaps, rather than a single table, due to the different types of their values.
+/// separate tables, rather than a single table, due to the different types of their values.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct StateTable<StorageT> {
-    actions: SparseVec<usize>,
-    state_actions: Vob,
-    gotos: SparseVec<usize>,
-    start_state: StIdx,
-    core_reduces: Vob,
-    state_shifts: Vob,
-    reduce_states: Vob,
+pub struct StateTable<StorageT: 'static> {
+    actions: SparseTable,
+    state_actions: BitSet,
+    gotos: SparseTable,
+    /// Which `(state, rule)` pairs have a goto entry, in the same layout as `state_actions`.
+    state_gotos: BitSet,
+    /// The start state of each of the grammar's entry points, in the same order as
+    /// `YaccGrammar::entries`.
+    start_states: StaticSlice<StIdx<StorageT>>,
+    core_reduces: BitSet,
+    state_shifts: BitSet,
+    reduce_states: BitSet,
+    states_len: usize,
     prods_len: PIdx<StorageT>,
     tokens_len: TIdx<StorageT>,
     conflicts: Option<Conflicts<StorageT>>,
-    final_state: StIdx,
+    /// `(offset, encoded action)` pairs, sorted by offset, for the actions which lost conflicts
+    /// resolved by Yacc's default rules.
+    conflict_actions: StaticSlice<(usize, usize)>,
+    warnings: StaticSlice<StateTableWarning<StorageT>>,
+    final_state: StIdx<StorageT>,
+}
+
+/// The contents of a [StateTable] as plain slices, allowing a state table to be embedded in
+/// generated source code as `static` arrays (see lrpar's `CTParserBuilder`) rather than as a
+/// serialised blob. [StateTable::from_static] borrows these slices, rather than copying or
+/// decoding them. `actions` and `gotos` are row displacement tables (`*_uniform`,
+/// `*_displacement`, and `*_data`, each stored using the narrowest integer type that can hold
+/// its values) whose non-empty entries are recorded in `state_actions` and `state_gotos`
+/// respectively. Bit vectors are stored as `u64` blocks. This interface is
+/// unstable and should only be used by grmtools itself.
+#[doc(hidden)]
+pub struct StaticStateTable<'a, StorageT: 'static> {
+    pub actions_row_len: usize,
+    pub actions_uniform: StaticUInts<'a>,
+    pub actions_displacement: StaticUInts<'a>,
+    pub actions_data: StaticUInts<'a>,
+    pub state_actions: &'a [u64],
+    pub gotos_row_len: usize,
+    pub gotos_uniform: StaticUInts<'a>,
+    pub gotos_displacement: StaticUInts<'a>,
+    pub gotos_data: StaticUInts<'a>,
+    pub state_gotos: &'a [u64],
+    pub start_states: &'a [StIdx<StorageT>],
+    pub core_reduces: &'a [u64],
+    pub state_shifts: &'a [u64],
+    pub reduce_states: &'a [u64],
+    pub states_len: usize,
+    pub prods_len: PIdx<StorageT>,
+    pub tokens_len: TIdx<StorageT>,
+    pub reduce_reduce: &'a [(PIdx<StorageT>, PIdx<StorageT>, StIdx<StorageT>)],
+    pub shift_reduce: &'a [(TIdx<StorageT>, PIdx<StorageT>, StIdx<StorageT>)],
+    /// `(offset, encoded action)` pairs for the actions which lost automatically resolved
+    /// conflicts.
+    pub conflict_actions: &'a [(usize, usize)],
+    pub warnings: &'a [StateTableWarning<StorageT>],
+    pub final_state: StIdx<StorageT>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Action<StorageT> {
     /// Shift to state X in the statetable.
-    Shift(StIdx),
+    Shift(StIdx<StorageT>),
     /// Reduce production X in the grammar.
     Reduce(PIdx<StorageT>),
     /// Accept this input.
@@ -149,6 +447,59 @@ pub enum Action<StorageT> {
     Error,
 }
 
+impl<StorageT: Copy> StateTable<StorageT> {
+    /// Create a `StateTable` which borrows the contents of a [StaticStateTable]. This interface is
+    /// unstable and should only be used by code generated by grmtools itself.
+    #[doc(hidden)]
+    pub const fn from_static(sstable: &StaticStateTable<'static, StorageT>) -> Self {
+        let conflicts = if sstable.reduce_reduce.is_empty() && sstable.shift_reduce.is_empty() {
+            None
+        } else {
+            Some(Conflicts {
+                reduce_reduce: StaticSlice::Borrowed(sstable.reduce_reduce),
+                shift_reduce: StaticSlice::Borrowed(sstable.shift_reduce),
+            })
+        };
+        StateTable {
+            actions: SparseTable {
+                row_len: sstable.actions_row_len,
+                uniform: UIntSlice::from_static(sstable.actions_uniform),
+                displacement: UIntSlice::from_static(sstable.actions_displacement),
+                data: UIntSlice::from_static(sstable.actions_data),
+            },
+            state_actions: BitSet {
+                blocks: StaticSlice::Borrowed(sstable.state_actions),
+            },
+            gotos: SparseTable {
+                row_len: sstable.gotos_row_len,
+                uniform: UIntSlice::from_static(sstable.gotos_uniform),
+                displacement: UIntSlice::from_static(sstable.gotos_displacement),
+                data: UIntSlice::from_static(sstable.gotos_data),
+            },
+            state_gotos: BitSet {
+                blocks: StaticSlice::Borrowed(sstable.state_gotos),
+            },
+            start_states: StaticSlice::Borrowed(sstable.start_states),
+            core_reduces: BitSet {
+                blocks: StaticSlice::Borrowed(sstable.core_reduces),
+            },
+            state_shifts: BitSet {
+                blocks: StaticSlice::Borrowed(sstable.state_shifts),
+            },
+            reduce_states: BitSet {
+                blocks: StaticSlice::Borrowed(sstable.reduce_states),
+            },
+            states_len: sstable.states_len,
+            prods_len: sstable.prods_len,
+            tokens_len: sstable.tokens_len,
+            conflicts,
+            conflict_actions: StaticSlice::Borrowed(sstable.conflict_actions),
+            warnings: StaticSlice::Borrowed(sstable.warnings),
+            final_state: sstable.final_state,
+        }
+    }
+}
+
 const SHIFT: usize = 1;
 const REDUCE: usize = 2;
 const ACCEPT: usize = 3;
@@ -162,8 +513,7 @@ where
         grm: &YaccGrammar<StorageT>,
         sg: &StateGraph<StorageT>,
     ) -> Result<Self, StateTableError<StorageT>> {
-        let mut state_actions = Vob::from_elem(
-            false,
+        let mut state_actions = BitSet::new(
             usize::from(sg.all_states_len())
                 .checked_mul(usize::from(grm.tokens_len()))
                 .unwrap(),
@@ -175,28 +525,39 @@ where
         assert!(usize::from(grm.rules_len()) < (usize::max_value() - 4));
         let mut actions: Vec<usize> = vec![0; maxa];
 
-        // Since 0 is reserved for the error type, and states are encoded by adding 1, we can only
-        // store max_value - 1 states within the goto table
-        assert!(usize::from(sg.all_states_len()) < (usize::from(StIdx::max_value()) - 1));
         let mut gotos: Vec<usize> = vec![0; maxg];
 
         // Store automatically resolved conflicts, so we can print them out later
         let mut reduce_reduce = Vec::new();
         let mut shift_reduce = Vec::new();
+        // The actions (keyed by offset) discarded when automatically resolving those conflicts,
+        // so that generalised parsers can still explore them.
+        let mut conflict_actions: HashMap<usize, Vec<usize>> = HashMap::new();
         let mut final_state = None;
+        // Which productions could be reduced, and which tokens could be shifted, if there were no
+        // conflicts? Comparing these to the final actions allows us to warn about productions and
+        // tokens made useless by conflict resolution.
+        let mut reducible = Vob::from_elem(false, usize::from(grm.prods_len()));
+        let mut shiftable = Vob::from_elem(false, usize::from(grm.tokens_len()));
+        // Which tokens' and productions' precedences were used to resolve conflicts?
+        let mut used_token_precs = Vob::from_elem(false, usize::from(grm.tokens_len()));
+        let mut used_prod_precs = Vob::from_elem(false, usize::from(grm.prods_len()));
 
         for (stidx, state) in sg
             .iter_closed_states()
             .enumerate()
-            // x goes from 0..states_len(), and we know the latter can safely fit into an
-            // StIdxStorageT, so the cast is safe.
-            .map(|(x, y)| (StIdx(x as StIdxStorageT), y))
+            // x goes from 0..states_len(), and we know the latter can safely fit into a
+            // StorageT, so the cast is safe.
+            .map(|(x, y)| (StIdx(x.as_()), y))
         {
             // Populate reduce and accepts
             for (&(pidx, dot), ctx) in &state.items {
                 if dot < grm.prod_len(pidx) {
                     continue;
                 }
+                if !grm.is_entry_prod(pidx) && ctx.iter_set_bits(..).next().is_some() {
+                    reducible.set(usize::from(pidx), true);
+                }
                 for tidx in ctx.iter_set_bits(..) {
                     let off = actions_offset(
                         grm.tokens_len(),
@@ -205,11 +566,10 @@ where
                         // to as_ is safe.
                         TIdx(tidx.as_()),
                     );
-                    state_actions.set(off, true);
+                    state_actions.set(off);
                     match StateTable::decode(actions[off]) {
                         Action::Reduce(r_pidx) => {
-                            if pidx == grm.start_prod() && tidx == usize::from(grm.eof_token_idx())
-                            {
+                            if grm.is_entry_prod(pidx) && tidx == usize::from(grm.eof_token_idx()) {
                                 return Err(StateTableError {
                                     kind: StateTableErrorKind::AcceptReduceConflict,
                                     pidx,
@@ -221,8 +581,18 @@ where
                                 Ordering::Less => {
                                     reduce_reduce.push((pidx, r_pidx, stidx));
                                     actions[off] = StateTable::encode(Action::Reduce(pidx));
+                                    conflict_actions
+                                        .entry(off)
+                                        .or_default()
+                                        .push(StateTable::encode(Action::Reduce(r_pidx)));
+                                }
+                                Ordering::Greater => {
+                                    reduce_reduce.push((r_pidx, pidx, stidx));
+                                    conflict_actions
+                                        .entry(off)
+                                        .or_default()
+                                        .push(StateTable::encode(Action::Reduce(pidx)));
                                 }
-                                Ordering::Greater => reduce_reduce.push((r_pidx, pidx, stidx)),
                                 Ordering::Equal => (),
                             }
                         }
@@ -233,10 +603,11 @@ where
                             });
                         }
                         Action::Error => {
-                            if pidx == grm.start_prod() && tidx == usize::from(grm.eof_token_idx())
-                            {
-                                assert!(final_state.is_none());
-                                final_state = Some(stidx);
+                            if grm.is_entry_prod(pidx) && tidx == usize::from(grm.eof_token_idx()) {
+                                if pidx == grm.start_prod() {
+                                    assert!(final_state.is_none());
+                                    final_state = Some(stidx);
+                                }
                                 actions[off] = StateTable::encode(Action::Accept);
                             } else {
                                 actions[off] = StateTable::encode(Action::Reduce(pidx));
@@ -260,10 +631,12 @@ where
                     Symbol::Token(s_tidx) => {
                         // Populate shifts
                         let off = actions_offset(grm.tokens_len(), stidx, s_tidx);
-                        state_actions.set(off, true);
+                        state_actions.set(off);
+                        shiftable.set(usize::from(s_tidx), true);
                         match StateTable::decode(actions[off]) {
-                            Action::Shift(x) => assert_eq!(*ref_stidx, x),
+                            Action::Shift(x) => assert!(*ref_stidx == x),
                             Action::Reduce(r_pidx) => {
+                                let sr_len = shift_reduce.len();
                                 resolve_shift_reduce(
                                     grm,
                                     &mut actions,
@@ -273,7 +646,22 @@ where
                                     *ref_stidx,
                                     &mut shift_reduce,
                                     stidx,
+                                    &mut used_token_precs,
+                                    &mut used_prod_precs,
                                 );
+                                if shift_reduce.len() > sr_len {
+                                    conflict_actions
+                                        .entry(off)
+                                        .or_default()
+                                        .push(StateTable::encode(Action::Reduce(r_pidx)));
+                                } else if !matches!(
+                                    StateTable::decode(actions[off]),
+                                    Action::Reduce(_)
+                                ) {
+                                    // Precedence removed the reduction, and thus also any other
+                                    // reductions it had won a reduce/reduce conflict against.
+                                    conflict_actions.remove(&off);
+                                }
                             }
                             Action::Accept => panic!("Internal error"),
                             Action::Error => {
@@ -287,19 +675,19 @@ where
         assert!(final_state.is_some());
 
         let mut nt_depth = HashMap::new();
-        let mut core_reduces = Vob::from_elem(
-            false,
+        let mut core_reduces = BitSet::new(
             usize::from(sg.all_states_len())
                 .checked_mul(usize::from(grm.prods_len()))
                 .unwrap(),
         );
-        let mut state_shifts = Vob::from_elem(
-            false,
+        let mut state_shifts = BitSet::new(
             usize::from(sg.all_states_len())
                 .checked_mul(usize::from(grm.tokens_len()))
                 .unwrap(),
         );
-        let mut reduce_states = Vob::from_elem(false, usize::from(sg.all_states_len()));
+        let mut reduce_states = BitSet::new(usize::from(sg.all_states_len()));
+        let mut reduced = Vob::from_elem(false, usize::from(grm.prods_len()));
+        let mut shifted = Vob::from_elem(false, usize::from(grm.tokens_len()));
         for stidx in sg.iter_stidxs() {
             nt_depth.clear();
             let mut only_reduces = true;
@@ -307,13 +695,15 @@ where
                 let off = actions_offset(grm.tokens_len(), stidx, tidx);
                 match StateTable::decode(actions[off]) {
                     Action::Reduce(pidx) => {
+                        reduced.set(usize::from(pidx), true);
                         let prod_len = grm.prod(pidx).len();
                         let ridx = grm.prod_to_rule(pidx);
                         nt_depth.insert((ridx, prod_len), pidx);
                     }
                     Action::Shift(_) => {
                         only_reduces = false;
-                        state_shifts.set(off, true);
+                        state_shifts.set(off);
+                        shifted.set(usize::from(tidx), true);
                     }
                     Action::Accept => {
                         only_reduces = false;
@@ -329,23 +719,58 @@ where
                     .unwrap()
                     .checked_add(usize::from(pidx))
                     .unwrap();
-                if core_reduces.set(off, true) {
+                if core_reduces.set(off) {
                     distinct_reduces += 1;
                 }
             }
 
             if only_reduces && distinct_reduces == 1 {
-                reduce_states.set(usize::from(stidx), true);
+                reduce_states.set(usize::from(stidx));
+            }
+        }
+
+        let mut warnings = grm
+            .iter_pidxs()
+            .filter(|&pidx| reducible[usize::from(pidx)] && !reduced[usize::from(pidx)])
+            .map(StateTableWarning::NeverReduced)
+            .collect::<Vec<_>>();
+        warnings.extend(
+            grm.iter_tidxs()
+                .filter(|&tidx| shiftable[usize::from(tidx)] && !shifted[usize::from(tidx)])
+                .map(StateTableWarning::NeverShifted),
+        );
+        for pidx in grm.iter_pidxs() {
+            if used_prod_precs[usize::from(pidx)] {
+                if let Some(tidx) = prod_prec_tidx(grm, pidx) {
+                    used_token_precs.set(usize::from(tidx), true);
+                }
             }
         }
+        warnings.extend(
+            grm.iter_tidxs()
+                .filter(|&tidx| {
+                    grm.token_precedence(tidx).is_some() && !used_token_precs[usize::from(tidx)]
+                })
+                .map(StateTableWarning::UnusedPrecedence),
+        );
+
+        let mut state_gotos = BitSet::new(maxg);
+        for (off, _) in gotos.iter().enumerate().filter(|&(_, &x)| x != 0) {
+            state_gotos.set(off);
+        }
+        let actions_sv = SparseTable::new(&actions, usize::from(grm.tokens_len()), &state_actions);
+        let gotos_sv = SparseTable::new(&gotos, usize::from(grm.rules_len()), &state_gotos);
 
-        let actions_sv = SparseVec::<usize>::from(&actions, 0, usize::from(grm.tokens_len()));
-        let gotos_sv = SparseVec::<usize>::from(&gotos, 0, usize::from(grm.rules_len()));
+        let mut conflict_actions = conflict_actions
+            .into_iter()
+            .flat_map(|(off, v)| v.into_iter().map(move |a| (off, a)))
+            .collect::<Vec<_>>();
+        conflict_actions.sort_unstable();
 
         let conflicts = if !(reduce_reduce.is_empty() && shift_reduce.is_empty()) {
             Some(Conflicts {
-                reduce_reduce,
-                shift_reduce,
+                reduce_reduce: reduce_reduce.into(),
+                shift_reduce: shift_reduce.into(),
             })
         } else {
             None
@@ -355,26 +780,64 @@ where
             actions: actions_sv,
             state_actions,
             gotos: gotos_sv,
-            start_state: sg.start_state(),
+            state_gotos,
+            start_states: sg.start_states().to_vec().into(),
             state_shifts,
             core_reduces,
             reduce_states,
+            states_len: usize::from(sg.all_states_len()),
             prods_len: grm.prods_len(),
             tokens_len: grm.tokens_len(),
             conflicts,
+            conflict_actions: conflict_actions.into(),
+            warnings: warnings.into(),
             final_state: final_state.unwrap(),
         })
     }
 
+    /// Return a [StaticStateTable] representing this state table. This interface is unstable and
+    /// should only be used by grmtools itself.
+    #[doc(hidden)]
+    pub fn to_static(&self) -> StaticStateTable<'_, StorageT> {
+        let (reduce_reduce, shift_reduce) = match self.conflicts {
+            Some(ref c) => (&*c.reduce_reduce, &*c.shift_reduce),
+            None => (&[][..], &[][..]),
+        };
+        StaticStateTable {
+            actions_row_len: self.actions.row_len,
+            actions_uniform: self.actions.uniform.to_static(),
+            actions_displacement: self.actions.displacement.to_static(),
+            actions_data: self.actions.data.to_static(),
+            state_actions: &self.state_actions.blocks,
+            gotos_row_len: self.gotos.row_len,
+            gotos_uniform: self.gotos.uniform.to_static(),
+            gotos_displacement: self.gotos.displacement.to_static(),
+            gotos_data: self.gotos.data.to_static(),
+            state_gotos: &self.state_gotos.blocks,
+            start_states: &self.start_states,
+            core_reduces: &self.core_reduces.blocks,
+            state_shifts: &self.state_shifts.blocks,
+            reduce_states: &self.reduce_states.blocks,
+            states_len: self.states_len,
+            prods_len: self.prods_len,
+            tokens_len: self.tokens_len,
+            reduce_reduce,
+            shift_reduce,
+            conflict_actions: &self.conflict_actions,
+            warnings: &self.warnings,
+            final_state: self.final_state,
+        }
+    }
+
     fn decode(bits: usize) -> Action<StorageT> {
         let action = bits & 0b11;
         let val = bits >> 2;
 
         match action {
             SHIFT => {
-                // Since val was originally stored in an StIdxStorageT, we know that it's safe to
-                // cast it back to an StIdxStorageT here.
-                Action::Shift(StIdx::from(val as StIdxStorageT))
+                // Since val was originally stored in a StorageT, we know that it's safe to
+                // cast it back to a StorageT here.
+                Action::Shift(StIdx(val.as_()))
             }
             REDUCE => Action::Reduce(PIdx(val.as_())),
             ACCEPT => Action::Accept,
@@ -392,21 +855,50 @@ where
         }
     }
 
-    /// Return the action for `stidx` and `sym`, or `None` if there isn't any.
-    pub fn action(&self, stidx: StIdx, tidx: TIdx<StorageT>) -> Action<StorageT> {
-        StateTable::decode(
-            self.actions
-                .get(usize::from(stidx), usize::from(tidx))
-                .unwrap(),
+    /// Return the action for `stidx` and `sym`, or `None` if there isn't any. A `tidx` beyond the
+    /// grammar's tokens (e.g. that of a lexeme representing input which could not be lexed) always
+    /// has the action [Action::Error].
+    pub fn action(&self, stidx: StIdx<StorageT>, tidx: TIdx<StorageT>) -> Action<StorageT> {
+        if tidx >= self.tokens_len
+            || !self
+                .state_actions
+                .get(actions_offset(self.tokens_len, stidx, tidx))
+        {
+            return Action::Error;
+        }
+        StateTable::decode(self.actions.get(usize::from(stidx), usize::from(tidx)))
+    }
+
+    /// Return an iterator over all the actions for `stidx` and `tidx`: first the action returned by
+    /// [StateTable::action], then the actions it was chosen over when resolving the conflicts
+    /// reported by [StateTable::conflicts]. Actions removed because of precedence declarations
+    /// are not returned, as such conflicts are resolved deliberately. This allows generalised
+    /// parsers to explore all the alternatives of an ambiguous grammar.
+    pub fn all_actions(
+        &self,
+        stidx: StIdx<StorageT>,
+        tidx: TIdx<StorageT>,
+    ) -> impl Iterator<Item = Action<StorageT>> + '_ {
+        let off = actions_offset(self.tokens_len, stidx, tidx);
+        let start = if tidx < self.tokens_len {
+            self.conflict_actions.partition_point(|&(x, _)| x < off)
+        } else {
+            self.conflict_actions.len()
+        };
+        std::iter::once(self.action(stidx, tidx)).chain(
+            self.conflict_actions[start..]
+                .iter()
+                .take_while(move |&&(x, _)| x == off)
+                .map(|&(_, a)| StateTable::decode(a)),
         )
     }
 
     /// Return an iterator over the indexes of all non-empty actions of `stidx`.
-    pub fn state_actions(&self, stidx: StIdx) -> StateActionsIterator<StorageT> {
+    pub fn state_actions(&self, stidx: StIdx<StorageT>) -> StateActionsIterator<'_, StorageT> {
         let start = usize::from(stidx) * usize::from(self.tokens_len);
         let end = start + usize::from(self.tokens_len);
         StateActionsIterator {
-            iter: self.state_actions.iter_set_bits(start..end),
+            iter: self.state_actions.iter_set_bits(start, end),
             start,
             phantom: PhantomData,
         }
@@ -414,11 +906,11 @@ where
 
     /// Return an iterator over the indexes of all shift actions of `stidx`. By definition this
     /// is a subset of the indexes produced by [`state_actions`](#method.state_actions).
-    pub fn state_shifts(&self, stidx: StIdx) -> StateActionsIterator<StorageT> {
+    pub fn state_shifts(&self, stidx: StIdx<StorageT>) -> StateActionsIterator<'_, StorageT> {
         let start = usize::from(stidx) * usize::from(self.tokens_len);
         let end = start + usize::from(self.tokens_len);
         StateActionsIterator {
-            iter: self.state_shifts.iter_set_bits(start..end),
+            iter: self.state_shifts.iter_set_bits(start, end),
             start,
             phantom: PhantomData,
         }
@@ -426,8 +918,8 @@ where
 
     /// Does the state `stidx` 1) only contain reduce (and error) actions 2) do those
     /// reductions all reduce to the same production?
-    pub fn reduce_only_state(&self, stidx: StIdx) -> bool {
-        self.reduce_states[usize::from(stidx)]
+    pub fn reduce_only_state(&self, stidx: StIdx<StorageT>) -> bool {
+        self.reduce_states.get(usize::from(stidx))
     }
 
     /// Return an iterator over a set of "core" reduces of `stidx`. This is a minimal set of
@@ -445,50 +937,115 @@ where
     ///   And:    [F -> c., $]
     ///
     /// since the two [E -> ...] items both have the same effects on a parse stack.
-    pub fn core_reduces(&self, stidx: StIdx) -> CoreReducesIterator<StorageT> {
+    pub fn core_reduces(&self, stidx: StIdx<StorageT>) -> CoreReducesIterator<'_, StorageT> {
         let start = usize::from(stidx) * usize::from(self.prods_len);
         let end = start + usize::from(self.prods_len);
         CoreReducesIterator {
-            iter: self.core_reduces.iter_set_bits(start..end),
+            iter: self.core_reduces.iter_set_bits(start, end),
             start,
             phantom: PhantomData,
         }
     }
 
     /// Return the goto state for `stidx` and `ridx`, or `None` if there isn't any.
-    pub fn goto(&self, stidx: StIdx, ridx: RIdx<StorageT>) -> Option<StIdx> {
-        // Goto entries are encoded by adding 1 to their value, while 0 is reserved for no entry
-        // (i.e. error)
-        match self.gotos.get(usize::from(stidx), usize::from(ridx)) {
-            Some(0) => None,
-            // gotos can only contain state id's which we know can fit into StIdxStorageT so this
-            // cast is safe
-            Some(i) => Some(StIdx((i - 1) as StIdxStorageT)),
-            None => unreachable!(),
+    pub fn goto(&self, stidx: StIdx<StorageT>, ridx: RIdx<StorageT>) -> Option<StIdx<StorageT>> {
+        let off = usize::from(stidx) * self.gotos.row_len + usize::from(ridx);
+        if !self.state_gotos.get(off) {
+            return None;
+        }
+        // Goto entries are encoded by adding 1 to their value. Gotos can only contain state id's
+        // which we know can fit into StorageT so this cast is safe.
+        let i = self.gotos.get(usize::from(stidx), usize::from(ridx));
+        Some(StIdx((i - 1).as_()))
+    }
+
+    /// Return the tokens, in order, that can come next given the parse stack `pstack` (whose last
+    /// element is the current state). Unlike [StateTable::state_actions], reductions are followed
+    /// (via gotos) until a shift, accept, or error is reached, so tokens which would only lead to
+    /// an error after one or more reductions are excluded. If a reduction would pop more states
+    /// than `pstack` contains, the token is conservatively considered to be expected: passing
+    /// `&[stidx]` thus gives an over-approximation of the tokens expected in state `stidx`. The
+    /// `error` token is never included, since it cannot occur in the input.
+    pub fn expected_tokens(
+        &self,
+        grm: &YaccGrammar<StorageT>,
+        pstack: &[StIdx<StorageT>],
+    ) -> Vec<TIdx<StorageT>> {
+        let mut expected = Vec::new();
+        // Rather than copying `pstack` for each token, we consider only its first `plen` elements,
+        // with the states pushed by gotos in `pushed`.
+        let mut pushed = Vec::new();
+        for tidx in self.state_actions(*pstack.last().unwrap()) {
+            if Some(tidx) == grm.error_token_idx() {
+                continue;
+            }
+            let mut plen = pstack.len();
+            pushed.clear();
+            loop {
+                let stidx = *pushed.last().unwrap_or_else(|| &pstack[plen - 1]);
+                match self.action(stidx, tidx) {
+                    Action::Shift(_) | Action::Accept => {
+                        expected.push(tidx);
+                        break;
+                    }
+                    Action::Error => break,
+                    Action::Reduce(pidx) => {
+                        let mut pop = grm.prod(pidx).len();
+                        let pushed_pop = pop.min(pushed.len());
+                        pushed.truncate(pushed.len() - pushed_pop);
+                        pop -= pushed_pop;
+                        if pop >= plen {
+                            expected.push(tidx);
+                            break;
+                        }
+                        plen -= pop;
+                        let prior = *pushed.last().unwrap_or_else(|| &pstack[plen - 1]);
+                        match self.goto(prior, grm.prod_to_rule(pidx)) {
+                            Some(goto_stidx) => pushed.push(goto_stidx),
+                            None => break,
+                        }
+                    }
+                }
+            }
         }
+        expected
     }
 
     /// Return this state table's start state.
-    pub fn start_state(&self) -> StIdx {
-        self.start_state
+    pub fn start_state(&self) -> StIdx<StorageT> {
+        self.start_states[0]
+    }
+
+    /// Return the start state of each of the grammar's entry points, in the same order as
+    /// [YaccGrammar::entries] (so the first element is always [start_state](Self::start_state)).
+    pub fn start_states(&self) -> &[StIdx<StorageT>] {
+        &self.start_states
     }
 
     /// Return a struct containing all conflicts or `None` if there aren't any.
     pub fn conflicts(&self) -> Option<&Conflicts<StorageT>> {
         self.conflicts.as_ref()
     }
+
+    /// Return the productions which are never reduced, and the tokens which are never shifted,
+    /// because of the way conflicts were resolved (similar to Bison's "rule useless in parser due
+    /// to conflicts" warning), followed by the tokens whose precedences are never used (similar
+    /// to Bison's `-Wprecedence`).
+    pub fn warnings(&self) -> &[StateTableWarning<StorageT>] {
+        &self.warnings
+    }
 }
 
 fn actions_offset<StorageT: PrimInt + Unsigned>(
     tokens_len: TIdx<StorageT>,
-    stidx: StIdx,
+    stidx: StIdx<StorageT>,
     tidx: TIdx<StorageT>,
 ) -> usize {
     usize::from(stidx) * usize::from(tokens_len) + usize::from(tidx)
 }
 
 pub struct StateActionsIterator<'a, StorageT> {
-    iter: IterSetBits<'a, usize>,
+    iter: IterSetBits<'a>,
     start: usize,
     phantom: PhantomData<StorageT>,
 }
@@ -507,7 +1064,7 @@ where
 }
 
 pub struct CoreReducesIterator<'a, StorageT> {
-    iter: IterSetBits<'a, usize>,
+    iter: IterSetBits<'a>,
     start: usize,
     phantom: PhantomData<StorageT>,
 }
@@ -525,15 +1082,35 @@ where
     }
 }
 
+/// Return the token that the precedence of production `pidx` came from (if any). A production
+/// takes its precedence from the token named by its `%prec` declaration if it has one, or
+/// otherwise from its last token.
+fn prod_prec_tidx<StorageT: 'static + PrimInt + Unsigned>(
+    grm: &YaccGrammar<StorageT>,
+    pidx: PIdx<StorageT>,
+) -> Option<TIdx<StorageT>>
+where
+    usize: AsPrimitive<StorageT>,
+{
+    grm.prod_prec_token(pidx).or_else(|| {
+        grm.prod(pidx).iter().rev().find_map(|sym| match *sym {
+            Symbol::Token(tidx) => Some(tidx),
+            Symbol::Rule(_) => None,
+        })
+    })
+}
+
 fn resolve_shift_reduce<StorageT: 'static + Hash + PrimInt + Unsigned>(
     grm: &YaccGrammar<StorageT>,
     actions: &mut Vec<usize>,
     off: usize,
     tidx: TIdx<StorageT>,
     pidx: PIdx<StorageT>,
-    stidx: StIdx, // State we want to shift to
-    shift_reduce: &mut Vec<(TIdx<StorageT>, PIdx<StorageT>, StIdx)>,
-    conflict_stidx: StIdx, // State in which the conflict occured
+    stidx: StIdx<StorageT>, // State we want to shift to
+    shift_reduce: &mut Vec<(TIdx<StorageT>, PIdx<StorageT>, StIdx<StorageT>)>,
+    conflict_stidx: StIdx<StorageT>, // State in which the conflict occured
+    used_token_precs: &mut Vob,
+    used_prod_precs: &mut Vob,
 ) where
     usize: AsPrimitive<StorageT>,
 {
@@ -547,6 +1124,8 @@ fn resolve_shift_reduce<StorageT: 'static + Hash + PrimInt + Unsigned>(
             shift_reduce.push((tidx, pidx, conflict_stidx));
         }
         (Some(token_prec), Some(prod_prec)) => {
+            used_token_precs.set(usize::from(tidx), true);
+            used_prod_precs.set(usize::from(pidx), true);
             match token_prec.level.cmp(&prod_prec.level) {
                 Ordering::Equal => {
                     // Both token and production have the same level precedence, so we need to look
@@ -591,8 +1170,8 @@ mod test {
     };
     use std::collections::HashSet;
 
-    use super::{Action, StateTable, StateTableError, StateTableErrorKind};
-    use crate::{pager::pager_stategraph, StIdx};
+    use super::{Action, StateTable, StateTableError, StateTableErrorKind, StateTableWarning};
+    use crate::{from_yacc, pager::pager_stategraph, Minimiser, StIdx};
 
     #[test]
     #[rustfmt::skip]
@@ -608,7 +1187,7 @@ mod test {
             Factor : 'id';
           "
         ).unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         assert_eq!(sg.all_states_len(), StIdx(9));
 
         let s0 = sg.start_state();
@@ -625,7 +1204,7 @@ mod test {
 
         // Actions
         assert_eq!(st.actions.len(), 9*4);
-        let assert_reduce = |stidx: StIdx, tidx: TIdx<_>, rule: &str, prod_off: usize| {
+        let assert_reduce = |stidx: StIdx<_>, tidx: TIdx<_>, rule: &str, prod_off: usize| {
             let pidx = grm.rule_to_prods(grm.rule_idx(rule).unwrap())[prod_off];
             assert_eq!(st.action(stidx, tidx), Action::Reduce(pidx));
         };
@@ -646,6 +1225,9 @@ mod test {
         assert_reduce(s7, grm.eof_token_idx(), "Expr", 0);
         assert_reduce(s8, grm.token_idx("-").unwrap(), "Term", 0);
         assert_reduce(s8, grm.eof_token_idx(), "Term", 0);
+        // Tokens beyond the grammar's have no actions.
+        assert_eq!(st.action(s0, grm.tokens_len()), Action::Error);
+        assert_eq!(st.all_actions(s4, grm.tokens_len()).collect::<Vec<_>>(), vec![Action::Error]);
 
         let mut s4_actions = HashSet::new();
         s4_actions.extend(&[grm.token_idx("-").unwrap(),
@@ -687,7 +1269,7 @@ mod test {
             B : 'a';
             C : 'a';
           ").unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         let st = StateTable::new(&grm, &sg).unwrap();
 
         let len = usize::from(grm.tokens_len()) * usize::from(sg.all_states_len());
@@ -699,6 +1281,9 @@ mod test {
 
         assert_eq!(st.action(s4, grm.token_idx("x").unwrap()),
                    Action::Reduce(grm.rule_to_prods(grm.rule_idx("B").unwrap())[0]));
+        assert_eq!(st.all_actions(s4, grm.token_idx("x").unwrap()).collect::<Vec<_>>(),
+                   vec![Action::Reduce(grm.rule_to_prods(grm.rule_idx("B").unwrap())[0]),
+                        Action::Reduce(grm.rule_to_prods(grm.rule_idx("C").unwrap())[0])]);
     }
 
     #[test]
@@ -711,7 +1296,7 @@ mod test {
                  | Expr '*' Expr
                  | 'id' ;
           ").unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         let st = StateTable::new(&grm, &sg).unwrap();
         let len = usize::from(grm.tokens_len()) * usize::from(sg.all_states_len());
         assert_eq!(st.actions.len(), len);
@@ -728,6 +1313,12 @@ mod test {
 
         assert_eq!(st.action(s6, grm.token_idx("+").unwrap()), Action::Shift(s3));
         assert_eq!(st.action(s6, grm.token_idx("*").unwrap()), Action::Shift(s4));
+
+        let add_pidx = grm.rule_to_prods(grm.rule_idx("Expr").unwrap())[0];
+        assert_eq!(st.all_actions(s6, grm.token_idx("+").unwrap()).collect::<Vec<_>>(),
+                   vec![Action::Shift(s3), Action::Reduce(add_pidx)]);
+        assert_eq!(st.all_actions(s0, grm.token_idx("id").unwrap()).collect::<Vec<_>>(),
+                   vec![st.action(s0, grm.token_idx("id").unwrap())]);
     }
 
     #[test]
@@ -744,7 +1335,7 @@ mod test {
              | 'b';
             A: 'b';
             ").unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         let st = StateTable::new(&grm, &sg).unwrap();
         let s0 = sg.start_state();
         let s1 = sg.edge(s0, Symbol::Token(grm.token_idx("a").unwrap())).unwrap();
@@ -768,7 +1359,7 @@ mod test {
                  | Expr '*' Expr
                  | 'id' ;
           ").unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         let st = StateTable::new(&grm, &sg).unwrap();
         let len = usize::from(grm.tokens_len()) * usize::from(sg.all_states_len());
         assert_eq!(st.actions.len(), len);
@@ -793,6 +1384,9 @@ mod test {
                    Action::Shift(s4));
         assert_eq!(st.action(s6, grm.eof_token_idx()),
                    Action::Reduce(grm.rule_to_prods(grm.rule_idx("Expr").unwrap())[0]));
+        // Conflicts resolved by precedence don't lead to alternative actions.
+        assert_eq!(st.all_actions(s6, grm.token_idx("*").unwrap()).collect::<Vec<_>>(),
+                   vec![Action::Shift(s4)]);
     }
 
     #[test]
@@ -809,7 +1403,7 @@ mod test {
                  | Expr '=' Expr
                  | 'id' ;
           ").unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         let st = StateTable::new(&grm, &sg).unwrap();
         let len = usize::from(grm.tokens_len()) * usize::from(sg.all_states_len());
         assert_eq!(st.actions.len(), len);
@@ -867,7 +1461,7 @@ mod test {
                  | Expr '~' Expr
                  | 'id' ;
           ").unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         let st = StateTable::new(&grm, &sg).unwrap();
         let len = usize::from(grm.tokens_len()) * usize::from(sg.all_states_len());
         assert_eq!(st.actions.len(), len);
@@ -939,7 +1533,7 @@ C : 'a';
           ",
         )
         .unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         let st = StateTable::new(&grm, &sg).unwrap();
         let conflicts = st.conflicts().unwrap();
         assert_eq!(conflicts.sr_len(), 1);
@@ -973,7 +1567,7 @@ D : D;
           ",
         )
         .unwrap();
-        let sg = pager_stategraph(&grm);
+        let sg = pager_stategraph(&grm).unwrap();
         match StateTable::new(&grm, &sg) {
             Ok(_) => panic!("Infinitely recursive rule let through"),
             Err(StateTableError {
@@ -983,4 +1577,287 @@ D : D;
             Err(e) => panic!("Incorrect error returned {:?}", e),
         }
     }
+
+    #[test]
+    fn too_many_states() {
+        // Each of these productions requires its own chain of 100 states, so the stategraph has
+        // more states than a u8 can represent, but still fits in a u16.
+        let src = format!(
+            "
+%start S
+%%
+S : 'b' {as} | 'c' {as} | 'd' {as};
+          ",
+            as = "'a' ".repeat(100)
+        );
+        let yk = YaccKind::Original(YaccOriginalActionKind::GenericParseTree);
+        let grm = YaccGrammar::<u8>::new_with_storaget(yk, &src).unwrap();
+        for &m in &[
+            Minimiser::Pager,
+            Minimiser::Canonical,
+            Minimiser::LALR,
+            Minimiser::IELR,
+        ] {
+            match from_yacc(&grm, m) {
+                Err(StateTableError {
+                    kind: StateTableErrorKind::TooManyStates,
+                    pidx,
+                }) if pidx == grm.start_prod() => (),
+                Err(e) => panic!("Incorrect error returned {:?}", e),
+                Ok(_) => panic!("Too many states let through"),
+            }
+        }
+        let grm = YaccGrammar::<u16>::new_with_storaget(yk, &src).unwrap();
+        let (sg, _) = from_yacc(&grm, Minimiser::Pager).unwrap();
+        assert!(usize::from(sg.all_states_len()) > usize::from(u8::MAX));
+    }
+
+    #[test]
+    fn test_expected_tokens() {
+        // With Pager's algorithm, the states reached by 'a' after 'x' and after 'y' are merged, so
+        // that state reduces A on both 'b' and 'c', only one of which is valid for a given stack.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+%start S
+%%
+S : 'x' A 'b' | 'y' A 'c';
+A : 'a';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let st = StateTable::new(&grm, &sg).unwrap();
+        let tidx = |n| grm.token_idx(n).unwrap();
+        let s0 = sg.start_state();
+        let sx = st.action(s0, tidx("x"));
+        let sy = st.action(s0, tidx("y"));
+        let (sx, sy) = match (sx, sy) {
+            (Action::Shift(sx), Action::Shift(sy)) => (sx, sy),
+            _ => panic!(),
+        };
+        assert_eq!(st.expected_tokens(&grm, &[s0]), vec![tidx("x"), tidx("y")]);
+        assert_eq!(st.expected_tokens(&grm, &[s0, sx]), vec![tidx("a")]);
+        let sxa = match st.action(sx, tidx("a")) {
+            Action::Shift(s) => s,
+            _ => panic!(),
+        };
+        let sya = match st.action(sy, tidx("a")) {
+            Action::Shift(s) => s,
+            _ => panic!(),
+        };
+        assert_eq!(sxa, sya);
+        assert_eq!(
+            st.state_actions(sxa).collect::<Vec<_>>(),
+            vec![tidx("b"), tidx("c")]
+        );
+        assert_eq!(st.expected_tokens(&grm, &[s0, sx, sxa]), vec![tidx("b")]);
+        assert_eq!(st.expected_tokens(&grm, &[s0, sy, sya]), vec![tidx("c")]);
+        // Without the rest of the stack, both tokens have to be assumed to be expected.
+        assert_eq!(st.expected_tokens(&grm, &[sxa]), vec![tidx("b"), tidx("c")]);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+%start S
+%%
+S : A 'x' | B 'x' | 'c' 'y' | C 'y';
+A : 'a';
+B : 'a';
+C : 'c';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let st = StateTable::new(&grm, &sg).unwrap();
+        let b_pidx = grm.rule_to_prods(grm.rule_idx("B").unwrap())[0];
+        let c_pidx = grm.rule_to_prods(grm.rule_idx("C").unwrap())[0];
+        assert_eq!(
+            st.warnings(),
+            &[
+                StateTableWarning::NeverReduced(b_pidx),
+                StateTableWarning::NeverReduced(c_pidx)
+            ]
+        );
+        assert_eq!(
+            st.warnings()[0].pp(&grm),
+            "Production B: \"a\" is never reduced due to conflicts"
+        );
+
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+%start S
+%%
+S : A 'x';
+A : 'a';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let st = StateTable::new(&grm, &sg).unwrap();
+        assert!(st.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_unused_precedence() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+%start E
+%token 'UMINUS' 'UNUSED'
+%left '+'
+%nonassoc '('
+%left '*'
+%right 'UMINUS'
+%left 'UNUSED'
+%%
+E : E '+' E | E '*' E | '-' E %prec 'UMINUS' | '(' E ')' | 'INT';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let st = StateTable::new(&grm, &sg).unwrap();
+        assert!(st.conflicts().is_none());
+        assert_eq!(
+            st.warnings(),
+            &[
+                StateTableWarning::UnusedPrecedence(grm.token_idx("UNUSED").unwrap()),
+                StateTableWarning::UnusedPrecedence(grm.token_idx("(").unwrap())
+            ]
+        );
+        assert_eq!(
+            st.warnings()[1].pp(&grm),
+            "The precedence of token \"(\" is never used to resolve a conflict"
+        );
+    }
+
+    #[test]
+    fn test_unused_precedence_shared_level() {
+        // '!' shares 'UMINUS''s level, but only the token named by `%prec` is used.
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+%start E
+%token 'UMINUS' '!'
+%left '+'
+%right 'UMINUS' '!'
+%%
+E : E '+' E | '-' E %prec 'UMINUS' | 'INT';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        let st = StateTable::new(&grm, &sg).unwrap();
+        assert!(st.conflicts().is_none());
+        assert_eq!(
+            st.warnings(),
+            &[StateTableWarning::UnusedPrecedence(
+                grm.token_idx("!").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_entries() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+%start S
+%entry T
+%%
+S : T ';' | S T ';';
+T : 'a' | 'a' '+' T;
+          ",
+        )
+        .unwrap();
+
+        // Run the LR automaton from `start_state` over `toks`, returning true if it accepts.
+        let accepts = |st: &StateTable<u32>, start_state, toks: &[&str]| {
+            let mut pstack = vec![start_state];
+            let mut i = 0;
+            loop {
+                let tidx = match toks.get(i) {
+                    Some(t) => grm.token_idx(t).unwrap(),
+                    None => grm.eof_token_idx(),
+                };
+                match st.action(*pstack.last().unwrap(), tidx) {
+                    Action::Shift(stidx) => {
+                        pstack.push(stidx);
+                        i += 1;
+                    }
+                    Action::Reduce(pidx) => {
+                        pstack.truncate(pstack.len() - usize::from(grm.prod_len(pidx)));
+                        let stidx = st
+                            .goto(*pstack.last().unwrap(), grm.prod_to_rule(pidx))
+                            .unwrap();
+                        pstack.push(stidx);
+                    }
+                    Action::Accept => return true,
+                    Action::Error => return false,
+                }
+            }
+        };
+
+        for m in [
+            Minimiser::Pager,
+            Minimiser::Canonical,
+            Minimiser::LALR,
+            Minimiser::IELR,
+        ] {
+            let (sg, st) = from_yacc(&grm, m).unwrap();
+            assert_eq!(sg.start_states(), st.start_states());
+            assert_eq!(st.start_states().len(), 2);
+            let (s_stidx, t_stidx) = (st.start_states()[0], st.start_states()[1]);
+            assert_eq!(s_stidx, st.start_state());
+            assert!(accepts(&st, s_stidx, &["a", ";", "a", "+", "a", ";"]));
+            assert!(!accepts(&st, s_stidx, &["a"]));
+            assert!(accepts(&st, t_stidx, &["a", "+", "a"]));
+            assert!(!accepts(&st, t_stidx, &["a", ";"]));
+            assert!(st.conflicts().is_none());
+            assert!(st.warnings().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_static_roundtrip() {
+        let grm = YaccGrammar::new(
+            YaccKind::Original(YaccOriginalActionKind::GenericParseTree),
+            "
+%start A
+%%
+A : B 'x' | C 'x' 'x' | A 'b';
+B : 'a' | 'a' 'b';
+C : 'a';
+          ",
+        )
+        .unwrap();
+        let sg = pager_stategraph(&grm).unwrap();
+        // `from_static` borrows `'static` data, which a generated `static` would provide.
+        let st: &'static StateTable<u32> = Box::leak(Box::new(StateTable::new(&grm, &sg).unwrap()));
+        let st2 = StateTable::from_static(&st.to_static());
+        assert_eq!(st2.start_states(), st.start_states());
+        assert_eq!(st2.final_state, st.final_state);
+        for stidx in sg.iter_stidxs() {
+            for tidx in grm.iter_tidxs() {
+                assert_eq!(st2.action(stidx, tidx), st.action(stidx, tidx));
+                assert!(st2.all_actions(stidx, tidx).eq(st.all_actions(stidx, tidx)));
+            }
+            for ridx in grm.iter_rules() {
+                assert_eq!(st2.goto(stidx, ridx), st.goto(stidx, ridx));
+            }
+            assert!(st2.state_actions(stidx).eq(st.state_actions(stidx)));
+            assert!(st2.state_shifts(stidx).eq(st.state_shifts(stidx)));
+            assert!(st2.core_reduces(stidx).eq(st.core_reduces(stidx)));
+            assert_eq!(st2.reduce_only_state(stidx), st.reduce_only_state(stidx));
+        }
+        let (c, c2) = (st.conflicts().unwrap(), st2.conflicts().unwrap());
+        assert!(c2.rr_conflicts().eq(c.rr_conflicts()));
+        assert!(c2.sr_conflicts().eq(c.sr_conflicts()));
+        assert!(!st.warnings().is_empty());
+        assert_eq!(st2.warnings(), st.warnings());
+    }
 }
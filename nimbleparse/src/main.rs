@@ -10,7 +10,7 @@ use cfgrammar::yacc::{YaccGrammar, YaccKind, YaccOriginalActionKind};
 use getopts::Options;
 use lrlex::{DefaultLexeme, LRNonStreamingLexerDef, LexerDef};
 use lrpar::parser::{RTParserBuilder, RecoveryKind};
-use lrtable::{from_yacc, Minimiser};
+use lrtable::{from_yacc, DotOptions, Minimiser, Report};
 use num_traits::ToPrimitive;
 
 fn usage(prog: &str, msg: &str) -> ! {
@@ -24,7 +24,7 @@ fn usage(prog: &str, msg: &str) -> ! {
     }
     writeln!(
         &mut stderr(),
-        "Usage: {} [-r <cpctplus|none>] [-y <eco|grmtools|original>] [-q] <lexer.l> <parser.y> <input file>",
+        "Usage: {} [-d <file.dot>] [-m <canonical|ielr|lalr|pager>] [-o <text|xml>] [-r <cpctplus|none>] [-y <eco|grmtools|original>] [-q] <lexer.l> <parser.y> <input file>",
         leaf
     )
     .ok();
@@ -48,8 +48,26 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     let prog = &args[0];
     let matches = match Options::new()
+        .optopt(
+            "d",
+            "dot",
+            "Write the stategraph, in Graphviz DOT format, to the given file",
+            "file.dot",
+        )
         .optflag("h", "help", "")
         .optflag("q", "quiet", "Don't print warnings such as conflicts")
+        .optopt(
+            "m",
+            "minimiser",
+            "Stategraph minimiser to be used (default: pager)",
+            "canonical|ielr|lalr|pager",
+        )
+        .optopt(
+            "o",
+            "report",
+            "Print a report of the grammar's states, actions, and conflicts",
+            "text|xml",
+        )
         .optopt(
             "r",
             "recoverer",
@@ -74,6 +92,25 @@ fn main() {
 
     let quiet = matches.opt_present("q");
 
+    let minimiser = match matches.opt_str("m") {
+        None => Minimiser::Pager,
+        Some(s) => match &*s.to_lowercase() {
+            "canonical" => Minimiser::Canonical,
+            "ielr" => Minimiser::IELR,
+            "lalr" => Minimiser::LALR,
+            "pager" => Minimiser::Pager,
+            _ => usage(prog, &format!("Unknown minimiser '{}'.", s)),
+        },
+    };
+
+    let report = match matches.opt_str("o") {
+        None => None,
+        Some(s) => match &*s.to_lowercase() {
+            "text" | "xml" => Some(s.to_lowercase()),
+            _ => usage(prog, &format!("Unknown report format '{}'.", s)),
+        },
+    };
+
     let recoverykind = match matches.opt_str("r") {
         None => RecoveryKind::CPCTPlus,
         Some(s) => match &*s.to_lowercase() {
@@ -115,7 +152,7 @@ fn main() {
             process::exit(1);
         }
     };
-    let (sgraph, stable) = match from_yacc(&grm, Minimiser::Pager) {
+    let (sgraph, stable) = match from_yacc(&grm, minimiser) {
         Ok(x) => x,
         Err(s) => {
             writeln!(&mut stderr(), "{}: {}", &yacc_y_path, &s).ok();
@@ -123,7 +160,33 @@ fn main() {
         }
     };
 
+    if let Some(p) = matches.opt_str("d") {
+        let dot = sgraph.to_dot(
+            &grm,
+            &DotOptions {
+                conflicts: stable.conflicts(),
+                ..Default::default()
+            },
+        );
+        if let Err(e) = File::create(&p).and_then(|mut f| f.write_all(dot.as_bytes())) {
+            writeln!(&mut stderr(), "Can't write file {}: {}", p, e).ok();
+            process::exit(1);
+        }
+    }
+
+    if let Some(f) = report {
+        let r = Report::new(&grm, &sgraph, &stable);
+        if f == "xml" {
+            print!("{}", r.to_xml());
+        } else {
+            print!("{}", r.pp());
+        }
+    }
+
     if !quiet {
+        for w in stable.warnings() {
+            writeln!(&mut stderr(), "Warning: {}", w.pp(&grm)).ok();
+        }
         if let Some(c) = stable.conflicts() {
             let pp_rr = if let Some(i) = grm.expectrr() {
                 i != c.rr_len()
@@ -136,10 +199,10 @@ fn main() {
                 0 != c.sr_len()
             };
             if pp_rr {
-                println!("{}", c.pp_rr(&grm));
+                println!("{}", c.pp_rr_counterexamples(&grm, &sgraph));
             }
             if pp_sr {
-                println!("{}", c.pp_sr(&grm));
+                println!("{}", c.pp_sr_counterexamples(&grm, &sgraph));
             }
             if pp_rr || pp_sr {
                 println!("Stategraph:\n{}\n", sgraph.pp_core_states(&grm));